@@ -1,6 +1,7 @@
-use thiserror::Error;
 use std::io;
 
+use thiserror::Error;
+
 #[derive(Error, Debug)]
 pub enum DriverError {
     #[error("IO error: {0}")]
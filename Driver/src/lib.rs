@@ -1,19 +1,18 @@
 // ptree-driver: Windows service driver for real-time file system change tracking
 // Monitors NTFS USN Journal for incremental cache updates
 
-#[cfg(windows)]
-pub mod usn_journal;
 pub mod error;
-pub mod service;
 #[cfg(windows)]
 pub mod registration;
+pub mod service;
+#[cfg(windows)]
+pub mod usn_journal;
 
 pub use error::{DriverError, DriverResult};
-
+pub use ptree_core::driver_status::{ServiceStatus, ServiceStatusReport, STATUS_FILE_NAME};
+pub use service::{PtreeService, ServiceConfig};
 #[cfg(windows)]
-pub use usn_journal::{USNTracker, UsnRecord, USNJournalState, ChangeType};
-
-pub use service::{PtreeService, ServiceConfig, ServiceStatus};
+pub use usn_journal::{ChangeType, USNJournalState, USNTracker, UsnRecord};
 
 /// Driver version
 pub const DRIVER_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -1,7 +1,7 @@
 // ptree-driver: Windows service for real-time file system change tracking
 // Provides incremental cache updates via NTFS USN Journal monitoring
 
-use ptree_driver::{PtreeService, ServiceConfig, DRIVER_VERSION};
+use ptree_driver::{PtreeService, ServiceConfig, ServiceStatusReport, DRIVER_VERSION};
 use std::env;
 
 #[cfg(windows)]
@@ -176,8 +176,14 @@ fn stop_service() {
 /// Print service status
 fn print_status() {
     println!("ptree-driver v{}", DRIVER_VERSION);
-    println!("Status: Service status command");
-    println!("Note: Full status monitoring requires Windows service integration");
+
+    let status_path = ServiceConfig::default().status_path();
+    match ServiceStatusReport::read_from(&status_path) {
+        Ok(status) => print!("{}", status),
+        Err(e) => {
+            println!("Status: unknown (no status at {}: {})", status_path.display(), e);
+        }
+    }
 }
 
 /// Print version information
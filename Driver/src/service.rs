@@ -1,83 +1,116 @@
 // Windows service implementation for ptree-driver
 // Runs as a system service monitoring file system changes via USN Journal
 
-use crate::usn_journal::USNTracker;
-use crate::error::DriverResult;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+#[cfg(windows)]
 use std::time::{Duration, Instant};
-use log::{info, error, debug};
+
+#[cfg(windows)]
+use chrono::Utc;
+use log::warn;
+#[cfg(windows)]
+use log::{debug, error, info};
+use parking_lot::Mutex;
+use ptree_core::driver_status::{ServiceStatusReport, STATUS_FILE_NAME};
+
+use crate::error::{DriverError, DriverResult};
+#[cfg(windows)]
+use crate::usn_journal::USNTracker;
 
 /// Service configuration
 pub struct ServiceConfig {
     /// Drive letter to monitor (e.g., 'C')
     pub drive_letter: char,
-    
+
     /// Interval between journal checks (seconds)
     pub check_interval: u64,
-    
+
     /// Cache file path
     pub cache_path: std::path::PathBuf,
-    
+
     /// Log file path
     pub log_path: std::path::PathBuf,
 }
 
+impl ServiceConfig {
+    /// Where the running service publishes its `ServiceStatusReport`
+    pub fn status_path(&self) -> PathBuf {
+        self.cache_path.with_file_name(STATUS_FILE_NAME)
+    }
+}
+
 impl Default for ServiceConfig {
     fn default() -> Self {
         ServiceConfig {
-            drive_letter: 'C',
+            drive_letter:   'C',
             check_interval: 60,
-            cache_path: std::path::PathBuf::from(
-                std::env::var("APPDATA").unwrap_or_else(|_| "C:\\Users\\User\\AppData\\Roaming".to_string())
-            ).join("ptree")
+            cache_path:     std::path::PathBuf::from(
+                std::env::var("APPDATA").unwrap_or_else(|_| "C:\\Users\\User\\AppData\\Roaming".to_string()),
+            )
+            .join("ptree")
             .join("cache")
             .join("ptree.dat"),
-            log_path: std::path::PathBuf::from("C:\\ProgramData\\ptree")
-                .join("service.log"),
+            log_path:       std::path::PathBuf::from("C:\\ProgramData\\ptree").join("service.log"),
         }
     }
 }
 
 /// Service state
 pub struct PtreeService {
-    config: ServiceConfig,
+    config:          ServiceConfig,
     pub should_exit: Arc<AtomicBool>,
-    last_update: Instant,
+    status:          Arc<Mutex<ServiceStatusReport>>,
 }
 
 impl PtreeService {
     /// Create a new service instance
     pub fn new(config: ServiceConfig) -> Self {
+        let status = ServiceStatusReport::new(config.drive_letter);
         PtreeService {
             config,
             should_exit: Arc::new(AtomicBool::new(false)),
-            last_update: Instant::now(),
+            status: Arc::new(Mutex::new(status)),
+        }
+    }
+
+    /// Apply a status transition and publish the result to the status file
+    ///
+    /// A failed write is logged only; status reporting never stops the service.
+    fn update_status(&self, update: impl FnOnce(&mut ServiceStatusReport)) {
+        let snapshot = {
+            let mut status = self.status.lock();
+            update(&mut status);
+            status.clone()
+        };
+
+        if let Err(e) = snapshot.write_to(&self.config.status_path()) {
+            warn!("Failed to write service status: {}", e);
         }
     }
 
     /// Main service loop - runs continuously
+    #[cfg(windows)]
     pub fn run(&mut self) -> DriverResult<()> {
         info!("ptree-driver service starting");
         info!("Monitoring drive: {}", self.config.drive_letter);
         info!("Check interval: {} seconds", self.config.check_interval);
 
         // Create tracker for the specified drive
-        let mut tracker = USNTracker::new(
-            self.config.drive_letter,
-            Default::default(),
-        );
+        let mut tracker = USNTracker::new(self.config.drive_letter, Default::default());
 
         // Check if journal is available
         if !tracker.is_available()? {
-            error!("USN Journal not available on drive {}. Service cannot start.", 
-                   self.config.drive_letter);
+            error!("USN Journal not available on drive {}. Service cannot start.", self.config.drive_letter);
+            self.update_status(|s| s.record_error("USN Journal not available"));
             return Err(crate::error::DriverError::JournalNotFound(
                 "Service requires NTFS volume with active USN Journal".to_string(),
             ));
         }
 
         info!("USN Journal is active. Starting monitoring loop.");
+        self.update_status(|s| s.mark_running());
 
         let check_interval = Duration::from_secs(self.config.check_interval);
 
@@ -90,21 +123,25 @@ impl PtreeService {
                 Ok(changes) => {
                     if !changes.is_empty() {
                         info!("Detected {} changes", changes.len());
-                        
+
                         // Apply changes to cache
                         if let Err(e) = self.apply_changes(&changes) {
                             error!("Failed to apply changes to cache: {}", e);
+                            self.update_status(|s| s.record_error(format!("Failed to apply changes: {}", e)));
                         } else {
                             debug!("Successfully updated cache with {} changes", changes.len());
-                            self.last_update = Instant::now();
+                            let last_usn = tracker.state().last_usn;
+                            self.update_status(|s| s.record_update(last_usn, changes.len() as u64, Utc::now()));
                         }
                     } else {
                         debug!("No changes detected");
+                        self.update_status(|s| s.mark_running());
                     }
                 }
                 Err(e) => {
                     error!("Failed to read journal: {}", e);
-                    
+                    self.update_status(|s| s.record_error(format!("Failed to read journal: {}", e)));
+
                     // Check if journal is still valid
                     if let Err(validity_err) = tracker.check_journal_validity() {
                         error!("Journal validity check failed: {}", validity_err);
@@ -122,15 +159,24 @@ impl PtreeService {
         }
 
         info!("ptree-driver service stopping");
+        self.update_status(|s| s.mark_stopped());
         Ok(())
     }
 
+    /// The USN Journal only exists on NTFS volumes under Windows
+    #[cfg(not(windows))]
+    pub fn run(&mut self) -> DriverResult<()> {
+        self.update_status(|s| s.record_error("USN Journal monitoring requires Windows"));
+        Err(DriverError::JournalNotFound("Service requires NTFS volume with active USN Journal".to_string()))
+    }
+
     /// Signal the service to stop
     pub fn stop(&self) {
         self.should_exit.store(true, Ordering::Relaxed);
     }
 
     /// Apply changes to the ptree cache
+    #[cfg(windows)]
     fn apply_changes(&self, changes: &[crate::usn_journal::UsnRecord]) -> DriverResult<()> {
         use crate::usn_journal::ChangeType;
 
@@ -157,33 +203,22 @@ impl PtreeService {
             }
         }
 
-        debug!("Changes: {} created, {} modified, {} deleted",
-               creates, modifies, deletes);
+        debug!("Changes: {} created, {} modified, {} deleted", creates, modifies, deletes);
 
         Ok(())
     }
 
     /// Get service status
-    pub fn status(&self) -> ServiceStatus {
-        ServiceStatus {
-            is_running: !self.should_exit.load(Ordering::Relaxed),
-            last_update: self.last_update,
-            drive: self.config.drive_letter,
-            cache_path: self.config.cache_path.clone(),
-        }
+    pub fn status(&self) -> DriverResult<ServiceStatusReport> {
+        Ok(self.status.lock().clone())
     }
 }
 
-/// Service status information
-pub struct ServiceStatus {
-    pub is_running: bool,
-    pub last_update: Instant,
-    pub drive: char,
-    pub cache_path: std::path::PathBuf,
-}
-
 #[cfg(test)]
 mod tests {
+    use chrono::Utc;
+    use ptree_core::driver_status::ServiceStatus;
+
     use super::*;
 
     #[test]
@@ -193,6 +228,36 @@ mod tests {
         assert_eq!(service.config.drive_letter, 'C');
     }
 
+    #[test]
+    fn test_status_report_starts_stopped() {
+        let service = PtreeService::new(ServiceConfig::default());
+        let status = service.status().unwrap();
+        assert_eq!(status, ServiceStatusReport::new('C'));
+        assert_eq!(status.state, ServiceStatus::Stopped);
+        assert_eq!(status.last_cache_update, None);
+        assert_eq!(status.error_count, 0);
+    }
+
+    #[test]
+    fn test_status_file_round_trip() {
+        let dir = std::env::temp_dir().join("ptree_driver_test_status");
+        let _ = std::fs::remove_dir_all(&dir);
+        let config = ServiceConfig {
+            cache_path: dir.join("ptree.dat"),
+            ..ServiceConfig::default()
+        };
+
+        let mut status = ServiceStatusReport::new('C');
+        status.record_update(42, 1, Utc::now());
+        status.write_to(&config.status_path()).unwrap();
+
+        let json = std::fs::read_to_string(config.status_path()).unwrap();
+        assert!(json.contains("\"state\": \"running\""));
+        assert_eq!(ServiceStatusReport::read_from(&config.status_path()).unwrap(), status);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_service_stop_signal() {
         let config = ServiceConfig::default();
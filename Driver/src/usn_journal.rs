@@ -342,8 +342,10 @@ impl USNTracker {
             let bytes = &buffer[filename_offset..filename_offset + filename_len];
             // Interpret bytes as UTF-16 (2 bytes per character)
             let utf16_chars: Vec<u16> = bytes
-                .chunks_exact(2)
-                .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+                .as_chunks::<2>()
+                .0
+                .iter()
+                .map(|&chunk| u16::from_le_bytes(chunk))
                 .collect();
             String::from_utf16_lossy(&utf16_chars).to_string()
         } else {
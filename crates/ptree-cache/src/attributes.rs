@@ -0,0 +1,66 @@
+// ============================================================================
+// Windows File Attributes (`--attributes`)
+// ============================================================================
+
+pub const FILE_ATTRIBUTE_READONLY: u32 = 0x01;
+pub const FILE_ATTRIBUTE_HIDDEN: u32 = 0x02;
+pub const FILE_ATTRIBUTE_SYSTEM: u32 = 0x04;
+pub const FILE_ATTRIBUTE_ARCHIVE: u32 = 0x20;
+pub const FILE_ATTRIBUTE_COMPRESSED: u32 = 0x800;
+
+/// `--attributes` column for entries without a captured attribute word
+pub const UNKNOWN_ATTRIBUTES: &str = "?????";
+
+/// Flag letters in display order, `-` when the attribute is clear
+const FLAGS: [(u32, char); 5] = [
+    (FILE_ATTRIBUTE_HIDDEN, 'H'),
+    (FILE_ATTRIBUTE_SYSTEM, 'S'),
+    (FILE_ATTRIBUTE_READONLY, 'R'),
+    (FILE_ATTRIBUTE_ARCHIVE, 'A'),
+    (FILE_ATTRIBUTE_COMPRESSED, 'C'),
+];
+
+/// Render an attribute word as `HSRAC`, one fixed position per flag
+///
+/// Other attribute bits (directory, reparse point, ...) are not shown.
+pub fn format_attributes(attrs: u32) -> String {
+    FLAGS
+        .iter()
+        .map(|&(bit, flag)| if attrs & bit != 0 { flag } else { '-' })
+        .collect()
+}
+
+/// Whether an attribute word carries the hidden flag
+pub fn has_hidden_attribute(attrs: u32) -> bool {
+    attrs & FILE_ATTRIBUTE_HIDDEN != 0
+}
+
+/// Raw attribute word from already-fetched metadata (Windows only)
+#[cfg(windows)]
+pub fn attributes_from_metadata(metadata: &std::fs::Metadata) -> u32 {
+    use std::os::windows::fs::MetadataExt;
+
+    metadata.file_attributes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_attributes_known_combinations() {
+        assert_eq!(format_attributes(0), "-----");
+        assert_eq!(format_attributes(FILE_ATTRIBUTE_HIDDEN), "H----");
+        assert_eq!(format_attributes(FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM | FILE_ATTRIBUTE_READONLY), "HSR--");
+        assert_eq!(format_attributes(FILE_ATTRIBUTE_ARCHIVE | FILE_ATTRIBUTE_COMPRESSED), "---AC");
+        // FILE_ATTRIBUTE_DIRECTORY (0x10) and FILE_ATTRIBUTE_REPARSE_POINT (0x400) have no column
+        assert_eq!(format_attributes(0x10 | 0x400 | FILE_ATTRIBUTE_READONLY), "--R--");
+        assert_eq!(format_attributes(u32::MAX).len(), UNKNOWN_ATTRIBUTES.len());
+    }
+
+    #[test]
+    fn test_has_hidden_attribute() {
+        assert!(has_hidden_attribute(FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM));
+        assert!(!has_hidden_attribute(FILE_ATTRIBUTE_SYSTEM | FILE_ATTRIBUTE_ARCHIVE));
+    }
+}
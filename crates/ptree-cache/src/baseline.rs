@@ -0,0 +1,144 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use ptree_core::HashAlgorithm;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{DirEntry, DiskCache};
+use crate::diff::{diff_caches, ChangeKind};
+
+// ============================================================================
+// Baseline Snapshots (`--baseline`)
+// ============================================================================
+
+/// On-disk baseline: the entries at and below `root` when it was recorded
+#[derive(Debug, Serialize, Deserialize)]
+struct BaselineFile {
+    root:      PathBuf,
+    hash_algo: HashAlgorithm,
+    /// Sorted by path so baselines diff cleanly under version control
+    entries:   Vec<DirEntry>,
+}
+
+impl DiskCache {
+    /// Write the entries at and below `self.root` to `path` as pretty JSON
+    pub fn save_baseline(&self, path: &Path) -> Result<()> {
+        let mut entries: Vec<DirEntry> = self.subtree_entries().cloned().collect();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        let baseline = BaselineFile {
+            root: self.root.clone(),
+            hash_algo: self.hash_algo,
+            entries,
+        };
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let mut doc = serde_json::to_vec_pretty(&baseline)?;
+        doc.push(b'\n');
+        fs::write(path, doc).with_context(|| format!("Failed to write baseline {}", path.display()))
+    }
+
+    /// Read a baseline written by `save_baseline` into a cache holding only its entries
+    pub fn load_baseline(path: &Path) -> Result<DiskCache> {
+        let text = fs::read(path).with_context(|| format!("Failed to read baseline {}", path.display()))?;
+        let baseline: BaselineFile =
+            serde_json::from_slice(&text).with_context(|| format!("Invalid baseline {}", path.display()))?;
+
+        let mut cache = DiskCache::new_empty();
+        cache.root = baseline.root;
+        cache.hash_algo = baseline.hash_algo;
+        cache.entries = baseline
+            .entries
+            .into_iter()
+            .map(|entry| (entry.path.clone(), entry))
+            .collect();
+        Ok(cache)
+    }
+
+    /// Entries added, removed or modified since `baseline`, sorted by path
+    ///
+    /// Only `self.root` and what lies below it is compared, so other roots kept
+    /// in the same cache never show up as additions.
+    pub fn changes_since_baseline(&self, baseline: &DiskCache) -> Result<Vec<(PathBuf, ChangeKind)>> {
+        if baseline.root != self.root {
+            bail!(
+                "Baseline was recorded for {}, not {}; rerun with --update-baseline to replace it",
+                baseline.root.display(),
+                self.root.display()
+            );
+        }
+        if baseline.hash_algo != self.hash_algo {
+            bail!(
+                "Baseline was recorded with --hash-algo {}; rerun with it or with --update-baseline",
+                baseline.hash_algo
+            );
+        }
+
+        let mut current = DiskCache::new_empty();
+        current.root = self.root.clone();
+        current.entries = self
+            .subtree_entries()
+            .map(|entry| (entry.path.clone(), entry.clone()))
+            .collect();
+        Ok(diff_caches(baseline, &current))
+    }
+
+    fn subtree_entries(&self) -> impl Iterator<Item = &DirEntry> {
+        self.entries.values().filter(|e| e.path.starts_with(&self.root))
+    }
+}
+
+/// One `+ path`, `- path` or `~ path` line per change
+pub fn build_baseline_report(changes: &[(PathBuf, ChangeKind)]) -> String {
+    let mut out = String::new();
+    for (path, kind) in changes {
+        let _ = writeln!(out, "{} {}", kind.marker(), path.display());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{cache_with, dir, file};
+
+    fn fixture() -> DiskCache {
+        cache_with(
+            "/dist",
+            [
+                dir("/dist", &["app.js", "assets"]),
+                file("/dist/app.js", 120),
+                dir("/dist/assets", &["logo.svg"]),
+                file("/dist/assets/logo.svg", 40),
+                dir("/elsewhere", &[]),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_baseline_round_trip_and_changes() -> Result<()> {
+        let dir = std::env::temp_dir().join("ptree_test_baseline_module");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("baseline.json");
+
+        let mut cache = fixture();
+        cache.save_baseline(&path)?;
+        let baseline = DiskCache::load_baseline(&path)?;
+        assert_eq!(baseline.len(), 4, "entries outside the root are left out");
+        assert!(cache.changes_since_baseline(&baseline)?.is_empty());
+
+        cache.entries.get_mut(Path::new("/dist/app.js")).unwrap().size = 999;
+        cache.entries.remove(Path::new("/dist/assets/logo.svg"));
+        let changes = cache.changes_since_baseline(&baseline)?;
+        assert_eq!(build_baseline_report(&changes), "~ /dist/app.js\n- /dist/assets/logo.svg\n");
+
+        cache.root = PathBuf::from("/elsewhere");
+        assert!(cache.changes_since_baseline(&baseline).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+        Ok(())
+    }
+}
@@ -0,0 +1,196 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+
+use crate::cache::DirEntry;
+use crate::permissions::UnixPermissions;
+
+// ============================================================================
+// DirEntry Builder
+// ============================================================================
+
+/// Step-by-step `DirEntry` construction with scan defaults (see `DirEntry::builder`)
+#[derive(Debug, Clone)]
+pub struct DirEntryBuilder {
+    entry: DirEntry,
+}
+
+impl DirEntry {
+    /// Start an entry for `path`
+    ///
+    /// Defaults: named after the last path component (empty for roots like
+    /// `/`), modified now, a directory with no children, size 0, depth 0, and
+    /// no hash, symlink target, hidden flag, permissions or attributes.
+    pub fn builder(path: impl Into<PathBuf>) -> DirEntryBuilder {
+        let path = path.into();
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        DirEntryBuilder {
+            entry: DirEntry {
+                path,
+                name,
+                modified: Utc::now(),
+                content_hash: 0,
+                children: Vec::new(),
+                symlink_target: None,
+                is_hidden: false,
+                is_dir: true,
+                size: 0,
+                depth: 0,
+                permissions: None,
+                attributes: None,
+            },
+        }
+    }
+}
+
+impl DirEntryBuilder {
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.entry.name = name.into();
+        self
+    }
+
+    pub fn modified(mut self, modified: DateTime<Utc>) -> Self {
+        self.entry.modified = modified;
+        self
+    }
+
+    pub fn content_hash(mut self, content_hash: u64) -> Self {
+        self.entry.content_hash = content_hash;
+        self
+    }
+
+    pub fn children<I, S>(mut self, children: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.entry.children = children.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn symlink_target(mut self, target: Option<PathBuf>) -> Self {
+        self.entry.symlink_target = target;
+        self
+    }
+
+    pub fn hidden(mut self, is_hidden: bool) -> Self {
+        self.entry.is_hidden = is_hidden;
+        self
+    }
+
+    pub fn is_dir(mut self, is_dir: bool) -> Self {
+        self.entry.is_dir = is_dir;
+        self
+    }
+
+    pub fn size(mut self, size: u64) -> Self {
+        self.entry.size = size;
+        self
+    }
+
+    pub fn depth(mut self, depth: u32) -> Self {
+        self.entry.depth = depth;
+        self
+    }
+
+    pub fn permissions(mut self, permissions: Option<UnixPermissions>) -> Self {
+        self.entry.permissions = permissions;
+        self
+    }
+
+    pub fn attributes(mut self, attributes: Option<u32>) -> Self {
+        self.entry.attributes = attributes;
+        self
+    }
+
+    /// Finish the entry, checking its invariants
+    ///
+    /// `name` must be the last component of `path` (empty when there is none),
+    /// and only directories may list children.
+    pub fn build(self) -> Result<DirEntry> {
+        let entry = self.entry;
+
+        let expected = entry.path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        if entry.name != expected {
+            bail!(
+                "DirEntry name '{}' does not match its path {} (expected '{}')",
+                entry.name,
+                entry.path.display(),
+                expected
+            );
+        }
+        if !entry.is_dir && !entry.children.is_empty() {
+            bail!("DirEntry {} is not a directory but lists {} children", entry.path.display(), entry.children.len());
+        }
+        Ok(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn test_builder_defaults() -> Result<()> {
+        let before = Utc::now();
+        let entry = DirEntry::builder("/data/docs").build()?;
+
+        assert_eq!(entry.path, Path::new("/data/docs"));
+        assert_eq!(entry.name, "docs");
+        assert!(entry.modified >= before && entry.modified <= Utc::now());
+        assert!(entry.is_dir);
+        assert!(entry.children.is_empty());
+        assert_eq!((entry.size, entry.depth, entry.content_hash), (0, 0, 0));
+        assert!(!entry.is_hidden);
+        assert!(entry.symlink_target.is_none() && entry.permissions.is_none() && entry.attributes.is_none());
+
+        assert_eq!(DirEntry::builder("/").build()?.name, "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder_setters() -> Result<()> {
+        let entry = DirEntry::builder("/data/link")
+            .is_dir(false)
+            .size(42)
+            .depth(1)
+            .hidden(true)
+            .symlink_target(Some(PathBuf::from("/data/docs")))
+            .attributes(Some(0x02))
+            .build()?;
+        assert!(!entry.is_dir);
+        assert_eq!(entry.size, 42);
+        assert_eq!(entry.depth, 1);
+        assert!(entry.is_hidden);
+        assert_eq!(entry.symlink_target.as_deref(), Some(Path::new("/data/docs")));
+        assert_eq!(entry.attributes, Some(0x02));
+
+        let dir = DirEntry::builder("/data")
+            .children(["b", "a"])
+            .content_hash(7)
+            .build()?;
+        assert_eq!(dir.children, vec!["b".to_string(), "a".to_string()]);
+        assert_eq!(dir.content_hash, 7);
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder_rejects_broken_invariants() {
+        let err = DirEntry::builder("/data/docs").name("other").build().unwrap_err();
+        assert!(err.to_string().contains("does not match its path"), "{err}");
+
+        let err = DirEntry::builder("/data/file.txt")
+            .is_dir(false)
+            .children(["nested"])
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("not a directory"), "{err}");
+    }
+}
@@ -1,24 +1,56 @@
-use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
-use std::hash::{Hash, Hasher};
 use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
-use colored::Colorize;
+use colored::{Color, Colorize};
+use ptree_core::{CacheFormat, HashAlgorithm, NameFilters};
+#[cfg(not(feature = "single-threaded"))]
 use rayon::slice::ParallelSliceMut;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 
-#[cfg(windows)]
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct USNJournalState;
+use crate::attributes::{format_attributes, UNKNOWN_ATTRIBUTES};
+use crate::collapse::CollapseRule;
+use crate::connectors::ConnectorSet;
+use crate::filter::EntryFilter;
+use crate::hashing::{ContentHasher, SelectedHasher};
+use crate::permissions::{has_execute_bit, UnixPermissions, UNKNOWN_LONG_PREFIX};
+use crate::scan_mode::ScanMode;
+use crate::template::{render_entry, render_entry_colored, EntryTemplate};
+use crate::theme::Theme;
+use crate::units::{format_age, format_size};
+
+/// USN journal position recorded when the cache was last written
+///
+/// Only persisted on Windows. All zero when no position was recorded.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct USNJournalState {
+    pub journal_id: u64,
+    pub next_usn:   i64,
+}
 
-#[cfg(not(windows))]
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct USNJournalState;
+/// Name colors cycled by depth with `--depth-colors` (the root is depth 0)
+const DEPTH_PALETTE: [Color; 6] = [
+    Color::Blue,
+    Color::Green,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Red,
+];
+
+/// Appended to tree names modified since the previous scan (`--since-scan`)
+pub const SINCE_SCAN_MARKER: &str = " [changed]";
+
+/// Appended to hidden directories the scan listed but never entered
+/// (`--exclude-hidden-dirs-from-scan`), whose empty contents mean nothing
+pub const NOT_SCANNED_MARKER: &str = " [not scanned]";
+
+/// Write buffer for streaming the data file in `save`
+pub const SAVE_BUFFER_SIZE: usize = 8 * 1024 * 1024;
 
 /// Directory metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +63,56 @@ pub struct DirEntry {
     pub symlink_target: Option<PathBuf>, // If this entry is a symlink, store target
     pub is_hidden:      bool,            // Whether the directory has hidden attribute
     pub is_dir:         bool,            // Whether this entry is a directory (vs file/symlink)
+    pub size:           u64,             // Size in bytes; directories hold their subtree total (see `compute_sizes`)
+    pub depth:          u32,             // Depth below the scan root (root = 0)
+    pub permissions:    Option<UnixPermissions>, // Mode and owner, captured on Unix with `--long`
+    pub attributes:     Option<u32>,     // Raw file attribute word (Windows only)
+}
+
+/// `modified` stamped on entries scanned with `--no-metadata`
+///
+/// Such entries were never stat'ed: their size is 0 and they carry no
+/// permissions or attributes, whatever is on disk.
+pub const NO_METADATA_MODIFIED: DateTime<Utc> = DateTime::<Utc>::UNIX_EPOCH;
+
+impl DirEntry {
+    /// Whether size and times came from the filesystem (false after `--no-metadata`)
+    pub fn has_metadata(&self) -> bool {
+        self.modified != NO_METADATA_MODIFIED
+    }
+}
+
+/// Depth of `path` below `root` in path components (0 for the root itself)
+///
+/// Paths outside `root` report depth 0.
+pub fn relative_depth(root: &Path, path: &Path) -> u32 {
+    path.strip_prefix(root)
+        .map(|rel| rel.components().count() as u32)
+        .unwrap_or(0)
+}
+
+/// `path` relative to `prefix` for display (`.` for the prefix itself), or
+/// `None` when `path` is not under `prefix`
+pub fn strip_path_prefix(path: &Path, prefix: &Path) -> Option<String> {
+    let rel = path.strip_prefix(prefix).ok()?;
+    Some(if rel.as_os_str().is_empty() {
+        ".".to_string()
+    } else {
+        rel.to_string_lossy().to_string()
+    })
+}
+
+/// `name` cut to `max` characters, the last one an ellipsis, when it is longer
+///
+/// Counts `char`s, so multibyte characters are never split.
+pub fn truncate_name(name: &str, max: usize) -> Cow<'_, str> {
+    match name.char_indices().nth(max) {
+        Some(_) => {
+            let keep = name.char_indices().nth(max.saturating_sub(1)).map_or(0, |(i, _)| i);
+            Cow::Owned(format!("{}…", &name[..keep]))
+        }
+        None => Cow::Borrowed(name),
+    }
 }
 
 /// Compute Merkle tree-style content hash for a directory
@@ -54,23 +136,36 @@ pub fn compute_content_hash(
     children: &[String],
     child_hashes: &HashMap<PathBuf, u64>,
 ) -> u64 {
-    let mut hasher = DefaultHasher::new();
+    compute_content_hash_with(HashAlgorithm::default(), path, modified, children, child_hashes)
+}
+
+/// Compute the content hash (see `compute_content_hash`) using a specific algorithm
+pub fn compute_content_hash_with(
+    algo: HashAlgorithm,
+    path: &Path,
+    modified: DateTime<Utc>,
+    children: &[String],
+    child_hashes: &HashMap<PathBuf, u64>,
+) -> u64 {
+    let mut hasher = SelectedHasher::new(algo);
 
     // 1. Hash directory path (normalized)
     let normalized_path = path.to_string_lossy().to_lowercase();
-    normalized_path.hash(&mut hasher);
+    hasher.update(normalized_path.as_bytes());
+    hasher.update(&[0]);
 
     // 2. Hash modification timestamp (as i64)
-    modified.timestamp().hash(&mut hasher);
+    hasher.update(&modified.timestamp().to_le_bytes());
 
     // 3. Hash children count
-    children.len().hash(&mut hasher);
+    hasher.update(&(children.len() as u64).to_le_bytes());
 
-    // 4. Hash sorted child names
+    // 4. Hash sorted child names (NUL-separated so ["ab"] != ["a", "b"])
     let mut sorted_children = children.to_vec();
     sorted_children.sort();
     for child_name in &sorted_children {
-        child_name.hash(&mut hasher);
+        hasher.update(child_name.as_bytes());
+        hasher.update(&[0]);
     }
 
     // 5. Hash sorted child hashes (Merkle tree propagation)
@@ -91,10 +186,10 @@ pub fn compute_content_hash(
 
     child_hashes_list.sort_by(|a, b| a.0.cmp(&b.0));
     for (_, hash) in child_hashes_list {
-        hash.hash(&mut hasher);
+        hasher.update(&hash.to_le_bytes());
     }
 
-    hasher.finish()
+    hasher.finalize()
 }
 
 /// Check if a directory has changed by comparing content hashes
@@ -128,6 +223,12 @@ pub struct DiskCache {
     /// Last scanned directory (for subsequent runs to only scan current dir)
     pub last_scanned_root: PathBuf,
 
+    /// Algorithm that produced every `content_hash` in this cache
+    pub hash_algo: HashAlgorithm,
+
+    /// Scan settings the entries were produced with
+    pub scan_mode: ScanMode,
+
     /// USN Journal state for tracking changes (Windows only)
     #[cfg(windows)]
     pub usn_state: USNJournalState,
@@ -140,10 +241,125 @@ pub struct DiskCache {
     #[serde(skip)]
     pub flush_threshold: usize,
 
+    /// Number of non-empty pending-write flushes performed
+    #[serde(skip)]
+    pub flush_count: usize,
+
+    /// Free space (bytes) that must remain on the volume after a save
+    #[serde(skip)]
+    pub min_free_space: u64,
+
+    /// Record format used by `save` (`--cache-format`); set from the data file header on load
+    #[serde(skip)]
+    pub cache_format: CacheFormat,
+
     /// Whether to show hidden file attributes in output
     #[serde(skip)]
     pub show_hidden: bool,
 
+    /// Connector glyphs for tree output (`--tree-style`)
+    #[serde(skip)]
+    pub connectors: ConnectorSet,
+
+    /// Optional per-node template replacing the plain name in tree output
+    #[serde(skip)]
+    pub entry_template: Option<EntryTemplate>,
+
+    /// Render only entries matching this expression and their ancestors (`--entries-filter`)
+    #[serde(skip)]
+    pub entry_filter: Option<EntryFilter>,
+
+    /// Render only directories that directly hold a file, and their ancestors (`--only-dirs-with-files`)
+    #[serde(skip)]
+    pub only_dirs_with_files: bool,
+
+    /// Leave zero-byte files out of rendered output (`--exclude-empty-files`)
+    #[serde(skip)]
+    pub exclude_empty_files: bool,
+
+    /// Leave entries named `.*` out of rendered output (`--exclude-dotfiles`)
+    #[serde(skip)]
+    pub exclude_dotfiles: bool,
+
+    /// `--include` / `--exclude` globs applied to rendered entries
+    #[serde(skip)]
+    pub name_filters: NameFilters,
+
+    /// Leave out directories with no file shown anywhere below them (`--prune-empty`)
+    #[serde(skip)]
+    pub prune_empty: bool,
+
+    /// Render only this many randomly drawn directories and their ancestors (`--sample`)
+    #[serde(skip)]
+    pub sample: Option<usize>,
+
+    /// Seed for `sample` (`--seed`)
+    #[serde(skip)]
+    pub sample_seed: u64,
+
+    /// Prefix tree lines with permissions and ownership (`--long`)
+    #[serde(skip)]
+    pub long_listing: bool,
+
+    /// Prefix tree lines with Windows attribute flags (`--attributes`)
+    #[serde(skip)]
+    pub show_attributes: bool,
+
+    /// Color names in colored tree output by depth instead of by type (`--depth-colors`)
+    #[serde(skip)]
+    pub depth_colors: bool,
+
+    /// Text for the root line and JSON root `name` instead of the root path (`--render-root-name`)
+    #[serde(skip)]
+    pub root_label: Option<String>,
+
+    /// Label the root with its basename when no `root_label` is set (`--root-basename`)
+    #[serde(skip)]
+    pub root_basename: bool,
+
+    /// Prefix removed from rendered paths (`--strip-prefix`)
+    #[serde(skip)]
+    pub strip_prefix: Option<PathBuf>,
+
+    /// Fail instead of printing an absolute path outside `strip_prefix` (`--strict`)
+    #[serde(skip)]
+    pub strip_prefix_strict: bool,
+
+    /// Colors for colored tree output (`--theme`)
+    #[serde(skip)]
+    pub theme: Theme,
+
+    /// Append a type indicator (`/`, `@`, `*`) to tree names (`--classify`)
+    #[serde(skip)]
+    pub classify: bool,
+
+    /// Cut longer tree names to this many characters (`--max-name-length`)
+    #[serde(skip)]
+    pub max_name_length: Option<usize>,
+
+    /// `last_scan` as it was before the scan this run performed; `None` when
+    /// the cache was used as-is or there was no earlier snapshot
+    #[serde(skip)]
+    pub previous_scan: Option<DateTime<Utc>>,
+
+    /// Mark tree entries modified after this time (`--since-scan`)
+    #[serde(skip)]
+    pub mark_since: Option<DateTime<Utc>>,
+
+    /// Summarize directories with many children on one line (`--collapse-threshold`)
+    #[serde(skip)]
+    pub collapse: Option<CollapseRule>,
+
+    /// Show each entry's size in tree lines and JSON (`--size`)
+    ///
+    /// JSON nodes also get `modified` with it; there is no separate flag.
+    #[serde(skip)]
+    pub show_sizes: bool,
+
+    /// Sizes shown by `show_sizes` in SI units (`--si`)
+    #[serde(skip)]
+    pub si_sizes: bool,
+
     /// Skip statistics: count of skipped directories by name
     #[serde(skip)]
     pub skip_stats: std::collections::HashMap<String, usize>,
@@ -185,6 +401,27 @@ impl DiskCache {
         Ok(Self::new_empty())
     }
 
+    /// Open an existing cache for inspection only
+    ///
+    /// Never creates, rewrites or deletes anything on disk. A missing or corrupt
+    /// cache is reported as an error instead of silently falling back to an empty
+    /// cache. All entries are loaded eagerly so callers can inspect them directly.
+    pub fn open_readonly(path: &Path) -> Result<Self> {
+        use crate::cache_rkyv::RkyvMmapCache;
+
+        let index_path = path.with_extension("idx");
+        let data_path = path.with_extension("dat");
+
+        let rkyv_cache = RkyvMmapCache::open_readonly(&index_path, &data_path)?;
+        let entries = rkyv_cache
+            .get_all()
+            .map_err(|e| anyhow!("Corrupt cache data {}: {}", data_path.display(), e))?;
+
+        let mut cache = Self::from_index(&rkyv_cache);
+        cache.entries = entries;
+        Ok(cache)
+    }
+
     /// Load from lazy cache format - index only (fast cold start)
     /// Entries not loaded until output phase to minimize startup time
     fn load_from_lazy_cache(index_path: &Path, data_path: &Path) -> Result<Self> {
@@ -194,26 +431,63 @@ impl DiskCache {
 
         // DO NOT load all entries - keep HashMap empty for cold-start speed
         // Entries will be loaded on-demand during output formatting
+        Ok(Self::from_index(&rkyv_cache))
+    }
 
-        Ok(DiskCache {
+    /// Build a cache from persisted index metadata, with no entries loaded
+    fn from_index(rkyv_cache: &crate::cache_rkyv::RkyvMmapCache) -> Self {
+        DiskCache {
             entries:                   HashMap::new(), // Empty - entries loaded on-demand
             last_scan:                 rkyv_cache.index.last_scan,
             root:                      rkyv_cache.index.root.clone(),
             last_scanned_root:         rkyv_cache.index.last_scanned_root.clone(),
+            hash_algo:                 rkyv_cache.index.hash_algo,
+            scan_mode:                 rkyv_cache.index.scan_mode,
             #[cfg(windows)]
             usn_state:                 rkyv_cache.index.usn_state.clone(),
             pending_writes:            Vec::new(),
             flush_threshold:           5000,
+            flush_count:               0,
+            min_free_space:            0,
+            cache_format:              rkyv_cache.format,
             show_hidden:               false,
+            connectors:                ConnectorSet::default(),
+            entry_template:            None,
+            entry_filter:              None,
+            only_dirs_with_files:      false,
+            exclude_empty_files:       false,
+            exclude_dotfiles:          false,
+            name_filters:              NameFilters::default(),
+            prune_empty:               false,
+            sample:                    None,
+            sample_seed:               0,
+            long_listing:              false,
+            show_attributes:           false,
+            depth_colors:              false,
+            root_label:                None,
+            root_basename:             false,
+            strip_prefix:              None,
+            strip_prefix_strict:       false,
+            theme:                     Theme::default(),
+            classify:                  false,
+            max_name_length:           None,
+            previous_scan:             None,
+            mark_since:                None,
+            collapse:                  None,
+            show_sizes:                false,
+            si_sizes:                  false,
             skip_stats:                rkyv_cache.index.skip_stats.clone(),
             has_persisted_snapshot:    true,
             persisted_entry_count:     rkyv_cache.index.offsets.len(),
-        })
+        }
     }
 
     /// Create a new empty cache with default USN state
+    ///
+    /// Public for caches that are never opened from a file: the library scan
+    /// API, `--self-test` and baselines build their results on one.
     #[cfg(windows)]
-    fn new_empty() -> Self {
+    pub fn new_empty() -> Self {
         DiskCache {
             // Pre-allocate for typical disk with ~100k directories
             // Reduces reallocation overhead during traversal
@@ -221,10 +495,40 @@ impl DiskCache {
             last_scan:              Utc::now(),
             root:                   PathBuf::new(),
             last_scanned_root:      PathBuf::new(),
+            hash_algo:              HashAlgorithm::default(),
+            scan_mode:              ScanMode::default(),
             usn_state:              USNJournalState::default(),
             pending_writes:         Vec::with_capacity(5000),
             flush_threshold:        5000,
+            flush_count:            0,
+            min_free_space:         0,
+            cache_format:           CacheFormat::default(),
             show_hidden:            false,
+            connectors:             ConnectorSet::default(),
+            entry_template:         None,
+            entry_filter:           None,
+            only_dirs_with_files:   false,
+            exclude_empty_files:    false,
+            exclude_dotfiles:       false,
+            name_filters:           NameFilters::default(),
+            prune_empty:            false,
+            sample:                 None,
+            sample_seed:            0,
+            long_listing:           false,
+            show_attributes:        false,
+            depth_colors:           false,
+            root_label:             None,
+            root_basename:          false,
+            strip_prefix:           None,
+            strip_prefix_strict:    false,
+            theme:                  Theme::default(),
+            classify:               false,
+            max_name_length:        None,
+            previous_scan:          None,
+            mark_since:             None,
+            collapse:               None,
+            show_sizes:             false,
+            si_sizes:               false,
             skip_stats:             HashMap::new(),
             has_persisted_snapshot: false,
             persisted_entry_count:  0,
@@ -232,8 +536,10 @@ impl DiskCache {
     }
 
     /// Create a new empty cache with default USN state (non-Windows)
+    ///
+    /// Public for the same reason as the Windows variant.
     #[cfg(not(windows))]
-    fn new_empty() -> Self {
+    pub fn new_empty() -> Self {
         DiskCache {
             // Pre-allocate for typical disk with ~100k directories
             // Reduces reallocation overhead during traversal
@@ -241,28 +547,75 @@ impl DiskCache {
             last_scan:              Utc::now(),
             root:                   PathBuf::new(),
             last_scanned_root:      PathBuf::new(),
+            hash_algo:              HashAlgorithm::default(),
+            scan_mode:              ScanMode::default(),
             pending_writes:         Vec::with_capacity(5000),
             flush_threshold:        5000,
+            flush_count:            0,
+            min_free_space:         0,
+            cache_format:           CacheFormat::default(),
             show_hidden:            false,
+            connectors:             ConnectorSet::default(),
+            entry_template:         None,
+            entry_filter:           None,
+            only_dirs_with_files:   false,
+            exclude_empty_files:    false,
+            exclude_dotfiles:       false,
+            name_filters:           NameFilters::default(),
+            prune_empty:            false,
+            sample:                 None,
+            sample_seed:            0,
+            long_listing:           false,
+            show_attributes:        false,
+            depth_colors:           false,
+            root_label:             None,
+            root_basename:          false,
+            strip_prefix:           None,
+            strip_prefix_strict:    false,
+            theme:                  Theme::default(),
+            classify:               false,
+            max_name_length:        None,
+            previous_scan:          None,
+            mark_since:             None,
+            collapse:               None,
+            show_sizes:             false,
+            si_sizes:               false,
             skip_stats:             HashMap::new(),
             has_persisted_snapshot: false,
             persisted_entry_count:  0,
         }
     }
 
-    /// Save cache using rkyv mmap format (index + data files with O(1) access)
+    /// Save cache as index + data files with O(1) access, in `cache_format`
+    #[tracing::instrument(skip_all, fields(path = %path.display(), entries = self.entries.len()))]
     pub fn save(&mut self, path: &Path) -> Result<()> {
         self.flush_pending_writes();
-        self.has_persisted_snapshot = true;
-        self.persisted_entry_count = self.entries.len();
 
         let index_path = path.with_extension("idx");
         let data_path = path.with_extension("dat");
 
+        fs::create_dir_all(index_path.parent().unwrap())?;
+        self.ensure_space_for_save(&index_path, &data_path)?;
+
+        self.has_persisted_snapshot = true;
+        self.persisted_entry_count = self.entries.len();
+
         self.save_as_rkyv_mmap(&index_path, &data_path)?;
         Ok(())
     }
 
+    /// `--cache-max-age-warn` message when the last scan is more than `max_age` seconds old
+    pub fn stale_cache_warning(&self, max_age: u64) -> Option<String> {
+        let age = u64::try_from((Utc::now() - self.last_scan).num_seconds()).unwrap_or(0);
+        (age > max_age).then(|| {
+            format!(
+                "warning: cache is {} old (last scan {}); the tree may be outdated, run with --force to rescan",
+                format_age(age),
+                self.last_scan.format("%Y-%m-%d %H:%M UTC")
+            )
+        })
+    }
+
     /// True if we have an existing on-disk cache snapshot.
     pub fn has_cache_snapshot(&self) -> bool {
         self.has_persisted_snapshot
@@ -277,60 +630,60 @@ impl DiskCache {
         }
     }
 
-    /// Save cache in mmap format (index + data files with bincode serialization)
+    /// Save cache in mmap format (bincode index + data file in `cache_format`)
+    ///
+    /// Both files are streamed record by record through buffered writers into
+    /// temporary siblings, then renamed over the previous cache, so peak memory
+    /// stays at one offset per entry and a failed save leaves the old cache
+    /// intact. The data file starts with a header naming its format, which is
+    /// how `RkyvMmapCache` picks the record reader.
     fn save_as_rkyv_mmap(&self, index_path: &Path, data_path: &Path) -> Result<()> {
-        use crate::cache_rkyv::{RkyvCacheIndex, RkyvDirEntry};
+        use crate::cache_rkyv::{data_header, write_record, RkyvCacheIndexRef, RkyvDirEntryRef, DATA_HEADER_LEN};
 
         fs::create_dir_all(index_path.parent().unwrap())?;
 
-        // Build index with byte offsets
-        let mut rkyv_index = RkyvCacheIndex::new();
-        rkyv_index.offsets = HashMap::with_capacity(self.entries.len());
-        rkyv_index.root = self.root.clone();
-        rkyv_index.last_scanned_root = self.last_scanned_root.clone();
-        rkyv_index.last_scan = self.last_scan;
-        rkyv_index.skip_stats = self.skip_stats.clone();
-        #[cfg(windows)]
-        {
-            rkyv_index.usn_state = self.usn_state.clone();
-        }
+        let data_temp = data_path.with_extension("dat.tmp");
+        let index_temp = index_path.with_extension("tmp");
 
-        let data_file = File::create(data_path)?;
-        let mut data_file = BufWriter::with_capacity(8 * 1024 * 1024, data_file);
-        let mut offset: u64 = 0;
+        // Stream entries, recording where each record starts
+        let mut offsets = Vec::with_capacity(self.entries.len());
+        let mut data_file = BufWriter::with_capacity(SAVE_BUFFER_SIZE, File::create(&data_temp)?);
+        data_file.write_all(&data_header(self.cache_format))?;
+        let mut offset = DATA_HEADER_LEN;
 
         for (path, entry) in &self.entries {
-            let rkyv_entry = RkyvDirEntry {
-                path:           entry.path.clone(),
-                name:           entry.name.clone(),
-                modified:       entry.modified,
-                content_hash:   entry.content_hash,
-                children:       entry.children.clone(),
-                symlink_target: entry.symlink_target.clone(),
-                is_hidden:      entry.is_hidden,
-                is_dir:         entry.is_dir,
-            };
-
-            let serialized = bincode::serialize(&rkyv_entry)?;
-            let len = serialized.len() as u32;
-
-            rkyv_index.offsets.insert(path.clone(), offset);
-            data_file.write_all(&len.to_le_bytes())?;
-            data_file.write_all(&serialized)?;
-            offset += 4 + len as u64;
+            offsets.push((path.as_path(), offset));
+            offset += write_record(&mut data_file, self.cache_format, &RkyvDirEntryRef::from(entry))?;
+        }
+        data_file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+
+        // Stream index
+        let index = RkyvCacheIndexRef {
+            offsets:                   &offsets,
+            last_scan:                 self.last_scan,
+            root:                      &self.root,
+            last_scanned_root:         &self.last_scanned_root,
+            hash_algo:                 self.hash_algo,
+            #[cfg(windows)]
+            usn_state:                 &self.usn_state,
+            skip_stats:                &self.skip_stats,
+            scan_mode:                 self.scan_mode,
+        };
+        let mut index_file = BufWriter::new(File::create(&index_temp)?);
+        bincode::serialize_into(&mut index_file, &index)?;
+        index_file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+
+        // Drop the old index first: a crash between the renames then leaves no
+        // index (a fresh scan) rather than old offsets into the new data file
+        match fs::remove_file(index_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
         }
-        data_file.flush()?;
-        drop(data_file);
+        fs::rename(&data_temp, data_path)?;
+        fs::rename(&index_temp, index_path)?;
 
-        // Save index
-        let index_serialized = bincode::serialize(&rkyv_index)?;
-        let temp_path = index_path.with_extension("tmp");
-        let index_file = File::create(&temp_path)?;
-        let mut index_file = BufWriter::new(index_file);
-        index_file.write_all(&index_serialized)?;
-        index_file.flush()?;
-        drop(index_file);
-        fs::rename(&temp_path, index_path)?;
+        // The renames only survive a crash once the directory itself is flushed
+        sync_dir(index_path.parent().unwrap())?;
 
         Ok(())
     }
@@ -343,13 +696,19 @@ impl DiskCache {
     pub fn buffer_entry(&mut self, path: PathBuf, entry: DirEntry) {
         self.pending_writes.push((path, entry));
 
-        if self.pending_writes.len() >= self.flush_threshold {
+        // A threshold of 0 behaves like 1: flush on every entry
+        if self.pending_writes.len() >= self.flush_threshold.max(1) {
             self.flush_pending_writes();
         }
     }
 
     /// Flush all buffered writes to main cache HashMap
     pub fn flush_pending_writes(&mut self) {
+        if self.pending_writes.is_empty() {
+            return;
+        }
+        self.flush_count += 1;
+
         for (path, entry) in self.pending_writes.drain(..) {
             self.entries.insert(path, entry);
         }
@@ -381,6 +740,10 @@ impl DiskCache {
                         symlink_target: rkyv_entry.symlink_target,
                         is_hidden:      rkyv_entry.is_hidden,
                         is_dir:         rkyv_entry.is_dir,
+                        size:           rkyv_entry.size,
+                        depth:          rkyv_entry.depth,
+                        permissions:    rkyv_entry.permissions,
+                        attributes:     rkyv_entry.attributes,
                     };
                     self.entries.insert(path.clone(), entry);
                 }
@@ -405,9 +768,7 @@ impl DiskCache {
         let lazy_entries = rkyv_cache.get_all()?;
 
         for (path, entry) in lazy_entries {
-            if !self.entries.contains_key(&path) {
-                self.entries.insert(path, entry);
-            }
+            self.entries.entry(path).or_insert(entry);
         }
 
         Ok(())
@@ -423,6 +784,88 @@ impl DiskCache {
         self.entries.get(path)
     }
 
+    /// Entries for the children of the directory at `path`, sorted by name
+    ///
+    /// Names with no loaded entry (files in a `--structure-only` cache, or a
+    /// partially loaded cache) are skipped; empty when `path` isn't cached.
+    pub fn children_entries(&self, path: &Path) -> Vec<&DirEntry> {
+        let Some(entry) = self.get_entry(path) else {
+            return Vec::new();
+        };
+        let mut children: Vec<&DirEntry> = entry
+            .children
+            .iter()
+            .filter_map(|name| self.get_entry(&path.join(name)))
+            .collect();
+        children.sort_by(|a, b| a.name.cmp(&b.name));
+        children
+    }
+
+    /// All loaded entries exactly `depth` levels below the root, sorted by path
+    pub fn entries_at_depth(&self, depth: u32) -> Vec<&DirEntry> {
+        let mut entries: Vec<&DirEntry> = self.entries.values().filter(|e| e.depth == depth).collect();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        entries
+    }
+
+    /// Deepest level present among loaded entries (0 when empty)
+    pub fn max_entry_depth(&self) -> u32 {
+        self.entries.values().map(|e| e.depth).max().unwrap_or(0)
+    }
+
+    // ============================================================================
+    // Collection Access
+    // ============================================================================
+
+    /// Get entry by path (alias of `get_entry`)
+    pub fn get(&self, path: &Path) -> Option<&DirEntry> {
+        self.entries.get(path)
+    }
+
+    /// True if an entry for `path` is loaded
+    pub fn contains(&self, path: &Path) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    /// Number of loaded entries (pending writes are not counted until flushed)
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// True if no entries are loaded
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over loaded entries in arbitrary order
+    pub fn iter(&self) -> std::collections::hash_map::Iter<'_, PathBuf, DirEntry> {
+        self.entries.iter()
+    }
+
+    /// Whether child `name` of `parent` is rendered
+    ///
+    /// It must survive the entry filter (always, without one) and the name
+    /// filters, and hidden entries only show with `show_hidden`.
+    pub(crate) fn shows_child(&self, visible: Option<&HashSet<PathBuf>>, parent: &Path, name: &str) -> bool {
+        let path = parent.join(name);
+        visible.is_none_or(|v| v.contains(&path))
+            && (self.show_hidden || !self.get_entry(&path).is_some_and(|e| e.is_hidden))
+            && self.passes_name_filters(&path, name)
+    }
+
+    /// Whether `--exclude-dotfiles`, `--exclude` and `--include` keep the entry `name` at `path`
+    ///
+    /// The rules a `--no-cache` scan applies while listing; cached scans keep
+    /// every entry so runs with other filters can share them.
+    pub(crate) fn passes_name_filters(&self, path: &Path, name: &str) -> bool {
+        if self.exclude_dotfiles && name.starts_with('.') {
+            return false;
+        }
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        !self.name_filters.excludes(name, relative)
+            && (self.get_entry(path).is_some_and(|e| e.is_dir) || self.name_filters.includes(name, relative))
+    }
+
     /// Format a directory name with optional hidden indicator
     pub fn format_name(&self, name: &str, path: &Path, show_hidden: bool) -> String {
         if !show_hidden {
@@ -468,10 +911,128 @@ impl DiskCache {
         self.entries.retain(|k, _| !(k == path || k.starts_with(path)));
     }
 
+    /// Drop cached children of `dir` that are missing from its fresh `children` list
+    ///
+    /// Compares against the currently cached entry for `dir` (if any) and removes
+    /// each vanished child together with its descendants. Returns the number of
+    /// children removed.
+    pub fn reconcile_children(&mut self, dir: &Path, children: &[String]) -> usize {
+        let Some(old) = self.entries.get(dir) else {
+            return 0;
+        };
+
+        let current: HashSet<&String> = children.iter().collect();
+        let vanished: Vec<PathBuf> = old
+            .children
+            .iter()
+            .filter(|name| !current.contains(name))
+            .map(|name| dir.join(name))
+            .collect();
+
+        for path in &vanished {
+            self.remove_entry(path);
+        }
+        vanished.len()
+    }
+
+    /// Move the cached subtree at `from` to `to` without touching the disk
+    ///
+    /// Re-keys `from` and every descendant under the `to` prefix, fixing each
+    /// entry's `path` and `depth`, the moved entry's `name`, and the child
+    /// lists of both parents. Anything cached at `to` is replaced, as a rename
+    /// over it would. Returns the number of entries moved (0 when `from` isn't
+    /// cached).
+    pub fn rename_subtree(&mut self, from: &Path, to: &Path) -> Result<usize> {
+        if to.starts_with(from) {
+            if to == from {
+                return Ok(0);
+            }
+            anyhow::bail!("Cannot move {} into itself ({})", from.display(), to.display());
+        }
+        let Some(new_name) = to.file_name().map(|n| n.to_string_lossy().to_string()) else {
+            anyhow::bail!("Cannot move {} to {}: the target has no name", from.display(), to.display());
+        };
+
+        self.flush_pending_writes();
+        if !self.entries.contains_key(from) {
+            return Ok(0);
+        }
+        self.remove_entry(to);
+
+        let moved: Vec<PathBuf> = self.entries.keys().filter(|k| k.starts_with(from)).cloned().collect();
+        for old_path in &moved {
+            let Some(mut entry) = self.entries.remove(old_path) else {
+                continue;
+            };
+            let new_path = match old_path.strip_prefix(from) {
+                Ok(rel) if !rel.as_os_str().is_empty() => to.join(rel),
+                _ => to.to_path_buf(),
+            };
+            if new_path == to {
+                entry.name = new_name.clone();
+            }
+            entry.depth = relative_depth(&self.root, &new_path);
+            entry.path = new_path.clone();
+            self.entries.insert(new_path, entry);
+        }
+
+        let old_name = from.file_name().map(|n| n.to_string_lossy().to_string());
+        if let Some(parent) = from.parent().and_then(|p| self.entries.get_mut(p)) {
+            parent.children.retain(|c| Some(c) != old_name.as_ref());
+        }
+        if let Some(parent) = to.parent().and_then(|p| self.entries.get_mut(p)) {
+            if !parent.children.contains(&new_name) {
+                parent.children.push(new_name);
+            }
+        }
+        Ok(moved.len())
+    }
+
     // ============================================================================
     // ASCII Tree Output
     // ============================================================================
 
+    /// Custom label for a rendered `root`: `root_label`, or its basename with
+    /// `root_basename`; `None` keeps the plain path
+    pub fn root_name(&self, root: &Path) -> Option<String> {
+        // Every renderer labels its root first, so this counts renders for tests
+        #[cfg(any(test, feature = "fixtures"))]
+        crate::fixtures::note_render();
+
+        if let Some(label) = &self.root_label {
+            return Some(label.clone());
+        }
+        // A filesystem root like `/` has no basename and keeps its path
+        self.root_basename
+            .then(|| root.file_name().map(|name| name.to_string_lossy().to_string()))
+            .flatten()
+    }
+
+    /// First line of tree output for `root`
+    pub fn root_line(&self, root: &Path) -> Result<String> {
+        match self.root_name(root) {
+            Some(name) => Ok(name),
+            None => self.display_path(root),
+        }
+    }
+
+    /// `path` as rendered, with `strip_prefix` removed when it applies
+    ///
+    /// Paths outside the prefix are kept absolute, or rejected when
+    /// `strip_prefix_strict` is set.
+    pub fn display_path(&self, path: &Path) -> Result<String> {
+        let Some(prefix) = &self.strip_prefix else {
+            return Ok(path.to_string_lossy().to_string());
+        };
+        match strip_path_prefix(path, prefix) {
+            Some(stripped) => Ok(stripped),
+            None if self.strip_prefix_strict => {
+                Err(anyhow!("{} is not under --strip-prefix {}", path.display(), prefix.display()))
+            }
+            None => Ok(path.to_string_lossy().to_string()),
+        }
+    }
+
     /// Build ASCII tree output with optional max depth
     pub fn build_tree_output(&self) -> Result<String> {
         self.build_tree_output_with_depth(None)
@@ -486,10 +1047,11 @@ impl DiskCache {
         }
 
         let root = &self.root;
-        output.push_str(&format!("{}\n", root.display()));
+        output.push_str(&format!("{}\n", self.root_line(root)?));
 
         // No need for visited set - filesystem is acyclic and in_progress set prevents cycles during traversal
-        self.print_tree(&mut output, root, "", true, 0, max_depth)?;
+        let visible = self.filter_visible(root);
+        self.print_tree(&mut output, root, "", 0, max_depth, visible.as_ref())?;
 
         Ok(output)
     }
@@ -499,9 +1061,9 @@ impl DiskCache {
         output: &mut String,
         path: &Path,
         prefix: &str,
-        is_last: bool,
         current_depth: usize,
         max_depth: Option<usize>,
+        visible: Option<&HashSet<PathBuf>>,
     ) -> Result<()> {
         // Check depth limit
         if let Some(max) = max_depth {
@@ -512,40 +1074,24 @@ impl DiskCache {
 
         if let Some(entry) = self.get_entry(path) {
             // Sort children only at output time (not during traversal)
-            let mut children: Vec<_> = entry.children.iter().collect();
+            let mut children: Vec<_> = entry
+                .children
+                .iter()
+                .filter(|c| self.shows_child(visible, path, c))
+                .collect();
             children.sort();
 
             for (i, child_name) in children.iter().enumerate() {
                 let is_last_child = i == children.len() - 1;
-                let child_prefix = if is_last {
-                    "    ".to_string()
-                } else {
-                    "│   ".to_string()
-                };
-
-                let branch = if is_last_child { "└── " } else { "├── " };
-
-                // Check if this child is a symlink
-                let child_path = path.join(child_name);
-                let display_name = if let Some(entry) = self.get_entry(&child_path) {
-                    let base_name = if let Some(target) = &entry.symlink_target {
-                        format!("{} (→ {})", child_name, target.display())
-                    } else {
-                        self.format_name(child_name, &child_path, self.show_hidden)
-                    };
-                    base_name
-                } else {
-                    child_name.to_string()
-                };
-
-                output.push_str(&format!("{}{}{}\n", prefix, branch, display_name));
-                self.print_tree(
+                self.print_tree_child(
                     output,
-                    &child_path,
-                    &format!("{}{}", prefix, child_prefix),
+                    path,
+                    child_name,
+                    prefix,
                     is_last_child,
-                    current_depth + 1,
+                    current_depth,
                     max_depth,
+                    visible,
                 )?;
             }
         }
@@ -553,129 +1099,215 @@ impl DiskCache {
         Ok(())
     }
 
-    // ============================================================================
-    // Colored Tree Output
-    // ============================================================================
-
-    /// Build colored tree output
-    pub fn build_colored_tree_output(&self) -> Result<String> {
-        self.build_colored_tree_output_with_depth(None)
-    }
-
-    /// Build colored tree output with optional max depth limit
-    pub fn build_colored_tree_output_with_depth(&self, max_depth: Option<usize>) -> Result<String> {
-        let mut output = String::new();
-
-        if self.entries.is_empty() {
-            return Ok("(empty)\n".to_string());
-        }
-
-        let root = &self.root;
-        output.push_str(&format!("{}\n", root.display().to_string().blue().bold()));
-
-        // No need for visited set - filesystem is acyclic and in_progress set prevents cycles during traversal
-        self.print_colored_tree(&mut output, root, "", true, 0, max_depth)?;
-
-        Ok(output)
-    }
-
-    fn print_colored_tree(
+    /// Render one child line of `path` followed by its subtree
+    #[allow(clippy::too_many_arguments)]
+    fn print_tree_child(
         &self,
         output: &mut String,
         path: &Path,
+        child_name: &str,
         prefix: &str,
-        is_last: bool,
+        is_last_child: bool,
         current_depth: usize,
         max_depth: Option<usize>,
+        visible: Option<&HashSet<PathBuf>>,
     ) -> Result<()> {
-        // Check depth limit
-        if let Some(max) = max_depth {
-            if current_depth >= max {
-                return Ok(());
-            }
-        }
-
-        if let Some(entry) = self.get_entry(path) {
-            // Sort children only at output time (not during traversal)
-            // Use parallel sort for large directories (>500 children)
-            let mut children: Vec<_> = entry.children.iter().collect();
-            if children.len() > 500 {
-                children.par_sort();
-            } else {
-                children.sort();
-            }
+        let child_prefix = self.connectors.continuation(is_last_child);
+        let branch = self.connectors.connector(is_last_child);
 
-            for (i, child_name) in children.iter().enumerate() {
-                let is_last_child = i == children.len() - 1;
-                let child_prefix = if is_last {
-                    "    ".to_string()
-                } else {
-                    "│   ".to_string()
-                };
+        let child_path = path.join(child_name);
+        let display_name = self.display_name(child_name, &child_path);
 
-                let branch = if is_last_child { "└── " } else { "├── " };
-                let branch_colored = branch.cyan().to_string();
+        output.push_str(&format!("{}{}{}{}\n", self.line_prefix(&child_path), prefix, branch, display_name));
+        if self.get_entry(&child_path).is_some_and(|e| self.is_collapsed(e)) {
+            return Ok(());
+        }
+        self.print_tree(
+            output,
+            &child_path,
+            &format!("{}{}", prefix, child_prefix),
+            current_depth + 1,
+            max_depth,
+            visible,
+        )
+    }
 
-                // Check if this child is a symlink
-                let child_path = path.join(child_name);
-                let display_name = if let Some(entry) = self.get_entry(&child_path) {
-                    let base_name = if let Some(target) = &entry.symlink_target {
-                        format!("{} (→ {})", child_name, target.display())
-                    } else {
-                        self.format_name(child_name, &child_path, self.show_hidden)
-                    };
-                    base_name.bright_blue().to_string()
-                } else {
-                    child_name.bright_blue().to_string()
-                };
+    /// Label of a child line: the entry template, `name -> target` for symlinks,
+    /// or the name, with the `--classify` indicator after the name, then the
+    /// `--collapse-threshold` summary and the `--since-scan` marker
+    ///
+    /// Outside a template the name is cut to `max_name_length` first.
+    fn display_name(&self, child_name: &str, child_path: &Path) -> String {
+        let child_name = match self.max_name_length {
+            Some(max) => truncate_name(child_name, max),
+            None => Cow::Borrowed(child_name),
+        };
+        let Some(entry) = self.get_entry(child_path) else {
+            return child_name.into_owned();
+        };
+        let summary = self.summary_suffix(entry);
+        if let Some(template) = &self.entry_template {
+            return format!(
+                "{}{}{}{}",
+                render_entry(entry, template),
+                summary,
+                self.not_scanned_marker(entry),
+                self.since_marker(entry)
+            );
+        }
 
-                output.push_str(&format!("{}{}{}\n", prefix, branch_colored, display_name));
-                self.print_colored_tree(
-                    output,
-                    &child_path,
-                    &format!("{}{}", prefix, child_prefix),
-                    is_last_child,
-                    current_depth + 1,
-                    max_depth,
-                )?;
-            }
+        let mut name = format!("{}{}", child_name, self.classify_suffix(entry));
+        // A collapsed directory reads as one even without --classify
+        if self.is_collapsed(entry) && !self.classify {
+            name.push('/');
         }
+        let label = match &entry.symlink_target {
+            Some(target) => format!("{} -> {}", name, target.display()),
+            None => self.format_name(&name, child_path, self.show_hidden),
+        };
+        format!("{}{}{}{}", label, summary, self.not_scanned_marker(entry), self.since_marker(entry))
+    }
 
-        Ok(())
+    /// `--collapse-threshold` summary for `entry`, else its `--size`, else nothing
+    fn summary_suffix(&self, entry: &DirEntry) -> String {
+        if self.is_collapsed(entry) {
+            self.collapse_summary(entry)
+        } else if self.show_sizes {
+            format!(" ({})", format_size(entry.size, self.si_sizes))
+        } else {
+            String::new()
+        }
+    }
+
+    /// Whether `entry` was modified after `mark_since`
+    fn changed_since_mark(&self, entry: &DirEntry) -> bool {
+        self.mark_since.is_some_and(|since| entry.modified > since)
+    }
+
+    /// Whether `entry` is a directory the scan listed without enumerating it
+    ///
+    /// Scans that skip hidden directories still cache them, with no children;
+    /// the scan root itself is always enumerated.
+    pub fn is_unscanned(&self, entry: &DirEntry) -> bool {
+        self.scan_mode.skip_hidden_dirs && entry.is_dir && entry.is_hidden && entry.path != self.root
+    }
+
+    /// `NOT_SCANNED_MARKER` for directories the scan never entered
+    fn not_scanned_marker(&self, entry: &DirEntry) -> &'static str {
+        if self.is_unscanned(entry) {
+            NOT_SCANNED_MARKER
+        } else {
+            ""
+        }
+    }
+
+    /// `--since-scan` marker for entries modified after the previous scan
+    fn since_marker(&self, entry: &DirEntry) -> &'static str {
+        if self.changed_since_mark(entry) {
+            SINCE_SCAN_MARKER
+        } else {
+            ""
+        }
+    }
+
+    /// `--classify` indicator: `@` symlinks, `/` directories, `*` executables
+    ///
+    /// Executables are recognised by the execute bits captured in the cache;
+    /// entries scanned without their mode (e.g. on Windows) never get `*`.
+    fn classify_suffix(&self, entry: &DirEntry) -> &'static str {
+        if !self.classify {
+            ""
+        } else if entry.symlink_target.is_some() {
+            "@"
+        } else if entry.is_dir {
+            "/"
+        } else if is_executable(entry) {
+            "*"
+        } else {
+            ""
+        }
+    }
+
+    /// `--long` and `--attributes` columns for the line of `path`, with a trailing space
+    ///
+    /// Empty unless one of them is enabled. Entries without captured data
+    /// (scanned without `--long`, or on the other platform) show question marks.
+    fn line_prefix(&self, path: &Path) -> String {
+        let mut prefix = String::new();
+        let entry = self.get_entry(path);
+        if self.long_listing {
+            match entry.and_then(|e| e.permissions.map(|p| p.long_prefix(e.is_dir))) {
+                Some(long) => prefix.push_str(&long),
+                None => prefix.push_str(UNKNOWN_LONG_PREFIX),
+            }
+            prefix.push(' ');
+        }
+        if self.show_attributes {
+            match entry.and_then(|e| e.attributes) {
+                Some(attrs) => prefix.push_str(&format_attributes(attrs)),
+                None => prefix.push_str(UNKNOWN_ATTRIBUTES),
+            }
+            prefix.push(' ');
+        }
+        prefix
+    }
+
+    /// Render a single top-level branch of the tree (a direct child of the root)
+    ///
+    /// Concatenating the branches of every root child in sorted order, after the
+    /// root line, reproduces `build_tree_output_with_depth` exactly. Used to
+    /// stream output while a scan is still running.
+    pub fn build_tree_branch(&self, child_name: &str, is_last_child: bool, max_depth: Option<usize>) -> Result<String> {
+        let mut output = String::new();
+        if max_depth.is_some_and(|max| max == 0) {
+            return Ok(output);
+        }
+        if !self.shows_child(None, &self.root, child_name) {
+            return Ok(output);
+        }
+        let visible = self.filter_visible(&self.root.join(child_name));
+        if visible.as_ref().is_some_and(|v| v.is_empty()) {
+            return Ok(output);
+        }
+        self.print_tree_child(&mut output, &self.root, child_name, "", is_last_child, 0, max_depth, visible.as_ref())?;
+        Ok(output)
     }
 
     // ============================================================================
-    // JSON Tree Output
+    // Colored Tree Output
     // ============================================================================
 
-    /// Build JSON tree representation
-    pub fn build_json_output(&self) -> Result<String> {
-        self.build_json_output_with_depth(None)
+    /// Build colored tree output
+    pub fn build_colored_tree_output(&self) -> Result<String> {
+        self.build_colored_tree_output_with_depth(None)
     }
 
-    /// Build JSON tree representation with optional max depth limit
-    pub fn build_json_output_with_depth(&self, max_depth: Option<usize>) -> Result<String> {
-        let mut root_json = json!({
-            "path": self.root.to_string_lossy().to_string(),
-            "children": []
-        });
+    /// Build colored tree output with optional max depth limit
+    pub fn build_colored_tree_output_with_depth(&self, max_depth: Option<usize>) -> Result<String> {
+        let mut output = String::new();
 
         if self.entries.is_empty() {
-            return Ok(root_json.to_string());
+            return Ok("(empty)\n".to_string());
         }
 
+        let root = &self.root;
+        output.push_str(&format!("{}\n", self.root_line(root)?.color(self.theme.root).bold()));
+
         // No need for visited set - filesystem is acyclic and in_progress set prevents cycles during traversal
-        self.populate_json(&mut root_json, &self.root, 0, max_depth)?;
+        let visible = self.filter_visible(root);
+        self.print_colored_tree(&mut output, root, "", 0, max_depth, visible.as_ref())?;
 
-        Ok(serde_json::to_string_pretty(&root_json)?)
+        Ok(output)
     }
 
-    fn populate_json(
+    fn print_colored_tree(
         &self,
-        node: &mut serde_json::Value,
+        output: &mut String,
         path: &Path,
+        prefix: &str,
         current_depth: usize,
         max_depth: Option<usize>,
+        visible: Option<&HashSet<PathBuf>>,
     ) -> Result<()> {
         // Check depth limit
         if let Some(max) = max_depth {
@@ -685,47 +1317,489 @@ impl DiskCache {
         }
 
         if let Some(entry) = self.get_entry(path) {
-            let mut children_array = Vec::new();
-            let mut children_names: Vec<_> = entry.children.iter().collect();
             // Sort children only at output time (not during traversal)
-            // Use parallel sort for large directories (>500 children)
-            if children_names.len() > 500 {
-                children_names.par_sort();
-            } else {
-                children_names.sort();
-            }
+            let mut children: Vec<_> = entry
+                .children
+                .iter()
+                .filter(|c| self.shows_child(visible, path, c))
+                .collect();
+            sort_children(&mut children);
+
+            for (i, child_name) in children.iter().enumerate() {
+                let is_last_child = i == children.len() - 1;
+                let child_prefix = self.connectors.continuation(is_last_child);
+                let branch_colored = self
+                    .connectors
+                    .connector(is_last_child)
+                    .color(self.theme.connector)
+                    .to_string();
 
-            for child_name in children_names {
                 let child_path = path.join(child_name);
-                let mut child_json = json!({
-                    "name": child_name,
-                    "path": child_path.to_string_lossy().to_string(),
-                    "children": []
-                });
+                let display_name = self.colored_display_name(child_name, &child_path, current_depth + 1);
+
+                output.push_str(&format!(
+                    "{}{}{}{}\n",
+                    self.line_prefix(&child_path),
+                    prefix,
+                    branch_colored,
+                    display_name
+                ));
+                if self.get_entry(&child_path).is_some_and(|e| self.is_collapsed(e)) {
+                    continue;
+                }
+                self.print_colored_tree(
+                    output,
+                    &child_path,
+                    &format!("{}{}", prefix, child_prefix),
+                    current_depth + 1,
+                    max_depth,
+                    visible,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// `display_name` in its name color; a template's `{size}` gets the theme's size color
+    fn colored_display_name(&self, child_name: &str, child_path: &Path, depth: usize) -> String {
+        let entry = self.get_entry(child_path);
+        let color = self.name_color(depth, entry);
+        match (entry, &self.entry_template) {
+            (Some(entry), Some(template)) => {
+                format!(
+                    "{}{}{}{}",
+                    render_entry_colored(entry, template, color, self.theme.size),
+                    self.summary_suffix(entry).color(color),
+                    self.not_scanned_marker(entry).color(color),
+                    self.since_marker(entry).bold()
+                )
+            }
+            // Entries changed since the previous scan stand out in bold
+            (Some(entry), None) if self.changed_since_mark(entry) => {
+                self.display_name(child_name, child_path)
+                    .color(color)
+                    .bold()
+                    .to_string()
+            }
+            _ => self.display_name(child_name, child_path).color(color).to_string(),
+        }
+    }
+
+    /// Color of a name `depth` levels below the root in colored tree output
+    ///
+    /// `--depth-colors` wins; otherwise the theme color for the entry's type.
+    fn name_color(&self, depth: usize, entry: Option<&DirEntry>) -> Color {
+        if self.depth_colors {
+            return DEPTH_PALETTE[depth % DEPTH_PALETTE.len()];
+        }
+        match entry {
+            Some(e) if e.symlink_target.is_some() => self.theme.symlink,
+            Some(e) if !e.is_dir => self.theme.file,
+            _ => self.theme.directory,
+        }
+    }
+
+    // ============================================================================
+    // JSON Tree Output
+    // ============================================================================
+
+    /// Build JSON tree representation
+    pub fn build_json_output(&self) -> Result<String> {
+        self.build_json_output_with_depth(None)
+    }
+
+    /// Build JSON tree representation with optional max depth limit
+    pub fn build_json_output_with_depth(&self, max_depth: Option<usize>) -> Result<String> {
+        let mut buffer = Vec::new();
+        self.write_json_output(&mut buffer, max_depth)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    /// Stream the JSON tree straight into `writer`
+    ///
+    /// Serializes from borrowed cache entries, so no intermediate JSON value is
+    /// built. Pretty-printed, except that an empty cache is the one-line
+    /// `{"children":[],"path":...}`.
+    pub fn write_json_output<W: Write>(&self, writer: W, max_depth: Option<usize>) -> Result<()> {
+        let visible = self.filter_visible(&self.root);
+        let root = JsonNode::root(self, &self.root, max_depth, visible.as_ref());
+        if self.entries.is_empty() {
+            serde_json::to_writer(writer, &root)?;
+        } else {
+            serde_json::to_writer_pretty(writer, &root)?;
+        }
+        Ok(())
+    }
+
+    /// Build JSON for the cached subtree rooted at `path`
+    ///
+    /// Same shape as `build_json_output_with_depth`, with `path` as the root.
+    /// Returns `None` if the path is not a cached directory.
+    pub fn build_subtree_json_output(&self, path: &Path, max_depth: Option<usize>) -> Result<Option<String>> {
+        if !self.get_entry(path).is_some_and(|e| e.is_dir) {
+            return Ok(None);
+        }
+
+        let mut buffer = Vec::new();
+        let visible = self.filter_visible(path);
+        serde_json::to_writer_pretty(&mut buffer, &JsonNode::root(self, path, max_depth, visible.as_ref()))?;
+        Ok(Some(String::from_utf8(buffer)?))
+    }
+
+    // ============================================================================
+    // Property List Output
+    // ============================================================================
+
+    /// Build an XML property list of the tree for macOS tooling
+    ///
+    /// Walks the same `JsonNode` structure as the JSON output: every node is a
+    /// dictionary with `children`, `name` and `path` keys.
+    pub fn build_plist_output(&self, max_depth: Option<usize>) -> Result<String> {
+        let mut buffer = Vec::new();
+        let visible = self.filter_visible(&self.root);
+        plist::to_writer_xml(&mut buffer, &JsonNode::root(self, &self.root, max_depth, visible.as_ref()))?;
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    // ============================================================================
+    // YAML Output
+    // ============================================================================
+
+    /// Build a YAML document of the tree
+    ///
+    /// Serializes the same `JsonNode` structure as the JSON output, so every
+    /// node is a mapping with the same keys. An empty cache gives an empty
+    /// document.
+    pub fn build_yaml_output(&self, max_depth: Option<usize>) -> Result<String> {
+        if self.entries.is_empty() {
+            return Ok(String::new());
+        }
+
+        let visible = self.filter_visible(&self.root);
+        Ok(serde_yaml::to_string(&JsonNode::root(self, &self.root, max_depth, visible.as_ref()))?)
+    }
+
+    // ============================================================================
+    // TOML Output
+    // ============================================================================
+
+    /// Build a TOML document of the tree
+    ///
+    /// TOML cannot nest arrays of tables more than a few levels before every
+    /// header spells out the whole chain, so the tree is flattened: one
+    /// `[[entry]]` table per node in JSON order, with `path`, `name`, `parent`
+    /// and `is_dir`. The root has no `parent` (TOML has no null).
+    pub fn build_toml_output(&self, max_depth: Option<usize>) -> Result<String> {
+        let visible = self.filter_visible(&self.root);
+        let mut entry = Vec::new();
+        let mut stack = vec![(JsonNode::root(self, &self.root, max_depth, visible.as_ref()), None)];
+
+        while let Some((node, parent)) = stack.pop() {
+            let path = self.display_path(&node.path)?;
+            let children = node.children();
+            stack.extend(children.into_iter().rev().map(|child| (child, Some(path.clone()))));
+            entry.push(TomlEntry {
+                name: node.name.map(Cow::into_owned),
+                is_dir: self.get_entry(&node.path).is_some_and(|e| e.is_dir),
+                path,
+                parent,
+            });
+        }
 
-                self.populate_json(&mut child_json, &child_path, current_depth + 1, max_depth)?;
-                children_array.push(child_json);
+        Ok(toml::to_string(&TomlTree { entry })?)
+    }
+
+    // ============================================================================
+    // CSV Output
+    // ============================================================================
+
+    /// Build CSV with one row per entry, for spreadsheet import
+    pub fn build_csv_output(&self) -> Result<String> {
+        self.build_csv_output_with_depth(None)
+    }
+
+    /// Build CSV rows (`path,name,depth,is_dir,size,modified`) in tree order
+    ///
+    /// Rows follow the same depth-first walk as the JSON output. `path` and
+    /// `name` are always quoted so commas and quotes in names survive;
+    /// `modified` is empty for entries scanned without metadata.
+    pub fn build_csv_output_with_depth(&self, max_depth: Option<usize>) -> Result<String> {
+        let visible = self.filter_visible(&self.root);
+        let mut out = String::from("path,name,depth,is_dir,size,modified\n");
+        let mut stack = vec![JsonNode::root(self, &self.root, max_depth, visible.as_ref())];
+
+        while let Some(node) = stack.pop() {
+            stack.extend(node.children().into_iter().rev());
+            let entry = self.get_entry(&node.path);
+            let name = match node.name {
+                Some(name) => name.into_owned(),
+                None => entry.map(|e| e.name.clone()).unwrap_or_default(),
+            };
+            let modified = entry
+                .filter(|e| e.has_metadata())
+                .map(|e| e.modified.to_rfc3339())
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                csv_quote(&self.display_path(&node.path)?),
+                csv_quote(&name),
+                node.depth,
+                entry.is_some_and(|e| e.is_dir),
+                entry.map_or(0, |e| e.size),
+                modified
+            ));
+        }
+
+        Ok(out)
+    }
+
+    // ============================================================================
+    // Markdown Output
+    // ============================================================================
+
+    /// Build a GitHub-flavored nested bullet list of the tree
+    ///
+    /// One `- name` item per node, indented two spaces per level, with
+    /// children in the same order as the tree output and a trailing `/` on
+    /// directories. Characters Markdown would read as formatting are escaped.
+    pub fn build_markdown_output(&self, max_depth: Option<usize>) -> Result<String> {
+        if self.entries.is_empty() {
+            return Ok(String::new());
+        }
+
+        let visible = self.filter_visible(&self.root);
+        let mut out = String::new();
+        let mut stack = vec![JsonNode::root(self, &self.root, max_depth, visible.as_ref())];
+
+        while let Some(node) = stack.pop() {
+            stack.extend(node.children().into_iter().rev());
+            let mut name = match &node.name {
+                Some(name) => name.to_string(),
+                None => self.root_line(&node.path)?,
+            };
+            if self.get_entry(&node.path).is_some_and(|e| e.is_dir) && !name.ends_with('/') {
+                name.push('/');
             }
+            out.push_str(&format!("{}- {}\n", "  ".repeat(node.depth), escape_markdown(&name)));
+        }
+
+        Ok(out)
+    }
+}
 
-            node["children"] = serde_json::json!(children_array);
+/// Backslash-escape characters Markdown treats as inline formatting
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|') {
+            out.push('\\');
         }
+        out.push(c);
+    }
+    out
+}
+
+/// `value` as a quoted CSV field, doubling embedded quotes
+fn csv_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+/// `--format toml` document: the flattened tree under one `entry` array
+#[derive(Serialize)]
+struct TomlTree {
+    entry: Vec<TomlEntry>,
+}
+
+#[derive(Serialize)]
+struct TomlEntry {
+    path:   String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name:   Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent: Option<String>,
+    is_dir: bool,
+}
+
+// ============================================================================
+// Borrowing JSON Serializer
+// ============================================================================
+
+/// One node of the JSON tree, serialized lazily from the cache
+///
+/// Keys are written in sorted order (`children`, `name`, `path`) to keep the
+/// layout of the former `serde_json::Value`-based output.
+struct JsonNode<'a> {
+    cache:     &'a DiskCache,
+    name:      Option<Cow<'a, str>>,
+    path:      PathBuf,
+    depth:     usize,
+    max_depth: Option<usize>,
+    visible:   Option<&'a HashSet<PathBuf>>,
+}
+
+impl<'a> JsonNode<'a> {
+    /// Visible children in output order; none past `max_depth`
+    fn children(&self) -> Vec<JsonNode<'a>> {
+        let expand = !self.max_depth.is_some_and(|max| self.depth >= max);
+        let entry = self.cache.get_entry(&self.path).filter(|_| expand);
+
+        let mut names: Vec<&'a String> = entry
+            .map(|e| {
+                e.children
+                    .iter()
+                    .filter(|c| self.cache.shows_child(self.visible, &self.path, c))
+                    .collect()
+            })
+            .unwrap_or_default();
+        // Sort children only at output time (not during traversal)
+        sort_children(&mut names);
+
+        names
+            .into_iter()
+            .map(|name| {
+                JsonNode {
+                    cache:     self.cache,
+                    name:      Some(Cow::Borrowed(name)),
+                    path:      self.path.join(name),
+                    depth:     self.depth + 1,
+                    max_depth: self.max_depth,
+                    visible:   self.visible,
+                }
+            })
+            .collect()
+    }
+
+    fn root(
+        cache: &'a DiskCache,
+        path: &Path,
+        max_depth: Option<usize>,
+        visible: Option<&'a HashSet<PathBuf>>,
+    ) -> Self {
+        JsonNode {
+            cache,
+            name: cache.root_name(path).map(Cow::Owned),
+            path: path.to_path_buf(),
+            depth: 0,
+            max_depth,
+            visible,
+        }
+    }
+}
+
+impl Serialize for JsonNode<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::{Error, SerializeMap};
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("children", &JsonChildren(self))?;
+        let entry = self.cache.get_entry(&self.path).filter(|_| self.cache.show_sizes);
+        if let Some(entry) = entry.filter(|e| e.has_metadata()) {
+            map.serialize_entry("modified", &entry.modified)?;
+        }
+        if let Some(name) = &self.name {
+            map.serialize_entry("name", name)?;
+        }
+        let path = self.cache.display_path(&self.path).map_err(S::Error::custom)?;
+        map.serialize_entry("path", &path)?;
+        if let Some(entry) = entry {
+            map.serialize_entry("size", &entry.size)?;
+        }
+        map.end()
+    }
+}
 
+/// The sorted `children` array of a `JsonNode`; empty past `max_depth`
+struct JsonChildren<'a, 'b>(&'b JsonNode<'a>);
+
+impl Serialize for JsonChildren<'_, '_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+
+        let children = self.0.children();
+        let mut seq = serializer.serialize_seq(Some(children.len()))?;
+        for child in &children {
+            seq.serialize_element(child)?;
+        }
+        seq.end()
+    }
+}
+
+/// Flush a directory's entries (e.g. just-renamed files) to disk
+///
+/// Windows has no portable way to open a directory for syncing, and NTFS
+/// journals renames itself, so this is a no-op there.
+fn sync_dir(dir: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        File::open(dir)?.sync_all()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = dir;
         Ok(())
     }
 }
 
+/// Whether a file entry's cached mode has an execute bit set
+fn is_executable(entry: &DirEntry) -> bool {
+    entry.permissions.is_some_and(|p| has_execute_bit(p.mode))
+}
+
+/// Sort child names for output, in parallel for large directories (>500 children)
+/// unless built `single-threaded`
+fn sort_children<T: Ord + Send>(children: &mut [T]) {
+    #[cfg(not(feature = "single-threaded"))]
+    if children.len() > 500 {
+        children.par_sort();
+        return;
+    }
+    children.sort();
+}
+
+/// Consumes the cache, yielding every entry including unflushed pending writes
+impl IntoIterator for DiskCache {
+    type IntoIter = std::collections::hash_map::IntoIter<PathBuf, DirEntry>;
+    type Item = (PathBuf, DirEntry);
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        self.flush_pending_writes();
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a DiskCache {
+    type IntoIter = std::collections::hash_map::Iter<'a, PathBuf, DirEntry>;
+    type Item = (&'a PathBuf, &'a DirEntry);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+/// Panics if `path` has no loaded entry; use `get` for a fallible lookup
+impl std::ops::Index<&Path> for DiskCache {
+    type Output = DirEntry;
+
+    fn index(&self, path: &Path) -> &DirEntry {
+        self.entries
+            .get(path)
+            .unwrap_or_else(|| panic!("no cache entry for {}", path.display()))
+    }
+}
+
 /// Get cache directory path
 pub fn get_cache_path() -> Result<PathBuf> {
     #[cfg(windows)]
     {
         let appdata = std::env::var("APPDATA")?;
-        return Ok(PathBuf::from(appdata).join("ptree").join("cache").join("ptree.dat"));
+        Ok(PathBuf::from(appdata).join("ptree").join("cache").join("ptree.dat"))
     }
 
     #[cfg(not(windows))]
     {
         if let Some(cache_home) = xdg_absolute_dir("XDG_CACHE_HOME") {
-            return Ok(PathBuf::from(cache_home).join("ptree").join("ptree.dat"));
+            return Ok(cache_home.join("ptree").join("ptree.dat"));
         }
 
         if let Ok(home) = std::env::var("HOME") {
@@ -767,7 +1841,10 @@ pub fn get_cache_path_custom(custom_dir: Option<&str>) -> Result<PathBuf> {
 
 #[cfg(test)]
 mod tests {
+    use serde_json::json;
+
     use super::*;
+    use crate::fixtures::{cache_with, dir, file};
 
     #[test]
     fn test_cache_creation() -> Result<()> {
@@ -783,6 +1860,1006 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_collection_access() {
+        let mut cache = DiskCache::new_empty();
+        assert!(cache.is_empty());
+
+        for name in ["a", "b", "c"] {
+            let path = PathBuf::from("/data").join(name);
+            cache.add_entry(path.clone(), DirEntry::builder(path).depth(1).build().unwrap());
+        }
+        assert_eq!(cache.len(), 0, "buffered writes are not counted until flushed");
+        cache.flush_pending_writes();
+
+        assert_eq!(cache.len(), 3);
+        assert!(!cache.is_empty());
+        assert!(cache.contains(Path::new("/data/b")));
+        assert!(!cache.contains(Path::new("/data/z")));
+        assert_eq!(cache.get(Path::new("/data/c")).map(|e| e.name.as_str()), Some("c"));
+        assert_eq!(cache[Path::new("/data/a")].name, "a");
+        assert_eq!((&cache).into_iter().count(), 3);
+
+        let mut names: Vec<String> = cache.into_iter().map(|(_, e)| e.name).collect();
+        names.sort();
+        assert_eq!(names, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_children_entries_resolves_and_skips_missing() -> Result<()> {
+        let cache = cache_with(
+            "/data",
+            [
+                dir("/data", &["zeta", "alpha.txt", "missing.txt", "mid"]),
+                dir("/data/zeta", &[]),
+                file("/data/alpha.txt", 0),
+                dir("/data/mid", &["inner"]),
+                dir("/data/mid/inner", &[]),
+            ],
+        );
+
+        let children = cache.children_entries(Path::new("/data"));
+        let names: Vec<&str> = children.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["alpha.txt", "mid", "zeta"]);
+        assert_eq!(children[1].path, Path::new("/data/mid"));
+        assert!(!children[0].is_dir);
+
+        assert_eq!(cache.children_entries(Path::new("/data/mid"))[0].path, Path::new("/data/mid/inner"));
+        assert!(cache.children_entries(Path::new("/data/zeta")).is_empty());
+        assert!(cache.children_entries(Path::new("/data/alpha.txt")).is_empty());
+        assert!(cache.children_entries(Path::new("/elsewhere")).is_empty());
+        Ok(())
+    }
+
+    /// The former `serde_json::Value`-building JSON renderer, kept as a reference
+    fn populate_json_reference(
+        cache: &DiskCache,
+        node: &mut serde_json::Value,
+        path: &Path,
+        depth: usize,
+        max: Option<usize>,
+    ) {
+        if max.is_some_and(|max| depth >= max) {
+            return;
+        }
+        if let Some(entry) = cache.get_entry(path) {
+            let mut names: Vec<_> = entry.children.iter().collect();
+            names.sort();
+
+            let mut children = Vec::new();
+            for name in names {
+                let child_path = path.join(name);
+                let mut child = json!({
+                    "name": name,
+                    "path": child_path.to_string_lossy().to_string(),
+                    "children": []
+                });
+                populate_json_reference(cache, &mut child, &child_path, depth + 1, max);
+                children.push(child);
+            }
+            node["children"] = json!(children);
+        }
+    }
+
+    #[test]
+    fn test_json_serializer_matches_value_based_output() -> Result<()> {
+        let cache = cache_with("/data", []);
+
+        let reference = |cache: &DiskCache, max: Option<usize>| -> Result<String> {
+            let mut root = json!({ "path": cache.root.to_string_lossy().to_string(), "children": [] });
+            populate_json_reference(cache, &mut root, &cache.root, 0, max);
+            Ok(serde_json::to_string_pretty(&root)?)
+        };
+
+        assert_eq!(cache.build_json_output()?, r#"{"children":[],"path":"/data"}"#);
+        let mut written = Vec::new();
+        cache.write_json_output(&mut written, None)?;
+        assert_eq!(String::from_utf8(written)?, cache.build_json_output()?);
+
+        let cache = cache_with(
+            "/data",
+            [
+                dir("/data", &["zeta", "alpha \"quoted\"", "file.txt"]),
+                dir("/data/zeta", &["inner"]),
+                dir("/data/zeta/inner", &[]),
+                dir("/data/alpha \"quoted\"", &[]),
+            ],
+        );
+
+        for max in [None, Some(0), Some(1), Some(2)] {
+            assert_eq!(cache.build_json_output_with_depth(max)?, reference(&cache, max)?);
+        }
+
+        let mut written = Vec::new();
+        cache.write_json_output(&mut written, None)?;
+        assert_eq!(String::from_utf8(written)?, reference(&cache, None)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_prefix_columns() -> Result<()> {
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        for (path, children, permissions, attributes) in [
+            ("/data", vec!["boot.ini", "notes.txt"], None, None),
+            (
+                "/data/boot.ini",
+                vec![],
+                Some(UnixPermissions {
+                    mode: 0o644,
+                    uid:  0,
+                    gid:  0,
+                }),
+                Some(0x01 | 0x02 | 0x04),
+            ),
+            ("/data/notes.txt", vec![], None, None),
+        ] {
+            cache.entries.insert(
+                PathBuf::from(path),
+                DirEntry::builder(path)
+                    .children(children)
+                    .is_dir(path == "/data")
+                    .permissions(permissions)
+                    .attributes(attributes)
+                    .build()?,
+            );
+        }
+
+        assert_eq!(cache.build_tree_output()?, "/data\n├── boot.ini\n└── notes.txt\n");
+
+        cache.show_attributes = true;
+        assert_eq!(cache.build_tree_output()?, "/data\nHSR-- ├── boot.ini\n????? └── notes.txt\n");
+
+        cache.long_listing = true;
+        assert_eq!(
+            cache.build_tree_output()?,
+            "/data\n-rw-r--r--     0     0 HSR-- ├── boot.ini\n?????????? ????? ????? ????? └── notes.txt\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_classify_suffixes() -> Result<()> {
+        let mode = |mode| Some(UnixPermissions { mode, uid: 0, gid: 0 });
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        for (path, children, is_dir, symlink_target, permissions) in [
+            ("/data", vec!["bin", "latest", "notes.txt", "run.sh", "unscanned.sh"], true, None, None),
+            ("/data/bin", vec![], true, None, mode(0o40755)),
+            ("/data/latest", vec![], false, Some(PathBuf::from("/data/bin")), mode(0o120777)),
+            ("/data/notes.txt", vec![], false, None, mode(0o100644)),
+            ("/data/run.sh", vec![], false, None, mode(0o100750)),
+            // Mode not captured: no `*`, whatever the file on disk says
+            ("/data/unscanned.sh", vec![], false, None, None),
+        ] {
+            cache.entries.insert(
+                PathBuf::from(path),
+                DirEntry::builder(path)
+                    .children(children)
+                    .symlink_target(symlink_target)
+                    .is_dir(is_dir)
+                    .permissions(permissions)
+                    .build()?,
+            );
+        }
+
+        cache.classify = true;
+        assert_eq!(
+            cache.build_tree_output()?,
+            "/data\n├── bin/\n├── latest@ -> /data/bin\n├── notes.txt\n├── run.sh*\n└── unscanned.sh\n"
+        );
+
+        colored::control::set_override(true);
+        let colored = cache.build_colored_tree_output()?;
+        colored::control::unset_override();
+        for name in ["bin/", "latest@ -> /data/bin", "run.sh*"] {
+            assert!(colored.contains(name), "{name} missing from {colored:?}");
+        }
+
+        // Hidden marker follows the indicator
+        cache.entries.get_mut(Path::new("/data/bin")).unwrap().is_hidden = true;
+        cache.show_hidden = true;
+        assert!(cache.build_tree_output()?.contains("├── bin/ [H]\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_theme_colors_colored_tree() -> Result<()> {
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        for (path, children, is_dir) in [
+            ("/data", vec!["docs", "notes.txt"], true),
+            ("/data/docs", vec![], true),
+            ("/data/notes.txt", vec![], false),
+        ] {
+            cache.entries.insert(
+                PathBuf::from(path),
+                DirEntry::builder(path)
+                    .is_dir(is_dir)
+                    .children(children)
+                    .size(2048)
+                    .build()?,
+            );
+        }
+
+        cache.theme = Theme::parse(
+            r##"
+            directory = "#00ff00"
+            file = "red"
+            connector = 244
+            "##,
+        )?;
+        colored::control::set_override(true);
+        let output = cache.build_colored_tree_output()?;
+        cache.entry_template = Some(EntryTemplate::parse("{name} {size}")?);
+        cache.theme.size = Color::Yellow;
+        let templated = cache.build_colored_tree_output()?;
+        let docs = "docs".truecolor(0, 255, 0).to_string();
+        let notes = "notes.txt".red().to_string();
+        let connector = "├── ".truecolor(128, 128, 128).to_string();
+        let root = "/data".blue().bold().to_string();
+        let size = "2.0 KiB".yellow().to_string();
+        colored::control::unset_override();
+
+        assert!(output.contains(&docs), "{output:?}");
+        assert!(output.contains(&notes), "{output:?}");
+        assert!(output.contains(&connector), "{output:?}");
+        // Unset keys keep the default root color
+        assert!(output.starts_with(&root), "{output:?}");
+        assert!(templated.contains(&size), "{templated:?}");
+        assert!(templated.contains(&docs), "{templated:?}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_root_name_labels_only_the_root() -> Result<()> {
+        let mut cache = cache_with(
+            "/home/me/my-project",
+            [
+                dir("/home/me/my-project", &["src"]),
+                dir("/home/me/my-project/src", &["main.rs"]),
+                file("/home/me/my-project/src/main.rs", 0),
+            ],
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&cache.build_json_output()?)?;
+        assert!(json.get("name").is_none());
+
+        cache.root_label = Some("demo".to_string());
+        assert_eq!(cache.build_tree_output()?, "demo\n└── src\n    └── main.rs\n");
+        colored::control::set_override(true);
+        let colored = cache.build_colored_tree_output()?;
+        colored::control::unset_override();
+        assert!(colored.lines().next().unwrap().contains("demo"));
+        assert!(!colored.contains("/home/me"));
+
+        let json: serde_json::Value = serde_json::from_str(&cache.build_json_output()?)?;
+        assert_eq!(json["name"], "demo");
+        assert_eq!(json["path"], "/home/me/my-project");
+        assert_eq!(json["children"][0]["path"], "/home/me/my-project/src");
+        assert_eq!(json["children"][0]["children"][0]["path"], "/home/me/my-project/src/main.rs");
+
+        cache.root_label = None;
+        cache.root_basename = true;
+        assert!(cache.build_tree_output()?.starts_with("my-project\n└── src\n"));
+        cache.root = PathBuf::from("/");
+        assert_eq!(cache.root_line(Path::new("/"))?, "/");
+        Ok(())
+    }
+
+    #[test]
+    fn test_since_scan_marks_entries_newer_than_previous_scan() -> Result<()> {
+        let previous_scan = Utc::now() - chrono::Duration::hours(2);
+        let before = previous_scan - chrono::Duration::minutes(5);
+        let after = previous_scan + chrono::Duration::minutes(5);
+
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        for (path, children, is_dir, modified) in [
+            ("/data", vec!["new.txt", "old", "old.txt"], true, after),
+            ("/data/new.txt", vec![], false, after),
+            ("/data/old", vec!["edited.txt"], true, before),
+            ("/data/old/edited.txt", vec![], false, after),
+            ("/data/old.txt", vec![], false, previous_scan),
+        ] {
+            cache.entries.insert(
+                PathBuf::from(path),
+                DirEntry::builder(path)
+                    .children(children)
+                    .is_dir(is_dir)
+                    .modified(modified)
+                    .build()?,
+            );
+        }
+
+        // Nothing is marked until a previous scan time is set
+        assert!(!cache.build_tree_output()?.contains(SINCE_SCAN_MARKER));
+
+        cache.mark_since = Some(previous_scan);
+        assert_eq!(
+            cache.build_tree_output()?,
+            "/data\n├── new.txt [changed]\n├── old\n│   └── edited.txt [changed]\n└── old.txt\n"
+        );
+
+        cache.entry_template = Some(EntryTemplate::parse("{name}")?);
+        assert!(cache.build_tree_output()?.contains("└── edited.txt [changed]\n"));
+
+        colored::control::set_override(true);
+        let colored = cache.build_colored_tree_output()?;
+        assert!(colored.contains("edited.txt") && colored.contains(SINCE_SCAN_MARKER));
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip_prefix_in_tree_and_json() -> Result<()> {
+        let mut cache =
+            cache_with("/home/me/dev/repo", [dir("/home/me/dev/repo", &["src"]), dir("/home/me/dev/repo/src", &[])]);
+
+        cache.strip_prefix = Some(PathBuf::from("/home/me/dev"));
+        assert_eq!(cache.build_tree_output()?, "repo\n└── src\n");
+        let json: serde_json::Value = serde_json::from_str(&cache.build_json_output()?)?;
+        assert_eq!(json["path"], "repo");
+        assert_eq!(json["children"][0]["path"], Path::new("repo/src").to_string_lossy().as_ref());
+
+        // A prefix that doesn't cover the root leaves paths absolute
+        cache.strip_prefix = Some(PathBuf::from("/srv"));
+        assert_eq!(cache.build_tree_output()?, "/home/me/dev/repo\n└── src\n");
+        let json: serde_json::Value = serde_json::from_str(&cache.build_json_output()?)?;
+        assert_eq!(json["children"][0]["path"], "/home/me/dev/repo/src");
+
+        // ...unless --strict turns that into an error
+        cache.strip_prefix_strict = true;
+        let err = cache.build_tree_output().unwrap_err();
+        assert!(err.to_string().contains("is not under --strip-prefix /srv"), "{err}");
+        assert!(cache.build_json_output().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncate_name_on_char_boundaries() {
+        assert_eq!(truncate_name("short", 5), "short");
+        assert_eq!(truncate_name("verylongname.txt", 13), "verylongname…");
+        // Multibyte characters count once and are never split
+        assert_eq!(truncate_name("日本語のとても長いファイル名.txt", 6), "日本語のと…");
+        assert_eq!(truncate_name("café🎉party", 6), "café🎉…");
+        assert_eq!(truncate_name("abc", 1), "…");
+    }
+
+    #[test]
+    fn test_max_name_length_in_trees_but_not_json() -> Result<()> {
+        let long = "ünïcödé_directory_with_a_very_long_name";
+        let child = format!("/data/{long}");
+        let mut cache = cache_with("/data", [dir("/data", &[long]), dir(child.as_str(), &[])]);
+
+        cache.max_name_length = Some(10);
+        cache.classify = true;
+        assert_eq!(cache.build_tree_output()?, "/data\n└── ünïcödé_d…/\n");
+        colored::control::set_override(true);
+        let colored = cache.build_colored_tree_output()?;
+        colored::control::unset_override();
+        assert!(colored.contains("ünïcödé_d…/") && !colored.contains(long), "{colored:?}");
+
+        let json: serde_json::Value = serde_json::from_str(&cache.build_json_output()?)?;
+        assert_eq!(json["children"][0]["name"], long);
+        assert_eq!(cache.entries[Path::new(&child)].name, long);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hidden_entries_shown_only_with_show_hidden() -> Result<()> {
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        for (path, children, is_dir, hidden) in [
+            ("/data", vec![".cache", ".env", "src"], true, false),
+            ("/data/.cache", vec!["blob"], true, true),
+            ("/data/.cache/blob", vec![], false, false),
+            ("/data/.env", vec![], false, true),
+            ("/data/src", vec!["main.rs"], true, false),
+            ("/data/src/main.rs", vec![], false, false),
+        ] {
+            let entry = DirEntry::builder(path)
+                .children(children)
+                .is_dir(is_dir)
+                .hidden(hidden)
+                .build()?;
+            cache.entries.insert(PathBuf::from(path), entry);
+        }
+
+        assert_eq!(cache.build_tree_output()?, "/data\n└── src\n    └── main.rs\n");
+        let json: serde_json::Value = serde_json::from_str(&cache.build_json_output()?)?;
+        assert_eq!(json["children"].as_array().map(Vec::len), Some(1));
+        assert_eq!(cache.build_tree_branch(".cache", false, None)?, "");
+
+        cache.show_hidden = true;
+        assert_eq!(
+            cache.build_tree_output()?,
+            "/data\n├── .cache [H]\n│   └── blob\n├── .env [H]\n└── src\n    └── main.rs\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unscanned_hidden_dirs_are_marked() -> Result<()> {
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        for (path, children, is_dir, hidden) in [
+            ("/data", vec![".venv", ".env", "src"], true, false),
+            ("/data/.venv", vec![], true, true),
+            ("/data/.env", vec![], false, true),
+            ("/data/src", vec![], true, false),
+        ] {
+            let entry = DirEntry::builder(path)
+                .children(children)
+                .is_dir(is_dir)
+                .hidden(hidden)
+                .build()?;
+            cache.entries.insert(PathBuf::from(path), entry);
+        }
+        cache.show_hidden = true;
+        assert!(!cache.build_tree_output()?.contains(NOT_SCANNED_MARKER));
+
+        // Only the hidden directory was left unenumerated
+        cache.scan_mode.skip_hidden_dirs = true;
+        assert_eq!(cache.build_tree_output()?, "/data\n├── .env [H]\n├── .venv [H] [not scanned]\n└── src\n");
+        assert!(!cache.is_unscanned(&cache.entries[Path::new("/data")]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_show_sizes_in_tree_and_json() -> Result<()> {
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        for (path, children, is_dir, size) in [
+            ("/data", vec!["big.bin", "docs"], true, 0),
+            ("/data/big.bin", vec![], false, 2048),
+            ("/data/docs", vec!["a.txt", "b.txt"], true, 0),
+            ("/data/docs/a.txt", vec![], false, 1000),
+            ("/data/docs/b.txt", vec![], false, 24),
+        ] {
+            let entry = DirEntry::builder(path)
+                .children(children)
+                .is_dir(is_dir)
+                .size(size)
+                .modified(DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap())
+                .build()?;
+            cache.entries.insert(PathBuf::from(path), entry);
+        }
+        cache.compute_sizes();
+
+        // Off by default: no sizes anywhere
+        assert!(!cache.build_tree_output()?.contains(" B)"));
+        let json: serde_json::Value = serde_json::from_str(&cache.build_json_output()?)?;
+        assert!(json.get("size").is_none() && json["children"][0].get("modified").is_none());
+
+        cache.show_sizes = true;
+        assert_eq!(
+            cache.build_tree_output()?,
+            "/data\n├── big.bin (2.0 KiB)\n└── docs (1.0 KiB)\n    ├── a.txt (1000 B)\n    └── b.txt (24 B)\n"
+        );
+        cache.si_sizes = true;
+        assert!(cache.build_tree_output()?.contains("── docs (1.0 kB)\n"));
+
+        let json: serde_json::Value = serde_json::from_str(&cache.build_json_output()?)?;
+        assert_eq!(json["size"], 3072);
+        assert_eq!(json["children"][0]["name"], "big.bin");
+        assert_eq!(json["children"][0]["size"], 2048);
+        assert_eq!(json["children"][0]["modified"], "2023-11-14T22:13:20Z");
+        assert_eq!(json["children"][1]["size"], 1024);
+        Ok(())
+    }
+
+    #[test]
+    fn test_depth_colors_vary_by_level() -> Result<()> {
+        let mut cache = cache_with("/data", [dir("/data", &["a"]), dir("/data/a", &["b"]), dir("/data/a/b", &[])]);
+
+        colored::control::set_override(true);
+        let by_type = cache.build_colored_tree_output()?;
+        cache.depth_colors = true;
+        let by_depth = cache.build_colored_tree_output()?;
+        colored::control::unset_override();
+
+        let line = |output: &str, name: &str| output.lines().skip(1).find(|l| l.contains(name)).unwrap().to_string();
+        // Bright blue for every name by default; green then yellow by depth
+        assert!(line(&by_type, "a").contains("\x1b[94ma"));
+        assert!(line(&by_type, "b").contains("\x1b[94mb"));
+        assert!(line(&by_depth, "a").contains("\x1b[32ma"));
+        assert!(line(&by_depth, "b").contains("\x1b[33mb"));
+
+        // Without color the flag changes nothing
+        assert_eq!(cache.build_tree_output()?, "/data\n└── a\n    └── b\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_plist_output_round_trips() -> Result<()> {
+        fn count_nodes(node: &plist::Value) -> usize {
+            let children = node
+                .as_dictionary()
+                .and_then(|d| d.get("children"))
+                .and_then(|c| c.as_array());
+            1 + children.map_or(0, |c| c.iter().map(count_nodes).sum())
+        }
+
+        let cache = cache_with(
+            "/data",
+            [
+                dir("/data", &["docs", "notes <&>.txt"]),
+                dir("/data/docs", &["a.txt"]),
+                file("/data/docs/a.txt", 0),
+                file("/data/notes <&>.txt", 0),
+            ],
+        );
+
+        let output = cache.build_plist_output(None)?;
+        let root = plist::Value::from_reader_xml(output.as_bytes())?;
+        assert_eq!(count_nodes(&root), cache.entries.len());
+
+        let dict = root.as_dictionary().unwrap();
+        assert_eq!(dict.get("path").and_then(|p| p.as_string()), Some("/data"));
+        let first = &dict.get("children").and_then(|c| c.as_array()).unwrap()[0];
+        assert_eq!(
+            first
+                .as_dictionary()
+                .and_then(|d| d.get("name"))
+                .and_then(|n| n.as_string()),
+            Some("docs")
+        );
+
+        let shallow = plist::Value::from_reader_xml(cache.build_plist_output(Some(1))?.as_bytes())?;
+        assert_eq!(count_nodes(&shallow), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_toml_output_parses_back() -> Result<()> {
+        let cache = cache_with(
+            "/data",
+            [
+                dir("/data", &["docs", "notes \"quoted\".txt"]),
+                dir("/data/docs", &["a.txt", "deep"]),
+                file("/data/docs/a.txt", 0),
+                dir("/data/docs/deep", &["b.txt"]),
+                file("/data/docs/deep/b.txt", 0),
+                file("/data/notes \"quoted\".txt", 0),
+            ],
+        );
+
+        let doc: toml::Table = cache.build_toml_output(None)?.parse()?;
+        let entries = doc["entry"].as_array().unwrap();
+        assert_eq!(entries.len(), cache.entries.len());
+
+        // Parents come before their children, which follow in sorted order
+        let paths: Vec<&str> = entries.iter().map(|e| e["path"].as_str().unwrap()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/data",
+                "/data/docs",
+                "/data/docs/a.txt",
+                "/data/docs/deep",
+                "/data/docs/deep/b.txt",
+                "/data/notes \"quoted\".txt",
+            ]
+        );
+        assert!(entries[0].get("parent").is_none());
+        assert_eq!(entries[4]["parent"].as_str(), Some("/data/docs/deep"));
+        assert_eq!(entries[4]["name"].as_str(), Some("b.txt"));
+        assert_eq!(entries[3]["is_dir"].as_bool(), Some(true));
+        assert_eq!(entries[2]["is_dir"].as_bool(), Some(false));
+
+        let shallow: toml::Table = cache.build_toml_output(Some(1))?.parse()?;
+        assert_eq!(shallow["entry"].as_array().map(Vec::len), Some(3));
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_output_rows_in_tree_order() -> Result<()> {
+        let modified = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        for (path, children, is_dir, size) in [
+            ("/data", vec!["b, \"quoted\".txt", "docs"], true, 0),
+            ("/data/docs", vec!["a.txt"], true, 0),
+            ("/data/docs/a.txt", vec![], false, 12),
+            ("/data/b, \"quoted\".txt", vec![], false, 3),
+        ] {
+            cache.entries.insert(
+                PathBuf::from(path),
+                DirEntry::builder(path)
+                    .children(children)
+                    .is_dir(is_dir)
+                    .size(size)
+                    .modified(modified)
+                    .build()?,
+            );
+        }
+        cache.entries.get_mut(Path::new("/data/docs")).unwrap().modified = NO_METADATA_MODIFIED;
+
+        assert_eq!(
+            cache.build_csv_output()?,
+            "path,name,depth,is_dir,size,modified\n\
+             \"/data\",\"data\",0,true,0,2023-11-14T22:13:20+00:00\n\
+             \"/data/b, \"\"quoted\"\".txt\",\"b, \"\"quoted\"\".txt\",1,false,3,2023-11-14T22:13:20+00:00\n\
+             \"/data/docs\",\"docs\",1,true,0,\n\
+             \"/data/docs/a.txt\",\"a.txt\",2,false,12,2023-11-14T22:13:20+00:00\n"
+        );
+        assert_eq!(cache.build_csv_output_with_depth(Some(1))?.lines().count(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_markdown_output_nests_three_levels() -> Result<()> {
+        let cache = cache_with(
+            "/data",
+            [
+                dir("/data", &["src", "README.md", "my_notes"]),
+                dir("/data/src", &["main.rs", "cli"]),
+                dir("/data/src/cli", &["args.rs"]),
+                file("/data/src/cli/args.rs", 0),
+                file("/data/src/main.rs", 0),
+                file("/data/README.md", 0),
+                dir("/data/my_notes", &[]),
+            ],
+        );
+
+        assert_eq!(
+            cache.build_markdown_output(None)?,
+            "- /data/\n\
+             \u{20} - README.md\n\
+             \u{20} - my\\_notes/\n\
+             \u{20} - src/\n\
+             \u{20}   - cli/\n\
+             \u{20}     - args.rs\n\
+             \u{20}   - main.rs\n"
+        );
+        assert_eq!(cache.build_markdown_output(Some(1))?.lines().count(), 4);
+        assert_eq!(DiskCache::new_empty().build_markdown_output(None)?, "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_yaml_output_matches_json() -> Result<()> {
+        assert_eq!(DiskCache::new_empty().build_yaml_output(None)?, "");
+
+        let cache = cache_with(
+            "/data",
+            [
+                dir("/data", &["docs", "notes: draft.txt"]),
+                dir("/data/docs", &["deep"]),
+                dir("/data/docs/deep", &["a.txt"]),
+                file("/data/docs/deep/a.txt", 0),
+                file("/data/notes: draft.txt", 0),
+            ],
+        );
+
+        let yaml: serde_json::Value = serde_yaml::from_str(&cache.build_yaml_output(None)?)?;
+        let json: serde_json::Value = serde_json::from_str(&cache.build_json_output()?)?;
+        assert_eq!(yaml, json);
+        assert_eq!(yaml["children"][0]["children"][0]["children"][0]["name"], "a.txt");
+        assert_eq!(yaml["children"][1]["path"], "/data/notes: draft.txt");
+
+        let shallow: serde_json::Value = serde_yaml::from_str(&cache.build_yaml_output(Some(1))?)?;
+        assert_eq!(shallow, serde_json::from_str::<serde_json::Value>(&cache.build_json_output_with_depth(Some(1))?)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_subtree_json_output() -> Result<()> {
+        let cache = cache_with(
+            "/data",
+            [
+                dir("/data", &["docs"]),
+                dir("/data/docs", &["a.txt"]),
+                file("/data/docs/a.txt", 0),
+            ],
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&cache.build_subtree_json_output(Path::new("/data/docs"), None)?.unwrap())?;
+        assert_eq!(json["path"], "/data/docs");
+        assert_eq!(json["children"][0]["name"], "a.txt");
+
+        assert!(cache
+            .build_subtree_json_output(Path::new("/data/docs/a.txt"), None)?
+            .is_none());
+        assert!(cache
+            .build_subtree_json_output(Path::new("/elsewhere"), None)?
+            .is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reconcile_children_removes_vanished_subtrees() {
+        let mut cache = cache_with(
+            "/data",
+            [
+                dir("/data", &["keep", "gone"]),
+                dir("/data/keep", &[]),
+                dir("/data/gone", &["deep"]),
+                dir("/data/gone/deep", &[]),
+                dir("/data/gonegone", &[]),
+            ],
+        );
+
+        let removed = cache.reconcile_children(Path::new("/data"), &["keep".to_string(), "new".to_string()]);
+        assert_eq!(removed, 1);
+        assert!(cache.contains(Path::new("/data/keep")));
+        assert!(!cache.contains(Path::new("/data/gone")));
+        assert!(!cache.contains(Path::new("/data/gone/deep")));
+        assert!(cache.contains(Path::new("/data/gonegone")), "sibling sharing a name prefix is kept");
+
+        assert_eq!(cache.reconcile_children(Path::new("/uncached"), &[]), 0);
+    }
+
+    #[test]
+    fn test_rename_subtree_rekeys_paths_and_children() -> Result<()> {
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        for (path, children, is_dir) in [
+            ("/data", vec!["projects", "archive"], true),
+            ("/data/projects", vec!["app", "projectsx"], true),
+            ("/data/projects/app", vec!["src", "README.md"], true),
+            ("/data/projects/app/src", vec!["main.rs"], true),
+            ("/data/projects/app/src/main.rs", vec![], false),
+            ("/data/projects/app/README.md", vec![], false),
+            ("/data/projects/projectsx", vec![], true),
+            ("/data/archive", vec!["old"], true),
+            ("/data/archive/old", vec![], true),
+            ("/data/appx", vec![], true),
+        ] {
+            let depth = relative_depth(Path::new("/data"), Path::new(path));
+            cache.entries.insert(
+                PathBuf::from(path),
+                DirEntry::builder(path)
+                    .children(children)
+                    .is_dir(is_dir)
+                    .depth(depth)
+                    .build()?,
+            );
+        }
+
+        let moved = cache.rename_subtree(Path::new("/data/projects/app"), Path::new("/data/archive/2024/tool"))?;
+        assert_eq!(moved, 4);
+
+        let mut paths: Vec<&Path> = cache.entries.keys().map(PathBuf::as_path).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                "/data",
+                "/data/appx",
+                "/data/archive",
+                "/data/archive/2024/tool",
+                "/data/archive/2024/tool/README.md",
+                "/data/archive/2024/tool/src",
+                "/data/archive/2024/tool/src/main.rs",
+                "/data/archive/old",
+                "/data/projects",
+                "/data/projects/projectsx",
+            ]
+            .map(Path::new)
+        );
+        for (key, entry) in &cache.entries {
+            assert_eq!(&entry.path, key);
+            assert_eq!(entry.depth, relative_depth(&cache.root, key), "{}", key.display());
+        }
+        let tool = &cache.entries[Path::new("/data/archive/2024/tool")];
+        assert_eq!(tool.name, "tool");
+        assert_eq!(tool.children, ["src", "README.md"]);
+        assert_eq!(cache.entries[Path::new("/data/archive/2024/tool/src/main.rs")].name, "main.rs");
+
+        // The old parent forgets the name; an uncached new parent is left alone
+        assert_eq!(cache.entries[Path::new("/data/projects")].children, ["projectsx"]);
+
+        // Renaming in place updates the parent's list; a cached target is replaced
+        assert_eq!(cache.rename_subtree(Path::new("/data/archive/old"), Path::new("/data/appx"))?, 1);
+        assert_eq!(cache.entries[Path::new("/data/archive")].children, Vec::<String>::new());
+        assert_eq!(cache.entries[Path::new("/data")].children, ["projects", "archive", "appx"]);
+
+        assert_eq!(cache.rename_subtree(Path::new("/data/missing"), Path::new("/data/elsewhere"))?, 0);
+        assert!(cache
+            .rename_subtree(Path::new("/data/projects"), Path::new("/data/projects/inner"))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_flush_count_matches_threshold() {
+        let insert = |threshold: usize, count: usize| {
+            let mut cache = DiskCache::new_empty();
+            cache.flush_threshold = threshold;
+            for i in 0..count {
+                let path = PathBuf::from(format!("/data/{}", i));
+                cache.add_entry(path.clone(), DirEntry::builder(path).is_dir(false).depth(1).build().unwrap());
+            }
+            cache.flush_pending_writes();
+            assert_eq!(cache.entries.len(), count);
+            cache.flush_count
+        };
+
+        assert_eq!(insert(10, 25), 3);
+        assert_eq!(insert(10, 30), 3);
+        assert_eq!(insert(1, 7), 7);
+        assert_eq!(insert(0, 7), 7);
+        assert_eq!(insert(usize::MAX, 7), 1);
+        assert_eq!(insert(10, 0), 0);
+    }
+
+    #[test]
+    fn test_stale_cache_warning_past_threshold_only() {
+        let mut cache = DiskCache::new_empty();
+        let day = 24 * 60 * 60;
+
+        for (age, warns) in [
+            (0, false),
+            (3 * 3600, false),
+            (6 * day, false),
+            (8 * day, true),
+            (400 * day, true),
+        ] {
+            cache.last_scan = Utc::now() - chrono::Duration::seconds(age);
+            let warning = cache.stale_cache_warning(7 * day as u64);
+            assert_eq!(warning.is_some(), warns, "age {age}s");
+            if let Some(warning) = warning {
+                assert!(warning.starts_with(&format!("warning: cache is {}d", age / day)), "{warning}");
+                assert!(warning.contains("--force"));
+            }
+        }
+
+        // A clock that moved backwards never warns
+        cache.last_scan = Utc::now() + chrono::Duration::days(30);
+        assert!(cache.stale_cache_warning(0).is_none());
+    }
+
+    #[test]
+    fn test_open_readonly_loads_saved_cache() -> Result<()> {
+        let temp_dir = std::env::temp_dir().join("ptree_test_open_readonly");
+        let _ = fs::remove_dir_all(&temp_dir);
+        let cache_path = temp_dir.join("ptree.dat");
+
+        assert!(DiskCache::open_readonly(&cache_path).is_err());
+        assert!(!temp_dir.exists(), "read-only open must not create the cache directory");
+
+        let mut cache = DiskCache::open(&cache_path)?;
+        cache.root = PathBuf::from("/data");
+        cache.add_entry(PathBuf::from("/data"), DirEntry::builder("/data").content_hash(1).build()?);
+        cache.save(&cache_path)?;
+
+        let loaded = DiskCache::open_readonly(&cache_path)?;
+        assert_eq!(loaded.root, PathBuf::from("/data"));
+        assert!(loaded.entries.contains_key(Path::new("/data")));
+
+        let _ = fs::remove_dir_all(&temp_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_syncs_directory_and_reopens() -> Result<()> {
+        let temp_dir = std::env::temp_dir().join("ptree_test_save_dir_sync");
+        let _ = fs::remove_dir_all(&temp_dir);
+        let cache_path = temp_dir.join("nested").join("ptree.dat");
+
+        let mut cache = DiskCache::open(&cache_path)?;
+        cache.root = PathBuf::from("/data");
+        for name in ["a", "b"] {
+            let path = PathBuf::from("/data").join(name);
+            cache.add_entry(path.clone(), DirEntry::builder(path).is_dir(false).size(1).depth(1).build()?);
+            // Second pass overwrites the first cache through the same rename path
+            cache.save(&cache_path)?;
+        }
+        sync_dir(cache_path.parent().unwrap())?;
+
+        let leftovers: Vec<_> = fs::read_dir(cache_path.parent().unwrap())?
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|name| name.ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty(), "temp files left behind: {leftovers:?}");
+
+        let mut reopened = DiskCache::open(&cache_path)?;
+        assert!(reopened.has_cache_snapshot());
+        reopened.load_all_entries_lazy(&cache_path)?;
+        assert_eq!(reopened.entries.len(), 2);
+        assert_eq!(reopened.entries[Path::new("/data/b")].size, 1);
+
+        let _ = fs::remove_dir_all(&temp_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_formats_round_trip_and_autodetect() -> Result<()> {
+        use crate::cache_rkyv::{detect_format, DATA_HEADER_LEN};
+
+        let temp_dir = std::env::temp_dir().join("ptree_test_cache_formats");
+        let _ = fs::remove_dir_all(&temp_dir);
+        let cache_path = temp_dir.join("ptree.dat");
+
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        for (path, children, is_dir) in [
+            ("/data", vec!["docs", "link"], true),
+            ("/data/docs", vec!["notes \"v2\".txt"], true),
+            ("/data/docs/notes \"v2\".txt", vec![], false),
+            ("/data/link", vec![], false),
+        ] {
+            let path = PathBuf::from(path);
+            cache.entries.insert(
+                path.clone(),
+                DirEntry::builder(&path)
+                    .content_hash(path.as_os_str().len() as u64)
+                    .children(children)
+                    .symlink_target(path.ends_with("link").then(|| PathBuf::from("/elsewhere")))
+                    .is_dir(is_dir)
+                    .size(if is_dir { 0 } else { 1234 })
+                    .depth(relative_depth(Path::new("/data"), &path))
+                    .permissions(Some(UnixPermissions {
+                        mode: 0o644,
+                        uid:  1000,
+                        gid:  100,
+                    }))
+                    .attributes(Some(0x20))
+                    .build()?,
+            );
+        }
+        let expected: HashMap<PathBuf, Vec<u8>> = cache
+            .entries
+            .iter()
+            .map(|(path, entry)| (path.clone(), bincode::serialize(entry).unwrap()))
+            .collect();
+
+        for format in [CacheFormat::Bincode, CacheFormat::Rkyv, CacheFormat::Json] {
+            cache.cache_format = format;
+            cache.save(&cache_path)?;
+
+            let data = fs::read(&cache_path)?;
+            assert_eq!(detect_format(&data)?, format);
+
+            // The reader comes from the header, not from the opener's settings
+            let mut reopened = DiskCache::open(&cache_path)?;
+            assert_eq!(reopened.cache_format, format);
+            assert_eq!(reopened.persisted_entry_count, expected.len());
+            reopened.load_all_entries_lazy(&cache_path)?;
+
+            let readonly = DiskCache::open_readonly(&cache_path)?;
+            for loaded in [&reopened, &readonly] {
+                assert_eq!(loaded.entries.len(), expected.len(), "{format}");
+                for (path, entry) in &loaded.entries {
+                    assert_eq!(&bincode::serialize(entry)?, &expected[path], "{format}: {}", path.display());
+                }
+            }
+
+            if format == CacheFormat::Json {
+                let body = std::str::from_utf8(&data[DATA_HEADER_LEN as usize..])?;
+                assert_eq!(body.lines().count(), expected.len());
+                for line in body.lines() {
+                    serde_json::from_str::<serde_json::Value>(line)?;
+                }
+            }
+        }
+
+        let _ = fs::remove_dir_all(&temp_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_readonly_corrupt_cache_is_error_and_kept() -> Result<()> {
+        let temp_dir = std::env::temp_dir().join("ptree_test_open_readonly_corrupt");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir)?;
+        let cache_path = temp_dir.join("ptree.dat");
+        let index_path = cache_path.with_extension("idx");
+        fs::write(&index_path, b"not a cache index")?;
+        fs::write(cache_path.with_extension("dat"), b"garbage")?;
+
+        let err = DiskCache::open_readonly(&cache_path).unwrap_err();
+        assert!(err.to_string().contains("Corrupt cache index"), "{}", err);
+        assert_eq!(fs::read(&index_path)?, b"not a cache index");
+        assert!(cache_path.with_extension("dat").exists());
+
+        let _ = fs::remove_dir_all(&temp_dir);
+        Ok(())
+    }
+
     #[test]
     fn test_content_hash_stability() {
         // Same inputs should produce same hash
@@ -857,38 +2934,23 @@ mod tests {
     fn test_has_directory_changed() {
         let path = std::path::Path::new("C:\\test");
 
-        let old_entry = DirEntry {
-            path:           path.to_path_buf(),
-            name:           "test".to_string(),
-            modified:       Utc::now(),
-            content_hash:   12345u64,
-            children:       vec!["file.txt".to_string()],
-            symlink_target: None,
-            is_hidden:      false,
-            is_dir:         true,
-        };
+        let old_entry = DirEntry::builder(path)
+            .content_hash(12345u64)
+            .children(["file.txt"])
+            .build()
+            .unwrap();
 
-        let new_entry_unchanged = DirEntry {
-            path:           path.to_path_buf(),
-            name:           "test".to_string(),
-            modified:       Utc::now(),
-            content_hash:   12345u64,
-            children:       vec!["file.txt".to_string()],
-            symlink_target: None,
-            is_hidden:      false,
-            is_dir:         true,
-        };
+        let new_entry_unchanged = DirEntry::builder(path)
+            .content_hash(12345u64)
+            .children(["file.txt"])
+            .build()
+            .unwrap();
 
-        let new_entry_changed = DirEntry {
-            path:           path.to_path_buf(),
-            name:           "test".to_string(),
-            modified:       Utc::now(),
-            content_hash:   54321u64,
-            children:       vec!["file.txt".to_string(), "newfile.txt".to_string()],
-            symlink_target: None,
-            is_hidden:      false,
-            is_dir:         true,
-        };
+        let new_entry_changed = DirEntry::builder(path)
+            .content_hash(54321u64)
+            .children(["file.txt", "newfile.txt"])
+            .build()
+            .unwrap();
 
         assert!(!has_directory_changed(&old_entry, &new_entry_unchanged), "Same hash should not indicate change");
         assert!(has_directory_changed(&old_entry, &new_entry_changed), "Different hash should indicate change");
@@ -901,22 +2963,7 @@ mod tests {
         let child = std::path::PathBuf::from("/foo/bar");
         let sibling_prefix = std::path::PathBuf::from("/foobar");
 
-        let mk_entry = |path: &std::path::Path| {
-            DirEntry {
-                path:           path.to_path_buf(),
-                name:           path
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or_default()
-                    .to_string(),
-                modified:       Utc::now(),
-                content_hash:   0,
-                children:       Vec::new(),
-                symlink_target: None,
-                is_hidden:      false,
-                is_dir:         true,
-            }
-        };
+        let mk_entry = |path: &std::path::Path| DirEntry::builder(path).build().unwrap();
 
         cache.entries.insert(base.clone(), mk_entry(&base));
         cache.entries.insert(child.clone(), mk_entry(&child));
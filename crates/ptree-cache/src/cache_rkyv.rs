@@ -1,15 +1,20 @@
 use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::{Read, Seek, SeekFrom, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
 use chrono::{DateTime, Utc};
 use memmap2::Mmap;
+use ptree_core::{CacheFormat, HashAlgorithm};
+use rkyv::Deserialize as _;
 use serde::{Deserialize, Serialize};
 
 #[cfg(windows)]
 use crate::cache::USNJournalState;
+use crate::cache::{relative_depth, DirEntry};
+use crate::permissions::UnixPermissions;
+use crate::scan_mode::ScanMode;
 
 /// Serializable directory entry (serde-based for compatibility)
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -22,6 +27,466 @@ pub struct RkyvDirEntry {
     pub symlink_target: Option<PathBuf>,
     pub is_hidden:      bool,
     pub is_dir:         bool,
+    pub size:           u64,
+    pub depth:          u32,
+    pub permissions:    Option<UnixPermissions>,
+    pub attributes:     Option<u32>,
+}
+
+/// Borrowed view of a `DirEntry` that encodes exactly like `RkyvDirEntry`
+///
+/// Lets `save` stream records straight from the cache without cloning each
+/// entry or buffering its bytes.
+#[derive(Serialize)]
+pub(crate) struct RkyvDirEntryRef<'a> {
+    path:           &'a Path,
+    name:           &'a str,
+    modified:       DateTime<Utc>,
+    content_hash:   u64,
+    children:       &'a [String],
+    symlink_target: Option<&'a Path>,
+    is_hidden:      bool,
+    is_dir:         bool,
+    size:           u64,
+    depth:          u32,
+    permissions:    Option<UnixPermissions>,
+    attributes:     Option<u32>,
+}
+
+impl<'a> From<&'a DirEntry> for RkyvDirEntryRef<'a> {
+    fn from(entry: &'a DirEntry) -> Self {
+        RkyvDirEntryRef {
+            path:           &entry.path,
+            name:           &entry.name,
+            modified:       entry.modified,
+            content_hash:   entry.content_hash,
+            children:       &entry.children,
+            symlink_target: entry.symlink_target.as_deref(),
+            is_hidden:      entry.is_hidden,
+            is_dir:         entry.is_dir,
+            size:           entry.size,
+            depth:          entry.depth,
+            permissions:    entry.permissions,
+            attributes:     entry.attributes,
+        }
+    }
+}
+
+impl<'a> From<&'a RkyvDirEntry> for RkyvDirEntryRef<'a> {
+    fn from(entry: &'a RkyvDirEntry) -> Self {
+        RkyvDirEntryRef {
+            path:           &entry.path,
+            name:           &entry.name,
+            modified:       entry.modified,
+            content_hash:   entry.content_hash,
+            children:       &entry.children,
+            symlink_target: entry.symlink_target.as_deref(),
+            is_hidden:      entry.is_hidden,
+            is_dir:         entry.is_dir,
+            size:           entry.size,
+            depth:          entry.depth,
+            permissions:    entry.permissions,
+            attributes:     entry.attributes,
+        }
+    }
+}
+
+/// Entry layout written before `attributes` was persisted
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct PermissionedRkyvDirEntry {
+    path:           PathBuf,
+    name:           String,
+    modified:       DateTime<Utc>,
+    content_hash:   u64,
+    children:       Vec<String>,
+    symlink_target: Option<PathBuf>,
+    is_hidden:      bool,
+    is_dir:         bool,
+    size:           u64,
+    depth:          u32,
+    permissions:    Option<UnixPermissions>,
+}
+
+/// Entry layout written before `permissions` was persisted
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct SizedRkyvDirEntry {
+    path:           PathBuf,
+    name:           String,
+    modified:       DateTime<Utc>,
+    content_hash:   u64,
+    children:       Vec<String>,
+    symlink_target: Option<PathBuf>,
+    is_hidden:      bool,
+    is_dir:         bool,
+    size:           u64,
+    depth:          u32,
+}
+
+/// Entry layout written before `size` and `depth` were persisted
+///
+/// Decoded as a fallback so existing caches keep loading; depth is recomputed
+/// from the index root on load.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct LegacyRkyvDirEntry {
+    path:           PathBuf,
+    name:           String,
+    modified:       DateTime<Utc>,
+    content_hash:   u64,
+    children:       Vec<String>,
+    symlink_target: Option<PathBuf>,
+    is_hidden:      bool,
+    is_dir:         bool,
+}
+
+impl From<PermissionedRkyvDirEntry> for RkyvDirEntry {
+    fn from(entry: PermissionedRkyvDirEntry) -> Self {
+        RkyvDirEntry {
+            path:           entry.path,
+            name:           entry.name,
+            modified:       entry.modified,
+            content_hash:   entry.content_hash,
+            children:       entry.children,
+            symlink_target: entry.symlink_target,
+            is_hidden:      entry.is_hidden,
+            is_dir:         entry.is_dir,
+            size:           entry.size,
+            depth:          entry.depth,
+            permissions:    entry.permissions,
+            attributes:     None,
+        }
+    }
+}
+
+impl From<SizedRkyvDirEntry> for RkyvDirEntry {
+    fn from(entry: SizedRkyvDirEntry) -> Self {
+        RkyvDirEntry {
+            path:           entry.path,
+            name:           entry.name,
+            modified:       entry.modified,
+            content_hash:   entry.content_hash,
+            children:       entry.children,
+            symlink_target: entry.symlink_target,
+            is_hidden:      entry.is_hidden,
+            is_dir:         entry.is_dir,
+            size:           entry.size,
+            depth:          entry.depth,
+            permissions:    None,
+            attributes:     None,
+        }
+    }
+}
+
+/// Decode an entry, falling back through older layouts newest-first
+///
+/// Each layout only appends fields, so reading older bytes with a newer layout
+/// runs out of input; the first layout that decodes is the one it was written
+/// with. Legacy entries get their depth recomputed from `root`.
+fn decode_entry(bytes: &[u8], root: &Path) -> bincode::Result<RkyvDirEntry> {
+    let err = match bincode::deserialize::<RkyvDirEntry>(bytes) {
+        Ok(entry) => return Ok(entry),
+        Err(err) => err,
+    };
+    if let Ok(entry) = bincode::deserialize::<PermissionedRkyvDirEntry>(bytes) {
+        return Ok(entry.into());
+    }
+    if let Ok(entry) = bincode::deserialize::<SizedRkyvDirEntry>(bytes) {
+        return Ok(entry.into());
+    }
+
+    let legacy: LegacyRkyvDirEntry = bincode::deserialize(bytes).map_err(|_| err)?;
+    Ok(RkyvDirEntry {
+        depth:          relative_depth(root, &legacy.path),
+        path:           legacy.path,
+        name:           legacy.name,
+        modified:       legacy.modified,
+        content_hash:   legacy.content_hash,
+        children:       legacy.children,
+        symlink_target: legacy.symlink_target,
+        is_hidden:      legacy.is_hidden,
+        is_dir:         legacy.is_dir,
+        size:           0,
+        permissions:    None,
+        attributes:     None,
+    })
+}
+
+// ============================================================================
+// Data File Formats (`--cache-format`)
+// ============================================================================
+
+/// Leading bytes of a data file that carries a format header
+const DATA_MAGIC: &[u8] = b"PTREE-CACHE";
+
+/// Header layout version, stored as an ASCII digit
+const DATA_HEADER_VERSION: u8 = b'1';
+
+/// Length of the data file header; records start right after it
+pub const DATA_HEADER_LEN: u64 = 16;
+
+/// Alignment of rkyv records, which are validated in place in the mmap
+const RKYV_ALIGN: usize = 16;
+
+/// Data file header: `PTREE-CACHE 1 <tag>` and a newline
+///
+/// Plain ASCII, so a JSON cache reads as a header line followed by one
+/// entry per line. Its length keeps the first rkyv record aligned.
+pub fn data_header(format: CacheFormat) -> [u8; DATA_HEADER_LEN as usize] {
+    let mut header = [b' '; DATA_HEADER_LEN as usize];
+    header[..DATA_MAGIC.len()].copy_from_slice(DATA_MAGIC);
+    header[12] = DATA_HEADER_VERSION;
+    header[14] = match format {
+        CacheFormat::Bincode => b'b',
+        CacheFormat::Rkyv => b'r',
+        CacheFormat::Json => b'j',
+    };
+    header[15] = b'\n';
+    header
+}
+
+/// Format of a data file, read from its leading bytes
+///
+/// Files without the magic predate the header and hold bincode records from
+/// offset 0; their first bytes are a record length, which is never `PTRE`.
+pub fn detect_format(data: &[u8]) -> Result<CacheFormat> {
+    if !data.starts_with(DATA_MAGIC) {
+        return Ok(CacheFormat::Bincode);
+    }
+    let header = data
+        .get(..DATA_HEADER_LEN as usize)
+        .ok_or_else(|| anyhow!("Truncated cache data header"))?;
+    if header[12] != DATA_HEADER_VERSION {
+        bail!("Unsupported cache data header version '{}'", header[12] as char);
+    }
+    match header[14] {
+        b'b' => Ok(CacheFormat::Bincode),
+        b'r' => Ok(CacheFormat::Rkyv),
+        b'j' => Ok(CacheFormat::Json),
+        tag => bail!("Unknown cache data format '{}'", tag as char),
+    }
+}
+
+/// Entry layout archived by the rkyv format
+///
+/// rkyv cannot archive `PathBuf` or `DateTime`, so paths are stored as UTF-8
+/// strings and the modification time as seconds plus nanoseconds.
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+struct RkyvRecord {
+    path:           String,
+    name:           String,
+    modified_secs:  i64,
+    modified_nanos: u32,
+    content_hash:   u64,
+    children:       Vec<String>,
+    symlink_target: Option<String>,
+    is_hidden:      bool,
+    is_dir:         bool,
+    size:           u64,
+    depth:          u32,
+    permissions:    Option<(u32, u32, u32)>,
+    attributes:     Option<u32>,
+}
+
+impl TryFrom<&RkyvDirEntryRef<'_>> for RkyvRecord {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: &RkyvDirEntryRef<'_>) -> Result<Self> {
+        let utf8 = |path: &Path| {
+            path.to_str()
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("rkyv cache format cannot store non-UTF-8 path {}", path.display()))
+        };
+        Ok(RkyvRecord {
+            path:           utf8(entry.path)?,
+            name:           entry.name.to_string(),
+            modified_secs:  entry.modified.timestamp(),
+            modified_nanos: entry.modified.timestamp_subsec_nanos(),
+            content_hash:   entry.content_hash,
+            children:       entry.children.to_vec(),
+            symlink_target: entry.symlink_target.map(utf8).transpose()?,
+            is_hidden:      entry.is_hidden,
+            is_dir:         entry.is_dir,
+            size:           entry.size,
+            depth:          entry.depth,
+            permissions:    entry.permissions.map(|p| (p.mode, p.uid, p.gid)),
+            attributes:     entry.attributes,
+        })
+    }
+}
+
+impl TryFrom<RkyvRecord> for RkyvDirEntry {
+    type Error = anyhow::Error;
+
+    fn try_from(record: RkyvRecord) -> Result<Self> {
+        let modified = DateTime::from_timestamp(record.modified_secs, record.modified_nanos)
+            .ok_or_else(|| anyhow!("Invalid modification time in cache entry {}", record.path))?;
+        Ok(RkyvDirEntry {
+            path: PathBuf::from(record.path),
+            name: record.name,
+            modified,
+            content_hash: record.content_hash,
+            children: record.children,
+            symlink_target: record.symlink_target.map(PathBuf::from),
+            is_hidden: record.is_hidden,
+            is_dir: record.is_dir,
+            size: record.size,
+            depth: record.depth,
+            permissions: record
+                .permissions
+                .map(|(mode, uid, gid)| UnixPermissions { mode, uid, gid }),
+            attributes: record.attributes,
+        })
+    }
+}
+
+/// Write one entry record in `format`, returning the number of bytes written
+///
+/// - bincode: `u32` length prefix, then the encoded entry
+/// - rkyv: `u32` length padded to 16 bytes, then the archive padded to 16
+///   bytes, so every archive stays aligned within the file
+/// - json: the entry as a single line
+pub(crate) fn write_record<W: Write>(writer: &mut W, format: CacheFormat, record: &RkyvDirEntryRef<'_>) -> Result<u64> {
+    let too_large = || anyhow!("Cache entry too large to save: {}", record.path.display());
+
+    match format {
+        CacheFormat::Bincode => {
+            let len = u32::try_from(bincode::serialized_size(record)?).map_err(|_| too_large())?;
+            writer.write_all(&len.to_le_bytes())?;
+            bincode::serialize_into(&mut *writer, record)?;
+            Ok(4 + len as u64)
+        }
+        CacheFormat::Rkyv => {
+            let archive = rkyv::to_bytes::<_, 1024>(&RkyvRecord::try_from(record)?)
+                .map_err(|e| anyhow!("Failed to archive cache entry {}: {:?}", record.path.display(), e))?;
+            let len = u32::try_from(archive.len()).map_err(|_| too_large())?;
+            let padding = (RKYV_ALIGN - archive.len() % RKYV_ALIGN) % RKYV_ALIGN;
+
+            let mut prefix = [0u8; RKYV_ALIGN];
+            prefix[..4].copy_from_slice(&len.to_le_bytes());
+            writer.write_all(&prefix)?;
+            writer.write_all(&archive)?;
+            writer.write_all(&[0u8; RKYV_ALIGN][..padding])?;
+            Ok((RKYV_ALIGN + archive.len() + padding) as u64)
+        }
+        CacheFormat::Json => {
+            let line = serde_json::to_vec(record)
+                .with_context(|| format!("Failed to encode cache entry {} as JSON", record.path.display()))?;
+            writer.write_all(&line)?;
+            writer.write_all(b"\n")?;
+            Ok(line.len() as u64 + 1)
+        }
+    }
+}
+
+/// Decode the record starting at `offset` of a data file in `format`
+///
+/// Returns `None` when the record runs past the end of the data.
+fn read_record(data: &[u8], offset: u64, format: CacheFormat, root: &Path) -> Result<Option<RkyvDirEntry>> {
+    let Some(data_slice) = data.get(offset as usize..) else {
+        return Ok(None);
+    };
+
+    match format {
+        CacheFormat::Bincode => {
+            let Some(prefix) = data_slice.get(..4) else {
+                return Ok(None);
+            };
+            let len = u32::from_le_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize;
+            let Some(bytes) = data_slice.get(4..4 + len) else {
+                return Ok(None);
+            };
+
+            // Migrate entries written with older layouts
+            Ok(Some(decode_entry(bytes, root)?))
+        }
+        CacheFormat::Rkyv => {
+            let Some(prefix) = data_slice.get(..4) else {
+                return Ok(None);
+            };
+            let len = u32::from_le_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize;
+            let Some(bytes) = data_slice.get(RKYV_ALIGN..RKYV_ALIGN + len) else {
+                return Ok(None);
+            };
+
+            let archived = rkyv::check_archived_root::<RkyvRecord>(bytes)
+                .map_err(|e| anyhow!("Corrupt rkyv cache entry at offset {}: {}", offset, e))?;
+            let record: RkyvRecord = archived
+                .deserialize(&mut rkyv::Infallible)
+                .unwrap_or_else(|never| match never {});
+            Ok(Some(record.try_into()?))
+        }
+        CacheFormat::Json => {
+            let Some(end) = data_slice.iter().position(|&b| b == b'\n') else {
+                return Ok(None);
+            };
+            let entry = serde_json::from_slice(&data_slice[..end])
+                .with_context(|| format!("Corrupt JSON cache entry at offset {}", offset))?;
+            Ok(Some(entry))
+        }
+    }
+}
+
+/// Length of the record starting at `offset`, framing included
+///
+/// Returns `None` when the record runs past the end of the data.
+fn record_len(data: &[u8], offset: u64, format: CacheFormat) -> Option<u64> {
+    let data_slice = data.get(offset as usize..)?;
+    let prefix = || {
+        data_slice
+            .get(..4)
+            .map(|p| u32::from_le_bytes([p[0], p[1], p[2], p[3]]) as usize)
+    };
+
+    let len = match format {
+        CacheFormat::Bincode => 4 + prefix()?,
+        CacheFormat::Rkyv => {
+            let len = prefix()?;
+            RKYV_ALIGN + len + (RKYV_ALIGN - len % RKYV_ALIGN) % RKYV_ALIGN
+        }
+        CacheFormat::Json => data_slice.iter().position(|&b| b == b'\n')? + 1,
+    };
+    (len <= data_slice.len()).then_some(len as u64)
+}
+
+/// Rebuild a lost or corrupt index by walking every record of a data file
+///
+/// Records are read in file order, so an entry appended after an earlier save
+/// replaces the older record for the same path. A truncated tail ends the walk
+/// and records that no longer decode are skipped. The root is the directory
+/// with the fewest path components and `last_scan` is the data file's mtime;
+/// the hash algorithm, scan mode, USN state and skip counts are not in the data
+/// file and keep their defaults.
+pub fn rebuild_index_from_data(data_path: &Path) -> Result<RkyvCacheIndex> {
+    let data = fs::read(data_path).with_context(|| format!("Failed to read cache data {}", data_path.display()))?;
+    let format = detect_format(&data).with_context(|| format!("Unreadable cache data {}", data_path.display()))?;
+
+    let mut index = RkyvCacheIndex::new();
+    let mut root: Option<PathBuf> = None;
+    let mut offset = if data.starts_with(DATA_MAGIC) {
+        DATA_HEADER_LEN
+    } else {
+        0
+    };
+
+    while let Some(len) = record_len(&data, offset, format) {
+        if let Ok(Some(entry)) = read_record(&data, offset, format, Path::new("")) {
+            let depth = entry.path.components().count();
+            if entry.is_dir && root.as_ref().is_none_or(|r| depth < r.components().count()) {
+                root = Some(entry.path.clone());
+            }
+            index.offsets.insert(entry.path, offset);
+        }
+        offset += len;
+    }
+
+    if let Some(root) = root {
+        index.last_scanned_root = root.clone();
+        index.root = root;
+    }
+    if let Ok(modified) = fs::metadata(data_path).and_then(|m| m.modified()) {
+        index.last_scan = modified.into();
+    }
+    Ok(index)
 }
 
 /// Serializable cache index (serde-based for compatibility)
@@ -33,9 +498,133 @@ pub struct RkyvCacheIndex {
     pub last_scan:         DateTime<Utc>,
     pub root:              PathBuf,
     pub last_scanned_root: PathBuf,
+    pub hash_algo:         HashAlgorithm,
     #[cfg(windows)]
     pub usn_state:         USNJournalState,
     pub skip_stats:        HashMap<String, usize>,
+    pub scan_mode:         ScanMode,
+}
+
+impl Default for RkyvCacheIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Index layout written before `scan_mode` was persisted
+///
+/// Decoded as a fallback so existing caches keep loading; their mode is
+/// unknown and taken as the default, so a run in any other mode rescans.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct HashedRkyvCacheIndex {
+    offsets:           HashMap<PathBuf, u64>,
+    last_scan:         DateTime<Utc>,
+    root:              PathBuf,
+    last_scanned_root: PathBuf,
+    hash_algo:         HashAlgorithm,
+    #[cfg(windows)]
+    usn_state:         USNJournalState,
+    skip_stats:        HashMap<String, usize>,
+}
+
+impl From<HashedRkyvCacheIndex> for RkyvCacheIndex {
+    fn from(index: HashedRkyvCacheIndex) -> Self {
+        RkyvCacheIndex {
+            offsets:                   index.offsets,
+            last_scan:                 index.last_scan,
+            root:                      index.root,
+            last_scanned_root:         index.last_scanned_root,
+            hash_algo:                 index.hash_algo,
+            #[cfg(windows)]
+            usn_state:                 index.usn_state,
+            skip_stats:                index.skip_stats,
+            scan_mode:                 ScanMode::default(),
+        }
+    }
+}
+
+/// Index layout written before `hash_algo` was persisted
+///
+/// Decoded as a fallback so existing caches keep loading; they were hashed
+/// with the default algorithm.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct LegacyRkyvCacheIndex {
+    offsets:           HashMap<PathBuf, u64>,
+    last_scan:         DateTime<Utc>,
+    root:              PathBuf,
+    last_scanned_root: PathBuf,
+    #[cfg(windows)]
+    usn_state:         USNJournalState,
+    skip_stats:        HashMap<String, usize>,
+}
+
+impl From<LegacyRkyvCacheIndex> for RkyvCacheIndex {
+    fn from(index: LegacyRkyvCacheIndex) -> Self {
+        RkyvCacheIndex {
+            offsets:                   index.offsets,
+            last_scan:                 index.last_scan,
+            root:                      index.root,
+            last_scanned_root:         index.last_scanned_root,
+            hash_algo:                 HashAlgorithm::default(),
+            #[cfg(windows)]
+            usn_state:                 index.usn_state,
+            skip_stats:                index.skip_stats,
+            scan_mode:                 ScanMode::default(),
+        }
+    }
+}
+
+/// Decode an index, falling back to the layouts from before `scan_mode` and
+/// `hash_algo`
+///
+/// `hash_algo` sits in the middle of the index rather than at the end, so
+/// older bytes can misread as a newer layout instead of running out of
+/// input. Every layout is decoded strictly and only matches when it consumes
+/// every byte.
+pub(crate) fn decode_index(bytes: &[u8]) -> bincode::Result<RkyvCacheIndex> {
+    use bincode::Options;
+
+    let strict = || {
+        bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .reject_trailing_bytes()
+    };
+    let err = match strict().deserialize::<RkyvCacheIndex>(bytes) {
+        Ok(index) => return Ok(index),
+        Err(err) => err,
+    };
+    if let Ok(index) = strict().deserialize::<HashedRkyvCacheIndex>(bytes) {
+        return Ok(index.into());
+    }
+    strict()
+        .deserialize::<LegacyRkyvCacheIndex>(bytes)
+        .map(RkyvCacheIndex::from)
+        .map_err(|_| err)
+}
+
+/// Borrowed view of a `RkyvCacheIndex` that encodes exactly like it
+///
+/// Offsets are kept as a flat list of borrowed paths instead of a second
+/// map of owned paths, and serialized as a map.
+#[derive(Serialize)]
+pub(crate) struct RkyvCacheIndexRef<'a> {
+    #[serde(serialize_with = "serialize_offsets")]
+    pub offsets:           &'a [(&'a Path, u64)],
+    pub last_scan:         DateTime<Utc>,
+    pub root:              &'a Path,
+    pub last_scanned_root: &'a Path,
+    pub hash_algo:         HashAlgorithm,
+    #[cfg(windows)]
+    pub usn_state:         &'a USNJournalState,
+    pub skip_stats:        &'a HashMap<String, usize>,
+    pub scan_mode:         ScanMode,
+}
+
+fn serialize_offsets<S: serde::Serializer>(
+    offsets: &&[(&Path, u64)],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_map(offsets.iter().map(|(path, offset)| (path, offset)))
 }
 
 impl RkyvCacheIndex {
@@ -45,25 +634,42 @@ impl RkyvCacheIndex {
             last_scan:                 Utc::now(),
             root:                      PathBuf::new(),
             last_scanned_root:         PathBuf::new(),
+            hash_algo:                 HashAlgorithm::default(),
             #[cfg(windows)]
             usn_state:                 USNJournalState::default(),
             skip_stats:                HashMap::new(),
+            scan_mode:                 ScanMode::default(),
         }
     }
+
+    /// Write the index atomically (bincode, via a temp file and rename)
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let data = bincode::serialize(self)?;
+        let temp_path = path.with_extension("tmp");
+
+        let mut file = File::create(&temp_path)?;
+        file.write_all(&data)?;
+        file.sync_all()?;
+
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
 }
 
-/// Memory-mapped cache using rkyv for zero-copy single-node O(1) access
+/// Memory-mapped cache with single-node O(1) access
 ///
 /// Architecture:
-/// - index file (.idx): contains RkyvCacheIndex (rkyv serialized)
-/// - data file (.dat): contains rkyv-archived DirEntry objects at indexed offsets
+/// - index file (.idx): contains RkyvCacheIndex (bincode serialized)
+/// - data file (.dat): a `data_header` naming the `CacheFormat`, then one
+///   record per entry at the offsets kept in the index
 ///
-/// Single-node access is O(1): load offset from index, deserialize from mmap in-place
-/// No allocation or copying for field access during traversal
+/// Single-node access is O(1): load offset from index, decode from the mmap
 pub struct RkyvMmapCache {
-    pub index: RkyvCacheIndex,
-    mmap:      Option<Mmap>,
-    data_path: PathBuf,
+    pub index:  RkyvCacheIndex,
+    /// Record format, detected from the data file header
+    pub format: CacheFormat,
+    mmap:       Option<Mmap>,
+    data_path:  PathBuf,
 }
 
 impl RkyvMmapCache {
@@ -73,16 +679,13 @@ impl RkyvMmapCache {
         fs::create_dir_all(index_path.parent().unwrap())?;
 
         // Load index (small, safe to fully deserialize using serde)
+        // An undecodable index is an error, never an empty index that looks fresh
         let index = if index_path.exists() {
             let mut file = File::open(index_path)?;
             let mut data = Vec::new();
             file.read_to_end(&mut data)?;
 
-            // Deserialize index using serde bincode
-            match bincode::deserialize::<RkyvCacheIndex>(&data) {
-                Ok(idx) => idx,
-                Err(_) => RkyvCacheIndex::new(),
-            }
+            decode_index(&data).map_err(|e| anyhow!("Corrupt cache index {}: {}", index_path.display(), e))?
         } else {
             RkyvCacheIndex::new()
         };
@@ -94,16 +697,41 @@ impl RkyvMmapCache {
         } else {
             None
         };
+        let format = mmap.as_deref().map(detect_format).transpose()?.unwrap_or_default();
 
         Ok(RkyvMmapCache {
             index,
+            format,
             mmap,
             data_path: data_path.to_path_buf(),
         })
     }
 
+    /// Load an existing cache without creating directories or tolerating corruption
+    ///
+    /// Unlike `open`, a missing or undecodable index is an error rather than an
+    /// empty cache, and nothing on disk is created or modified.
+    pub fn open_readonly(index_path: &std::path::Path, data_path: &std::path::Path) -> Result<Self> {
+        let data = fs::read(index_path)
+            .map_err(|e| anyhow::anyhow!("Failed to read cache index {}: {}", index_path.display(), e))?;
+        let index =
+            decode_index(&data).map_err(|e| anyhow::anyhow!("Corrupt cache index {}: {}", index_path.display(), e))?;
+
+        let file = File::open(data_path)
+            .map_err(|e| anyhow::anyhow!("Failed to open cache data {}: {}", data_path.display(), e))?;
+        let mmap = unsafe { Mmap::map(&file)? };
+        let format = detect_format(&mmap).with_context(|| format!("Unreadable cache data {}", data_path.display()))?;
+
+        Ok(RkyvMmapCache {
+            index,
+            format,
+            mmap: Some(mmap),
+            data_path: data_path.to_path_buf(),
+        })
+    }
+
     /// O(1) lookup: get single directory entry via mmap offset
-    /// Deserializes from mmap-backed binary data
+    /// Decodes the record in the format named by the data file header
     pub fn get_entry(&self, path: &std::path::Path) -> Result<Option<RkyvDirEntry>> {
         let offset = match self.index.offsets.get(path) {
             Some(&off) => off,
@@ -112,22 +740,7 @@ impl RkyvMmapCache {
 
         let mmap = self.mmap.as_ref().ok_or_else(|| anyhow::anyhow!("No mmap loaded"))?;
 
-        let data_slice = &mmap[offset as usize..];
-
-        // Read length prefix
-        if data_slice.len() < 4 {
-            return Ok(None);
-        }
-
-        let len = u32::from_le_bytes([data_slice[0], data_slice[1], data_slice[2], data_slice[3]]) as usize;
-
-        if data_slice.len() < 4 + len {
-            return Ok(None);
-        }
-
-        // Deserialize entry from mmap'd region
-        let entry: RkyvDirEntry = bincode::deserialize(&data_slice[4..4 + len])?;
-        Ok(Some(entry))
+        read_record(mmap, offset, self.format, &self.index.root)
     }
 
     /// Get all entries (full deserialization - only for batch operations or output)
@@ -148,6 +761,10 @@ impl RkyvMmapCache {
                         symlink_target: entry.symlink_target,
                         is_hidden:      entry.is_hidden,
                         is_dir:         entry.is_dir,
+                        size:           entry.size,
+                        depth:          entry.depth,
+                        permissions:    entry.permissions,
+                        attributes:     entry.attributes,
                     },
                 );
             }
@@ -156,7 +773,7 @@ impl RkyvMmapCache {
         Ok(entries)
     }
 
-    /// Write bincode-serialized entry to data file
+    /// Append an entry to the data file in this cache's format
     /// Returns the offset where entry was written for index tracking
     pub fn append_entry(&self, entry: &RkyvDirEntry) -> Result<u64> {
         let mut data_file = std::fs::OpenOptions::new()
@@ -164,13 +781,13 @@ impl RkyvMmapCache {
             .append(true)
             .open(&self.data_path)?;
 
-        let serialized = bincode::serialize(entry)?;
-        let len = serialized.len() as u32;
-
-        let offset = data_file.seek(SeekFrom::End(0))?;
+        let mut offset = data_file.seek(SeekFrom::End(0))?;
+        if offset == 0 {
+            data_file.write_all(&data_header(self.format))?;
+            offset = DATA_HEADER_LEN;
+        }
 
-        data_file.write_all(&len.to_le_bytes())?;
-        data_file.write_all(&serialized)?;
+        write_record(&mut data_file, self.format, &RkyvDirEntryRef::from(entry))?;
         data_file.sync_all()?;
 
         Ok(offset)
@@ -178,15 +795,7 @@ impl RkyvMmapCache {
 
     /// Save index to disk (bincode serialized)
     pub fn save_index(&self, path: &std::path::Path) -> Result<()> {
-        let data = bincode::serialize(&self.index)?;
-        let temp_path = path.with_extension("tmp");
-
-        let mut file = File::create(&temp_path)?;
-        file.write_all(&data)?;
-        file.sync_all()?;
-
-        fs::rename(&temp_path, path)?;
-        Ok(())
+        self.index.save(path)
     }
 
     pub fn len(&self) -> usize {
@@ -203,6 +812,185 @@ mod tests {
     use std::env;
 
     use super::*;
+    use crate::fixtures::{cache_with, dir, file};
+
+    #[test]
+    fn test_borrowed_views_encode_like_owned_types() -> Result<()> {
+        let entry = DirEntry::builder("/data/link")
+            .content_hash(42)
+            .children(["a", "b"])
+            .symlink_target(Some(PathBuf::from("/elsewhere")))
+            .hidden(true)
+            .size(7)
+            .depth(1)
+            .permissions(Some(UnixPermissions {
+                mode: 0o120777,
+                uid:  1,
+                gid:  2,
+            }))
+            .attributes(Some(0x400))
+            .build()?;
+        let owned = RkyvDirEntry {
+            path:           entry.path.clone(),
+            name:           entry.name.clone(),
+            modified:       entry.modified,
+            content_hash:   entry.content_hash,
+            children:       entry.children.clone(),
+            symlink_target: entry.symlink_target.clone(),
+            is_hidden:      entry.is_hidden,
+            is_dir:         entry.is_dir,
+            size:           entry.size,
+            depth:          entry.depth,
+            permissions:    entry.permissions,
+            attributes:     entry.attributes,
+        };
+        assert_eq!(bincode::serialize(&RkyvDirEntryRef::from(&entry))?, bincode::serialize(&owned)?);
+
+        let mut index = RkyvCacheIndex::new();
+        index.root = PathBuf::from("/data");
+        index.last_scanned_root = PathBuf::from("/data/sub");
+        index.offsets.insert(entry.path.clone(), 128);
+        index.skip_stats.insert(".git".to_string(), 3);
+        let offsets = [(entry.path.as_path(), 128)];
+        let borrowed = RkyvCacheIndexRef {
+            offsets:                   &offsets,
+            last_scan:                 index.last_scan,
+            root:                      &index.root,
+            last_scanned_root:         &index.last_scanned_root,
+            hash_algo:                 index.hash_algo,
+            #[cfg(windows)]
+            usn_state:                 &index.usn_state,
+            skip_stats:                &index.skip_stats,
+            scan_mode:                 index.scan_mode,
+        };
+        assert_eq!(bincode::serialize(&borrowed)?, bincode::serialize(&index)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_format_from_header() -> Result<()> {
+        for format in [CacheFormat::Bincode, CacheFormat::Rkyv, CacheFormat::Json] {
+            let header = data_header(format);
+            assert_eq!(detect_format(&header)?, format);
+            assert!(header.ends_with(b"\n"));
+        }
+        assert_eq!(&data_header(CacheFormat::Json), b"PTREE-CACHE 1 j\n");
+
+        // Headerless files are bincode caches from before the header existed
+        assert_eq!(detect_format(&[12, 0, 0, 0, 1, 2, 3])?, CacheFormat::Bincode);
+        assert_eq!(detect_format(&[])?, CacheFormat::Bincode);
+
+        assert!(detect_format(b"PTREE-CACHE 1 x\n").is_err());
+        assert!(detect_format(b"PTREE-CACHE 9 b\n").is_err());
+        assert!(detect_format(b"PTREE-CACHE").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_entry_writes_header_then_aligned_rkyv_records() -> Result<()> {
+        let temp_dir = env::temp_dir().join("ptree_rkyv_append_test");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir)?;
+        let index_path = temp_dir.join("test.idx");
+        let data_path = temp_dir.join("test.dat");
+
+        let mut cache = RkyvMmapCache::open(&index_path, &data_path)?;
+        cache.format = CacheFormat::Rkyv;
+        let mut offsets = Vec::new();
+        for name in ["a", "bb", "ccc"] {
+            let entry = RkyvDirEntry {
+                path:           PathBuf::from("/data").join(name),
+                name:           name.to_string(),
+                modified:       Utc::now(),
+                content_hash:   name.len() as u64,
+                children:       Vec::new(),
+                symlink_target: None,
+                is_hidden:      false,
+                is_dir:         false,
+                size:           7,
+                depth:          1,
+                permissions:    None,
+                attributes:     None,
+            };
+            let offset = cache.append_entry(&entry)?;
+            assert_eq!(offset % RKYV_ALIGN as u64, 0);
+            cache.index.offsets.insert(entry.path.clone(), offset);
+            offsets.push(offset);
+        }
+        assert_eq!(offsets[0], DATA_HEADER_LEN);
+        cache.save_index(&index_path)?;
+
+        let reopened = RkyvMmapCache::open(&index_path, &data_path)?;
+        assert_eq!(reopened.format, CacheFormat::Rkyv);
+        let entry = reopened.get_entry(Path::new("/data/ccc"))?.expect("entry should load");
+        assert_eq!(entry.content_hash, 3);
+        assert_eq!(reopened.get_all()?.len(), 3);
+
+        let _ = fs::remove_dir_all(&temp_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rebuild_index_from_data_after_losing_index() -> Result<()> {
+        let temp_dir = env::temp_dir().join("ptree_rkyv_rebuild_index_test");
+        let _ = fs::remove_dir_all(&temp_dir);
+        let cache_path = temp_dir.join("ptree.dat");
+        let index_path = cache_path.with_extension("idx");
+
+        for format in [CacheFormat::Bincode, CacheFormat::Rkyv, CacheFormat::Json] {
+            let mut cache = cache_with(
+                "/data",
+                [
+                    dir("/data", &["docs", "a.txt"]),
+                    dir("/data/docs", &["b.txt"]),
+                    file("/data/a.txt", 0),
+                    file("/data/docs/b.txt", 0),
+                ],
+            );
+            cache.cache_format = format;
+            cache.save(&cache_path)?;
+
+            // A later record for the same path replaces the saved one
+            let appended = RkyvMmapCache::open(&index_path, &cache_path)?;
+            appended.append_entry(&RkyvDirEntry {
+                path:           PathBuf::from("/data/a.txt"),
+                name:           "a.txt".to_string(),
+                modified:       Utc::now(),
+                content_hash:   0,
+                children:       Vec::new(),
+                symlink_target: None,
+                is_hidden:      false,
+                is_dir:         false,
+                size:           99,
+                depth:          1,
+                permissions:    None,
+                attributes:     None,
+            })?;
+            drop(appended);
+
+            // Truncated tail from an interrupted append
+            let mut data = fs::OpenOptions::new().append(true).open(&cache_path)?;
+            data.write_all(&[0xff, 0x00])?;
+            drop(data);
+
+            fs::remove_file(&index_path)?;
+            let index = rebuild_index_from_data(&cache_path)?;
+            assert_eq!(index.offsets.len(), 4, "{format:?}");
+            assert_eq!(index.root, Path::new("/data"));
+
+            index.save(&index_path)?;
+            let reopened = RkyvMmapCache::open_readonly(&index_path, &cache_path)?;
+            for path in ["/data", "/data/docs", "/data/a.txt", "/data/docs/b.txt"] {
+                let entry = reopened.get_entry(Path::new(path))?.expect("entry should be reachable");
+                assert_eq!(entry.path, Path::new(path));
+            }
+            assert_eq!(reopened.get_entry(Path::new("/data/a.txt"))?.unwrap().size, 99);
+            fs::remove_file(&cache_path)?;
+        }
+
+        let _ = fs::remove_dir_all(&temp_dir);
+        Ok(())
+    }
 
     #[test]
     fn test_rkyv_dir_entry_serialization() -> Result<()> {
@@ -215,6 +1003,10 @@ mod tests {
             symlink_target: None,
             is_hidden:      false,
             is_dir:         true,
+            size:           0,
+            depth:          0,
+            permissions:    None,
+            attributes:     None,
         };
 
         let serialized = bincode::serialize(&entry)?;
@@ -240,4 +1032,221 @@ mod tests {
         let _ = fs::remove_dir_all(&temp_dir);
         Ok(())
     }
+
+    #[test]
+    fn test_baseline_index_decodes_with_default_hash_algo() -> Result<()> {
+        let last_scan = Utc::now() - chrono::Duration::days(3);
+        let baseline = LegacyRkyvCacheIndex {
+            offsets: HashMap::from([(PathBuf::from("/data"), 0), (PathBuf::from("/data/docs"), 64)]),
+            last_scan,
+            root: PathBuf::from("/data"),
+            last_scanned_root: PathBuf::from("/data"),
+            #[cfg(windows)]
+            usn_state: USNJournalState::default(),
+            skip_stats: HashMap::from([(".git".to_string(), 2), ("node_modules".to_string(), 1)]),
+        };
+        let index = decode_index(&bincode::serialize(&baseline)?)?;
+        assert_eq!(index.offsets.len(), 2);
+        assert_eq!(index.last_scan, last_scan);
+        assert_eq!(index.root, Path::new("/data"));
+        assert_eq!(index.hash_algo, HashAlgorithm::default());
+        assert_eq!(index.skip_stats.get(".git"), Some(&2));
+
+        // The current layout still round-trips, including a non-default algorithm
+        let mut current = RkyvCacheIndex::new();
+        current.hash_algo = HashAlgorithm::Sha256;
+        current.skip_stats.insert(".git".to_string(), 1);
+        assert_eq!(decode_index(&bincode::serialize(&current)?)?.hash_algo, HashAlgorithm::Sha256);
+
+        assert!(decode_index(b"not an index").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_without_scan_mode_decodes_with_default_mode() -> Result<()> {
+        let hashed = HashedRkyvCacheIndex {
+            offsets:                   HashMap::from([(PathBuf::from("/data"), 0)]),
+            last_scan:                 Utc::now(),
+            root:                      PathBuf::from("/data"),
+            last_scanned_root:         PathBuf::from("/data"),
+            hash_algo:                 HashAlgorithm::Sha256,
+            #[cfg(windows)]
+            usn_state:                 USNJournalState::default(),
+            skip_stats:                HashMap::from([(".git".to_string(), 2)]),
+        };
+        let index = decode_index(&bincode::serialize(&hashed)?)?;
+        assert_eq!(index.hash_algo, HashAlgorithm::Sha256);
+        assert_eq!(index.skip_stats.get(".git"), Some(&2));
+        assert_eq!(index.scan_mode, ScanMode::default());
+
+        let mut current = RkyvCacheIndex::new();
+        current.scan_mode = ScanMode {
+            structure_only:   true,
+            no_metadata:      true,
+            skip_hidden_dirs: true,
+            symlinks:         ptree_core::SymlinkMode::Show,
+        };
+        assert_eq!(decode_index(&bincode::serialize(&current)?)?.scan_mode, current.scan_mode);
+        Ok(())
+    }
+
+    #[test]
+    fn test_undecodable_index_is_not_a_snapshot() -> Result<()> {
+        let temp_dir = env::temp_dir().join("ptree_rkyv_corrupt_index_test");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir)?;
+        let cache_path = temp_dir.join("ptree.dat");
+        fs::write(cache_path.with_extension("idx"), b"not an index")?;
+        fs::write(&cache_path, data_header(CacheFormat::Bincode))?;
+
+        assert!(RkyvMmapCache::open(&cache_path.with_extension("idx"), &cache_path).is_err());
+        let cache = crate::cache::DiskCache::open(&cache_path)?;
+        assert!(!cache.has_cache_snapshot());
+
+        let _ = fs::remove_dir_all(&temp_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_legacy_entry_gets_depth_on_load() -> Result<()> {
+        let temp_dir = env::temp_dir().join("ptree_rkyv_legacy_test");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir)?;
+        let cache_path = temp_dir.join("ptree.dat");
+
+        // A baseline cache: headerless length-prefixed records and the index
+        // layout from before `hash_algo`
+        let root = PathBuf::from("/data");
+        let nested = root.join("a").join("b");
+        let mut data = Vec::new();
+        let mut offsets = HashMap::new();
+        for (path, children) in [(&root, vec!["a".to_string()]), (&nested, Vec::new())] {
+            let legacy = LegacyRkyvDirEntry {
+                path: path.clone(),
+                name: path.file_name().unwrap().to_string_lossy().to_string(),
+                modified: Utc::now(),
+                content_hash: 7,
+                children,
+                symlink_target: None,
+                is_hidden: false,
+                is_dir: true,
+            };
+            let serialized = bincode::serialize(&legacy)?;
+            offsets.insert(path.clone(), data.len() as u64);
+            data.extend_from_slice(&(serialized.len() as u32).to_le_bytes());
+            data.extend_from_slice(&serialized);
+        }
+        fs::write(&cache_path, data)?;
+        let index = LegacyRkyvCacheIndex {
+            offsets,
+            last_scan: Utc::now(),
+            root: root.clone(),
+            last_scanned_root: root.clone(),
+            #[cfg(windows)]
+            usn_state: USNJournalState::default(),
+            skip_stats: HashMap::new(),
+        };
+        fs::write(cache_path.with_extension("idx"), bincode::serialize(&index)?)?;
+
+        let mut cache = crate::cache::DiskCache::open(&cache_path)?;
+        assert!(cache.has_cache_snapshot());
+        assert_eq!(cache.entry_count_hint(), 2);
+        cache.load_all_entries_lazy(&cache_path)?;
+        assert_eq!(cache.entries[&root].depth, 0);
+        let entry = &cache.entries[&nested];
+        assert_eq!(entry.depth, 2);
+        assert_eq!(entry.content_hash, 7);
+
+        let _ = fs::remove_dir_all(&temp_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_entry_without_permissions_keeps_size_on_load() -> Result<()> {
+        let temp_dir = env::temp_dir().join("ptree_rkyv_sized_test");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir)?;
+        let index_path = temp_dir.join("test.idx");
+        let data_path = temp_dir.join("test.dat");
+
+        let path = PathBuf::from("/data/file.bin");
+        let sized = SizedRkyvDirEntry {
+            path:           path.clone(),
+            name:           "file.bin".to_string(),
+            modified:       Utc::now(),
+            content_hash:   0,
+            children:       Vec::new(),
+            symlink_target: None,
+            is_hidden:      false,
+            is_dir:         false,
+            size:           4096,
+            depth:          1,
+        };
+        let serialized = bincode::serialize(&sized)?;
+        let mut data = (serialized.len() as u32).to_le_bytes().to_vec();
+        data.extend_from_slice(&serialized);
+        fs::write(&data_path, data)?;
+
+        let mut index = RkyvCacheIndex::new();
+        index.root = PathBuf::from("/data");
+        index.offsets.insert(path.clone(), 0);
+        fs::write(&index_path, bincode::serialize(&index)?)?;
+
+        let cache = RkyvMmapCache::open(&index_path, &data_path)?;
+        let entry = cache.get_entry(&path)?.expect("entry should load");
+        assert_eq!(entry.size, 4096);
+        assert_eq!(entry.depth, 1);
+        assert_eq!(entry.permissions, None);
+        assert_eq!(entry.attributes, None);
+
+        let _ = fs::remove_dir_all(&temp_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_entry_without_attributes_keeps_permissions_on_load() -> Result<()> {
+        let temp_dir = env::temp_dir().join("ptree_rkyv_permissioned_test");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir)?;
+        let index_path = temp_dir.join("test.idx");
+        let data_path = temp_dir.join("test.dat");
+
+        let path = PathBuf::from("/data/run.sh");
+        let permissions = UnixPermissions {
+            mode: 0o755,
+            uid:  1000,
+            gid:  1000,
+        };
+        let entry = PermissionedRkyvDirEntry {
+            path:           path.clone(),
+            name:           "run.sh".to_string(),
+            modified:       Utc::now(),
+            content_hash:   0,
+            children:       Vec::new(),
+            symlink_target: None,
+            is_hidden:      false,
+            is_dir:         false,
+            size:           12,
+            depth:          1,
+            permissions:    Some(permissions),
+        };
+        let serialized = bincode::serialize(&entry)?;
+        let mut data = (serialized.len() as u32).to_le_bytes().to_vec();
+        data.extend_from_slice(&serialized);
+        fs::write(&data_path, data)?;
+
+        let mut index = RkyvCacheIndex::new();
+        index.root = PathBuf::from("/data");
+        index.offsets.insert(path.clone(), 0);
+        fs::write(&index_path, bincode::serialize(&index)?)?;
+
+        let cache = RkyvMmapCache::open(&index_path, &data_path)?;
+        let entry = cache.get_entry(&path)?.expect("entry should load");
+        assert_eq!(entry.size, 12);
+        assert_eq!(entry.permissions, Some(permissions));
+        assert_eq!(entry.attributes, None);
+
+        let _ = fs::remove_dir_all(&temp_dir);
+        Ok(())
+    }
 }
@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::cache::{DirEntry, DiskCache};
+use crate::units::{format_size, group_thousands};
+
+// ============================================================================
+// Collapsed Directories (`--collapse-threshold`)
+// ============================================================================
+
+/// Which directories tree output shows as a single summary line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CollapseRule {
+    /// Directories with more direct children than this are collapsed
+    pub threshold: usize,
+    /// Directories always listed in full (`--expand`)
+    pub expand:    HashSet<PathBuf>,
+    /// Summary sizes in SI units (`--si`)
+    pub si:        bool,
+}
+
+impl DiskCache {
+    /// Whether the tree shows `entry` as a summary line instead of its children
+    pub(crate) fn is_collapsed(&self, entry: &DirEntry) -> bool {
+        self.collapse.as_ref().is_some_and(|rule| {
+            entry.is_dir && entry.children.len() > rule.threshold && !rule.expand.contains(&entry.path)
+        })
+    }
+
+    /// ` (48,213 entries, 1.2 GiB)` for a collapsed directory, counting everything below it
+    pub(crate) fn collapse_summary(&self, entry: &DirEntry) -> String {
+        let (count, size) = self.subtree_totals(&entry.path);
+        let si = self.collapse.as_ref().is_some_and(|rule| rule.si);
+        format!(" ({} entries, {})", group_thousands(count), format_size(size, si))
+    }
+
+    /// Number of cached entries below `path` and their total size
+    pub(crate) fn subtree_totals(&self, path: &Path) -> (usize, u64) {
+        let mut count = 0;
+        let mut size = 0;
+        let mut stack = vec![path.to_path_buf()];
+        while let Some(dir) = stack.pop() {
+            for child in self.children_entries(&dir) {
+                count += 1;
+                if child.is_dir {
+                    stack.push(child.path.clone());
+                } else {
+                    size += child.size;
+                }
+            }
+        }
+        (count, size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{cache_with, dir, file};
+
+    fn fixture() -> DiskCache {
+        let deps: Vec<String> = (0..5).map(|i| format!("dep{i}")).collect();
+        let dep_entries = deps.iter().flat_map(|dep| {
+            let path = format!("/proj/node_modules/{dep}");
+            [
+                DirEntry {
+                    depth: 2,
+                    ..dir(&path, &["index.js"])
+                },
+                DirEntry {
+                    depth: 3,
+                    ..file(format!("{path}/index.js"), 1024)
+                },
+            ]
+        });
+        let dep_names: Vec<&str> = deps.iter().map(String::as_str).collect();
+        cache_with(
+            "/proj",
+            [
+                dir("/proj", &["node_modules", "src"]),
+                DirEntry {
+                    depth: 1,
+                    ..dir("/proj/node_modules", &dep_names)
+                },
+                DirEntry {
+                    depth: 1,
+                    ..dir("/proj/src", &["lib.rs", "main.rs"])
+                },
+                DirEntry {
+                    depth: 2,
+                    ..file("/proj/src/lib.rs", 10)
+                },
+                DirEntry {
+                    depth: 2,
+                    ..file("/proj/src/main.rs", 10)
+                },
+            ]
+            .into_iter()
+            .chain(dep_entries),
+        )
+    }
+
+    #[test]
+    fn test_directories_over_threshold_collapse_to_summary() -> anyhow::Result<()> {
+        let mut cache = fixture();
+        cache.collapse = Some(CollapseRule {
+            threshold: 3,
+            ..CollapseRule::default()
+        });
+
+        // node_modules has 5 children: one line for its 10 entries; src has 2 and is listed
+        assert_eq!(
+            cache.build_tree_output()?,
+            "/proj\n├── node_modules/ (10 entries, 5.0 KiB)\n└── src\n    ├── lib.rs\n    └── main.rs\n"
+        );
+
+        // --classify already supplies the slash
+        cache.classify = true;
+        assert!(cache
+            .build_tree_output()?
+            .contains("── node_modules/ (10 entries, 5.0 KiB)\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_under_threshold_and_expanded_render_normally() -> anyhow::Result<()> {
+        let mut cache = fixture();
+        let full = cache.build_tree_output()?;
+        assert!(full.contains("dep4\n"));
+
+        cache.collapse = Some(CollapseRule {
+            threshold: 5,
+            ..CollapseRule::default()
+        });
+        assert_eq!(cache.build_tree_output()?, full);
+
+        cache.collapse = Some(CollapseRule {
+            threshold: 1,
+            expand:    HashSet::from([PathBuf::from("/proj/node_modules")]),
+            si:        true,
+        });
+        let out = cache.build_tree_output()?;
+        assert!(out.contains("├── node_modules\n"), "{out}");
+        assert!(out.contains("└── src/ (2 entries, 20 B)\n"), "{out}");
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+use std::path::Path;
+use std::{fmt, fs};
+
+use anyhow::Result;
+
+use crate::cache::DiskCache;
+use crate::prune::CacheIssue;
+use crate::units::format_size;
+
+// ============================================================================
+// Cache Compaction (`--cache-compact`)
+// ============================================================================
+
+/// Outcome of `DiskCache::compact`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompactionReport {
+    /// Entries dropped because their path no longer exists
+    pub entries_removed: usize,
+    /// Child names dropped or added so child lists match the cached entries
+    pub links_fixed:     usize,
+    /// Size of the index and data files before and after the rewrite
+    pub bytes_before:    u64,
+    pub bytes_after:     u64,
+}
+
+impl CompactionReport {
+    pub fn bytes_reclaimed(&self) -> u64 {
+        self.bytes_before.saturating_sub(self.bytes_after)
+    }
+}
+
+impl fmt::Display for CompactionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<20}{}", "Entries removed:", self.entries_removed)?;
+        writeln!(f, "{:<20}{}", "Child links fixed:", self.links_fixed)?;
+        writeln!(
+            f,
+            "{:<20}{} ({} -> {})",
+            "Bytes reclaimed:",
+            format_size(self.bytes_reclaimed(), false),
+            format_size(self.bytes_before, false),
+            format_size(self.bytes_after, false)
+        )
+    }
+}
+
+impl DiskCache {
+    /// Load every entry, drop stale ones, repair child lists and rewrite the cache
+    ///
+    /// The rewrite goes through `save`, so it uses `cache_format` and the data
+    /// file keeps only records the index points at: records orphaned by earlier
+    /// appends or removals are reclaimed. No directory is scanned.
+    pub fn compact(&mut self, cache_path: &Path) -> Result<CompactionReport> {
+        let bytes_before = cache_files_size(cache_path);
+        self.load_all_entries_lazy(cache_path)?;
+
+        let entries_removed = self.prune_stale();
+        let links_fixed = self.rebuild_children();
+        self.save(cache_path)?;
+
+        Ok(CompactionReport {
+            entries_removed,
+            links_fixed,
+            bytes_before,
+            bytes_after: cache_files_size(cache_path),
+        })
+    }
+
+    /// Make child lists agree with the loaded entries
+    ///
+    /// Names without a cached entry are dropped and entries missing from their
+    /// cached parent's list are added back. Returns the number of names changed.
+    pub fn rebuild_children(&mut self) -> usize {
+        let issues = self.verify();
+        for issue in &issues {
+            match issue {
+                CacheIssue::MissingChild { parent, child } => {
+                    if let Some(entry) = self.entries.get_mut(parent) {
+                        entry.children.retain(|name| name != child);
+                    }
+                }
+                CacheIssue::Orphan(path) => {
+                    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+                        continue;
+                    };
+                    if let Some(entry) = self.entries.get_mut(parent) {
+                        entry.children.push(name.to_string_lossy().to_string());
+                    }
+                }
+            }
+        }
+        issues.len()
+    }
+}
+
+/// Combined size of the `.idx` and `.dat` files behind `cache_path` (0 if missing)
+fn cache_files_size(cache_path: &Path) -> u64 {
+    ["idx", "dat"]
+        .iter()
+        .filter_map(|ext| fs::metadata(cache_path.with_extension(ext)).ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use chrono::Utc;
+
+    use super::*;
+    use crate::cache_rkyv::{RkyvDirEntry, RkyvMmapCache};
+    use crate::fixtures::{cache_with, dir, file};
+
+    #[test]
+    fn test_compact_drops_stale_entries_and_dead_records() -> Result<()> {
+        let temp = std::env::temp_dir().join("ptree_test_cache_compact");
+        let _ = fs::remove_dir_all(&temp);
+        let root = temp.join("tree");
+        fs::create_dir_all(root.join("live"))?;
+        fs::write(root.join("live").join("keep.txt"), b"x")?;
+        let cache_path = temp.join("cache").join("ptree.dat");
+
+        let mut cache = cache_with(
+            root.clone(),
+            [
+                dir(root.clone(), &["live", "gone"]),
+                dir(root.join("live"), &["keep.txt"]),
+                file(root.join("live/keep.txt"), 0),
+                dir(root.join("gone"), &["old.txt"]),
+                file(root.join("gone/old.txt"), 0),
+            ],
+        );
+        cache.save(&cache_path)?;
+
+        // Dead records: appended to the data file but never indexed
+        let rkyv = RkyvMmapCache::open(&cache_path.with_extension("idx"), &cache_path.with_extension("dat"))?;
+        for i in 0..50 {
+            let path = root.join(format!("dead{i}"));
+            rkyv.append_entry(&RkyvDirEntry {
+                name: path.file_name().unwrap().to_string_lossy().to_string(),
+                path,
+                modified: Utc::now(),
+                content_hash: 0,
+                children: Vec::new(),
+                symlink_target: None,
+                is_hidden: false,
+                is_dir: false,
+                size: 0,
+                depth: 1,
+                permissions: None,
+                attributes: None,
+            })?;
+        }
+        drop(rkyv);
+
+        let mut cache = DiskCache::open(&cache_path)?;
+        let report = cache.compact(&cache_path)?;
+        assert_eq!(report.entries_removed, 2);
+        assert_eq!(report.links_fixed, 0);
+        assert!(report.bytes_after < report.bytes_before, "{report:?}");
+        assert_eq!(report.bytes_reclaimed(), report.bytes_before - report.bytes_after);
+
+        let reopened = DiskCache::open_readonly(&cache_path)?;
+        let mut paths: Vec<&PathBuf> = reopened.entries.keys().collect();
+        paths.sort();
+        assert_eq!(paths, vec![&root, &root.join("live"), &root.join("live/keep.txt")]);
+        assert_eq!(reopened.entries[&root].children, vec!["live".to_string()]);
+        assert_eq!(reopened.entries[&root.join("live")].children, vec!["keep.txt".to_string()]);
+
+        let _ = fs::remove_dir_all(&temp);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rebuild_children_repairs_links() {
+        let mut cache = cache_with("/data", [dir("/data", &["a", "ghost"]), dir("/data/a", &[]), file("/data/b", 0)]);
+
+        assert_eq!(cache.rebuild_children(), 2);
+        let mut children = cache.entries[Path::new("/data")].children.clone();
+        children.sort();
+        assert_eq!(children, vec!["a".to_string(), "b".to_string()]);
+        assert!(cache.verify().is_empty());
+    }
+}
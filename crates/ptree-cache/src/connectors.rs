@@ -0,0 +1,143 @@
+use ptree_core::TreeStyle;
+
+/// Glyphs used to draw tree branches
+///
+/// Every piece is four columns wide so nested prefixes line up regardless of
+/// preset. Shared by the plain, colored and changed-only tree builders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectorSet {
+    /// Connector before a child that has later siblings
+    pub branch:   &'static str,
+    /// Connector before the last child
+    pub last:     &'static str,
+    /// Prefix continuation below a child that has later siblings
+    pub vertical: &'static str,
+    /// Prefix continuation below the last child
+    pub space:    &'static str,
+}
+
+impl ConnectorSet {
+    pub const ASCII: ConnectorSet = ConnectorSet {
+        branch:   "|-- ",
+        last:     "`-- ",
+        vertical: "|   ",
+        space:    "    ",
+    };
+    pub const BOLD: ConnectorSet = ConnectorSet {
+        branch:   "┣━━ ",
+        last:     "┗━━ ",
+        vertical: "┃   ",
+        space:    "    ",
+    };
+    pub const CLASSIC: ConnectorSet = ConnectorSet {
+        branch:   "├── ",
+        last:     "└── ",
+        vertical: "│   ",
+        space:    "    ",
+    };
+    pub const MINIMAL: ConnectorSet = ConnectorSet {
+        branch:   "    ",
+        last:     "    ",
+        vertical: "    ",
+        space:    "    ",
+    };
+    pub const ROUNDED: ConnectorSet = ConnectorSet {
+        branch:   "├── ",
+        last:     "╰── ",
+        vertical: "│   ",
+        space:    "    ",
+    };
+
+    /// Connector drawn in front of a child's name
+    pub fn connector(&self, is_last_child: bool) -> &'static str {
+        if is_last_child {
+            self.last
+        } else {
+            self.branch
+        }
+    }
+
+    /// Prefix extension for the child's own children
+    pub fn continuation(&self, is_last_child: bool) -> &'static str {
+        if is_last_child {
+            self.space
+        } else {
+            self.vertical
+        }
+    }
+}
+
+impl Default for ConnectorSet {
+    fn default() -> Self {
+        ConnectorSet::CLASSIC
+    }
+}
+
+impl From<TreeStyle> for ConnectorSet {
+    fn from(style: TreeStyle) -> Self {
+        match style {
+            TreeStyle::Classic => ConnectorSet::CLASSIC,
+            TreeStyle::Rounded => ConnectorSet::ROUNDED,
+            TreeStyle::Ascii => ConnectorSet::ASCII,
+            TreeStyle::Minimal => ConnectorSet::MINIMAL,
+            TreeStyle::Bold => ConnectorSet::BOLD,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::cache::DiskCache;
+    use crate::fixtures::{cache_with, dir};
+
+    /// /data
+    /// ├── a
+    /// │   └── inner
+    /// └── b
+    fn fixture() -> DiskCache {
+        cache_with(
+            "/data",
+            [
+                dir("/data", &["a", "b"]),
+                dir("/data/a", &["inner"]),
+                dir("/data/a/inner", &[]),
+                dir("/data/b", &[]),
+            ],
+        )
+    }
+
+    fn render(style: TreeStyle) -> Result<String> {
+        let mut cache = fixture();
+        cache.connectors = style.into();
+        cache.build_tree_output()
+    }
+
+    #[test]
+    fn test_each_preset_renders_its_connectors() -> Result<()> {
+        assert_eq!(render(TreeStyle::Classic)?, "/data\n├── a\n│   └── inner\n└── b\n");
+        assert_eq!(render(TreeStyle::Rounded)?, "/data\n├── a\n│   ╰── inner\n╰── b\n");
+        assert_eq!(render(TreeStyle::Ascii)?, "/data\n|-- a\n|   `-- inner\n`-- b\n");
+        assert_eq!(render(TreeStyle::Minimal)?, "/data\n    a\n        inner\n    b\n");
+        assert_eq!(render(TreeStyle::Bold)?, "/data\n┣━━ a\n┃   ┗━━ inner\n┗━━ b\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_presets_compose_with_color() -> Result<()> {
+        colored::control::set_override(true);
+        let mut cache = fixture();
+        cache.connectors = TreeStyle::Bold.into();
+        let colored = cache.build_colored_tree_output()?;
+        colored::control::unset_override();
+
+        assert!(colored.contains('\x1b'));
+        assert!(colored.contains("┣━━ "));
+        assert!(colored.contains("┗━━ "));
+        assert!(colored.contains("┃   "));
+        assert!(!colored.contains("├── "));
+        Ok(())
+    }
+}
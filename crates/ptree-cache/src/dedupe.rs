@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use ptree_core::NameKind;
+use serde::Serialize;
+
+use crate::cache::DiskCache;
+
+// ============================================================================
+// Duplicate Names (`--dedupe-names`)
+// ============================================================================
+
+/// Entries below the root that share one name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NameGroup {
+    pub name:  String,
+    /// Sorted; always more than one
+    pub paths: Vec<PathBuf>,
+}
+
+impl DiskCache {
+    /// Names used by more than one cached entry below `self.root`
+    ///
+    /// Purely name-based: names are compared exactly and contents are never
+    /// looked at. Largest groups come first, ties by name.
+    pub fn duplicate_names(&self, kind: NameKind) -> Vec<NameGroup> {
+        let mut by_name: HashMap<&str, Vec<PathBuf>> = HashMap::new();
+
+        for (path, entry) in &self.entries {
+            let wanted = match kind {
+                NameKind::All => true,
+                NameKind::Files => !entry.is_dir,
+                NameKind::Dirs => entry.is_dir,
+            };
+            if wanted && *path != self.root && path.starts_with(&self.root) {
+                by_name.entry(&entry.name).or_default().push(path.clone());
+            }
+        }
+
+        let mut groups: Vec<NameGroup> = by_name
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .map(|(name, mut paths)| {
+                paths.sort();
+                NameGroup {
+                    name: name.to_string(),
+                    paths,
+                }
+            })
+            .collect();
+        groups.sort_by(|a, b| b.paths.len().cmp(&a.paths.len()).then_with(|| a.name.cmp(&b.name)));
+        groups
+    }
+
+    /// Render the duplicate names as `name (N)` headers, each followed by its paths
+    pub fn build_duplicate_names_report(&self, kind: NameKind) -> String {
+        let groups = self.duplicate_names(kind);
+        if groups.is_empty() {
+            return "(no duplicate names)\n".to_string();
+        }
+
+        let mut out = String::new();
+        for (i, group) in groups.iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            let _ = writeln!(out, "{} ({})", group.name, group.paths.len());
+            for path in &group.paths {
+                let _ = writeln!(out, "  {}", path.display());
+            }
+        }
+        out
+    }
+
+    /// Render the duplicate names as a pretty JSON array of `{name, paths}`
+    pub fn build_duplicate_names_json(&self, kind: NameKind) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(&self.duplicate_names(kind))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{cache_with, dir, file};
+
+    fn fixture() -> DiskCache {
+        cache_with(
+            "/data",
+            [
+                dir("/data", &[]),
+                file("/data/README.md", 0),
+                dir("/data/config", &[]),
+                file("/data/config/settings.toml", 0),
+                dir("/data/app", &[]),
+                file("/data/app/README.md", 0),
+                dir("/data/app/config", &[]),
+                file("/data/app/config/settings.toml", 0),
+                dir("/data/backup", &[]),
+                file("/data/backup/README.md", 0),
+                file("/data/backup/readme.md", 0),
+                file("/data/unique.txt", 0),
+                file("/elsewhere/README.md", 0),
+            ],
+        )
+    }
+
+    fn summary(groups: &[NameGroup]) -> Vec<(&str, usize)> {
+        groups.iter().map(|g| (g.name.as_str(), g.paths.len())).collect()
+    }
+
+    #[test]
+    fn test_duplicate_names_grouped_and_sorted() -> anyhow::Result<()> {
+        let cache = fixture();
+
+        let groups = cache.duplicate_names(NameKind::All);
+        assert_eq!(summary(&groups), vec![("README.md", 3), ("config", 2), ("settings.toml", 2)]);
+        assert_eq!(
+            groups[0].paths,
+            vec![
+                PathBuf::from("/data/README.md"),
+                PathBuf::from("/data/app/README.md"),
+                PathBuf::from("/data/backup/README.md"),
+            ]
+        );
+
+        assert_eq!(summary(&cache.duplicate_names(NameKind::Files)), vec![("README.md", 3), ("settings.toml", 2)]);
+        assert_eq!(summary(&cache.duplicate_names(NameKind::Dirs)), vec![("config", 2)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_names_report_and_json() -> anyhow::Result<()> {
+        let cache = fixture();
+        assert_eq!(
+            cache.build_duplicate_names_report(NameKind::Dirs),
+            "config (2)\n  /data/app/config\n  /data/config\n"
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&cache.build_duplicate_names_json(NameKind::Files)?)?;
+        assert_eq!(json[1]["name"], "settings.toml");
+        assert_eq!(json[1]["paths"].as_array().map(Vec::len), Some(2));
+
+        let mut single = DiskCache::new_empty();
+        single.root = PathBuf::from("/data");
+        assert_eq!(single.build_duplicate_names_report(NameKind::All), "(no duplicate names)\n");
+        Ok(())
+    }
+}
@@ -0,0 +1,390 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use ptree_core::{matches_skip, HashAlgorithm};
+use serde_json::{json, Value};
+
+use crate::cache::{compute_content_hash_with, has_directory_changed, DirEntry, DiskCache};
+use crate::connectors::ConnectorSet;
+
+/// Kind of change detected for a directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl ChangeKind {
+    /// Single-character marker used in change renders (`+`, `-`, `~`)
+    pub fn marker(&self) -> char {
+        match self {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Modified => '~',
+        }
+    }
+}
+
+/// Snapshot a single directory from the live filesystem
+///
+/// Produces a `DirEntry` hashed the same way traversal hashes directories,
+/// so it can be compared against a cached entry with `has_directory_changed`.
+/// Children matching `skip_dirs` (under the given case mode) are excluded,
+/// mirroring traversal. Returns None if the path is missing or not a readable directory.
+pub fn live_dir_entry(
+    path: &Path,
+    skip_dirs: &HashSet<String>,
+    case_sensitive: bool,
+    hash_algo: HashAlgorithm,
+) -> Option<DirEntry> {
+    let metadata = fs::metadata(path).ok()?;
+    if !metadata.is_dir() {
+        return None;
+    }
+
+    let modified = metadata
+        .modified()
+        .map(DateTime::<Utc>::from)
+        .unwrap_or_else(|_| Utc::now());
+
+    let children: Vec<String> = fs::read_dir(path)
+        .ok()?
+        .flatten()
+        // Non-UTF-8 names are never cached, so they can't count as changes either
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| !matches_skip(name, skip_dirs, case_sensitive))
+        .collect();
+
+    let content_hash = compute_content_hash_with(hash_algo, path, modified, &children, &HashMap::new());
+
+    Some(DirEntry {
+        path: path.to_path_buf(),
+        name: path
+            .file_name()
+            .and_then(|n| n.to_str().map(|s| s.to_string()))
+            .unwrap_or_default(),
+        modified,
+        content_hash,
+        children,
+        symlink_target: None,
+        is_hidden: false,
+        is_dir: true,
+        size: 0,
+        depth: 0,
+        permissions: None,
+        attributes: None,
+    })
+}
+
+impl DiskCache {
+    // ============================================================================
+    // Live Change Detection
+    // ============================================================================
+
+    /// Compare every cached directory against the live filesystem
+    ///
+    /// - Cached directories that no longer exist are reported as `Removed`
+    /// - Cached directories whose content hash differs are reported as `Modified`
+    /// - Subdirectories of a modified directory missing from the cache are `Added`
+    ///
+    /// Results are sorted by path for stable output.
+    pub fn detect_live_changes(&self, skip_dirs: &HashSet<String>, case_sensitive: bool) -> Vec<(PathBuf, ChangeKind)> {
+        let mut changes = Vec::new();
+
+        for (path, cached) in self.entries.iter().filter(|(_, e)| e.is_dir) {
+            let live = match live_dir_entry(path, skip_dirs, case_sensitive, self.hash_algo) {
+                Some(live) => live,
+                None => {
+                    changes.push((path.clone(), ChangeKind::Removed));
+                    continue;
+                }
+            };
+
+            if !has_directory_changed(cached, &live) {
+                continue;
+            }
+            changes.push((path.clone(), ChangeKind::Modified));
+
+            for child_name in &live.children {
+                let child_path = path.join(child_name);
+                if !self.entries.contains_key(&child_path) && child_path.is_dir() {
+                    changes.push((child_path, ChangeKind::Added));
+                }
+            }
+        }
+
+        changes.sort_by(|a, b| a.0.cmp(&b.0));
+        changes
+    }
+
+    /// Build a tree containing only changed directories and their ancestors
+    ///
+    /// Changed nodes are prefixed with their `ChangeKind` marker; ancestors
+    /// included purely for context are rendered without a marker.
+    pub fn build_changed_tree_output(&self, skip_dirs: &HashSet<String>, case_sensitive: bool) -> Result<String> {
+        let changes = self.detect_live_changes(skip_dirs, case_sensitive);
+        Ok(self.render_changes(&changes))
+    }
+
+    fn render_changes(&self, changes: &[(PathBuf, ChangeKind)]) -> String {
+        if changes.is_empty() {
+            return "(no changes since last scan)\n".to_string();
+        }
+
+        let markers: HashMap<&Path, ChangeKind> = changes.iter().map(|(p, k)| (p.as_path(), *k)).collect();
+
+        // Collect changed paths plus every ancestor up to the root, grouped by parent
+        let mut tree: BTreeMap<PathBuf, BTreeSet<String>> = BTreeMap::new();
+        for (path, _) in changes {
+            let mut current = path.as_path();
+            while current != self.root {
+                let (Some(parent), Some(name)) = (current.parent(), current.file_name()) else {
+                    break;
+                };
+                tree.entry(parent.to_path_buf())
+                    .or_default()
+                    .insert(name.to_string_lossy().to_string());
+                current = parent;
+            }
+        }
+
+        let mut output = format!("{}\n", self.root.display());
+        render_change_level(&mut output, &self.root, "", &tree, &markers, &self.connectors);
+        output
+    }
+}
+
+// ============================================================================
+// Cache-to-Cache Delta (`GET /tree/updates`)
+// ============================================================================
+
+/// Entries added, removed or modified going from `old` to `new`, sorted by path
+///
+/// Directories count as modified when their content hash or child names
+/// differ; files when their size, type or symlink target does. File
+/// timestamps are ignored since they record scan time, not content.
+pub fn diff_caches(old: &DiskCache, new: &DiskCache) -> Vec<(PathBuf, ChangeKind)> {
+    let mut changes: Vec<(PathBuf, ChangeKind)> = old
+        .entries
+        .keys()
+        .filter(|path| !new.entries.contains_key(*path))
+        .map(|path| (path.clone(), ChangeKind::Removed))
+        .collect();
+
+    for (path, entry) in &new.entries {
+        match old.entries.get(path) {
+            None => changes.push((path.clone(), ChangeKind::Added)),
+            Some(previous) if entry_changed(previous, entry) => changes.push((path.clone(), ChangeKind::Modified)),
+            Some(_) => {}
+        }
+    }
+
+    changes.sort_by(|a, b| a.0.cmp(&b.0));
+    changes
+}
+
+/// `{added, removed, modified}` delta describing how `old` became `new`
+///
+/// `added` and `modified` hold the new state of each entry (`path`, `name`,
+/// `is_dir`, `size`, sorted `children`); `removed` holds paths only. Applying
+/// the removals, then the additions and modifications, to `old` yields `new`.
+pub fn build_json_patch(old: &DiskCache, new: &DiskCache) -> Value {
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut modified = Vec::new();
+
+    for (path, kind) in diff_caches(old, new) {
+        match kind {
+            ChangeKind::Added => added.push(patch_node(&new.entries[&path])),
+            ChangeKind::Removed => removed.push(json!(path.to_string_lossy())),
+            ChangeKind::Modified => modified.push(patch_node(&new.entries[&path])),
+        }
+    }
+
+    json!({ "added": added, "removed": removed, "modified": modified })
+}
+
+fn entry_changed(old: &DirEntry, new: &DirEntry) -> bool {
+    if old.is_dir != new.is_dir {
+        return true;
+    }
+    if new.is_dir {
+        has_directory_changed(old, new) || sorted_children(old) != sorted_children(new)
+    } else {
+        old.size != new.size || old.symlink_target != new.symlink_target
+    }
+}
+
+fn sorted_children(entry: &DirEntry) -> Vec<&String> {
+    let mut children: Vec<&String> = entry.children.iter().collect();
+    children.sort();
+    children
+}
+
+fn patch_node(entry: &DirEntry) -> Value {
+    json!({
+        "path": entry.path.to_string_lossy(),
+        "name": entry.name,
+        "is_dir": entry.is_dir,
+        "size": entry.size,
+        "children": sorted_children(entry),
+    })
+}
+
+fn render_change_level(
+    output: &mut String,
+    path: &Path,
+    prefix: &str,
+    tree: &BTreeMap<PathBuf, BTreeSet<String>>,
+    markers: &HashMap<&Path, ChangeKind>,
+    connectors: &ConnectorSet,
+) {
+    let Some(children) = tree.get(path) else {
+        return;
+    };
+
+    for (i, child_name) in children.iter().enumerate() {
+        let is_last_child = i == children.len() - 1;
+        let branch = connectors.connector(is_last_child);
+        let child_prefix = connectors.continuation(is_last_child);
+
+        let child_path = path.join(child_name);
+        let label = match markers.get(child_path.as_path()) {
+            Some(kind) => format!("{} {}", kind.marker(), child_name),
+            None => child_name.clone(),
+        };
+
+        output.push_str(&format!("{}{}{}\n", prefix, branch, label));
+        render_change_level(output, &child_path, &format!("{}{}", prefix, child_prefix), tree, markers, connectors);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(root: &Path) -> DiskCache {
+        let mut cache = DiskCache::new_empty();
+        cache.root = root.to_path_buf();
+        let mut stack = vec![root.to_path_buf()];
+        while let Some(dir) = stack.pop() {
+            let entry = live_dir_entry(&dir, &HashSet::new(), true, cache.hash_algo).unwrap();
+            for child in &entry.children {
+                let child_path = dir.join(child);
+                if child_path.is_dir() {
+                    stack.push(child_path);
+                }
+            }
+            cache.entries.insert(dir, entry);
+        }
+        cache
+    }
+
+    #[test]
+    fn test_changed_only_omits_unchanged_and_keeps_ancestors() -> Result<()> {
+        let root = std::env::temp_dir().join("ptree_test_changed_only");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("a").join("deep"))?;
+        fs::create_dir_all(root.join("b"))?;
+        fs::create_dir_all(root.join("gone"))?;
+
+        let cache = snapshot(&root);
+        assert!(cache.detect_live_changes(&HashSet::new(), true).is_empty());
+
+        fs::write(root.join("a").join("deep").join("new.txt"), b"x")?;
+        fs::remove_dir_all(root.join("gone"))?;
+
+        let changes = cache.detect_live_changes(&HashSet::new(), true);
+        assert!(changes.contains(&(root.join("a").join("deep"), ChangeKind::Modified)));
+        assert!(changes.contains(&(root.join("gone"), ChangeKind::Removed)));
+        assert!(!changes
+            .iter()
+            .any(|(p, _)| p == &root.join("a") || p == &root.join("b")));
+
+        let output = cache.build_changed_tree_output(&HashSet::new(), true)?;
+        assert!(output.contains("── a\n"), "ancestor should be shown without marker: {}", output);
+        assert!(output.contains("~ deep"));
+        assert!(output.contains("- gone"));
+        assert!(!output.contains("── b"));
+
+        let _ = fs::remove_dir_all(&root);
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_patch_between_cache_states() {
+        let build = |entries: &[(&str, &[&str], bool, u64)]| {
+            let mut cache = DiskCache::new_empty();
+            cache.root = PathBuf::from("/data");
+            for &(path, children, is_dir, size) in entries {
+                let entry = DirEntry::builder(path)
+                    .is_dir(is_dir)
+                    .children(children.iter().copied())
+                    .size(size)
+                    .build()
+                    .unwrap();
+                cache.entries.insert(PathBuf::from(path), entry);
+            }
+            cache
+        };
+
+        let old = build(&[
+            ("/data", &["docs", "old", "readme.md"], true, 0),
+            ("/data/docs", &["a.txt"], true, 0),
+            ("/data/docs/a.txt", &[], false, 10),
+            ("/data/old", &[], true, 0),
+            ("/data/readme.md", &[], false, 5),
+        ]);
+        let new = build(&[
+            ("/data", &["readme.md", "docs", "new"], true, 0),
+            ("/data/docs", &["a.txt"], true, 0),
+            ("/data/docs/a.txt", &[], false, 20),
+            ("/data/new", &[], true, 0),
+            ("/data/readme.md", &[], false, 5),
+        ]);
+
+        assert!(build_json_patch(&old, &old.clone())
+            .as_object()
+            .unwrap()
+            .values()
+            .all(|v| v.as_array().unwrap().is_empty()));
+
+        let patch = build_json_patch(&old, &new);
+        assert_eq!(patch["removed"], json!(["/data/old"]));
+        assert_eq!(
+            patch["added"],
+            json!([{ "path": "/data/new", "name": "new", "is_dir": true, "size": 0, "children": [] }])
+        );
+        let modified: Vec<&str> = patch["modified"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|n| n["path"].as_str().unwrap())
+            .collect();
+        // Child order alone ("readme.md" moved) is not a change; the swapped name is
+        assert_eq!(modified, ["/data", "/data/docs/a.txt"]);
+        assert_eq!(patch["modified"][0]["children"], json!(["docs", "new", "readme.md"]));
+        assert_eq!(patch["modified"][1]["size"], 20);
+    }
+
+    #[test]
+    fn test_changed_only_reports_added_directory() -> Result<()> {
+        let root = std::env::temp_dir().join("ptree_test_changed_only_added");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("a"))?;
+
+        let cache = snapshot(&root);
+        fs::create_dir_all(root.join("a").join("fresh"))?;
+
+        let output = cache.build_changed_tree_output(&HashSet::new(), true)?;
+        assert!(output.contains("~ a"));
+        assert!(output.contains("+ fresh"));
+
+        let _ = fs::remove_dir_all(&root);
+        Ok(())
+    }
+}
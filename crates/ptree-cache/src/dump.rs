@@ -0,0 +1,81 @@
+use std::fmt::Write;
+
+use crate::cache::DiskCache;
+
+impl DiskCache {
+    // ============================================================================
+    // Raw Cache Dump
+    // ============================================================================
+
+    /// Flat, greppable dump of the cache's internal representation
+    ///
+    /// Metadata comes first as `key: value` lines, then one tab-separated
+    /// `key=value` record per entry, sorted by path so dumps diff cleanly.
+    pub fn build_dump_output(&self) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "root: {}", self.root.display());
+        let _ = writeln!(out, "last_scan: {}", self.last_scan.to_rfc3339());
+        let _ = writeln!(out, "last_scanned_root: {}", self.last_scanned_root.display());
+        let _ = writeln!(out, "hash_algo: {}", self.hash_algo);
+        let _ = writeln!(out, "entries: {}", self.entries.len());
+        out.push_str("---\n");
+
+        let mut entries: Vec<_> = self.entries.values().collect();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        for entry in entries {
+            let symlink = entry
+                .symlink_target
+                .as_ref()
+                .map(|t| t.display().to_string())
+                .unwrap_or_default();
+            // `--no-metadata` entries carry placeholder times, not real ones
+            let modified = if entry.has_metadata() {
+                entry.modified.to_rfc3339()
+            } else {
+                "unknown".to_string()
+            };
+            let _ = writeln!(
+                out,
+                "path={}\tname={}\tkind={}\tmodified={}\tsize={}\tchildren={}\tdepth={}\thash={:016x}\thidden={}\tsymlink={}",
+                entry.path.display(),
+                entry.name,
+                if entry.is_dir { "dir" } else { "file" },
+                modified,
+                entry.size,
+                entry.children.len(),
+                entry.depth,
+                entry.content_hash,
+                entry.is_hidden,
+                symlink,
+            );
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::DirEntry;
+    use crate::fixtures::cache_with;
+
+    #[test]
+    fn test_dump_lists_metadata_and_entries() {
+        let root = DirEntry::builder("/data")
+            .content_hash(0xabc)
+            .children(["a", "b"])
+            .build()
+            .unwrap();
+        let cache = cache_with("/data", [root]);
+
+        let dump = cache.build_dump_output();
+        assert!(dump.starts_with("root: /data\n"));
+        assert!(dump.contains("entries: 1\n"));
+        let record = dump.lines().find(|l| l.starts_with("path=/data\t")).unwrap();
+        assert!(record.contains("\tchildren=2\t"));
+        assert!(record.contains("\tkind=dir\t"));
+        assert!(record.contains("\thash=0000000000000abc\t"));
+    }
+}
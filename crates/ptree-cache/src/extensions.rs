@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use ptree_core::ExtensionSort;
+use serde::Serialize;
+
+use crate::cache::DiskCache;
+use crate::units::{display_width, format_size, group_thousands, pad_right};
+
+/// Bucket for files without an extension
+pub const NO_EXTENSION: &str = "(none)";
+
+// ============================================================================
+// Extension Summary
+// ============================================================================
+
+/// File count and total size for one extension
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExtensionTally {
+    /// Lowercased extension with its dot (`.rs`), or `(none)`
+    pub extension: String,
+    pub files:     usize,
+    pub size:      u64,
+}
+
+impl DiskCache {
+    /// Tally cached files below `self.root` by extension
+    ///
+    /// Extensions are compared case-insensitively; dotfiles such as
+    /// `.gitignore` have no extension. Symlinks are not counted.
+    pub fn extension_tallies(&self, sort: ExtensionSort) -> Vec<ExtensionTally> {
+        let mut buckets: HashMap<String, (usize, u64)> = HashMap::new();
+
+        for (path, entry) in &self.entries {
+            if entry.is_dir || entry.symlink_target.is_some() || !path.starts_with(&self.root) {
+                continue;
+            }
+            let extension = Path::new(&entry.name)
+                .extension()
+                .map(|ext| format!(".{}", ext.to_string_lossy().to_lowercase()))
+                .unwrap_or_else(|| NO_EXTENSION.to_string());
+
+            let bucket = buckets.entry(extension).or_default();
+            bucket.0 += 1;
+            bucket.1 += entry.size;
+        }
+
+        let mut tallies: Vec<ExtensionTally> = buckets
+            .into_iter()
+            .map(|(extension, (files, size))| ExtensionTally { extension, files, size })
+            .collect();
+
+        tallies.sort_by(|a, b| {
+            let primary = match sort {
+                ExtensionSort::Count => b.files.cmp(&a.files).then(b.size.cmp(&a.size)),
+                ExtensionSort::Size => b.size.cmp(&a.size).then(b.files.cmp(&a.files)),
+            };
+            primary.then_with(|| a.extension.cmp(&b.extension))
+        });
+        tallies
+    }
+
+    /// Render the extension summary as an aligned table
+    ///
+    /// One row per extension, e.g. `.rs   1,234 files   45.6 MiB`.
+    pub fn build_extension_table(&self, sort: ExtensionSort, si: bool) -> String {
+        let rows: Vec<(String, String, String)> = self
+            .extension_tallies(sort)
+            .into_iter()
+            .map(|t| {
+                let noun = if t.files == 1 { "file" } else { "files" };
+                (t.extension, format!("{} {}", group_thousands(t.files), noun), format_size(t.size, si))
+            })
+            .collect();
+
+        // Extensions are taken from file names, so they may be in any script
+        let ext_width = rows.iter().map(|r| display_width(&r.0)).max().unwrap_or(0);
+        let count_width = rows.iter().map(|r| r.1.len()).max().unwrap_or(0);
+        let size_width = rows.iter().map(|r| r.2.len()).max().unwrap_or(0);
+
+        let mut out = String::new();
+        for (extension, count, size) in rows {
+            let _ =
+                writeln!(out, "{}  {:>count_width$}  {:>size_width$}", pad_right(&extension, ext_width), count, size);
+        }
+        out
+    }
+
+    /// Render the extension summary as a pretty JSON array
+    pub fn build_extension_json(&self, sort: ExtensionSort) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(&self.extension_tallies(sort))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{cache_with, dir, file};
+
+    fn fixture() -> DiskCache {
+        cache_with(
+            "/data",
+            [
+                file("/data/main.rs", 100),
+                file("/data/lib.RS", 200),
+                file("/data/src/util.rs", 300),
+                file("/data/video.mp4", 5_000_000),
+                file("/data/notes.md", 10),
+                file("/data/todo.md", 20),
+                file("/data/Makefile", 7),
+                file("/data/.gitignore", 3),
+                file("/elsewhere/other.rs", 1),
+                dir("/data/src", &[]),
+            ],
+        )
+    }
+
+    fn summary(tallies: &[ExtensionTally]) -> Vec<(&str, usize, u64)> {
+        tallies
+            .iter()
+            .map(|t| (t.extension.as_str(), t.files, t.size))
+            .collect()
+    }
+
+    #[test]
+    fn test_tallies_sorted_by_count_and_size() {
+        let cache = fixture();
+
+        assert_eq!(
+            summary(&cache.extension_tallies(ExtensionSort::Count)),
+            vec![
+                (".rs", 3, 600),
+                (".md", 2, 30),
+                (NO_EXTENSION, 2, 10),
+                (".mp4", 1, 5_000_000)
+            ]
+        );
+        assert_eq!(
+            summary(&cache.extension_tallies(ExtensionSort::Size)),
+            vec![
+                (".mp4", 1, 5_000_000),
+                (".rs", 3, 600),
+                (".md", 2, 30),
+                (NO_EXTENSION, 2, 10)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_table_and_json_output() {
+        let cache = fixture();
+
+        let table = cache.build_extension_table(ExtensionSort::Size, false);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                ".mp4     1 file  4.8 MiB",
+                ".rs     3 files    600 B",
+                ".md     2 files     30 B",
+                "(none)  2 files     10 B",
+            ]
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&cache.build_extension_json(ExtensionSort::Count).unwrap()).unwrap();
+        assert_eq!(json[0]["extension"], ".rs");
+        assert_eq!(json[0]["files"], 3);
+        assert_eq!(json[0]["size"], 600);
+    }
+
+    #[test]
+    fn test_table_aligns_wide_extensions() {
+        let mut cache = fixture();
+        for (path, size) in [("/data/報告.資料", 1), ("/data/icon.🦀", 2), ("/data/a.e\u{301}", 3)] {
+            let file = file(path, size);
+            cache.entries.insert(file.path.clone(), file);
+        }
+
+        // Every row is padded to the same display width whatever the script
+        let table = cache.build_extension_table(ExtensionSort::Count, false);
+        let widths: Vec<usize> = table.lines().map(display_width).collect();
+        assert_eq!(widths.len(), 7);
+        assert!(widths.windows(2).all(|w| w[0] == w[1]), "{table}");
+        assert!(table.ends_with("\n.資料    1 file      1 B\n"), "{table}");
+    }
+}
@@ -0,0 +1,796 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use ptree_core::{parse_byte_size, parse_duration};
+use regex::{Regex, RegexBuilder};
+
+use crate::cache::{DirEntry, DiskCache};
+
+// ============================================================================
+// Entry Filter Expressions (`--entries-filter`)
+// ============================================================================
+
+/// Compiled predicate over a cached entry
+type Predicate = Arc<dyn Fn(&DirEntry) -> bool + Send + Sync>;
+
+/// Parsed `--entries-filter` expression, e.g. `size > 1mb && name ~ "\.log$"`
+///
+/// Comparisons:
+/// - `size`, `depth`: `<` `<=` `>` `>=` `==` `!=` against a number (sizes take
+///   units such as `1mb` or `2GiB`)
+/// - `name`, `path`: `==` / `!=` against a string, `~` / `!~` against a regex
+/// - `mtime`: ordering against a date (`2024-01-31`) or RFC 3339 timestamp
+/// - `age`: ordering against a duration such as `90s`, `30m`, `12h`, `7d`, `2w`
+///   (a bare number is seconds)
+///
+/// The flags `dir`, `file`, `symlink` and `hidden` stand alone. Terms combine
+/// with `!`, `&&`, `||` and parentheses; `&&` binds tighter than `||`.
+/// Strings are double-quoted (only `\"` is an escape, so regex backslashes are
+/// written once) or bare words.
+#[derive(Clone)]
+pub struct EntryFilter {
+    source:    String,
+    predicate: Predicate,
+}
+
+impl EntryFilter {
+    /// Parse an expression; `match_case` controls regex case sensitivity
+    pub fn parse(expr: &str, match_case: bool) -> Result<Self> {
+        let tokens = tokenize(expr)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            match_case,
+            now: Utc::now(),
+        };
+        let predicate = parser.parse_or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            bail!("Unexpected {} in entries filter", token);
+        }
+
+        Ok(EntryFilter {
+            source: expr.to_string(),
+            predicate,
+        })
+    }
+
+    /// Keep entries whose name matches `pattern` (`--match`), plus their parents
+    pub fn name_regex(pattern: &str, match_case: bool) -> Result<Self> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(!match_case)
+            .build()
+            .map_err(|e| anyhow!("Invalid --match regex '{}': {}", pattern, e))?;
+        Ok(EntryFilter {
+            source:    format!("name ~ {:?}", pattern),
+            predicate: Arc::new(move |e| regex.is_match(&e.name)),
+        })
+    }
+
+    /// Filter from an already compiled predicate; `source` is shown by `Debug`
+    pub(crate) fn from_predicate(source: String, predicate: Predicate) -> Self {
+        EntryFilter { source, predicate }
+    }
+
+    /// Whether `entry` satisfies the expression
+    pub fn matches(&self, entry: &DirEntry) -> bool {
+        (self.predicate)(entry)
+    }
+}
+
+impl fmt::Debug for EntryFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("EntryFilter").field(&self.source).finish()
+    }
+}
+
+impl DiskCache {
+    /// Paths under `start` kept by `entry_filter`, `only_dirs_with_files` and
+    /// `sample`, or `None` when none is set
+    ///
+    /// An entry is kept if it passes the filters or any entry below it does, so
+    /// every match stays reachable from `start`. `start` itself is included only
+    /// when something was kept. A `sample` is then drawn from the kept directories.
+    pub(crate) fn filter_visible(&self, start: &Path) -> Option<HashSet<PathBuf>> {
+        let mut visible = None;
+        if self.entry_filter.is_some() || self.only_dirs_with_files || self.exclude_empty_files || self.prune_empty {
+            let mut matches = Vec::new();
+            self.collect_matches(start, &mut matches);
+            visible = Some(minimal_subtree(start, matches.iter().map(PathBuf::as_path)));
+        }
+        if let Some(n) = self.sample {
+            // Drawn over the whole tree so every branch of a --stream render agrees
+            visible = Some(self.sample_visible(n, visible.as_ref()));
+        }
+        visible
+    }
+
+    /// Entries reachable from `path` through child lists that pass the filters
+    ///
+    /// Excluded empty files never match, and under `prune_empty` directories
+    /// don't match on their own: they are only kept as ancestors of a file.
+    /// Entries left out by the name filters are not descended into.
+    fn collect_matches(&self, path: &Path, matches: &mut Vec<PathBuf>) {
+        let Some(entry) = self.get_entry(path) else {
+            return;
+        };
+
+        if self.entry_filter.as_ref().is_none_or(|filter| filter.matches(entry))
+            && (!self.only_dirs_with_files || !entry.is_dir || self.has_file_child(path))
+            && !(self.exclude_empty_files && is_empty_file(entry))
+            && !(self.prune_empty && entry.is_dir)
+        {
+            matches.push(path.to_path_buf());
+        }
+        for child in &entry.children {
+            let child_path = path.join(child);
+            if self.passes_name_filters(&child_path, child) {
+                self.collect_matches(&child_path, matches);
+            }
+        }
+    }
+
+    /// Whether any cached child of the directory at `path` that passes the name
+    /// filters is not a directory
+    fn has_file_child(&self, path: &Path) -> bool {
+        self.children_entries(path)
+            .iter()
+            .any(|child| !child.is_dir && self.passes_name_filters(&child.path, &child.name))
+    }
+}
+
+/// Zero-byte regular file (symlinks and directories never count)
+pub fn is_empty_file(entry: &DirEntry) -> bool {
+    !entry.is_dir && entry.symlink_target.is_none() && entry.size == 0
+}
+
+impl DiskCache {
+    /// Zero-byte files under the root, sorted by path (`--only-empty-files`)
+    pub fn empty_files(&self) -> Vec<&DirEntry> {
+        self.find(|e| is_empty_file(e) && e.path.starts_with(&self.root))
+    }
+}
+
+/// The smallest tree under `root` that shows every match: the matches plus each
+/// ancestor between them and `root`
+///
+/// Renderers keep a child only when its path is in the set, so a deep match
+/// appears with its chain of parents while their other children are pruned.
+/// Matches outside `root` are ignored; `root` is included only when something
+/// under it matched.
+pub fn minimal_subtree<'a>(root: &Path, matches: impl IntoIterator<Item = &'a Path>) -> HashSet<PathBuf> {
+    let mut subtree = HashSet::new();
+    for path in matches.into_iter().filter(|p| p.starts_with(root)) {
+        for ancestor in path.ancestors().take_while(|a| a.starts_with(root)) {
+            // Everything above an already-kept path is kept too
+            if !subtree.insert(ancestor.to_path_buf()) {
+                break;
+            }
+        }
+    }
+    subtree
+}
+
+// ============================================================================
+// Tokenizer
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    Match,
+    NotMatch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Op(CmpOp),
+    Str(String),
+    Word(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+            Token::And => write!(f, "'&&'"),
+            Token::Or => write!(f, "'||'"),
+            Token::Not => write!(f, "'!'"),
+            Token::Op(op) => write!(f, "operator {:?}", op),
+            Token::Str(s) => write!(f, "string \"{}\"", s),
+            Token::Word(w) => write!(f, "'{}'", w),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | ':' | '+' | '/' | '\\' | '*' | '$' | '^')
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let mut next_is = |expected: char| chars.next_if_eq(&expected).is_some();
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '&' if next_is('&') => Token::And,
+            '|' if next_is('|') => Token::Or,
+            '!' if next_is('=') => Token::Op(CmpOp::Ne),
+            '!' if next_is('~') => Token::Op(CmpOp::NotMatch),
+            '!' => Token::Not,
+            '<' if next_is('=') => Token::Op(CmpOp::Le),
+            '<' => Token::Op(CmpOp::Lt),
+            '>' if next_is('=') => Token::Op(CmpOp::Ge),
+            '>' => Token::Op(CmpOp::Gt),
+            '=' => {
+                next_is('=');
+                Token::Op(CmpOp::Eq)
+            }
+            '~' => Token::Op(CmpOp::Match),
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') if chars.peek() == Some(&'"') => s.push(chars.next().unwrap()),
+                        Some(ch) => s.push(ch),
+                        None => bail!("Unterminated string in entries filter"),
+                    }
+                }
+                Token::Str(s)
+            }
+            c if is_word_char(c) => {
+                let mut word = c.to_string();
+                while let Some(ch) = chars.next_if(|&ch| is_word_char(ch)) {
+                    word.push(ch);
+                }
+                Token::Word(word)
+            }
+            other => bail!("Unexpected character '{}' in entries filter", other),
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+// ============================================================================
+// Recursive-Descent Parser
+// ============================================================================
+
+struct Parser {
+    tokens:     Vec<Token>,
+    pos:        usize,
+    match_case: bool,
+    /// Reference time for `age` comparisons, fixed once per parse
+    now:        DateTime<Utc>,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        if self.tokens.get(self.pos) == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// `or := and ("||" and)*`
+    fn parse_or(&mut self) -> Result<Predicate> {
+        let mut terms = vec![self.parse_and()?];
+        while self.eat(&Token::Or) {
+            terms.push(self.parse_and()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.pop().unwrap()
+        } else {
+            Arc::new(move |entry| terms.iter().any(|term| term(entry)))
+        })
+    }
+
+    /// `and := unary ("&&" unary)*`
+    fn parse_and(&mut self) -> Result<Predicate> {
+        let mut terms = vec![self.parse_unary()?];
+        while self.eat(&Token::And) {
+            terms.push(self.parse_unary()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.pop().unwrap()
+        } else {
+            Arc::new(move |entry| terms.iter().all(|term| term(entry)))
+        })
+    }
+
+    /// `unary := "!" unary | "(" or ")" | term`
+    fn parse_unary(&mut self) -> Result<Predicate> {
+        if self.eat(&Token::Not) {
+            let inner = self.parse_unary()?;
+            return Ok(Arc::new(move |entry| !inner(entry)));
+        }
+        if self.eat(&Token::LParen) {
+            let inner = self.parse_or()?;
+            if !self.eat(&Token::RParen) {
+                bail!("Missing ')' in entries filter");
+            }
+            return Ok(inner);
+        }
+        self.parse_term()
+    }
+
+    /// `term := flag | field op value`
+    fn parse_term(&mut self) -> Result<Predicate> {
+        let field = match self.next() {
+            Some(Token::Word(word)) => word.to_lowercase(),
+            Some(other) => bail!("Expected a field or flag in entries filter, found {}", other),
+            None => bail!("Entries filter ended early"),
+        };
+
+        let flag: Option<Predicate> = match field.as_str() {
+            "dir" => Some(Arc::new(|e: &DirEntry| e.is_dir)),
+            "file" => Some(Arc::new(|e: &DirEntry| !e.is_dir && e.symlink_target.is_none())),
+            "symlink" => Some(Arc::new(|e: &DirEntry| e.symlink_target.is_some())),
+            "hidden" => Some(Arc::new(|e: &DirEntry| e.is_hidden)),
+            _ => None,
+        };
+        if let Some(flag) = flag {
+            return Ok(flag);
+        }
+
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            Some(other) => bail!("Expected a comparison after '{}', found {}", field, other),
+            None => bail!("Expected a comparison after '{}'", field),
+        };
+        let value = match self.next() {
+            Some(Token::Str(s) | Token::Word(s)) => s,
+            Some(other) => bail!("Expected a value after '{}', found {}", field, other),
+            None => bail!("Expected a value after '{}'", field),
+        };
+
+        match field.as_str() {
+            "size" => {
+                let size = parse_byte_size(&value).map_err(|e| anyhow!("{} in entries filter", e))?;
+                let cmp = ordering(op, &field)?;
+                Ok(Arc::new(move |e| cmp(e.size.cmp(&size))))
+            }
+            "depth" => {
+                let depth: u32 = value
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid depth '{}' in entries filter", value))?;
+                let cmp = ordering(op, &field)?;
+                Ok(Arc::new(move |e| cmp(e.depth.cmp(&depth))))
+            }
+            "mtime" => {
+                let time = parse_time(&value)?;
+                let cmp = ordering(op, &field)?;
+                Ok(Arc::new(move |e| cmp(e.modified.cmp(&time))))
+            }
+            "age" => {
+                let age = parse_age(&value)?;
+                let now = self.now;
+                let cmp = ordering(op, &field)?;
+                Ok(Arc::new(move |e| cmp((now - e.modified).cmp(&age))))
+            }
+            "name" => self.text_predicate(op, value, |e| e.name.clone()),
+            "path" => self.text_predicate(op, value, |e| e.path.to_string_lossy().into_owned()),
+            other => bail!(
+                "Unknown field '{}' in entries filter (expected size, name, path, depth, mtime, age, dir, file, symlink, \
+                 hidden)",
+                other
+            ),
+        }
+    }
+
+    /// `==` / `!=` exact comparison or `~` / `!~` regex search on a text field
+    fn text_predicate(
+        &self,
+        op: CmpOp,
+        value: String,
+        field: impl Fn(&DirEntry) -> String + Send + Sync + 'static,
+    ) -> Result<Predicate> {
+        match op {
+            CmpOp::Eq => Ok(Arc::new(move |e| field(e) == value)),
+            CmpOp::Ne => Ok(Arc::new(move |e| field(e) != value)),
+            CmpOp::Match | CmpOp::NotMatch => {
+                let regex: Regex = RegexBuilder::new(&value)
+                    .case_insensitive(!self.match_case)
+                    .build()
+                    .map_err(|e| anyhow!("Invalid regex in entries filter: {}", e))?;
+                let expected = op == CmpOp::Match;
+                Ok(Arc::new(move |e| regex.is_match(&field(e)) == expected))
+            }
+            _ => bail!("Text fields support ==, !=, ~ and !~ in entries filter"),
+        }
+    }
+}
+
+/// Turn an ordering operator into a test on `actual.cmp(&expected)`
+fn ordering(op: CmpOp, field: &str) -> Result<fn(std::cmp::Ordering) -> bool> {
+    use std::cmp::Ordering;
+
+    Ok(match op {
+        CmpOp::Lt => |o| o == Ordering::Less,
+        CmpOp::Le => |o| o != Ordering::Greater,
+        CmpOp::Gt => |o| o == Ordering::Greater,
+        CmpOp::Ge => |o| o != Ordering::Less,
+        CmpOp::Eq => |o| o == Ordering::Equal,
+        CmpOp::Ne => |o| o != Ordering::Equal,
+        CmpOp::Match | CmpOp::NotMatch => bail!("'{}' does not support regex matching in entries filter", field),
+    })
+}
+
+/// `YYYY-MM-DD` (midnight UTC) or an RFC 3339 timestamp
+fn parse_time(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|_| anyhow!("Invalid time '{}' in entries filter (expected YYYY-MM-DD or RFC 3339)", value))
+}
+
+/// Duration such as `90s`, `30m`, `12h`, `7d` or `2w` (bare numbers are seconds)
+fn parse_age(value: &str) -> Result<Duration> {
+    let seconds = parse_duration(value).map_err(|e| anyhow!("{} in entries filter", e))?;
+    i64::try_from(seconds)
+        .ok()
+        .and_then(Duration::try_seconds)
+        .ok_or_else(|| anyhow!("Age '{}' is too large in entries filter", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn entry(path: &str, size: u64, depth: u32, days_old: i64) -> DirEntry {
+        DirEntry::builder(path)
+            .modified(Utc::now() - Duration::days(days_old))
+            .is_dir(false)
+            .size(size)
+            .depth(depth)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_tokenize_operators_and_strings() -> Result<()> {
+        assert_eq!(
+            tokenize(r#"!(size>=1mb)||name !~ "a\"b\.c""#)?,
+            vec![
+                Token::Not,
+                Token::LParen,
+                Token::Word("size".into()),
+                Token::Op(CmpOp::Ge),
+                Token::Word("1mb".into()),
+                Token::RParen,
+                Token::Or,
+                Token::Word("name".into()),
+                Token::Op(CmpOp::NotMatch),
+                Token::Str(r#"a"b\.c"#.into()),
+            ]
+        );
+        assert!(tokenize("name == \"open").is_err());
+        assert!(tokenize("size > 1 & depth < 2").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors_are_reported() {
+        for expr in [
+            "",
+            "size >",
+            "size > lots",
+            "colour == red",
+            "(depth < 2",
+            "depth < 2)",
+            "name ~ \"(\"",
+            "depth ~ 3",
+            "name < a",
+            "age > 3 days",
+            "mtime > yesterday",
+            "dir dir",
+        ] {
+            assert!(EntryFilter::parse(expr, true).is_err(), "{expr:?} should not parse");
+        }
+    }
+
+    #[test]
+    fn test_filter_expressions() -> Result<()> {
+        let big_log = entry("/data/logs/app.log", 5 << 20, 2, 1);
+        let small_log = entry("/data/logs/old.LOG", 10, 2, 40);
+        let deep = entry("/data/a/b/c/d/readme.md", 100, 5, 3);
+        let mut dir = entry("/data/logs", 0, 1, 0);
+        dir.is_dir = true;
+
+        let check = |expr: &str, expected: [bool; 4]| -> Result<()> {
+            let filter = EntryFilter::parse(expr, true)?;
+            let actual = [&big_log, &small_log, &deep, &dir].map(|e| filter.matches(e));
+            assert_eq!(actual, expected, "{expr}");
+            Ok(())
+        };
+
+        check(r#"size > 1mb && name ~ "\.log$" && depth < 4"#, [true, false, false, false])?;
+        check(r#"name ~ "\.log$" || depth >= 5"#, [true, false, true, false])?;
+        check("dir", [false, false, false, true])?;
+        check("!dir && size <= 100", [false, true, true, false])?;
+        check("age > 7d", [false, true, false, false])?;
+        check("age < 2d && file", [true, false, false, false])?;
+        check("depth == 2 && !(size < 1k)", [true, false, false, false])?;
+        check("path ~ ^/data/logs", [true, true, false, true])?;
+        check("name == readme.md || name != readme.md && size > 1G", [false, false, true, false])?;
+        check("mtime > 2000-01-01T00:00:00Z && mtime < 2999-12-31", [true, true, true, true])?;
+
+        // Regex case follows --case-sensitive / --ignore-case
+        let insensitive = EntryFilter::parse(r#"name ~ "\.log$""#, false)?;
+        assert!(insensitive.matches(&small_log));
+        Ok(())
+    }
+
+    #[test]
+    fn test_minimal_subtree_keeps_only_ancestor_chains() {
+        let paths = |set: HashSet<PathBuf>| {
+            let mut paths: Vec<PathBuf> = set.into_iter().collect();
+            paths.sort();
+            paths
+        };
+        let root = Path::new("/data");
+        let matches = [
+            Path::new("/data/a/b/c/deep.txt"),
+            Path::new("/data/a/b/other.txt"),
+            Path::new("/elsewhere/x.txt"),
+        ];
+        assert_eq!(
+            paths(minimal_subtree(root, matches)),
+            [
+                "/data",
+                "/data/a",
+                "/data/a/b",
+                "/data/a/b/c",
+                "/data/a/b/c/deep.txt",
+                "/data/a/b/other.txt"
+            ]
+            .map(PathBuf::from)
+        );
+
+        // Rooted lower down, only the part under that root is kept
+        assert_eq!(
+            paths(minimal_subtree(Path::new("/data/a/b/c"), matches)),
+            ["/data/a/b/c", "/data/a/b/c/deep.txt"].map(PathBuf::from)
+        );
+        assert!(minimal_subtree(root, [Path::new("/other")]).is_empty());
+        assert_eq!(paths(minimal_subtree(root, [root])), vec![PathBuf::from("/data")]);
+    }
+
+    #[test]
+    fn test_deep_match_prunes_siblings_at_every_level() -> Result<()> {
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        for (path, children) in [
+            ("/data", vec!["a", "z.txt"]),
+            ("/data/z.txt", vec![]),
+            ("/data/a", vec!["b", "sibling", "y.txt"]),
+            ("/data/a/y.txt", vec![]),
+            ("/data/a/sibling", vec!["noise.txt"]),
+            ("/data/a/sibling/noise.txt", vec![]),
+            ("/data/a/b", vec!["target.env", "x.txt"]),
+            ("/data/a/b/target.env", vec![]),
+            ("/data/a/b/x.txt", vec![]),
+        ] {
+            let mut e = entry(path, 0, 0, 0);
+            e.is_dir = !path.contains('.');
+            e.children = children.into_iter().map(String::from).collect();
+            cache.entries.insert(e.path.clone(), e);
+        }
+
+        cache.entry_filter = Some(EntryFilter::parse(r#"name ~ "\.env$""#, true)?);
+        assert_eq!(cache.build_tree_output()?, "/data\n└── a\n    └── b\n        └── target.env\n");
+        let json: serde_json::Value = serde_json::from_str(&cache.build_json_output()?)?;
+        assert_eq!(json["children"][0]["children"][0]["children"][0]["name"], "target.env");
+        assert_eq!(json["children"][0]["children"].as_array().unwrap().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_prunes_rendered_outputs() -> Result<()> {
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        for (path, children, size) in [
+            ("/data", vec!["docs", "logs", "src"], 0),
+            ("/data/docs", vec!["guide.md"], 0),
+            ("/data/docs/guide.md", vec![], 10),
+            ("/data/logs", vec!["app.log", "old"], 0),
+            ("/data/logs/app.log", vec![], 5 << 20),
+            ("/data/logs/old", vec!["rotated.log"], 0),
+            ("/data/logs/old/rotated.log", vec![], 2 << 20),
+            ("/data/src", vec!["main.rs"], 0),
+            ("/data/src/main.rs", vec![], 4 << 20),
+        ] {
+            let mut e = entry(path, size, crate::cache::relative_depth(Path::new("/data"), Path::new(path)), 0);
+            e.is_dir = !children.is_empty();
+            e.children = children.into_iter().map(String::from).collect();
+            cache.entries.insert(e.path.clone(), e);
+        }
+
+        cache.entry_filter = Some(EntryFilter::parse(r#"size > 1mb && name ~ "\.log$""#, true)?);
+        assert_eq!(
+            cache.build_tree_output()?,
+            "/data\n\
+             └── logs\n\
+            \u{20}   ├── app.log\n\
+            \u{20}   └── old\n\
+            \u{20}       └── rotated.log\n"
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&cache.build_json_output()?)?;
+        assert_eq!(json["children"].as_array().unwrap().len(), 1);
+        assert_eq!(json["children"][0]["name"], "logs");
+        assert_eq!(cache.build_mermaid_output(None).lines().count(), 4);
+
+        // Directories can match on their own; nothing matching leaves just the root
+        cache.entry_filter = Some(EntryFilter::parse("dir && depth == 1 && name != logs", true)?);
+        assert_eq!(cache.build_tree_output()?, "/data\n├── docs\n└── src\n");
+        cache.entry_filter = Some(EntryFilter::parse("size > 1T", true)?);
+        assert_eq!(cache.build_tree_output()?, "/data\n");
+        assert_eq!(cache.build_tree_branch("logs", true, None)?, "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_name_regex_keeps_matches_and_ancestors() -> Result<()> {
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/proj");
+        for (path, children) in [
+            ("/proj", vec!["docs", "src"]),
+            ("/proj/docs", vec!["guide.md"]),
+            ("/proj/docs/guide.md", vec![]),
+            ("/proj/src", vec!["cli", "main.rs"]),
+            ("/proj/src/main.rs", vec![]),
+            ("/proj/src/cli", vec!["Args.RS"]),
+            ("/proj/src/cli/Args.RS", vec![]),
+        ] {
+            let mut e = entry(path, 1, crate::cache::relative_depth(Path::new("/proj"), Path::new(path)), 0);
+            e.is_dir = !children.is_empty();
+            e.children = children.into_iter().map(String::from).collect();
+            cache.entries.insert(e.path.clone(), e);
+        }
+
+        cache.entry_filter = Some(EntryFilter::name_regex(r"\.rs$", true)?);
+        assert_eq!(cache.build_tree_output()?, "/proj\n└── src\n    └── main.rs\n");
+        cache.entry_filter = Some(EntryFilter::name_regex(r"\.rs$", false)?);
+        assert_eq!(cache.build_tree_output()?, "/proj\n└── src\n    ├── cli\n    │   └── Args.RS\n    └── main.rs\n");
+
+        let err = EntryFilter::name_regex("(unclosed", true).unwrap_err();
+        assert!(err.to_string().starts_with("Invalid --match regex '(unclosed'"), "{err}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_file_flags_and_prune_empty() -> Result<()> {
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        for (path, children, size) in [
+            ("/data", vec!["docs", "placeholders", "void"], 0),
+            ("/data/docs", vec!["empty.md", "guide.md"], 0),
+            ("/data/docs/empty.md", vec![], 0),
+            ("/data/docs/guide.md", vec![], 10),
+            ("/data/placeholders", vec![".keep", "stub.txt"], 0),
+            ("/data/placeholders/.keep", vec![], 0),
+            ("/data/placeholders/stub.txt", vec![], 0),
+            ("/data/void", vec![], 0),
+        ] {
+            let mut e = entry(path, size, 0, 0);
+            e.is_dir = !path.contains('.');
+            e.children = children.into_iter().map(String::from).collect();
+            cache.entries.insert(e.path.clone(), e);
+        }
+        cache
+            .entries
+            .insert(PathBuf::from("/elsewhere/zero"), entry("/elsewhere/zero", 0, 0, 0));
+
+        let paths = |entries: Vec<&DirEntry>| entries.iter().map(|e| e.path.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            paths(cache.empty_files()),
+            [
+                "/data/docs/empty.md",
+                "/data/placeholders/.keep",
+                "/data/placeholders/stub.txt"
+            ]
+            .map(PathBuf::from)
+        );
+
+        cache.exclude_empty_files = true;
+        assert_eq!(cache.build_tree_output()?, "/data\n├── docs\n│   └── guide.md\n├── placeholders\n└── void\n");
+
+        // A folder holding only empty files collapses once they are excluded
+        cache.prune_empty = true;
+        assert_eq!(cache.build_tree_output()?, "/data\n└── docs\n    └── guide.md\n");
+
+        // Without the exclusion, empty files count as files and keep their folder
+        cache.exclude_empty_files = false;
+        assert_eq!(
+            cache.build_tree_output()?,
+            "/data\n├── docs\n│   ├── empty.md\n│   └── guide.md\n└── placeholders\n    ├── .keep\n    └── stub.txt\n"
+        );
+        // Stored data is untouched
+        assert_eq!(cache.entries.len(), 9);
+        Ok(())
+    }
+
+    #[test]
+    fn test_only_dirs_with_files_keeps_ancestors() -> Result<()> {
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        for (path, children, is_dir) in [
+            ("/data", vec!["empty", "scaffold", "src", "top.txt"], true),
+            ("/data/empty", vec![], true),
+            ("/data/scaffold", vec!["a", "b"], true),
+            ("/data/scaffold/a", vec![], true),
+            ("/data/scaffold/b", vec!["c"], true),
+            ("/data/scaffold/b/c", vec!["keep.rs"], true),
+            ("/data/scaffold/b/c/keep.rs", vec![], false),
+            ("/data/src", vec!["lib.rs", "nested"], true),
+            ("/data/src/lib.rs", vec![], false),
+            ("/data/src/nested", vec!["deeper"], true),
+            ("/data/src/nested/deeper", vec![], true),
+            ("/data/top.txt", vec![], false),
+        ] {
+            let mut e = entry(path, 0, crate::cache::relative_depth(Path::new("/data"), Path::new(path)), 0);
+            e.is_dir = is_dir;
+            e.children = children.into_iter().map(String::from).collect();
+            cache.entries.insert(e.path.clone(), e);
+        }
+
+        cache.only_dirs_with_files = true;
+        assert_eq!(
+            cache.build_tree_output()?,
+            "/data\n\
+             ├── scaffold\n\
+             │   └── b\n\
+             │       └── c\n\
+             │           └── keep.rs\n\
+             ├── src\n\
+             │   └── lib.rs\n\
+             └── top.txt\n"
+        );
+
+        // Composes with --entries-filter: both must hold for an entry to be kept on its own
+        cache.entry_filter = Some(EntryFilter::parse("dir || name == lib.rs", true)?);
+        assert_eq!(
+            cache.build_tree_output()?,
+            "/data\n\
+             ├── scaffold\n\
+             │   └── b\n\
+             │       └── c\n\
+             └── src\n\
+            \u{20}   └── lib.rs\n"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+use std::fmt;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use regex::{Regex, RegexBuilder};
+
+use crate::cache::{DirEntry, DiskCache};
+use crate::filter::EntryFilter;
+
+// ============================================================================
+// Cache Search (`--find`)
+// ============================================================================
+
+impl DiskCache {
+    /// Loaded entries for which `pred` holds, sorted by path
+    ///
+    /// Only the cache is consulted; nothing on disk is read.
+    pub fn find<F: Fn(&DirEntry) -> bool>(&self, pred: F) -> Vec<&DirEntry> {
+        let mut found: Vec<&DirEntry> = self.entries.values().filter(|e| pred(e)).collect();
+        found.sort_by(|a, b| a.path.cmp(&b.path));
+        found
+    }
+}
+
+/// `--find` pattern, matched against entry names
+///
+/// A pattern with `*`, `?` or `[...]` is a glob over the whole name (`*.env`,
+/// `report-202?.pdf`, `[!.]*`); anything else matches as a substring.
+#[derive(Clone)]
+pub struct NamePattern {
+    source: String,
+    regex:  Regex,
+}
+
+impl NamePattern {
+    /// Compile a glob or substring; `match_case` controls case sensitivity
+    pub fn parse(pattern: &str, match_case: bool) -> Result<Self> {
+        let is_glob = pattern.contains(['*', '?', '[']);
+        let source = if is_glob {
+            glob_to_regex(pattern)
+        } else {
+            regex::escape(pattern)
+        };
+        let regex = RegexBuilder::new(&source)
+            .case_insensitive(!match_case)
+            .build()
+            .map_err(|e| anyhow!("Invalid --find pattern '{}': {}", pattern, e))?;
+
+        Ok(NamePattern {
+            source: pattern.to_string(),
+            regex,
+        })
+    }
+
+    /// Whether the name of `entry` matches
+    pub fn matches(&self, entry: &DirEntry) -> bool {
+        self.regex.is_match(&entry.name)
+    }
+}
+
+impl fmt::Debug for NamePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("NamePattern").field(&self.source).finish()
+    }
+}
+
+/// Keeps matching entries and their parents, for a pruned `--find --format` render
+impl From<NamePattern> for EntryFilter {
+    fn from(pattern: NamePattern) -> Self {
+        let source = format!("name matches {:?}", pattern.source);
+        EntryFilter::from_predicate(source, Arc::new(move |e| pattern.matches(e)))
+    }
+}
+
+/// Anchored regex for a glob: `*` any run, `?` one character, `[...]` a class
+/// (`[!...]` negated); an unclosed `[` is literal
+fn glob_to_regex(glob: &str) -> String {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut regex = String::from("^");
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => {
+                let Some(len) = chars[i + 1..].iter().position(|&c| c == ']').filter(|&len| len > 0) else {
+                    regex.push_str(r"\[");
+                    i += 1;
+                    continue;
+                };
+                let mut class = &chars[i + 1..i + 1 + len];
+                regex.push('[');
+                if let Some(('!', rest)) = class.split_first() {
+                    regex.push('^');
+                    class = rest;
+                }
+                for &c in class {
+                    if matches!(c, '\\' | '[' | '^' | '&' | '~') {
+                        regex.push('\\');
+                    }
+                    regex.push(c);
+                }
+                regex.push(']');
+                i += len + 2;
+                continue;
+            }
+            c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+        i += 1;
+    }
+    regex.push('$');
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::fixtures::{cache_with, dir, file};
+
+    fn fixture() -> Result<DiskCache> {
+        let cache = cache_with(
+            "/proj",
+            [
+                dir("/proj", &[".env", "api", "README.md"]),
+                file("/proj/.env", 0),
+                file("/proj/README.md", 0),
+                dir("/proj/api", &[".env.local", "Env.rs", "main.rs"]),
+                file("/proj/api/.env.local", 0),
+                file("/proj/api/Env.rs", 0),
+                file("/proj/api/main.rs", 0),
+            ],
+        );
+        Ok(cache)
+    }
+
+    fn found<'a>(cache: &'a DiskCache, pattern: &str, match_case: bool) -> Result<Vec<&'a Path>> {
+        let pattern = NamePattern::parse(pattern, match_case)?;
+        Ok(cache
+            .find(|e| pattern.matches(e))
+            .into_iter()
+            .map(|e| e.path.as_path())
+            .collect())
+    }
+
+    #[test]
+    fn test_find_returns_sorted_matches() -> Result<()> {
+        let cache = fixture()?;
+        let files: Vec<&Path> = cache
+            .find(|e| !e.is_dir)
+            .into_iter()
+            .map(|e| e.path.as_path())
+            .collect();
+        assert_eq!(files.len(), 5);
+        assert!(files.windows(2).all(|w| w[0] < w[1]), "{files:?}");
+        assert!(cache.find(|e| e.size > 0).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_pattern_glob_and_substring() -> Result<()> {
+        let cache = fixture()?;
+        assert_eq!(found(&cache, "*.env", true)?, vec![Path::new("/proj/.env")]);
+        assert_eq!(found(&cache, ".env*", true)?, vec![Path::new("/proj/.env"), Path::new("/proj/api/.env.local")]);
+        assert_eq!(found(&cache, "?ain.rs", true)?, vec![Path::new("/proj/api/main.rs")]);
+        assert_eq!(found(&cache, "[!.]*.rs", true)?.len(), 2);
+        assert_eq!(found(&cache, "[A-Z]*", true)?, vec![Path::new("/proj/README.md"), Path::new("/proj/api/Env.rs")]);
+
+        // Substrings match anywhere in the name; case follows `match_case`
+        assert_eq!(found(&cache, "env", true)?, vec![Path::new("/proj/.env"), Path::new("/proj/api/.env.local")]);
+        assert_eq!(found(&cache, "env", false)?.len(), 3);
+        assert_eq!(found(&cache, "a.b", true)?, Vec::<&Path>::new());
+        // An unclosed bracket is a literal character
+        assert!(found(&cache, "[oops", true)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_pattern_prunes_tree() -> Result<()> {
+        let mut cache = fixture()?;
+        cache.entry_filter = Some(NamePattern::parse("*.rs", true)?.into());
+        assert_eq!(cache.build_tree_output()?, "/proj\n└── api\n    ├── Env.rs\n    └── main.rs\n");
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+// Cache fixtures shared by the unit tests, in this crate and (through the
+// `fixtures` feature) in the crates built on it
+// Entries go through `DirEntry::builder`, so a test states only what it checks
+
+use std::cell::Cell;
+use std::path::PathBuf;
+
+use crate::cache::{DirEntry, DiskCache};
+
+/// Directory entry for `path` listing `children`
+pub fn dir(path: impl Into<PathBuf>, children: &[&str]) -> DirEntry {
+    DirEntry::builder(path)
+        .children(children.iter().copied())
+        .build()
+        .unwrap()
+}
+
+/// File entry for `path` holding `size` bytes
+pub fn file(path: impl Into<PathBuf>, size: u64) -> DirEntry {
+    DirEntry::builder(path).is_dir(false).size(size).build().unwrap()
+}
+
+/// Cache rooted at `root` holding `entries`, keyed by their paths
+pub fn cache_with(root: impl Into<PathBuf>, entries: impl IntoIterator<Item = DirEntry>) -> DiskCache {
+    let mut cache = DiskCache::new_empty();
+    cache.root = root.into();
+    for entry in entries {
+        cache.entries.insert(entry.path.clone(), entry);
+    }
+    cache
+}
+
+thread_local! {
+    static RENDERS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Trees rendered on this thread so far, to check that a path renders none
+pub fn renders() -> usize {
+    RENDERS.with(Cell::get)
+}
+
+/// Called by each renderer as it starts
+pub(crate) fn note_render() {
+    RENDERS.with(|count| count.set(count.get() + 1));
+}
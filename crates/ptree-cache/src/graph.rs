@@ -0,0 +1,216 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::cache::DiskCache;
+
+// ============================================================================
+// Directory Graph (shared by the diagram exporters)
+// ============================================================================
+
+/// One directory in a graph export
+#[derive(Debug, Clone)]
+pub struct GraphNode {
+    pub id:    usize,
+    pub path:  PathBuf,
+    pub label: String,
+}
+
+/// Directories reachable from the cache root, with parent -> child edges
+///
+/// Nodes are numbered in sorted depth-first order starting at the root (id 0),
+/// so the same cache always yields the same ids.
+#[derive(Debug, Clone, Default)]
+pub struct DirGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl DiskCache {
+    /// Enumerate directory nodes and edges below `self.root`
+    ///
+    /// Files are left out; `max_depth` limits expansion the same way as the
+    /// tree output.
+    pub fn directory_graph(&self, max_depth: Option<usize>) -> DirGraph {
+        let mut graph = DirGraph::default();
+        graph.nodes.push(GraphNode {
+            id:    0,
+            path:  self.root.clone(),
+            label: self.root.display().to_string(),
+        });
+        let visible = self.filter_visible(&self.root);
+        self.collect_graph(&mut graph, 0, &self.root, 0, max_depth, visible.as_ref());
+        graph
+    }
+
+    fn collect_graph(
+        &self,
+        graph: &mut DirGraph,
+        parent: usize,
+        path: &Path,
+        depth: usize,
+        max_depth: Option<usize>,
+        visible: Option<&HashSet<PathBuf>>,
+    ) {
+        if max_depth.is_some_and(|max| depth >= max) {
+            return;
+        }
+
+        for child in self.children_entries(path) {
+            if !child.is_dir || !self.shows_child(visible, path, &child.name) {
+                continue;
+            }
+
+            let id = graph.nodes.len();
+            graph.nodes.push(GraphNode {
+                id,
+                label: self.format_name(&child.name, &child.path, self.show_hidden),
+                path: child.path.clone(),
+            });
+            graph.edges.push((parent, id));
+            self.collect_graph(graph, id, &child.path, depth + 1, max_depth, visible);
+        }
+    }
+
+    // ============================================================================
+    // Mermaid Output
+    // ============================================================================
+
+    /// Build a Mermaid `graph TD` definition of the directory tree
+    ///
+    /// Each child node is declared with its label on the edge line from its
+    /// parent, e.g. `n0 --> n1["src"]`.
+    pub fn build_mermaid_output(&self, max_depth: Option<usize>) -> String {
+        let graph = self.directory_graph(max_depth);
+        let mut out = String::from("graph TD\n");
+
+        let _ = writeln!(out, "    n0[\"{}\"]", escape_mermaid(&graph.nodes[0].label));
+        for &(from, to) in &graph.edges {
+            let _ = writeln!(out, "    n{} --> n{}[\"{}\"]", from, to, escape_mermaid(&graph.nodes[to].label));
+        }
+
+        out
+    }
+
+    // ============================================================================
+    // Graphviz Output
+    // ============================================================================
+
+    /// Build a Graphviz `digraph` of the directory tree
+    ///
+    /// Node ids are hashes of the full path (`d3f1...`), so they stay the same
+    /// when directories are added elsewhere in the tree; pipe the output to
+    /// `dot -Tsvg` to render it.
+    pub fn build_dot_output(&self, max_depth: Option<usize>) -> String {
+        let graph = self.directory_graph(max_depth);
+        let mut out = String::from("digraph ptree {\n    node [shape=folder];\n");
+
+        for node in &graph.nodes {
+            let _ = writeln!(out, "    {} [label=\"{}\"];", dot_id(&node.path), escape_dot(&node.label));
+        }
+        for &(from, to) in &graph.edges {
+            let _ = writeln!(out, "    {} -> {};", dot_id(&graph.nodes[from].path), dot_id(&graph.nodes[to].path));
+        }
+
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Stable DOT node id for `path`
+fn dot_id(path: &Path) -> String {
+    format!("d{:016x}", xxh3_64(path.as_os_str().as_encoded_bytes()))
+}
+
+/// Escape a label for use inside a quoted DOT string
+fn escape_dot(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Escape a label for use inside a quoted Mermaid node (`["..."]`)
+///
+/// Mermaid has no backslash escapes; characters that would end the label or be
+/// read as markup are written as `#code;` entities instead.
+fn escape_mermaid(label: &str) -> String {
+    let mut out = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '#' => out.push_str("#35;"),
+            '"' => out.push_str("#quot;"),
+            '<' => out.push_str("#lt;"),
+            '>' => out.push_str("#gt;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{cache_with, dir, file};
+
+    #[test]
+    fn test_mermaid_output_escapes_labels() {
+        let cache = cache_with(
+            "/data",
+            [
+                dir("/data", &["say \"hi\" #1", "notes.txt"]),
+                dir("/data/say \"hi\" #1", &["<inner>"]),
+                dir("/data/say \"hi\" #1/<inner>", &[]),
+                file("/data/notes.txt", 0),
+            ],
+        );
+
+        let out = cache.build_mermaid_output(None);
+        assert!(out.starts_with("graph TD\n"));
+        assert!(out.contains("    n0[\"/data\"]\n"));
+        assert!(out.contains("    n0 --> n1[\"say #quot;hi#quot; #35;1\"]\n"));
+        assert!(out.contains("    n1 --> n2[\"#lt;inner#gt;\"]\n"));
+        assert!(!out.contains("notes.txt"), "files are not graph nodes");
+    }
+
+    #[test]
+    fn test_directory_graph_respects_max_depth() {
+        let cache = cache_with("/data", [dir("/data", &["a"]), dir("/data/a", &["b"]), dir("/data/a/b", &[])]);
+
+        assert_eq!(cache.directory_graph(None).edges, vec![(0, 1), (1, 2)]);
+        assert_eq!(cache.directory_graph(Some(1)).edges, vec![(0, 1)]);
+        assert!(!cache.build_mermaid_output(Some(1)).contains("n2"));
+    }
+
+    #[test]
+    fn test_dot_output_is_one_digraph_with_an_edge_per_directory() {
+        let cache = cache_with(
+            "/data",
+            [
+                dir("/data", &["a", "b \"q\"", "notes.txt"]),
+                dir("/data/a", &["c"]),
+                dir("/data/a/c", &[]),
+                dir("/data/b \"q\"", &[]),
+                file("/data/notes.txt", 0),
+            ],
+        );
+
+        let out = cache.build_dot_output(None);
+        assert!(out.starts_with("digraph ptree {\n"));
+        assert!(out.ends_with("}\n"));
+        assert_eq!(out.matches('{').count(), 1);
+        assert_eq!(out.matches('}').count(), 1);
+
+        let edges: Vec<&str> = out.lines().filter(|l| l.contains(" -> ")).collect();
+        assert_eq!(edges.len(), 3, "{out}");
+        let id = |path: &str| dot_id(Path::new(path));
+        assert!(edges.contains(&format!("    {} -> {};", id("/data"), id("/data/a")).as_str()));
+        assert!(edges.contains(&format!("    {} -> {};", id("/data/a"), id("/data/a/c")).as_str()));
+        assert!(out.contains(&format!("    {} [label=\"b \\\"q\\\"\"];\n", id("/data/b \"q\""))));
+        assert!(out.contains("[label=\"c\"]"));
+        assert!(!out.contains("notes.txt"), "files are not graph nodes");
+
+        // Ids depend only on the path, not on what else is in the tree
+        assert!(cache.build_dot_output(Some(1)).contains(&id("/data/a")));
+        assert_eq!(cache.build_dot_output(Some(1)).matches(" -> ").count(), 2);
+    }
+}
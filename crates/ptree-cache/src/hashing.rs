@@ -0,0 +1,129 @@
+use ptree_core::HashAlgorithm;
+use sha2::Digest;
+
+/// Incremental hasher producing a 64-bit content digest
+///
+/// Cryptographic digests are truncated to their first 8 bytes (little-endian)
+/// so every algorithm fits the `DirEntry::content_hash` field.
+pub trait ContentHasher {
+    fn update(&mut self, bytes: &[u8]);
+    fn finalize(self) -> u64;
+}
+
+/// xxHash3 (64-bit) content hasher
+pub struct XxHasher(xxhash_rust::xxh3::Xxh3);
+
+impl ContentHasher for XxHasher {
+    fn update(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finalize(self) -> u64 {
+        self.0.digest()
+    }
+}
+
+/// BLAKE3 content hasher
+pub struct Blake3Hasher(blake3::Hasher);
+
+impl ContentHasher for Blake3Hasher {
+    fn update(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finalize(self) -> u64 {
+        truncate_digest(self.0.finalize().as_bytes())
+    }
+}
+
+/// SHA-256 content hasher
+pub struct Sha256Hasher(sha2::Sha256);
+
+impl ContentHasher for Sha256Hasher {
+    fn update(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finalize(self) -> u64 {
+        truncate_digest(&self.0.finalize())
+    }
+}
+
+/// Runtime-selected hasher dispatching to the configured algorithm
+pub enum SelectedHasher {
+    Xxhash(Box<XxHasher>),
+    Blake3(Box<Blake3Hasher>),
+    Sha256(Sha256Hasher),
+}
+
+impl SelectedHasher {
+    pub fn new(algo: HashAlgorithm) -> Self {
+        match algo {
+            HashAlgorithm::Xxhash => SelectedHasher::Xxhash(Box::new(XxHasher(xxhash_rust::xxh3::Xxh3::new()))),
+            HashAlgorithm::Blake3 => SelectedHasher::Blake3(Box::new(Blake3Hasher(blake3::Hasher::new()))),
+            HashAlgorithm::Sha256 => SelectedHasher::Sha256(Sha256Hasher(sha2::Sha256::new())),
+        }
+    }
+}
+
+impl ContentHasher for SelectedHasher {
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            SelectedHasher::Xxhash(h) => h.update(bytes),
+            SelectedHasher::Blake3(h) => h.update(bytes),
+            SelectedHasher::Sha256(h) => h.update(bytes),
+        }
+    }
+
+    fn finalize(self) -> u64 {
+        match self {
+            SelectedHasher::Xxhash(h) => h.finalize(),
+            SelectedHasher::Blake3(h) => h.finalize(),
+            SelectedHasher::Sha256(h) => h.finalize(),
+        }
+    }
+}
+
+fn truncate_digest(digest: &[u8]) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest(algo: HashAlgorithm, input: &[u8]) -> u64 {
+        let mut hasher = SelectedHasher::new(algo);
+        hasher.update(input);
+        hasher.finalize()
+    }
+
+    #[test]
+    fn test_each_algorithm_is_stable() {
+        for algo in [HashAlgorithm::Xxhash, HashAlgorithm::Blake3, HashAlgorithm::Sha256] {
+            assert_eq!(digest(algo, b"ptree"), digest(algo, b"ptree"), "{} not stable", algo);
+            assert_ne!(digest(algo, b"ptree"), digest(algo, b"ptrees"), "{} not input-sensitive", algo);
+        }
+    }
+
+    #[test]
+    fn test_algorithms_produce_distinct_digests() {
+        let xx = digest(HashAlgorithm::Xxhash, b"ptree");
+        let b3 = digest(HashAlgorithm::Blake3, b"ptree");
+        let sha = digest(HashAlgorithm::Sha256, b"ptree");
+        assert_ne!(xx, b3);
+        assert_ne!(xx, sha);
+        assert_ne!(b3, sha);
+    }
+
+    #[test]
+    fn test_sha256_matches_known_digest_prefix() {
+        // SHA-256("abc") = ba7816bf 8f01cfea ...
+        assert_eq!(
+            digest(HashAlgorithm::Sha256, b"abc"),
+            u64::from_le_bytes([0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea])
+        );
+    }
+}
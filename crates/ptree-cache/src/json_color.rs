@@ -0,0 +1,113 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::cache::DiskCache;
+
+/// Add ANSI syntax highlighting to a serialized JSON document
+///
+/// jq-style palette: bold blue keys, green strings, bold brackets, grey null.
+/// Works on the serialized text, so whitespace and layout are preserved exactly;
+/// stripping the escape codes yields the original input. Numbers and booleans
+/// are left uncolored. Goes through `colored`, so `--color never` and
+/// `NO_COLOR` leave the text plain.
+pub fn colorize_json(json: &str) -> String {
+    let mut out = String::with_capacity(json.len() * 2);
+    let mut chars = json.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '"' => {
+                // Consume the whole string literal, honouring backslash escapes
+                let mut end = json.len();
+                let mut escaped = false;
+                for (i, ch) in chars.by_ref() {
+                    if escaped {
+                        escaped = false;
+                    } else if ch == '\\' {
+                        escaped = true;
+                    } else if ch == '"' {
+                        end = i + 1;
+                        break;
+                    }
+                }
+
+                let literal = &json[start..end];
+                if json[end..].trim_start().starts_with(':') {
+                    out.push_str(&literal.blue().bold().to_string());
+                } else {
+                    out.push_str(&literal.green().to_string());
+                }
+            }
+            '{' | '}' | '[' | ']' => out.push_str(&c.to_string().bold().to_string()),
+            'n' if json[start..].starts_with("null") => {
+                out.push_str(&"null".black().bold().to_string());
+                chars.nth(2);
+            }
+            other => out.push(other),
+        }
+    }
+
+    out
+}
+
+impl DiskCache {
+    /// Build JSON output with ANSI syntax highlighting (for terminal viewing)
+    pub fn build_colored_json_output_with_depth(&self, max_depth: Option<usize>) -> Result<String> {
+        Ok(colorize_json(&self.build_json_output_with_depth(max_depth)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip_ansi(s: &str) -> String {
+        let mut out = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                for ch in chars.by_ref() {
+                    if ch == 'm' {
+                        break;
+                    }
+                }
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_colorize_json_highlights_tokens() {
+        let input = r#"{"path": "C:\\a \"b\"", "children": [], "size": 3, "link": null}"#;
+        colored::control::set_override(true);
+        let highlighted = colorize_json(input);
+        colored::control::unset_override();
+
+        assert!(highlighted.contains("\x1b[1;34m\"path\"\x1b[0m"));
+        assert!(highlighted.contains("\x1b[32m\"C:\\\\a \\\"b\\\"\"\x1b[0m"));
+        assert!(highlighted.contains("\x1b[1m[\x1b[0m"));
+        assert!(highlighted.contains("\x1b[1;30mnull\x1b[0m"));
+        assert_eq!(strip_ansi(&highlighted), input);
+    }
+
+    #[test]
+    fn test_colored_json_only_when_requested() -> Result<()> {
+        let mut cache = DiskCache::new_empty();
+        cache.root = std::path::PathBuf::from("/data");
+
+        let plain = cache.build_json_output_with_depth(None)?;
+        assert!(!plain.contains('\x1b'));
+        serde_json::from_str::<serde_json::Value>(&plain)?;
+
+        colored::control::set_override(true);
+        let highlighted = cache.build_colored_json_output_with_depth(None)?;
+        colored::control::unset_override();
+        assert!(highlighted.contains('\x1b'));
+        let stripped = strip_ansi(&highlighted);
+        assert_eq!(stripped, plain);
+        serde_json::from_str::<serde_json::Value>(&stripped)?;
+        Ok(())
+    }
+}
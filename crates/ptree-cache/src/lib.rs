@@ -1,16 +1,81 @@
+#[cfg(not(any(feature = "parallel", feature = "single-threaded")))]
+compile_error!("enable either the `parallel` or the `single-threaded` feature");
+
+pub mod attributes;
+pub mod baseline;
+pub mod builder;
 pub mod cache;
 // pub mod cache_lazy;
 // pub mod cache_limcode;
 // pub mod cache_mmap;
 // pub mod cache_opt;
 pub mod cache_rkyv;
+pub mod collapse;
+pub mod compact;
+pub mod connectors;
+pub mod dedupe;
+pub mod diff;
+pub mod dump;
+pub mod extensions;
+pub mod filter;
+pub mod find;
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures;
+pub mod graph;
+pub mod hashing;
+pub mod json_color;
+pub mod permissions;
+pub mod prune;
+pub mod remap;
+pub mod resume;
+pub mod sample;
+pub mod scan_mode;
+pub mod schema;
+pub mod sizes;
+pub mod space;
+pub mod stats;
+pub mod template;
+pub mod theme;
+pub mod units;
 
+pub use attributes::format_attributes;
+pub use baseline::build_baseline_report;
+pub use builder::DirEntryBuilder;
 pub use cache::{
     compute_content_hash,
+    compute_content_hash_with,
     get_cache_path,
     get_cache_path_custom,
     has_directory_changed,
+    relative_depth,
+    truncate_name,
     DirEntry,
     DiskCache,
     USNJournalState,
+    NOT_SCANNED_MARKER,
+    NO_METADATA_MODIFIED,
+    SAVE_BUFFER_SIZE,
+    SINCE_SCAN_MARKER,
 };
+pub use cache_rkyv::rebuild_index_from_data;
+pub use collapse::CollapseRule;
+pub use compact::CompactionReport;
+pub use connectors::ConnectorSet;
+pub use dedupe::NameGroup;
+pub use diff::{build_json_patch, diff_caches, live_dir_entry, ChangeKind};
+pub use extensions::{ExtensionTally, NO_EXTENSION};
+pub use filter::{is_empty_file, minimal_subtree, EntryFilter};
+pub use find::NamePattern;
+pub use graph::{DirGraph, GraphNode};
+pub use json_color::colorize_json;
+pub use permissions::{format_permissions, UnixPermissions};
+pub use prune::CacheIssue;
+pub use remap::{remap_drive_letter, PathValidation};
+pub use resume::ScanProgress;
+pub use scan_mode::ScanMode;
+pub use schema::{build_json_schema_output, json_schema, JSON_OUTPUT_VERSION};
+pub use space::{available_space, check_free_space};
+pub use stats::CacheStats;
+pub use template::{render_entry, EntryTemplate};
+pub use theme::Theme;
+pub use units::{display_width, format_age, format_size, group_thousands, pad_left, pad_right};
@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Unix Permissions (`--long`)
+// ============================================================================
+
+/// `--long` prefix for entries cached without permissions (same width as a real one)
+pub const UNKNOWN_LONG_PREFIX: &str = "?????????? ????? ?????";
+
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+
+/// Mode bits and numeric owner of an entry, captured on Unix with `--long`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnixPermissions {
+    pub mode: u32,
+    pub uid:  u32,
+    pub gid:  u32,
+}
+
+impl UnixPermissions {
+    /// Read mode and ownership from already-fetched metadata
+    #[cfg(unix)]
+    pub fn from_metadata(metadata: &std::fs::Metadata) -> Self {
+        use std::os::unix::fs::MetadataExt;
+
+        UnixPermissions {
+            mode: metadata.mode(),
+            uid:  metadata.uid(),
+            gid:  metadata.gid(),
+        }
+    }
+
+    /// `ls -ln`-style prefix: `drwxr-xr-x  1000  1000`
+    pub fn long_prefix(&self, is_dir: bool) -> String {
+        format!("{} {:>5} {:>5}", format_permissions(self.mode, is_dir), self.uid, self.gid)
+    }
+}
+
+/// Whether any of the owner, group or other execute bits is set
+pub fn has_execute_bit(mode: u32) -> bool {
+    mode & 0o111 != 0
+}
+
+/// Render mode bits the way `ls -l` does, including the file type character
+///
+/// The type comes from the mode's file type bits for symlinks (`l`), otherwise
+/// from `is_dir`. Setuid, setgid and sticky bits replace the matching execute
+/// position with `s`/`S` or `t`/`T`.
+pub fn format_permissions(mode: u32, is_dir: bool) -> String {
+    let kind = if mode & S_IFMT == S_IFLNK {
+        'l'
+    } else if is_dir {
+        'd'
+    } else {
+        '-'
+    };
+
+    let mut out = String::with_capacity(10);
+    out.push(kind);
+    for (shift, special, special_char) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = (mode >> shift) & 0o7;
+        out.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        out.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        out.push(match (bits & 0o1 != 0, mode & special != 0) {
+            (true, true) => special_char,
+            (false, true) => special_char.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_permissions_known_modes() {
+        assert_eq!(format_permissions(0o755, true), "drwxr-xr-x");
+        assert_eq!(&format_permissions(0o755, false)[1..], "rwxr-xr-x");
+        assert_eq!(format_permissions(0o100644, false), "-rw-r--r--");
+        assert_eq!(format_permissions(0o000, false), "----------");
+        assert_eq!(format_permissions(0o120777, false), "lrwxrwxrwx");
+        assert_eq!(format_permissions(0o4755, false), "-rwsr-xr-x");
+        assert_eq!(format_permissions(0o2745, false), "-rwxr-Sr-x");
+        assert_eq!(format_permissions(0o1777, true), "drwxrwxrwt");
+        assert_eq!(format_permissions(0o1776, true), "drwxrwxrwT");
+    }
+
+    #[test]
+    fn test_has_execute_bit() {
+        assert!(has_execute_bit(0o100755));
+        assert!(has_execute_bit(0o010));
+        assert!(!has_execute_bit(0o100644));
+    }
+
+    #[test]
+    fn test_long_prefix_width_matches_unknown() {
+        let perms = UnixPermissions {
+            mode: 0o755,
+            uid:  1000,
+            gid:  100,
+        };
+        assert_eq!(perms.long_prefix(true), "drwxr-xr-x  1000   100");
+        assert_eq!(perms.long_prefix(true).len(), UNKNOWN_LONG_PREFIX.len());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_from_metadata_reads_mode() -> std::io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = std::env::temp_dir().join("ptree_test_unix_permissions");
+        let _ = std::fs::remove_dir_all(&temp);
+        std::fs::create_dir_all(&temp)?;
+        let file = temp.join("script.sh");
+        std::fs::write(&file, b"#!/bin/sh\n")?;
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o750))?;
+
+        let perms = UnixPermissions::from_metadata(&std::fs::metadata(&file)?);
+        assert_eq!(format_permissions(perms.mode, false), "-rwxr-x---");
+
+        let _ = std::fs::remove_dir_all(&temp);
+        Ok(())
+    }
+}
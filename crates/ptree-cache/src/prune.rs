@@ -0,0 +1,189 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[cfg(not(feature = "single-threaded"))]
+use rayon::prelude::*;
+
+use crate::cache::{DirEntry, DiskCache};
+
+// ============================================================================
+// Stale Entry Pruning and Consistency Checks
+// ============================================================================
+
+/// Structural problem reported by `DiskCache::verify`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CacheIssue {
+    /// `parent` lists `child` but no entry is cached for it
+    MissingChild { parent: PathBuf, child: String },
+    /// Entry whose cached parent does not list it among its children
+    Orphan(PathBuf),
+}
+
+impl DiskCache {
+    /// Cached paths that no longer exist on disk, sorted
+    ///
+    /// The existence checks run in parallel over `entries` (unless built
+    /// `single-threaded`). Symlinks are checked without following them, so a
+    /// dangling link is still present.
+    pub fn stale_paths(&self) -> Vec<PathBuf> {
+        #[cfg(not(feature = "single-threaded"))]
+        let entries = self.entries.par_iter();
+        #[cfg(feature = "single-threaded")]
+        let entries = self.entries.iter();
+
+        let mut stale: Vec<PathBuf> = entries
+            .filter(|(path, _)| !path_present(path))
+            .map(|(path, _)| path.clone())
+            .collect();
+        stale.sort();
+        stale
+    }
+
+    /// Drop every entry whose path no longer exists, with its descendants
+    ///
+    /// Stale paths are found in parallel (`stale_paths`); removal and the fix-up of
+    /// parent child lists happen afterwards in one serial pass. Returns the number
+    /// of entries removed.
+    pub fn prune_stale(&mut self) -> usize {
+        let before = self.entries.len();
+
+        for path in self.stale_paths() {
+            self.remove_entry(&path);
+            let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+                continue;
+            };
+            if let Some(parent_entry) = self.entries.get_mut(parent) {
+                let name = name.to_string_lossy();
+                parent_entry.children.retain(|child| *child != name);
+            }
+        }
+
+        before - self.entries.len()
+    }
+
+    /// Check parent/child links between loaded entries, in parallel unless built `single-threaded`
+    ///
+    /// Only loaded entries are checked: call `load_all_entries_lazy` first on a
+    /// freshly opened cache. Issues are sorted so the result is deterministic.
+    pub fn verify(&self) -> Vec<CacheIssue> {
+        #[cfg(not(feature = "single-threaded"))]
+        let entries = self.entries.par_iter();
+        #[cfg(feature = "single-threaded")]
+        let entries = self.entries.iter();
+
+        let mut issues: Vec<CacheIssue> = entries
+            .flat_map(|(path, entry)| self.verify_entry(path, entry))
+            .collect();
+        issues.sort();
+        issues
+    }
+
+    /// Issues attributable to a single entry (see `verify`)
+    fn verify_entry(&self, path: &Path, entry: &DirEntry) -> Vec<CacheIssue> {
+        let mut issues: Vec<CacheIssue> = entry
+            .children
+            .iter()
+            .filter(|child| !self.entries.contains_key(&path.join(child)))
+            .map(|child| {
+                CacheIssue::MissingChild {
+                    parent: path.to_path_buf(),
+                    child:  child.clone(),
+                }
+            })
+            .collect();
+
+        if path != self.root {
+            let listed = match (path.parent().and_then(|p| self.entries.get(p)), path.file_name()) {
+                (Some(parent), Some(name)) => parent.children.iter().any(|child| *child == *name.to_string_lossy()),
+                // Parent not loaded (lazy cache or partial scan): nothing to check against
+                _ => true,
+            };
+            if !listed {
+                issues.push(CacheIssue::Orphan(path.to_path_buf()));
+            }
+        }
+        issues
+    }
+}
+
+/// Whether `path` still exists, without following a final symlink
+fn path_present(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{cache_with, dir, file};
+
+    #[test]
+    fn test_parallel_prune_matches_serial() -> std::io::Result<()> {
+        let root = std::env::temp_dir().join("ptree_test_prune_stale");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("kept"))?;
+
+        // 400 files under two directories, every third one missing on disk
+        let names: Vec<String> = (0..200).map(|i| format!("f{i}.txt")).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let mut entries = vec![dir(&root, &["kept", "gone"])];
+        for sub in ["kept", "gone"] {
+            let sub_path = root.join(sub);
+            for (i, name) in names.iter().enumerate() {
+                if sub == "kept" && i % 3 != 0 {
+                    fs::write(sub_path.join(name), b"x")?;
+                }
+                entries.push(file(sub_path.join(name), 0));
+            }
+            entries.push(dir(sub_path, &names));
+        }
+        let mut cache = cache_with(&root, entries);
+
+        let mut serial: Vec<PathBuf> = cache.entries.keys().filter(|p| !path_present(p)).cloned().collect();
+        serial.sort();
+        assert_eq!(cache.stale_paths(), serial);
+        assert_eq!(serial.len(), 67 + 201);
+
+        let mut serial_issues: Vec<CacheIssue> = cache
+            .entries
+            .iter()
+            .flat_map(|(p, e)| cache.verify_entry(p, e))
+            .collect();
+        serial_issues.sort();
+        assert_eq!(cache.verify(), serial_issues);
+        assert!(serial_issues.is_empty());
+
+        assert_eq!(cache.prune_stale(), 67 + 201);
+        assert!(cache.stale_paths().is_empty());
+        assert_eq!(cache.entries[&root].children, vec!["kept".to_string()]);
+        assert_eq!(cache.entries[&root.join("kept")].children.len(), 133);
+        assert!(cache.verify().is_empty());
+
+        let _ = fs::remove_dir_all(&root);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_reports_missing_children_and_orphans() {
+        let cache = cache_with(
+            "/data",
+            [
+                dir("/data", &["a", "ghost"]),
+                dir("/data/a", &[]),
+                file("/data/stray", 0),
+                // Parent not loaded: not reported
+                file("/data/unloaded/x", 0),
+            ],
+        );
+
+        assert_eq!(
+            cache.verify(),
+            vec![
+                CacheIssue::MissingChild {
+                    parent: PathBuf::from("/data"),
+                    child:  "ghost".to_string(),
+                },
+                CacheIssue::Orphan(PathBuf::from("/data/stray")),
+            ]
+        );
+    }
+}
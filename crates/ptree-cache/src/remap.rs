@@ -0,0 +1,199 @@
+use std::path::{Path, PathBuf};
+use std::{fmt, mem};
+
+use crate::cache::DiskCache;
+
+// ============================================================================
+// Path Validation and Drive Remapping (`--validate-paths`, `--remap-drive`)
+// ============================================================================
+
+/// Outcome of `DiskCache::validate_paths`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PathValidation {
+    /// Entries loaded when validation started
+    pub checked:  usize,
+    /// Entries moved to another drive letter
+    pub remapped: usize,
+    /// Entries dropped because their path no longer exists
+    pub removed:  usize,
+}
+
+impl PathValidation {
+    pub fn changed(&self) -> bool {
+        self.remapped > 0 || self.removed > 0
+    }
+}
+
+impl fmt::Display for PathValidation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<20}{}", "Paths checked:", self.checked)?;
+        writeln!(f, "{:<20}{}", "Paths remapped:", self.remapped)?;
+        writeln!(f, "{:<20}{}", "Missing removed:", self.removed)
+    }
+}
+
+impl DiskCache {
+    /// Move every loaded path on drive `from` to drive `to`
+    ///
+    /// Rewrites entry keys, `path` fields, symlink targets and the cache root.
+    /// Child lists hold bare names, so they follow their parents unchanged.
+    /// Returns the number of entries moved.
+    pub fn remap_drive(&mut self, from: char, to: char) -> usize {
+        if let Some(root) = remap_drive_letter(&self.root, from, to) {
+            self.root = root;
+        }
+
+        let mut remapped = 0;
+        for (path, mut entry) in mem::take(&mut self.entries) {
+            let path = match remap_drive_letter(&path, from, to) {
+                Some(new_path) => {
+                    remapped += 1;
+                    entry.path = new_path.clone();
+                    new_path
+                }
+                None => path,
+            };
+            if let Some(target) = entry
+                .symlink_target
+                .as_deref()
+                .and_then(|t| remap_drive_letter(t, from, to))
+            {
+                entry.symlink_target = Some(target);
+            }
+            self.entries.insert(path, entry);
+        }
+        remapped
+    }
+
+    /// Optionally remap a drive letter, then drop entries whose path is gone
+    ///
+    /// Works on the loaded entries; load the whole cache first to validate all
+    /// of it.
+    pub fn validate_paths(&mut self, remap: Option<(char, char)>) -> PathValidation {
+        let checked = self.entries.len();
+        let remapped = remap.map_or(0, |(from, to)| self.remap_drive(from, to));
+        let removed = self.prune_stale();
+        PathValidation {
+            checked,
+            remapped,
+            removed,
+        }
+    }
+}
+
+/// `path` moved from drive `from` to drive `to`, or `None` if it is on another drive
+///
+/// Handles `C:\...` and verbatim `\\?\C:\...` forms; letters match case-insensitively.
+pub fn remap_drive_letter(path: &Path, from: char, to: char) -> Option<PathBuf> {
+    let text = path.to_str()?;
+    let (verbatim, rest) = match text.strip_prefix(r"\\?\") {
+        Some(rest) => (r"\\?\", rest),
+        None => ("", text),
+    };
+
+    let mut chars = rest.chars();
+    let letter = chars.next()?;
+    if !letter.eq_ignore_ascii_case(&from) || chars.next() != Some(':') {
+        return None;
+    }
+    Some(PathBuf::from(format!("{}{}:{}", verbatim, to.to_ascii_uppercase(), &rest[2..])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{cache_with, dir, file};
+
+    #[test]
+    fn test_remap_drive_letter_forms() {
+        let remap = |p: &str| remap_drive_letter(Path::new(p), 'C', 'D');
+        assert_eq!(remap(r"C:\Users\me"), Some(PathBuf::from(r"D:\Users\me")));
+        assert_eq!(remap(r"c:\data"), Some(PathBuf::from(r"D:\data")));
+        assert_eq!(remap(r"\\?\C:\long"), Some(PathBuf::from(r"\\?\D:\long")));
+        assert_eq!(remap(r"C:\"), Some(PathBuf::from(r"D:\")));
+        assert_eq!(remap(r"E:\other"), None);
+        assert_eq!(remap("/home/me"), None);
+        assert_eq!(remap("Cx"), None);
+    }
+
+    #[test]
+    fn test_remap_drive_rewrites_entries_and_children() {
+        let mut cache = cache_with(
+            r"C:\data",
+            [
+                dir(r"C:\data", &["docs", "link"]),
+                dir(r"C:\data\docs", &["a.txt"]),
+                file(r"C:\data\docs\a.txt", 0),
+                file(r"C:\data\link", 0),
+                dir(r"E:\elsewhere", &[]),
+            ],
+        );
+        cache
+            .entries
+            .get_mut(Path::new(r"C:\data\link"))
+            .unwrap()
+            .symlink_target = Some(PathBuf::from(r"C:\data\docs"));
+
+        assert_eq!(cache.remap_drive('C', 'D'), 4);
+        assert_eq!(cache.root, Path::new(r"D:\data"));
+
+        let mut keys: Vec<&str> = cache.entries.keys().map(|p| p.to_str().unwrap()).collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                r"D:\data",
+                r"D:\data\docs",
+                r"D:\data\docs\a.txt",
+                r"D:\data\link",
+                r"E:\elsewhere"
+            ]
+        );
+        for (path, entry) in &cache.entries {
+            assert_eq!(&entry.path, path);
+        }
+        assert_eq!(
+            cache.entries[Path::new(r"D:\data\link")].symlink_target.as_deref(),
+            Some(Path::new(r"D:\data\docs"))
+        );
+
+        // Children are names, so they still resolve against the remapped parent
+        let parent = &cache.entries[Path::new(r"D:\data\docs")];
+        assert_eq!(parent.children, vec!["a.txt".to_string()]);
+        assert!(cache
+            .entries
+            .contains_key(&PathBuf::from(format!(r"D:\data\docs\{}", parent.children[0]))));
+    }
+
+    #[test]
+    fn test_validate_paths_drops_missing_entries() -> std::io::Result<()> {
+        let root = std::env::temp_dir().join("ptree_test_validate_paths");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("kept"))?;
+
+        let mut cache = cache_with(
+            root.clone(),
+            [
+                dir(root.clone(), &["kept", "moved"]),
+                dir(root.join("kept"), &[]),
+                dir(root.join("moved"), &[]),
+            ],
+        );
+
+        let report = cache.validate_paths(None);
+        assert_eq!(
+            report,
+            PathValidation {
+                checked:  3,
+                remapped: 0,
+                removed:  1,
+            }
+        );
+        assert!(report.changed());
+        assert_eq!(cache.entries[&root].children, vec!["kept".to_string()]);
+        assert!(!cache.validate_paths(None).changed());
+
+        let _ = std::fs::remove_dir_all(&root);
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::DiskCache;
+
+// ============================================================================
+// Interrupted Scan Progress
+// ============================================================================
+
+/// Sidecar written next to the cache when a scan stops before finishing
+///
+/// Every directory the scan discovered is either enumerated (it has a cache
+/// entry) or listed in `frontier`. While the sidecar exists the cache is
+/// partial: `--resume` reseeds the work queue from the frontier, anything else
+/// rescans from scratch.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanProgress {
+    pub root:     PathBuf,
+    /// Discovered but not yet enumerated directories, with their depth below `root`
+    pub frontier: Vec<(PathBuf, usize)>,
+}
+
+impl ScanProgress {
+    /// Sidecar location for the cache at `cache_path`
+    pub fn path_for(cache_path: &Path) -> PathBuf {
+        cache_path.with_extension("resume")
+    }
+
+    /// Read the sidecar, or `None` if the last scan finished
+    pub fn load(cache_path: &Path) -> Result<Option<Self>> {
+        let path = Self::path_for(cache_path);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let data = fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let progress =
+            serde_json::from_slice(&data).with_context(|| format!("Corrupt scan progress {}", path.display()))?;
+        Ok(Some(progress))
+    }
+
+    /// Mark the cache at `cache_path` as complete
+    pub fn clear(cache_path: &Path) -> Result<()> {
+        match fs::remove_file(Self::path_for(cache_path)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl DiskCache {
+    /// Save an unfinished scan: the entries enumerated so far plus its frontier
+    ///
+    /// The sidecar is written before the cache files, so a crash in between
+    /// leaves the cache flagged as partial rather than looking complete.
+    pub fn save_partial(&mut self, cache_path: &Path, frontier: Vec<(PathBuf, usize)>) -> Result<()> {
+        let progress = ScanProgress {
+            root: self.root.clone(),
+            frontier,
+        };
+
+        fs::create_dir_all(cache_path.parent().unwrap())?;
+        fs::write(ScanProgress::path_for(cache_path), serde_json::to_vec(&progress)?)?;
+        self.save(cache_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_round_trip_and_clear() -> Result<()> {
+        let temp = std::env::temp_dir().join("ptree_test_scan_progress");
+        let _ = fs::remove_dir_all(&temp);
+        let cache_path = temp.join("ptree.dat");
+
+        assert_eq!(ScanProgress::load(&cache_path)?, None);
+
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        let frontier = vec![(PathBuf::from("/data/a"), 1), (PathBuf::from("/data/b/c"), 2)];
+        cache.save_partial(&cache_path, frontier.clone())?;
+
+        let progress = ScanProgress::load(&cache_path)?.unwrap();
+        assert_eq!(progress.root, PathBuf::from("/data"));
+        assert_eq!(progress.frontier, frontier);
+        assert!(cache_path.with_extension("idx").exists());
+
+        ScanProgress::clear(&cache_path)?;
+        ScanProgress::clear(&cache_path)?;
+        assert_eq!(ScanProgress::load(&cache_path)?, None);
+
+        fs::write(ScanProgress::path_for(&cache_path), b"not json")?;
+        assert!(ScanProgress::load(&cache_path).is_err());
+
+        let _ = fs::remove_dir_all(&temp);
+        Ok(())
+    }
+}
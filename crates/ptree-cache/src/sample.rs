@@ -0,0 +1,129 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::cache::DiskCache;
+use crate::filter::minimal_subtree;
+
+// ============================================================================
+// Random Directory Sample (`--sample`, `--seed`)
+// ============================================================================
+
+/// SplitMix64: small, seedable and stable across releases, so a seed always
+/// reproduces the same sample
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform index in `0..bound` (`bound` > 0)
+    fn below(&mut self, bound: usize) -> usize {
+        ((self.next_u64() as u128 * bound as u128) >> 64) as usize
+    }
+}
+
+/// Pick `n` distinct indices out of `0..len` from `seed`, in draw order
+///
+/// A partial Fisher-Yates shuffle; all indices are returned when `n >= len`.
+pub fn sample_indices(len: usize, n: usize, seed: u64) -> Vec<usize> {
+    let mut rng = SplitMix64(seed);
+    let mut indices: Vec<usize> = (0..len).collect();
+    let n = n.min(len);
+    for i in 0..n {
+        let j = i + rng.below(len - i);
+        indices.swap(i, j);
+    }
+    indices.truncate(n);
+    indices
+}
+
+impl DiskCache {
+    /// `sample` directories below the root drawn with `sample_seed`, plus their ancestors
+    ///
+    /// Candidates are the directories in `candidates` when another filter already
+    /// ran, otherwise every loaded directory. They are sorted by path before
+    /// drawing, so the result only depends on the seed and the cached tree.
+    pub(crate) fn sample_visible(&self, n: usize, candidates: Option<&HashSet<PathBuf>>) -> HashSet<PathBuf> {
+        let mut dirs: Vec<&PathBuf> = self
+            .entries
+            .iter()
+            .filter(|(path, entry)| entry.is_dir && **path != self.root && path.starts_with(&self.root))
+            .filter(|(path, _)| candidates.is_none_or(|kept| kept.contains(*path)))
+            .map(|(path, _)| path)
+            .collect();
+        dirs.sort();
+
+        let drawn = sample_indices(dirs.len(), n, self.sample_seed);
+        minimal_subtree(&self.root, drawn.into_iter().map(|index| dirs[index].as_path()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::cache::DirEntry;
+    use crate::fixtures::{cache_with, dir, file};
+
+    fn fixture() -> DiskCache {
+        let names: Vec<String> = (0..10).map(|i| format!("d{i}")).collect();
+        let mut entries = vec![DirEntry::builder("/data").children(&names).build().unwrap()];
+        for name in &names {
+            entries.push(dir(format!("/data/{name}"), &["sub", "f.txt"]));
+            entries.push(dir(format!("/data/{name}/sub"), &[]));
+            entries.push(file(format!("/data/{name}/f.txt"), 0));
+        }
+        cache_with("/data", entries)
+    }
+
+    #[test]
+    fn test_sample_indices_deterministic_and_distinct() {
+        let first = sample_indices(100, 10, 42);
+        assert_eq!(first, sample_indices(100, 10, 42));
+        assert_ne!(first, sample_indices(100, 10, 43));
+        assert_eq!(first.iter().collect::<HashSet<_>>().len(), 10);
+        assert!(first.iter().all(|&i| i < 100));
+
+        let mut all = sample_indices(5, 10, 7);
+        all.sort();
+        assert_eq!(all, vec![0, 1, 2, 3, 4]);
+        assert!(sample_indices(0, 3, 7).is_empty());
+    }
+
+    #[test]
+    fn test_sample_keeps_ancestors_and_repeats_per_seed() -> anyhow::Result<()> {
+        let mut cache = fixture();
+        cache.sample = Some(4);
+        cache.sample_seed = 9;
+        let visible = cache.filter_visible(&cache.root).unwrap();
+
+        let sampled: Vec<&PathBuf> = visible.iter().filter(|p| **p != cache.root).collect();
+        assert!(!sampled.is_empty());
+        for path in &sampled {
+            assert!(cache.entries[*path].is_dir, "files are never sampled: {}", path.display());
+            for ancestor in path.ancestors().take_while(|a| a.starts_with(Path::new("/data"))) {
+                assert!(visible.contains(ancestor), "missing ancestor {}", ancestor.display());
+            }
+        }
+        let leaves = sampled
+            .iter()
+            .filter(|p| !sampled.iter().any(|other| other.parent() == Some(p.as_path())))
+            .count();
+        assert!(leaves <= 4);
+
+        let output = cache.build_tree_output()?;
+        assert_eq!(output, cache.build_tree_output()?);
+        assert_eq!(output.lines().count(), visible.len());
+        assert!(!output.contains("f.txt"));
+
+        cache.sample_seed = 10;
+        assert_ne!(cache.filter_visible(&cache.root).unwrap(), visible);
+        Ok(())
+    }
+}
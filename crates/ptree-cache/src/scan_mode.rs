@@ -0,0 +1,27 @@
+use ptree_core::SymlinkMode;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Scan Mode
+// ============================================================================
+
+/// Scan settings that change what ends up in the cache
+///
+/// Render-time options only filter what the cache already holds, but these
+/// decide what gets enumerated and stat'ed in the first place. The mode is
+/// persisted with the index, and a run asking for a different one rescans
+/// instead of reusing entries that would be missing or zeroed for it.
+///
+/// The default describes a scan with none of them, which is also what caches
+/// written before the mode was recorded contain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanMode {
+    /// Files were neither listed nor cached (`--structure-only`)
+    pub structure_only:   bool,
+    /// Nothing was stat'ed: sizes are 0 and times a placeholder (`--no-metadata`)
+    pub no_metadata:      bool,
+    /// Hidden directories were listed but never enumerated (`--exclude-hidden-dirs-from-scan`)
+    pub skip_hidden_dirs: bool,
+    /// How symlinks were treated (`--symlinks`)
+    pub symlinks:         SymlinkMode,
+}
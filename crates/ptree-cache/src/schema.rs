@@ -0,0 +1,108 @@
+use serde_json::{json, Value};
+
+// ============================================================================
+// JSON Output Schema (`--json-schema`)
+// ============================================================================
+
+/// Version of the JSON tree layout; bump together with `json_schema` on any shape change
+pub const JSON_OUTPUT_VERSION: u32 = 2;
+
+/// JSON Schema (draft-07) for the documents produced by `--format json`
+///
+/// Every node has `path` and `children`; the root has a `name` only with
+/// `--render-root-name` or `--root-basename`.
+/// `--size` adds `size` and `modified` to every node; `modified` is left out
+/// for entries scanned without metadata. `is_dir` is reserved and not
+/// emitted yet.
+pub fn json_schema() -> Value {
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "$id": format!("urn:ptree:tree-node:v{}", JSON_OUTPUT_VERSION),
+        "title": "ptree tree node",
+        "$ref": "#/definitions/node",
+        "definitions": {
+            "node": {
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Entry name; on the root node only when a root label is set"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "Absolute path (lossy UTF-8)"
+                    },
+                    "size": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "description": "Size in bytes, directories totalling their contents (--size)"
+                    },
+                    "modified": {
+                        "type": "string",
+                        "format": "date-time",
+                        "description": "Last modification time (RFC 3339, --size)"
+                    },
+                    "is_dir": {
+                        "type": "boolean"
+                    },
+                    "children": {
+                        "type": "array",
+                        "description": "Child nodes sorted by name; empty for files and past --max-depth",
+                        "items": { "$ref": "#/definitions/node" }
+                    }
+                },
+                "required": ["path", "children"],
+                "additionalProperties": false
+            }
+        }
+    })
+}
+
+/// Pretty-printed `json_schema` document
+pub fn build_json_schema_output() -> String {
+    serde_json::to_string_pretty(&json_schema()).expect("schema is valid JSON")
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonschema::JSONSchema;
+
+    use super::*;
+    use crate::cache::{DirEntry, NO_METADATA_MODIFIED};
+    use crate::fixtures::{cache_with, dir, file};
+
+    #[test]
+    fn test_json_output_validates_against_schema() -> anyhow::Result<()> {
+        let schema = JSONSchema::compile(&json_schema()).map_err(|e| anyhow::anyhow!("invalid schema: {}", e))?;
+
+        let empty = cache_with("/data", []);
+        assert!(schema.is_valid(&serde_json::from_str(&empty.build_json_output()?)?));
+
+        let mut cache = cache_with(
+            "/data",
+            [
+                dir("/data", &["docs", "readme.md"]),
+                dir("/data/docs", &["a.txt"]),
+                file("/data/docs/a.txt", 3),
+                DirEntry {
+                    modified: NO_METADATA_MODIFIED,
+                    ..file("/data/readme.md", 0)
+                },
+            ],
+        );
+        for show_sizes in [false, true] {
+            cache.show_sizes = show_sizes;
+            for max_depth in [None, Some(1)] {
+                let document: Value = serde_json::from_str(&cache.build_json_output_with_depth(max_depth)?)?;
+                assert!(schema.is_valid(&document), "output does not match schema: {document}");
+                assert_eq!(document.get("size").is_some(), show_sizes);
+            }
+        }
+
+        let unknown_field = json!({ "path": "/data", "children": [], "owner": "root" });
+        assert!(!schema.is_valid(&unknown_field));
+        let missing_children = json!({ "path": "/data", "children": [{ "name": "a", "path": "/data/a" }] });
+        assert!(!schema.is_valid(&missing_children));
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use std::cmp::Reverse;
+use std::path::PathBuf;
+
+use crate::cache::DiskCache;
+
+// ============================================================================
+// Directory Size Aggregation
+// ============================================================================
+
+impl DiskCache {
+    /// Set every directory's `size` to the total of the files below it
+    ///
+    /// Workers record a directory before its subdirectories are listed, so
+    /// totals can only be summed after the walk: deepest directories first,
+    /// each adding its files' sizes and its subdirectories' finished totals.
+    pub fn compute_sizes(&mut self) {
+        let mut dirs: Vec<PathBuf> = self
+            .entries
+            .values()
+            .filter(|e| e.is_dir)
+            .map(|e| e.path.clone())
+            .collect();
+        dirs.sort_by_key(|dir| Reverse(dir.components().count()));
+
+        for dir in dirs {
+            let total = self.children_entries(&dir).iter().map(|child| child.size).sum();
+            if let Some(entry) = self.entries.get_mut(&dir) {
+                entry.size = total;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::DirEntry;
+
+    #[test]
+    fn test_compute_sizes_totals_descendants() -> anyhow::Result<()> {
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        for (path, children, is_dir, size) in [
+            ("/data", vec!["a", "top.txt", "empty"], true, 0),
+            ("/data/top.txt", vec![], false, 7),
+            ("/data/a", vec!["b", "one.bin"], true, 0),
+            ("/data/a/one.bin", vec![], false, 100),
+            ("/data/a/b", vec!["c", "two.bin"], true, 0),
+            ("/data/a/b/two.bin", vec![], false, 20),
+            ("/data/a/b/c", vec!["three.bin"], true, 0),
+            ("/data/a/b/c/three.bin", vec![], false, 3),
+            ("/data/empty", vec![], true, 999),
+        ] {
+            cache.entries.insert(
+                PathBuf::from(path),
+                DirEntry::builder(path)
+                    .children(children)
+                    .is_dir(is_dir)
+                    .size(size)
+                    .build()?,
+            );
+        }
+
+        cache.compute_sizes();
+        let size = |cache: &DiskCache, path: &str| cache.entries[&PathBuf::from(path)].size;
+        assert_eq!(size(&cache, "/data/a/b/c"), 3);
+        assert_eq!(size(&cache, "/data/a/b"), 23);
+        assert_eq!(size(&cache, "/data/a"), 123);
+        assert_eq!(size(&cache, "/data/empty"), 0);
+        assert_eq!(size(&cache, "/data"), 130);
+        assert_eq!(size(&cache, "/data/a/one.bin"), 100, "file sizes are left alone");
+
+        // Rerunning after a change doesn't count old totals twice
+        cache.entries.get_mut(&PathBuf::from("/data/a/b/two.bin")).unwrap().size = 0;
+        cache.compute_sizes();
+        assert_eq!(size(&cache, "/data"), 110);
+        assert_eq!(cache.stats().total_size, 110, "stats count file bytes only");
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+use std::io;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use crate::cache::{DirEntry, DiskCache};
+use crate::cache_rkyv::{write_record, RkyvCacheIndexRef, RkyvDirEntryRef, DATA_HEADER_LEN};
+use crate::units::format_size;
+
+// ============================================================================
+// Free-Space Guard
+// ============================================================================
+
+/// Caches up to this many entries are measured entry by entry; larger ones
+/// are measured on an evenly spaced sample of about this size
+const SAMPLE_ENTRIES: usize = 4096;
+
+impl DiskCache {
+    /// Bytes `save` will write for the index and data files
+    ///
+    /// Entries are encoded with the same record writer `save` uses (into a
+    /// sink), so the estimate follows the cache format and any new fields. It
+    /// is exact up to `SAMPLE_ENTRIES` entries and extrapolated from a sample
+    /// beyond that.
+    pub fn estimated_save_size(&self) -> u64 {
+        let total = self.entries.len() + self.pending_writes.len();
+        let step = total.div_ceil(SAMPLE_ENTRIES).max(1);
+        let entries = self.entries.values().chain(self.pending_writes.iter().map(|(_, e)| e));
+        let (sampled, bytes) = entries
+            .step_by(step)
+            .fold((0u64, 0u64), |(count, bytes), entry| (count + 1, bytes + self.saved_entry_size(entry)));
+        let entry_bytes = (bytes * total as u64).checked_div(sampled).unwrap_or(0);
+
+        entry_bytes + DATA_HEADER_LEN + self.index_fixed_size()
+    }
+
+    /// Data record plus index offset written for `entry`
+    fn saved_entry_size(&self, entry: &DirEntry) -> u64 {
+        let record = write_record(&mut io::sink(), self.cache_format, &RkyvDirEntryRef::from(entry)).unwrap_or(0);
+        let offset = bincode::serialized_size(&(entry.path.as_path(), 0u64)).unwrap_or(0);
+        record + offset
+    }
+
+    /// Index bytes that don't depend on the number of entries
+    fn index_fixed_size(&self) -> u64 {
+        let index = RkyvCacheIndexRef {
+            offsets:                   &[],
+            last_scan:                 self.last_scan,
+            root:                      &self.root,
+            last_scanned_root:         &self.last_scanned_root,
+            hash_algo:                 self.hash_algo,
+            #[cfg(windows)]
+            usn_state:                 &self.usn_state,
+            skip_stats:                &self.skip_stats,
+            scan_mode:                 self.scan_mode,
+        };
+        bincode::serialized_size(&index).unwrap_or(0)
+    }
+
+    /// Refuse to save if the cache wouldn't fit, or would leave less than
+    /// `min_free_space` bytes free on the target volume
+    ///
+    /// The new files are written next to the old ones before being renamed over
+    /// them, so they must fit without the old cache's space; `min_free_space`
+    /// applies to the end state, once the old files are gone. Does nothing if
+    /// free space can't be determined.
+    pub(crate) fn ensure_space_for_save(&self, index_path: &Path, data_path: &Path) -> Result<()> {
+        let Some(dir) = data_path.parent() else {
+            return Ok(());
+        };
+        let Some(available) = available_space(dir) else {
+            return Ok(());
+        };
+
+        let reclaimable = [index_path, data_path]
+            .iter()
+            .filter_map(|p| std::fs::metadata(p).ok())
+            .map(|m| m.len())
+            .sum();
+
+        let needed = self.estimated_save_size();
+        check_free_space(available, 0, needed, 0)?;
+        check_free_space(available, reclaimable, needed, self.min_free_space)
+    }
+}
+
+/// Compare free space against what a save needs
+///
+/// `reclaimable` is space freed once the previous cache files are replaced.
+pub fn check_free_space(available: u64, reclaimable: u64, needed: u64, min_free: u64) -> Result<()> {
+    let room = available.saturating_add(reclaimable);
+
+    if needed > room {
+        bail!(
+            "Not enough disk space to save the cache: need ~{}, only {} available",
+            format_size(needed, false),
+            format_size(room, false)
+        );
+    }
+
+    let remaining = room - needed;
+    if remaining < min_free {
+        bail!(
+            "Saving the cache (~{}) would leave {} free, below --min-free-space {}",
+            format_size(needed, false),
+            format_size(remaining, false),
+            format_size(min_free, false)
+        );
+    }
+
+    Ok(())
+}
+
+/// Free bytes on the volume holding `path`, if it can be determined
+///
+/// Picks the mounted disk with the longest mount point containing `path`.
+pub fn available_space(path: &Path) -> Option<u64> {
+    let path = path.canonicalize().ok()?;
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+
+    disks
+        .list()
+        .iter()
+        .filter(|d| path.starts_with(d.mount_point()))
+        .max_by_key(|d| d.mount_point().as_os_str().len())
+        .map(|d| d.available_space())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ptree_core::CacheFormat;
+
+    use super::*;
+    use crate::permissions::UnixPermissions;
+
+    const MIB: u64 = 1024 * 1024;
+
+    #[test]
+    fn test_check_free_space_thresholds() {
+        // Plenty of room, no threshold
+        assert!(check_free_space(100 * MIB, 0, 10 * MIB, 0).is_ok());
+        // Exactly fits
+        assert!(check_free_space(10 * MIB, 0, 10 * MIB, 0).is_ok());
+
+        // Doesn't fit at all
+        let err = check_free_space(5 * MIB, 0, 10 * MIB, 0).unwrap_err().to_string();
+        assert!(err.contains("Not enough disk space"), "{}", err);
+
+        // Fits, but would drop below the threshold
+        let err = check_free_space(100 * MIB, 0, 60 * MIB, 50 * MIB)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("--min-free-space"), "{}", err);
+        assert!(check_free_space(100 * MIB, 0, 50 * MIB, 50 * MIB).is_ok());
+    }
+
+    #[test]
+    fn test_check_free_space_counts_replaced_files() {
+        assert!(check_free_space(5 * MIB, 0, 8 * MIB, 0).is_err());
+        assert!(check_free_space(5 * MIB, 4 * MIB, 8 * MIB, 0).is_ok());
+        assert!(check_free_space(u64::MAX, u64::MAX, 1, 0).is_ok());
+    }
+
+    #[test]
+    fn test_estimated_save_size_matches_real_save() -> Result<()> {
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        cache.skip_stats.insert("node_modules".to_string(), 3);
+        for i in 0..50 {
+            let dir = format!("/data/dir{}", i);
+            let file = format!("{}/a.txt", dir);
+            cache.entries.insert(
+                PathBuf::from(&dir),
+                DirEntry::builder(&dir)
+                    .children(["a.txt", "link"])
+                    .size(i * 1000)
+                    .depth(1)
+                    .permissions(Some(UnixPermissions {
+                        mode: 0o40755,
+                        uid:  1000,
+                        gid:  1000,
+                    }))
+                    .attributes(Some(0x10))
+                    .build()?,
+            );
+            cache
+                .entries
+                .insert(PathBuf::from(&file), DirEntry::builder(&file).is_dir(false).size(i).depth(2).build()?);
+            let link = format!("{}/link", dir);
+            cache.entries.insert(
+                PathBuf::from(&link),
+                DirEntry::builder(&link)
+                    .is_dir(false)
+                    .symlink_target(Some(PathBuf::from("/elsewhere/target")))
+                    .depth(2)
+                    .build()?,
+            );
+        }
+
+        let temp = std::env::temp_dir().join("ptree_test_estimated_size");
+        for format in [CacheFormat::Bincode, CacheFormat::Rkyv, CacheFormat::Json] {
+            let _ = std::fs::remove_dir_all(&temp);
+            let cache_path = temp.join("ptree.dat");
+            cache.cache_format = format;
+            cache.save(&cache_path)?;
+            let actual = std::fs::metadata(cache_path.with_extension("dat"))?.len()
+                + std::fs::metadata(cache_path.with_extension("idx"))?.len();
+
+            assert_eq!(cache.estimated_save_size(), actual, "{format}");
+        }
+
+        let _ = std::fs::remove_dir_all(&temp);
+        Ok(())
+    }
+
+    #[test]
+    fn test_estimated_save_size_extrapolates_large_caches() {
+        let mut cache = DiskCache::new_empty();
+        for i in 0..3 * SAMPLE_ENTRIES {
+            let path = format!("/data/file{:05}", i);
+            cache
+                .entries
+                .insert(PathBuf::from(&path), DirEntry::builder(&path).is_dir(false).build().unwrap());
+        }
+        let exact: u64 = cache.entries.values().map(|e| cache.saved_entry_size(e)).sum::<u64>()
+            + DATA_HEADER_LEN
+            + cache.index_fixed_size();
+
+        let estimate = cache.estimated_save_size();
+        assert!(estimate.abs_diff(exact) * 100 < exact, "estimate {} vs exact {}", estimate, exact);
+    }
+}
@@ -0,0 +1,188 @@
+use serde_json::{json, Value};
+
+use crate::cache::{relative_depth, DirEntry, DiskCache};
+
+// ============================================================================
+// Aggregate Cache Metrics
+// ============================================================================
+
+/// Totals over every loaded cache entry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub entry_count:    usize,
+    pub total_size:     u64,
+    /// Deepest entry below `root`, in path components
+    pub max_depth:      u32,
+    /// Sum of every entry's child list length
+    pub total_children: usize,
+    pub dir_count:      usize,
+    pub file_count:     usize,
+}
+
+impl CacheStats {
+    /// `--count-only` line: `dirs files bytes`
+    pub fn count_line(&self) -> String {
+        format!("{} {} {}", self.dir_count, self.file_count, self.total_size)
+    }
+
+    /// `--count-only --format json` document
+    pub fn count_json(&self) -> Value {
+        json!({
+            "dirs": self.dir_count,
+            "files": self.file_count,
+            "bytes": self.total_size,
+        })
+    }
+
+    /// Count `entry`, found at `depth` below the root
+    fn add(&mut self, entry: &DirEntry, depth: u32) {
+        self.entry_count += 1;
+        self.max_depth = self.max_depth.max(depth);
+        self.total_children += entry.children.len();
+        if entry.is_dir {
+            self.dir_count += 1;
+        } else {
+            // Directory sizes are totals of these, so only files are added up
+            self.file_count += 1;
+            self.total_size += entry.size;
+        }
+    }
+}
+
+impl DiskCache {
+    /// Compute `CacheStats` in one pass over `entries`
+    ///
+    /// Only loaded entries are counted: call `load_all_entries_lazy` first on a
+    /// freshly opened cache, and `flush_pending_writes` during a scan.
+    pub fn stats(&self) -> CacheStats {
+        let mut stats = CacheStats::default();
+
+        for (path, entry) in &self.entries {
+            stats.add(entry, relative_depth(&self.root, path));
+        }
+
+        stats
+    }
+
+    /// `CacheStats` over just the entries the tree render shows (`--count-only`)
+    ///
+    /// Walks from `root` with the renderer's `filter_visible` and `shows_child`
+    /// checks, stopping at `max_depth` and at collapsed directories, so the
+    /// totals match the rendered tree. Listed names with no entry (files in a
+    /// `--structure-only` cache) count as empty files.
+    pub fn visible_stats(&self, max_depth: Option<usize>) -> CacheStats {
+        let mut stats = CacheStats::default();
+        let Some(root) = self.get_entry(&self.root) else {
+            return stats;
+        };
+        stats.add(root, 0);
+
+        let visible = self.filter_visible(&self.root);
+        let mut stack = vec![(self.root.clone(), 0)];
+        while let Some((path, depth)) = stack.pop() {
+            if max_depth.is_some_and(|max| depth >= max) {
+                continue;
+            }
+            let Some(entry) = self.get_entry(&path) else {
+                continue;
+            };
+            for child in entry
+                .children
+                .iter()
+                .filter(|c| self.shows_child(visible.as_ref(), &path, c))
+            {
+                let child_path = path.join(child);
+                match self.get_entry(&child_path) {
+                    Some(child_entry) => {
+                        stats.add(child_entry, depth as u32 + 1);
+                        if !self.is_collapsed(child_entry) {
+                            stack.push((child_path, depth + 1));
+                        }
+                    }
+                    None => {
+                        stats.entry_count += 1;
+                        stats.file_count += 1;
+                        stats.max_depth = stats.max_depth.max(depth as u32 + 1);
+                    }
+                }
+            }
+        }
+
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{cache_with, dir, file};
+
+    #[test]
+    fn test_stats_on_fixture() {
+        assert_eq!(DiskCache::new_empty().stats(), CacheStats::default());
+
+        // Stored depths are left at 0: stats must derive depth from the path
+        let cache = cache_with(
+            "/data",
+            [
+                dir("/data", &["a", "f.txt"]),
+                dir("/data/a", &["b"]),
+                dir("/data/a/b", &["g.bin"]),
+                file("/data/a/b/g.bin", 100),
+                file("/data/f.txt", 20),
+            ],
+        );
+
+        let stats = cache.stats();
+        assert_eq!(stats.entry_count, 5);
+        assert_eq!(stats.total_size, 120);
+        assert_eq!(stats.max_depth, 3);
+        assert_eq!(stats.total_children, 4);
+        assert_eq!(stats.dir_count, 3);
+        assert_eq!(stats.file_count, 2);
+
+        assert_eq!(stats.count_line(), "3 2 120");
+        assert_eq!(stats.count_json(), json!({ "dirs": 3, "files": 2, "bytes": 120 }));
+    }
+
+    #[test]
+    fn test_visible_stats_match_rendered_tree() -> anyhow::Result<()> {
+        let mut cache = cache_with(
+            "/data",
+            [
+                dir("/data", &[".config", "src", "secret.txt", "listed-only"]),
+                dir("/data/.config", &["settings.toml"]),
+                file("/data/.config/settings.toml", 5),
+                dir("/data/src", &["main.rs"]),
+                file("/data/src/main.rs", 40),
+                DirEntry::builder("/data/secret.txt")
+                    .is_dir(false)
+                    .size(9)
+                    .hidden(true)
+                    .build()?,
+            ],
+        );
+        let rendered = |cache: &DiskCache| cache.build_tree_output().unwrap().lines().count();
+
+        // Hidden entries stay out until shown; names without an entry still render
+        let stats = cache.visible_stats(None);
+        assert_eq!(stats.count_line(), "3 3 45");
+        assert_eq!(stats.entry_count, rendered(&cache));
+        assert_eq!(stats.max_depth, 2);
+
+        cache.show_hidden = true;
+        assert_eq!(cache.visible_stats(None).count_line(), "3 4 54");
+        assert_eq!(cache.visible_stats(None).entry_count, rendered(&cache));
+
+        cache.exclude_dotfiles = true;
+        assert_eq!(cache.visible_stats(None).count_line(), "2 3 49");
+        assert_eq!(cache.visible_stats(None).entry_count, rendered(&cache));
+
+        assert_eq!(cache.visible_stats(Some(1)).count_line(), "2 2 9");
+        assert_eq!(
+            cache.visible_stats(Some(1)).entry_count,
+            cache.build_tree_output_with_depth(Some(1))?.lines().count()
+        );
+        Ok(())
+    }
+}
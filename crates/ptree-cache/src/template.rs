@@ -0,0 +1,222 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use colored::{Color, Colorize};
+
+use crate::cache::{strip_path_prefix, DirEntry};
+use crate::units::format_size;
+
+/// One segment of a parsed `--entries-format` template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplatePart {
+    Literal(String),
+    Name,
+    Path,
+    Size,
+    Mtime,
+    Children,
+}
+
+/// Parsed per-node rendering template (e.g. `"{name} ({size})"`)
+///
+/// Supported placeholders: `{name}`, `{path}`, `{size}`, `{mtime}`, `{children}`.
+/// Literal braces are written as `{{` and `}}`. Templates are validated once at
+/// parse time so rendering a node can never fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryTemplate {
+    parts:        Vec<TemplatePart>,
+    si:           bool,
+    strip_prefix: Option<PathBuf>,
+}
+
+impl EntryTemplate {
+    /// Parse a template string, rejecting unknown or unterminated placeholders
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut key = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(ch) => key.push(ch),
+                            None => return Err(anyhow!("Unterminated placeholder '{{{}' in entries format", key)),
+                        }
+                    }
+
+                    let part = match key.as_str() {
+                        "name" => TemplatePart::Name,
+                        "path" => TemplatePart::Path,
+                        "size" => TemplatePart::Size,
+                        "mtime" => TemplatePart::Mtime,
+                        "children" => TemplatePart::Children,
+                        other => {
+                            return Err(anyhow!(
+                                "Unknown placeholder '{{{}}}' in entries format (expected name, path, size, mtime, \
+                                 children)",
+                                other
+                            ))
+                        }
+                    };
+
+                    if !literal.is_empty() {
+                        parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(part);
+                }
+                '}' => return Err(anyhow!("Unmatched '}}' in entries format (use '}}}}' for a literal brace)")),
+                other => literal.push(other),
+            }
+        }
+
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(literal));
+        }
+
+        Ok(EntryTemplate {
+            parts,
+            si: false,
+            strip_prefix: None,
+        })
+    }
+
+    /// Use SI units when expanding `{size}`
+    pub fn with_si(mut self, si: bool) -> Self {
+        self.si = si;
+        self
+    }
+
+    /// Remove `prefix` from `{path}`; paths outside it stay absolute
+    pub fn with_strip_prefix(mut self, prefix: Option<PathBuf>) -> Self {
+        self.strip_prefix = prefix;
+        self
+    }
+
+    pub fn parts(&self) -> &[TemplatePart] {
+        &self.parts
+    }
+}
+
+/// Expand a template for a single cache entry
+pub fn render_entry(entry: &DirEntry, template: &EntryTemplate) -> String {
+    template
+        .parts
+        .iter()
+        .map(|part| expand_part(part, entry, template))
+        .collect()
+}
+
+/// Expand a template in `color`, with `{size}` in `size_color` (colored tree output)
+pub fn render_entry_colored(entry: &DirEntry, template: &EntryTemplate, color: Color, size_color: Color) -> String {
+    template
+        .parts
+        .iter()
+        .map(|part| {
+            let text = expand_part(part, entry, template);
+            let part_color = if *part == TemplatePart::Size { size_color } else { color };
+            text.color(part_color).to_string()
+        })
+        .collect()
+}
+
+fn expand_part(part: &TemplatePart, entry: &DirEntry, template: &EntryTemplate) -> String {
+    match part {
+        TemplatePart::Literal(text) => text.clone(),
+        TemplatePart::Name => entry.name.clone(),
+        TemplatePart::Path => {
+            template
+                .strip_prefix
+                .as_deref()
+                .and_then(|prefix| strip_path_prefix(&entry.path, prefix))
+                .unwrap_or_else(|| entry.path.to_string_lossy().to_string())
+        }
+        TemplatePart::Size => format_size(entry.size, template.si),
+        TemplatePart::Mtime => entry.modified.format("%Y-%m-%d %H:%M:%S").to_string(),
+        TemplatePart::Children => entry.children.len().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn sample_entry() -> DirEntry {
+        DirEntry::builder("/data/logs")
+            .modified(Utc.with_ymd_and_hms(2024, 3, 1, 12, 30, 0).unwrap())
+            .children(["a.log", "b.log"])
+            .size(2048)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_template_parts() -> Result<()> {
+        let template = EntryTemplate::parse("{name} [{children}]")?;
+        assert_eq!(
+            template.parts(),
+            &[
+                TemplatePart::Name,
+                TemplatePart::Literal(" [".to_string()),
+                TemplatePart::Children,
+                TemplatePart::Literal("]".to_string()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_placeholders() {
+        assert!(EntryTemplate::parse("{nme}").is_err());
+        assert!(EntryTemplate::parse("{name").is_err());
+        assert!(EntryTemplate::parse("name}").is_err());
+    }
+
+    #[test]
+    fn test_render_entry_templates() -> Result<()> {
+        let entry = sample_entry();
+
+        assert_eq!(render_entry(&entry, &EntryTemplate::parse("{name}")?), "logs");
+        assert_eq!(render_entry(&entry, &EntryTemplate::parse("{name} ({size})")?), "logs (2.0 KiB)");
+        assert_eq!(render_entry(&entry, &EntryTemplate::parse("{size}")?.with_si(true)), "2.0 kB");
+        assert_eq!(render_entry(&entry, &EntryTemplate::parse("{{{children}}} {mtime}")?), "{2} 2024-03-01 12:30:00");
+        assert_eq!(render_entry(&entry, &EntryTemplate::parse("{path}")?), "/data/logs");
+        let strip = |prefix: &str| EntryTemplate::parse("{path}").map(|t| t.with_strip_prefix(Some(prefix.into())));
+        assert_eq!(render_entry(&entry, &strip("/data")?), "logs");
+        assert_eq!(render_entry(&entry, &strip("/data/logs")?), ".");
+        // Not a component-wise prefix: left absolute
+        assert_eq!(render_entry(&entry, &strip("/da")?), "/data/logs");
+        Ok(())
+    }
+
+    #[test]
+    fn test_tree_output_uses_template() -> Result<()> {
+        let mut cache = crate::cache::DiskCache::new_empty();
+        let mut root = sample_entry();
+        root.children = vec!["logs".to_string()];
+        root.path = PathBuf::from("/data");
+        cache.root = root.path.clone();
+        cache.entries.insert(root.path.clone(), root);
+        cache.entries.insert(PathBuf::from("/data/logs"), sample_entry());
+        cache.entry_template = Some(EntryTemplate::parse("{name}: {children} children")?);
+
+        let output = cache.build_tree_output()?;
+        assert!(output.contains("└── logs: 2 children"), "{}", output);
+        Ok(())
+    }
+}
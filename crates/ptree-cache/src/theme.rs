@@ -0,0 +1,192 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use colored::Color;
+
+// ============================================================================
+// Color Themes (`--theme`)
+// ============================================================================
+
+/// The 16 standard terminal colors, in ANSI index order
+const ANSI_16: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+    Color::BrightBlack,
+    Color::BrightRed,
+    Color::BrightGreen,
+    Color::BrightYellow,
+    Color::BrightBlue,
+    Color::BrightMagenta,
+    Color::BrightCyan,
+    Color::BrightWhite,
+];
+
+/// Colors used by the colored tree output
+///
+/// Defaults reproduce the built-in look: a bold blue root, cyan connectors and
+/// bright blue names. `--depth-colors` still overrides directory and file colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub root:      Color,
+    pub connector: Color,
+    pub directory: Color,
+    pub file:      Color,
+    pub symlink:   Color,
+    /// `{size}` in an `--entries-format` template
+    pub size:      Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            root:      Color::Blue,
+            connector: Color::Cyan,
+            directory: Color::BrightBlue,
+            file:      Color::BrightBlue,
+            symlink:   Color::BrightBlue,
+            size:      Color::BrightBlue,
+        }
+    }
+}
+
+impl Theme {
+    /// Read a TOML theme file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).with_context(|| format!("Failed to read theme {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid theme {}", path.display()))
+    }
+
+    /// Parse a TOML theme: top-level keys `root`, `connector`, `directory`,
+    /// `file`, `symlink` and `size`, each optional
+    ///
+    /// Values are color names (`"blue"`, `"bright magenta"`), hex strings
+    /// (`"#ff8800"`) or 256-color palette indices (`208`).
+    pub fn parse(text: &str) -> Result<Self> {
+        let table: toml::Table = text.parse()?;
+        let mut theme = Theme::default();
+
+        for (key, value) in &table {
+            let slot = match key.as_str() {
+                "root" => &mut theme.root,
+                "connector" => &mut theme.connector,
+                "directory" => &mut theme.directory,
+                "file" => &mut theme.file,
+                "symlink" => &mut theme.symlink,
+                "size" => &mut theme.size,
+                other => {
+                    bail!("Unknown theme key '{}' (expected root, connector, directory, file, symlink, size)", other)
+                }
+            };
+            *slot = match value {
+                toml::Value::String(s) => parse_color(s),
+                toml::Value::Integer(i) => {
+                    u8::try_from(*i)
+                        .map(ansi_256)
+                        .map_err(|_| anyhow!("256-color index {} is out of range (0-255)", i))
+                }
+                other => Err(anyhow!("expected a color name, \"#rrggbb\" or 0-255, found a {}", other.type_str())),
+            }
+            .with_context(|| format!("Bad color for theme key '{}'", key))?;
+        }
+        Ok(theme)
+    }
+}
+
+/// Parse a color name, `#rrggbb` hex, or a 256-color index given as a string
+pub fn parse_color(value: &str) -> Result<Color> {
+    let value = value.trim();
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            bail!("Invalid hex color '{}' (expected #rrggbb)", value);
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+        return match (channel(0), channel(2), channel(4)) {
+            (Ok(r), Ok(g), Ok(b)) => Ok(Color::TrueColor { r, g, b }),
+            _ => bail!("Invalid hex color '{}' (expected #rrggbb)", value),
+        };
+    }
+    if let Ok(index) = value.parse::<u8>() {
+        return Ok(ansi_256(index));
+    }
+
+    let name = value.replace(['_', '-'], " ");
+    name.parse::<Color>()
+        .map_err(|_| anyhow!("Unknown color '{}' (try blue, bright_red, #ff8800 or 208)", value))
+}
+
+/// xterm 256-color palette entry: the 16 standard colors, the 6x6x6 cube, then 24 grays
+fn ansi_256(index: u8) -> Color {
+    match index {
+        0..=15 => ANSI_16[index as usize],
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + 40 * v };
+            let i = index - 16;
+            Color::TrueColor {
+                r: level(i / 36),
+                g: level((i / 6) % 6),
+                b: level(i % 6),
+            }
+        }
+        232..=255 => {
+            let gray = 8 + 10 * (index - 232);
+            Color::TrueColor {
+                r: gray,
+                g: gray,
+                b: gray,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color_forms() -> Result<()> {
+        assert_eq!(parse_color("green")?, Color::Green);
+        assert_eq!(parse_color("Bright_Magenta")?, Color::BrightMagenta);
+        assert_eq!(parse_color("bright-cyan")?, Color::BrightCyan);
+        assert_eq!(parse_color("#FF8800")?, Color::TrueColor { r: 255, g: 136, b: 0 });
+        assert_eq!(parse_color("9")?, Color::BrightRed);
+        assert_eq!(parse_color("208")?, Color::TrueColor { r: 255, g: 135, b: 0 });
+        assert_eq!(parse_color("232")?, Color::TrueColor { r: 8, g: 8, b: 8 });
+        assert!(parse_color("#12345").is_err());
+        assert!(parse_color("#gg0000").is_err());
+        assert!(parse_color("chartreuse").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_theme_with_fallbacks() -> Result<()> {
+        let theme = Theme::parse(
+            r##"
+            directory = "#00ff00"
+            file = 244
+            symlink = "bright yellow"
+            "##,
+        )?;
+        assert_eq!(theme.directory, Color::TrueColor { r: 0, g: 255, b: 0 });
+        assert_eq!(theme.file, Color::TrueColor { r: 128, g: 128, b: 128 });
+        assert_eq!(theme.symlink, Color::BrightYellow);
+        // Missing keys keep the defaults
+        assert_eq!(theme.root, Theme::default().root);
+        assert_eq!(theme.connector, Color::Cyan);
+
+        assert_eq!(Theme::parse("")?, Theme::default());
+        assert!(Theme::parse("directroy = \"red\"")
+            .unwrap_err()
+            .to_string()
+            .contains("Unknown theme key"));
+        assert!(format!("{:#}", Theme::parse("file = 300").unwrap_err()).contains("out of range"));
+        assert!(Theme::parse("file = true").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use unicode_width::UnicodeWidthStr;
+
+const BINARY_UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+const SI_UNITS: [&str; 7] = ["B", "kB", "MB", "GB", "TB", "PB", "EB"];
+
+/// Format a byte count as a human-readable size
+///
+/// - Binary (default): base 1024 with `KiB`/`MiB`/... suffixes
+/// - SI (`si = true`): base 1000 with `kB`/`MB`/... suffixes
+///
+/// Values below one unit are shown as whole bytes (`1023 B`); larger values
+/// use one decimal place (`1.0 KiB`). A value that would round up to the next
+/// unit boundary (e.g. `1023.96 KiB`) is promoted to that unit instead.
+pub fn format_size(bytes: u64, si: bool) -> String {
+    let (base, units) = if si {
+        (1000.0, &SI_UNITS)
+    } else {
+        (1024.0, &BINARY_UNITS)
+    };
+
+    if (bytes as f64) < base {
+        return format!("{} B", bytes);
+    }
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= base && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+
+    // Avoid displaying "1024.0 KiB" when rounding reaches the next unit
+    if (value * 10.0).round() / 10.0 >= base && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", value, units[unit])
+}
+
+/// Format an age in seconds with its two largest units, e.g. `3d 4h`, `12m 5s`
+pub fn format_age(seconds: u64) -> String {
+    const UNITS: [(u64, &str); 4] = [(24 * 60 * 60, "d"), (60 * 60, "h"), (60, "m"), (1, "s")];
+
+    let Some(first) = UNITS.iter().position(|&(size, _)| seconds >= size) else {
+        return "0s".to_string();
+    };
+    let (size, unit) = UNITS[first];
+    let mut out = format!("{}{}", seconds / size, unit);
+    if let Some(&(next_size, next_unit)) = UNITS.get(first + 1) {
+        let rest = seconds % size / next_size;
+        if rest > 0 {
+            out.push_str(&format!(" {}{}", rest, next_unit));
+        }
+    }
+    out
+}
+
+/// Format `n` with comma thousands separators (`1234` -> `1,234`)
+pub fn group_thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Terminal columns `text` occupies: wide CJK and emoji take two, combining marks none
+///
+/// `format!` padding counts `char`s, which misaligns columns holding such text.
+pub fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// `text` followed by spaces up to `width` display columns (left-aligned)
+pub fn pad_right(text: &str, width: usize) -> String {
+    format!("{}{}", text, " ".repeat(width.saturating_sub(display_width(text))))
+}
+
+/// Spaces up to `width` display columns followed by `text` (right-aligned)
+pub fn pad_left(text: &str, width: usize) -> String {
+    format!("{}{}", " ".repeat(width.saturating_sub(display_width(text))), text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_thousands() {
+        assert_eq!(group_thousands(0), "0");
+        assert_eq!(group_thousands(999), "999");
+        assert_eq!(group_thousands(1234), "1,234");
+        assert_eq!(group_thousands(1_234_567), "1,234,567");
+    }
+
+    #[test]
+    fn test_display_width_and_padding() {
+        assert_eq!(display_width("docs"), 4);
+        assert_eq!(display_width("資料"), 4);
+        assert_eq!(display_width("🦀.rs"), 5);
+        // `e` + combining acute accent is one column
+        assert_eq!(display_width("cafe\u{301}"), 4);
+
+        for name in ["docs", "資料", "🦀.rs", "cafe\u{301}"] {
+            assert_eq!(display_width(&pad_right(name, 8)), 8, "{name}");
+            assert_eq!(display_width(&pad_left(name, 8)), 8, "{name}");
+        }
+        assert_eq!(pad_right("資料", 6), "資料  ");
+        assert_eq!(pad_left("資料", 6), "  資料");
+        // Never truncates
+        assert_eq!(pad_right("toolong", 3), "toolong");
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(0), "0s");
+        assert_eq!(format_age(59), "59s");
+        assert_eq!(format_age(60), "1m");
+        assert_eq!(format_age(12 * 60 + 5), "12m 5s");
+        assert_eq!(format_age(3 * 86_400 + 4 * 3600 + 59), "3d 4h");
+        assert_eq!(format_age(86_400 + 30), "1d");
+    }
+
+    #[test]
+    fn test_format_size_binary() {
+        assert_eq!(format_size(0, false), "0 B");
+        assert_eq!(format_size(1023, false), "1023 B");
+        assert_eq!(format_size(1024, false), "1.0 KiB");
+        assert_eq!(format_size(1536, false), "1.5 KiB");
+        assert_eq!(format_size(1024 * 1024 - 1, false), "1.0 MiB");
+        assert_eq!(format_size(1024 * 1024, false), "1.0 MiB");
+        assert_eq!(format_size(5 * 1024 * 1024 * 1024, false), "5.0 GiB");
+        assert_eq!(format_size(u64::MAX, false), "16.0 EiB");
+    }
+
+    #[test]
+    fn test_format_size_si() {
+        assert_eq!(format_size(999, true), "999 B");
+        assert_eq!(format_size(1000, true), "1.0 kB");
+        assert_eq!(format_size(1023, true), "1.0 kB");
+        assert_eq!(format_size(1024, true), "1.0 kB");
+        assert_eq!(format_size(1_500_000, true), "1.5 MB");
+        assert_eq!(format_size(999_999, true), "1.0 MB");
+        assert_eq!(format_size(2_000_000_000_000, true), "2.0 TB");
+    }
+}
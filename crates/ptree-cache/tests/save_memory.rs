@@ -0,0 +1,109 @@
+// Peak memory of a streamed save, measured with a counting global allocator
+// Kept in its own test binary so the allocator doesn't wrap the unit tests
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fs;
+use std::path::PathBuf;
+
+use ptree_cache::{DirEntry, DiskCache, SAVE_BUFFER_SIZE};
+
+/// Counts heap bytes allocated by threads inside `measure_peak`; others pass straight through
+struct TrackingAllocator;
+
+thread_local! {
+    static TRACKING: Cell<bool> = const { Cell::new(false) };
+    static LIVE_BYTES: Cell<isize> = const { Cell::new(0) };
+    static PEAK_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+fn track(delta: isize) {
+    let _ = TRACKING.try_with(|tracking| {
+        if tracking.get() {
+            let live = LIVE_BYTES.get() + delta;
+            LIVE_BYTES.set(live);
+            PEAK_BYTES.set(PEAK_BYTES.get().max(live));
+        }
+    });
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        track(layout.size() as isize);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        track(-(layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+/// Run `f`, returning its result and the peak bytes it held on this thread
+fn measure_peak<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    LIVE_BYTES.set(0);
+    PEAK_BYTES.set(0);
+    TRACKING.set(true);
+    let result = f();
+    TRACKING.set(false);
+    (result, PEAK_BYTES.get().max(0) as usize)
+}
+
+#[test]
+fn streamed_save_round_trips_large_cache() -> anyhow::Result<()> {
+    const ENTRIES: usize = 100_000;
+
+    let temp_dir = std::env::temp_dir().join("ptree_test_streamed_save");
+    let _ = fs::remove_dir_all(&temp_dir);
+    let cache_path = temp_dir.join("ptree.dat");
+
+    let (cache, cache_bytes) = measure_peak(|| -> anyhow::Result<DiskCache> {
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/data");
+        for i in 0..ENTRIES {
+            let path = PathBuf::from(format!("/data/dir{:03}/entry{:06}", i % 500, i));
+            let entry = DirEntry::builder(&path)
+                .content_hash(i as u64)
+                .children((0..3).map(|c| format!("child{c}.txt")))
+                .size(i as u64)
+                .depth(2)
+                .attributes(Some(i as u32))
+                .build()?;
+            cache.entries.insert(path, entry);
+        }
+        Ok(cache)
+    });
+    let mut cache = cache?;
+
+    let (saved, save_peak) = measure_peak(|| cache.save(&cache_path));
+    saved?;
+    // Beyond the write buffer, only a borrowed path and offset per entry are held
+    let streamed_bound = SAVE_BUFFER_SIZE + ENTRIES * 48;
+    assert!(
+        save_peak < cache_bytes / 2 && save_peak < streamed_bound,
+        "save held {} bytes for a {} byte cache (bound {})",
+        save_peak,
+        cache_bytes,
+        streamed_bound
+    );
+    assert!(!cache_path.with_extension("dat.tmp").exists());
+    assert!(!cache_path.with_extension("tmp").exists());
+
+    let mut reloaded = DiskCache::open(&cache_path)?;
+    assert_eq!(reloaded.entry_count_hint(), ENTRIES);
+    reloaded.load_all_entries_lazy(&cache_path)?;
+    assert_eq!(reloaded.entries.len(), ENTRIES);
+    for (path, entry) in &cache.entries {
+        let loaded = &reloaded.entries[path];
+        assert_eq!(loaded.name, entry.name);
+        assert_eq!(loaded.children, entry.children);
+        assert_eq!(loaded.content_hash, entry.content_hash);
+        assert_eq!(loaded.attributes, entry.attributes);
+    }
+
+    let _ = fs::remove_dir_all(&temp_dir);
+    Ok(())
+}
@@ -1,26 +1,51 @@
 use std::collections::HashSet;
 
-use clap::Parser;
+use clap::error::ErrorKind;
+use clap::{ArgAction, CommandFactory, FromArgMatches, Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
 
 // ============================================================================
 // Output Format Options
 // ============================================================================
 
-#[derive(Debug, Clone, Copy)]
+/// Output format (`--format`); serde names match the clap value names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
 pub enum OutputFormat {
+    #[serde(alias = "ascii")]
+    #[value(name = "tree", alias = "ascii")]
     Tree,
+    #[value(name = "json")]
     Json,
+    #[value(name = "mermaid")]
+    Mermaid,
+    #[value(name = "plist")]
+    Plist,
+    #[value(name = "toml")]
+    Toml,
+    #[serde(alias = "yml")]
+    #[value(name = "yaml", alias = "yml")]
+    Yaml,
+    #[value(name = "csv")]
+    Csv,
+    #[value(name = "dot")]
+    Dot,
+    #[serde(alias = "md")]
+    #[value(name = "markdown", alias = "md")]
+    Markdown,
 }
 
 impl std::str::FromStr for OutputFormat {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "tree" | "ascii" => Ok(OutputFormat::Tree),
-            "json" => Ok(OutputFormat::Json),
-            other => Err(format!("Unknown format: {}", other)),
-        }
+        <Self as ValueEnum>::from_str(s, true)
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_value_name(self, f)
     }
 }
 
@@ -28,22 +53,287 @@ impl std::str::FromStr for OutputFormat {
 // Color Mode Options
 // ============================================================================
 
-#[derive(Debug, Clone, Copy)]
+/// When to color output (`--color`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
 pub enum ColorMode {
+    #[value(name = "auto")]
     Auto,
+    #[value(name = "always")]
     Always,
+    #[value(name = "never")]
     Never,
 }
 
 impl std::str::FromStr for ColorMode {
     type Err = String;
 
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true)
+    }
+}
+
+impl std::fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_value_name(self, f)
+    }
+}
+
+/// Write the canonical clap name of a `ValueEnum` variant
+fn fmt_value_name<T: ValueEnum>(value: &T, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match value.to_possible_value() {
+        Some(value) => f.write_str(value.get_name()),
+        None => Ok(()),
+    }
+}
+
+// ============================================================================
+// Scan Scope Options
+// ============================================================================
+
+/// Which directory a normal (non-stdin) run scans
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScanScope {
+    /// Current directory, or the whole drive with --force - default
+    #[default]
+    Auto,
+    /// Always the whole drive (filesystem root on Unix)
+    Drive,
+    /// Always the current directory
+    Cwd,
+    /// The root recorded in the cache (current directory if the cache is empty)
+    Root,
+}
+
+impl std::str::FromStr for ScanScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(ScanScope::Auto),
+            "drive" => Ok(ScanScope::Drive),
+            "cwd" => Ok(ScanScope::Cwd),
+            "root" => Ok(ScanScope::Root),
+            other => Err(format!("Unknown scan scope: {}", other)),
+        }
+    }
+}
+
+// ============================================================================
+// Symlink Handling Options
+// ============================================================================
+
+/// How symbolic links encountered during traversal are treated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkMode {
+    /// Leave symlinks out of the tree entirely - default
+    #[default]
+    Skip,
+    /// Descend into linked directories, refusing links that would loop
+    Follow,
+    /// List the link as a leaf rendered `name -> target`, never descended
+    Show,
+}
+
+impl std::str::FromStr for SymlinkMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "skip" => Ok(SymlinkMode::Skip),
+            "follow" => Ok(SymlinkMode::Follow),
+            "show" => Ok(SymlinkMode::Show),
+            other => Err(format!("Unknown symlink mode: {}", other)),
+        }
+    }
+}
+
+// ============================================================================
+// Scan Error Policy
+// ============================================================================
+
+/// What happens when directories couldn't be read during a scan
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Print how many directories were unreadable - default
+    #[default]
+    Report,
+    /// Say nothing (`--quiet-errors`)
+    Quiet,
+    /// Fail the run (`--strict-errors`)
+    Strict,
+}
+
+// ============================================================================
+// Tree Style Options
+// ============================================================================
+
+/// Connector glyph preset for tree output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TreeStyle {
+    /// `├──` / `└──` box drawing - default
+    #[default]
+    Classic,
+    /// Box drawing with a rounded last-child corner (`╰──`)
+    Rounded,
+    /// Plain ASCII (`|--` / `` `-- ``) for terminals without box drawing
+    Ascii,
+    /// Indentation only, no connector glyphs
+    Minimal,
+    /// Heavy box drawing (`┣━━` / `┗━━`)
+    #[serde(alias = "heavy")]
+    Bold,
+}
+
+impl std::str::FromStr for TreeStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "classic" => Ok(TreeStyle::Classic),
+            "rounded" => Ok(TreeStyle::Rounded),
+            "ascii" => Ok(TreeStyle::Ascii),
+            "minimal" => Ok(TreeStyle::Minimal),
+            "bold" | "heavy" => Ok(TreeStyle::Bold),
+            other => Err(format!("Unknown tree style: {}", other)),
+        }
+    }
+}
+
+// ============================================================================
+// Extension Summary Options
+// ============================================================================
+
+/// Ordering of the `--group-by-extension` report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtensionSort {
+    /// Most files first - default
+    #[default]
+    Count,
+    /// Largest total size first
+    Size,
+}
+
+impl std::str::FromStr for ExtensionSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "count" => Ok(ExtensionSort::Count),
+            "size" => Ok(ExtensionSort::Size),
+            other => Err(format!("Unknown extension sort: {}", other)),
+        }
+    }
+}
+
+// ============================================================================
+// Duplicate Name Report Options
+// ============================================================================
+
+/// Entries considered by the `--dedupe-names` report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NameKind {
+    /// Files and directories - default
+    #[default]
+    All,
+    Files,
+    Dirs,
+}
+
+impl std::str::FromStr for NameKind {
+    type Err = String;
+
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
-            "auto" => Ok(ColorMode::Auto),
-            "always" => Ok(ColorMode::Always),
-            "never" => Ok(ColorMode::Never),
-            other => Err(format!("Unknown color mode: {}", other)),
+            "all" => Ok(NameKind::All),
+            "files" => Ok(NameKind::Files),
+            "dirs" => Ok(NameKind::Dirs),
+            other => Err(format!("Unknown name kind: {}", other)),
+        }
+    }
+}
+
+// ============================================================================
+// Content Hash Algorithm Options
+// ============================================================================
+
+/// Algorithm used to compute directory content hashes for change detection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HashAlgorithm {
+    /// xxHash3 (fast, non-cryptographic) - default
+    #[default]
+    Xxhash,
+    /// BLAKE3 (fast, cryptographic)
+    Blake3,
+    /// SHA-256 (cryptographic, slowest; suited to integrity auditing)
+    Sha256,
+}
+
+impl std::str::FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "xxhash" | "xxh3" => Ok(HashAlgorithm::Xxhash),
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            other => Err(format!("Unknown hash algorithm: {}", other)),
+        }
+    }
+}
+
+impl std::fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HashAlgorithm::Xxhash => write!(f, "xxhash"),
+            HashAlgorithm::Blake3 => write!(f, "blake3"),
+            HashAlgorithm::Sha256 => write!(f, "sha256"),
+        }
+    }
+}
+
+// ============================================================================
+// Cache Format Options
+// ============================================================================
+
+/// On-disk encoding of cache entries (`--cache-format`)
+///
+/// Recorded in the cache data header, so any format is read back regardless
+/// of the flag given when opening.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CacheFormat {
+    /// Length-prefixed bincode records - default
+    #[default]
+    Bincode,
+    /// Aligned rkyv archives, validated and read in place from the mmap
+    Rkyv,
+    /// One JSON object per line (larger and slower, but human-inspectable)
+    Json,
+}
+
+impl std::str::FromStr for CacheFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bincode" => Ok(CacheFormat::Bincode),
+            "rkyv" => Ok(CacheFormat::Rkyv),
+            "json" => Ok(CacheFormat::Json),
+            other => Err(format!("Unknown cache format: {}", other)),
+        }
+    }
+}
+
+impl std::fmt::Display for CacheFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheFormat::Bincode => write!(f, "bincode"),
+            CacheFormat::Rkyv => write!(f, "rkyv"),
+            CacheFormat::Json => write!(f, "json"),
         }
     }
 }
@@ -71,6 +361,32 @@ pub struct Args {
     #[arg(short, long)]
     pub force: bool,
 
+    /// Scan scope: auto, drive, cwd, root (default: auto)
+    #[arg(long, alias = "first-run-scan", default_value = "auto")]
+    pub scan_scope: ScanScope,
+
+    /// Read newline-delimited scan roots from stdin and scan each one
+    #[arg(long)]
+    pub stdin_roots: bool,
+
+    /// Continue a scan that was interrupted (Ctrl-C) instead of starting over
+    ///
+    /// Multi-root scans from --stdin-roots write no resume checkpoint.
+    #[arg(long, conflicts_with = "stdin_roots")]
+    pub resume: bool,
+
+    /// Treat invalid inputs (e.g. nonexistent stdin roots) as errors instead of warnings
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Fail with a nonzero exit if any directory couldn't be read during the scan
+    #[arg(long, conflicts_with = "quiet_errors")]
+    pub strict_errors: bool,
+
+    /// Don't report directories that couldn't be read or entries skipped for non-UTF-8 names
+    #[arg(long)]
+    pub quiet_errors: bool,
+
     // ========================================================================
     // Cache Options
     // ========================================================================
@@ -82,10 +398,79 @@ pub struct Args {
     #[arg(long)]
     pub cache_dir: Option<String>,
 
+    /// Warn on stderr when the displayed cache is older than AGE (e.g. 12h, 7d); doesn't force a rescan
+    #[arg(long, value_name = "AGE", value_parser = parse_duration)]
+    pub cache_max_age_warn: Option<u64>,
+
+    /// Let drive activity (Windows USN journal) override --cache-ttl: idle drives reuse the cache, busy ones rescan
+    #[arg(long)]
+    pub adaptive_freshness: bool,
+
     /// Disable cache entirely (scan fresh every time)
     #[arg(long)]
     pub no_cache: bool,
 
+    /// Content hash algorithm for change detection: xxhash, blake3, sha256
+    #[arg(long, default_value = "xxhash")]
+    pub hash_algo: HashAlgorithm,
+
+    /// Encoding used when saving the cache: bincode, rkyv, json (default: keep the existing cache's format)
+    #[arg(long, value_name = "FORMAT")]
+    pub cache_format: Option<CacheFormat>,
+
+    /// Refuse to save the cache if it would leave less than SIZE free (e.g. 500MB, 2GiB)
+    #[arg(long, value_name = "SIZE", value_parser = parse_byte_size)]
+    pub min_free_space: Option<u64>,
+
+    /// Print the cache file path this configuration uses and exit (nothing is loaded)
+    #[arg(long)]
+    pub print_cache_path: bool,
+
+    /// Print every cached entry as a flat record (read-only, for debugging)
+    #[arg(long)]
+    pub dump_cache: bool,
+
+    /// On load, drop cached entries whose paths no longer exist (after any --remap-drive)
+    #[arg(long)]
+    pub validate_paths: bool,
+
+    /// On load, move cached paths from one drive letter to another, e.g. `D=E`
+    #[arg(long, value_name = "FROM=TO", value_parser = parse_drive_remap)]
+    pub remap_drive: Option<(char, char)>,
+
+    /// Drop stale entries and dead records from the cache, rewrite it and exit (no scan)
+    #[arg(long)]
+    pub cache_compact: bool,
+
+    /// Rebuild a lost or corrupt cache index from the data file and exit (no scan)
+    #[arg(long)]
+    pub cache_repair: bool,
+
+    /// Show the state last reported by the ptree-driver service and exit
+    #[arg(long)]
+    pub driver_status: bool,
+
+    /// Print the JSON Schema describing `--format json` output and exit
+    #[arg(long)]
+    pub json_schema: bool,
+
+    /// List mounted volumes with label, filesystem and free/total space, then exit (honors --format json)
+    #[arg(long)]
+    pub list_drives: bool,
+
+    /// Serve the cached tree as JSON over HTTP on ADDR (requires the `serve` feature)
+    #[arg(long, value_name = "ADDR")]
+    pub serve: Option<String>,
+
+    /// Keep running and re-render the tree when files under the scan root change
+    /// (requires the `watch` feature)
+    #[arg(long, conflicts_with_all = ["stdin_roots", "stream", "count_only", "quiet"])]
+    pub watch: bool,
+
+    /// With --watch, coalesce filesystem events into one rescan and re-render per MS window
+    #[arg(long, value_name = "MS", default_value_t = 300, requires = "watch")]
+    pub watch_debounce: u64,
+
     // ========================================================================
     // Output & Display Options
     // ========================================================================
@@ -93,26 +478,173 @@ pub struct Args {
     #[arg(short, long)]
     pub quiet: bool,
 
-    /// Output format: tree or json
-    #[arg(long, default_value = "tree")]
+    /// Scan, then print only `dirs files bytes` of the entries the tree would show (or JSON with
+    /// --format json); no tree is built
+    #[arg(long, conflicts_with = "stream")]
+    pub count_only: bool,
+
+    /// Never pipe long tree output through $PAGER (default: less -R, or more on Windows)
+    #[arg(long)]
+    pub no_pager: bool,
+
+    /// Output format (`ascii` is an alias for `tree`)
+    #[arg(long, value_name = "FORMAT", default_value_t = OutputFormat::Tree, ignore_case = true)]
     pub format: OutputFormat,
 
-    /// Color output: auto, always, never
-    #[arg(long, default_value = "auto")]
+    /// Color output
+    #[arg(long, value_name = "MODE", default_value_t = ColorMode::Auto, ignore_case = true)]
     pub color: ColorMode,
 
-    /// Include directory sizes in output
+    /// TOML file with colors for root, connector, directory, file, symlink and size
+    #[arg(long, value_name = "FILE")]
+    pub theme: Option<String>,
+
+    /// Color tree names by depth (cycling blue, green, yellow, ...) instead of by type
+    #[arg(long)]
+    pub depth_colors: bool,
+
+    /// Print LABEL instead of the root path on the first line (and as the JSON root's name)
+    #[arg(long, value_name = "LABEL")]
+    pub render_root_name: Option<String>,
+
+    /// Label the root with its basename instead of the full path
+    #[arg(long, conflicts_with = "render_root_name")]
+    pub root_basename: bool,
+
+    /// Remove PATH from the start of rendered paths (root line, `{path}`, JSON `path`);
+    /// paths outside it stay absolute, or fail with --strict
+    #[arg(long, value_name = "PATH")]
+    pub strip_prefix: Option<String>,
+
+    /// Append `/` to directories, `@` to symlinks and `*` to executables, like `ls -F`
+    #[arg(short = 'F', long, alias = "trailing-slash")]
+    pub classify: bool,
+
+    /// Cut tree names longer than N characters to N, ending in `…` (display only)
+    #[arg(long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub max_name_length: Option<usize>,
+
+    /// Connector style: classic, rounded, ascii, minimal, bold
+    #[arg(long, default_value = "classic")]
+    pub tree_style: TreeStyle,
+
+    /// Count the size of a file reached through several hard links only once
+    #[arg(long)]
+    pub count_hardlinks_once: bool,
+
+    /// Show sizes in tree and JSON output; directories total everything below them.
+    /// JSON nodes also get their modification time
     #[arg(long)]
     pub size: bool,
 
+    /// Use SI size units (kB, MB; base 1000) instead of binary (KiB, MiB; base 1024)
+    #[arg(long)]
+    pub si: bool,
+
     /// Include file count per directory
     #[arg(long)]
     pub file_count: bool,
 
+    /// Prefix each line with `ls -l`-style mode bits and numeric owner/group (Unix only)
+    #[arg(short, long)]
+    pub long: bool,
+
+    /// Prefix each line with file attribute flags: Hidden, System, Readonly, Archive, Compressed (Windows only)
+    #[arg(long)]
+    pub attributes: bool,
+
+    /// Per-node template, e.g. "{name} ({size})" (placeholders: name, path, size, mtime, children)
+    #[arg(long)]
+    pub entries_format: Option<String>,
+
+    /// Show only entries matching EXPR and their parent directories, e.g. 'size > 1mb && name ~ "\.log$"'
+    #[arg(long, alias = "filter", value_name = "EXPR")]
+    pub entries_filter: Option<String>,
+
+    /// Show only entries whose name matches REGEX, plus their parent directories
+    #[arg(long = "match", value_name = "REGEX", conflicts_with_all = ["entries_filter", "find"])]
+    pub match_name: Option<String>,
+
+    /// List cached paths whose name matches a glob (`*.env`) or substring and exit
+    /// (no scan); with `--format`, render them and their parents in that format
+    #[arg(long, value_name = "PATTERN", conflicts_with = "entries_filter")]
+    pub find: Option<String>,
+
+    /// Set by `parse_args` when `--format` was given on the command line
+    #[arg(skip)]
+    pub format_given: bool,
+
+    /// Show only directories with at least one file directly inside, plus their parent directories
+    #[arg(long)]
+    pub only_dirs_with_files: bool,
+
+    /// Leave zero-byte files out of the output (they stay in the cache)
+    #[arg(long)]
+    pub exclude_empty_files: bool,
+
+    /// Scan, then list the paths of all zero-byte files instead of a tree (JSON with --format json)
+    #[arg(long, conflicts_with_all = ["exclude_empty_files", "count_only", "stream"])]
+    pub only_empty_files: bool,
+
+    /// Leave out directories with no file shown anywhere below them
+    #[arg(long)]
+    pub prune_empty: bool,
+
+    /// Show directories with more than N children as one line with their entry count and size
+    #[arg(long, value_name = "N")]
+    pub collapse_threshold: Option<usize>,
+
+    /// List PATH in full despite --collapse-threshold (repeatable)
+    #[arg(long, value_name = "PATH", requires = "collapse_threshold")]
+    pub expand: Vec<String>,
+
+    /// Show only N randomly drawn directories and their parent directories, for a quick look at huge trees
+    #[arg(long, value_name = "N")]
+    pub sample: Option<usize>,
+
+    /// Seed for --sample; the same seed and cache always draw the same directories
+    #[arg(long, value_name = "SEED", default_value_t = 0)]
+    pub seed: u64,
+
+    /// Print file count and total size per extension instead of the tree, sorted by count or size
+    #[arg(
+        long,
+        value_name = "SORT",
+        num_args = 0..=1,
+        default_missing_value = "count"
+    )]
+    pub group_by_extension: Option<ExtensionSort>,
+
+    /// Print names used by more than one entry, with their paths, instead of the tree (all, files or dirs)
+    #[arg(
+        long,
+        value_name = "KIND",
+        num_args = 0..=1,
+        default_missing_value = "all",
+        conflicts_with = "group_by_extension"
+    )]
+    pub dedupe_names: Option<NameKind>,
+
+    /// Render only directories that changed since the cached snapshot (+ added, - removed, ~ modified)
+    #[arg(long)]
+    pub changed_only: bool,
+
+    /// Mark entries modified since the previous scan with [changed] (needs a rescan, e.g. --force)
+    #[arg(long)]
+    pub since_scan: bool,
+
+    /// Compare the scan with the baseline in FILE, print every difference and fail if there are any
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["count_only", "only_empty_files", "stream"])]
+    pub baseline: Option<String>,
+
+    /// Rewrite the --baseline file with the current scan instead of comparing
+    #[arg(long, requires = "baseline")]
+    pub update_baseline: bool,
+
     // ========================================================================
     // Filtering & Traversal Options
     // ========================================================================
-    /// Maximum depth to display
+    /// Maximum depth to display; with --no-cache, deeper directories aren't scanned either
     #[arg(short, long)]
     pub max_depth: Option<usize>,
 
@@ -120,17 +652,89 @@ pub struct Args {
     #[arg(short, long)]
     pub skip: Option<String>,
 
-    /// Show hidden files
+    /// Don't apply the built-in skip list (.git, $Recycle.Bin, System Volume Information)
+    #[arg(long)]
+    pub no_ignore_defaults: bool,
+
+    /// Match skip names case-sensitively (default on Unix)
+    #[arg(long, conflicts_with = "ignore_case")]
+    pub case_sensitive: bool,
+
+    /// Match skip names case-insensitively (default on Windows)
+    #[arg(long)]
+    pub ignore_case: bool,
+
+    /// Show hidden files and directories (dot-named, or hidden on Windows), marked [H]
     #[arg(long)]
     pub hidden: bool,
 
+    /// Don't descend into hidden directories (leading dot or hidden attribute); `=false` or
+    /// `--hidden` scans them
+    #[arg(
+        long,
+        value_name = "BOOL",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        default_value_t = true,
+        default_missing_value = "true"
+    )]
+    pub exclude_hidden_dirs_from_scan: bool,
+
+    /// Leave out every file and directory whose name starts with `.` (by name only;
+    /// the Windows hidden attribute is not consulted)
+    #[arg(long)]
+    pub exclude_dotfiles: bool,
+
+    /// Keep only files whose name or root-relative path matches GLOB (repeatable);
+    /// directories are still scanned so matches below them show up
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Leave out entries whose name or root-relative path matches GLOB (repeatable);
+    /// with --no-cache, excluded directories are not scanned
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Symlink handling: skip, follow, show (default: skip)
+    #[arg(long, default_value = "skip")]
+    pub symlinks: SymlinkMode,
+
+    /// Print tree branches as soon as they finish scanning (tree format only)
+    #[arg(long)]
+    pub stream: bool,
+
+    /// Show a live `N dirs scanned, ~ETA remaining` line on stderr while scanning
+    #[arg(long)]
+    pub progress: bool,
+
     // ========================================================================
     // Performance Options
     // ========================================================================
-    /// Maximum threads (default: physical cores * 2, capped at 3x cores)
-    #[arg(short = 'j', long)]
+    /// Worker threads, at most 256 (default or 0: all cores with --force, otherwise up to 4)
+    #[arg(short = 'j', long, value_parser = parse_thread_count)]
     pub threads: Option<usize>,
 
+    /// Cache and show directories only; files are left out of `children` to shrink the cache
+    #[arg(long)]
+    pub structure_only: bool,
+
+    /// Never stat entries: sizes are cached as 0, times as the Unix epoch, and no attributes are kept
+    #[arg(
+        long,
+        conflicts_with_all = ["size", "long", "classify", "attributes", "count_hardlinks_once", "since_scan"]
+    )]
+    pub no_metadata: bool,
+
+    /// Buffered cache writes before flushing into the cache (0 or 1 flushes every entry)
+    #[arg(long, value_name = "N", default_value_t = 5000)]
+    pub flush_threshold: usize,
+
+    /// Soft cap on memory growth during a scan (e.g. 512MB): writes are flushed
+    /// as it nears, and reaching it saves progress for --resume and stops the
+    /// scan. Entries a resumed scan reloads don't count against it
+    #[arg(long, value_name = "SIZE", value_parser = parse_byte_size)]
+    pub max_runtime_memory: Option<u64>,
+
     /// Display summary statistics (total dirs, files, timing, cache location)
     #[arg(long)]
     pub stats: bool,
@@ -139,6 +743,23 @@ pub struct Args {
     #[arg(long)]
     pub skip_stats: bool,
 
+    /// Write a JSON audit record of this run (root, times, counts, skips, errors) to FILE
+    #[arg(long, value_name = "FILE")]
+    pub scan_report: Option<String>,
+
+    /// Append the --scan-report record as one JSON line instead of overwriting FILE
+    #[arg(long, requires = "scan_report")]
+    pub scan_report_append: bool,
+
+    /// Log traversal, worker and cache-save spans with timings to stderr
+    /// (`PTREE_TRACE` or `RUST_LOG` set a filter, e.g. `ptree_traversal=trace`)
+    #[arg(long)]
+    pub trace: bool,
+
+    /// Scan the current directory single- and multi-threaded and report any divergence
+    #[arg(long, alias = "compare-threads", hide = true)]
+    pub self_test: bool,
+
     // ========================================================================
     // Scheduler Options
     // ========================================================================
@@ -153,16 +774,43 @@ pub struct Args {
     /// Show scheduler status
     #[arg(long)]
     pub scheduler_status: bool,
+
+    /// Run the scheduled cache refresh immediately to verify it works
+    #[arg(long)]
+    pub scheduler_run_now: bool,
 }
 
+/// Parse CLI flags, then fill anything not given from `PTREE_*` environment variables
 pub fn parse_args() -> Args {
-    Args::parse()
+    parse_args_from(std::env::args_os())
+}
+
+/// `parse_args` over an explicit argument list (the first item is the program name)
+pub fn parse_args_from<I, T>(argv: I) -> Args
+where
+    I: IntoIterator<Item = T>,
+    T: Into<std::ffi::OsString> + Clone,
+{
+    let matches = Args::command().get_matches_from(argv);
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    args.format_given = matches.value_source("format") == Some(clap::parser::ValueSource::CommandLine);
+
+    match crate::config::SettingsLayer::from_env() {
+        Ok(env) => args.apply_layers(&matches, &[env]),
+        Err(e) => Args::command().error(ErrorKind::ValueValidation, e).exit(),
+    }
+
+    args
 }
 
 impl Args {
     /// Build skip directory set based on arguments
     pub fn skip_dirs(&self) -> HashSet<String> {
-        let mut skip = Self::default_skip_dirs();
+        let mut skip = if self.no_ignore_defaults {
+            HashSet::new()
+        } else {
+            Self::default_skip_dirs()
+        };
 
         // Add system directories unless in admin mode
         if !self.admin {
@@ -182,6 +830,31 @@ impl Args {
         skip
     }
 
+    /// Policy for unreadable directories (`--strict-errors`, `--quiet-errors`)
+    pub fn error_policy(&self) -> ErrorPolicy {
+        if self.strict_errors {
+            ErrorPolicy::Strict
+        } else if self.quiet_errors {
+            ErrorPolicy::Quiet
+        } else {
+            ErrorPolicy::Report
+        }
+    }
+
+    /// Whether name filters compare case-sensitively
+    ///
+    /// `--case-sensitive` / `--ignore-case` win; otherwise Windows matches
+    /// case-insensitively and other platforms case-sensitively.
+    pub fn match_case(&self) -> bool {
+        if self.case_sensitive {
+            true
+        } else if self.ignore_case {
+            false
+        } else {
+            !cfg!(windows)
+        }
+    }
+
     /// Default directories to always skip
     fn default_skip_dirs() -> HashSet<String> {
         vec![
@@ -193,3 +866,262 @@ impl Args {
         .collect()
     }
 }
+
+/// True if `name` matches any entry in `skip_dirs` under the given case mode
+pub fn matches_skip(name: &str, skip_dirs: &HashSet<String>, case_sensitive: bool) -> bool {
+    if case_sensitive {
+        skip_dirs.contains(name)
+    } else {
+        skip_dirs.iter().any(|skip| name.eq_ignore_ascii_case(skip))
+    }
+}
+
+/// Most worker threads a scan will start
+pub const MAX_THREADS: usize = 256;
+
+/// Parse `--threads`: 0 picks the default count, anything above `MAX_THREADS` is refused
+pub fn parse_thread_count(s: &str) -> Result<usize, String> {
+    let threads: usize = s.trim().parse().map_err(|_| format!("Invalid thread count: {}", s))?;
+    if threads > MAX_THREADS {
+        return Err(format!("{} threads is too many (at most {})", threads, MAX_THREADS));
+    }
+    Ok(threads)
+}
+
+/// Parse a byte size such as `1048576`, `500MB`, `1.5GiB` or `2G`
+///
+/// Units follow the names sizes are printed with: `KiB`/`MiB`/`GiB`/`TiB` are
+/// binary (base 1024, the default output), while `kB`/`MB`/`GB`/`TB` and their
+/// `K`/`M`/`G`/`T` shorthands are SI (base 1000, as shown with `--si`).
+/// Case-insensitive.
+pub fn parse_byte_size(s: &str) -> Result<u64, String> {
+    let trimmed = s.trim();
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+
+    let value: f64 = number.parse().map_err(|_| format!("Invalid size: {}", s))?;
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1000,
+        "m" | "mb" => 1000u64.pow(2),
+        "g" | "gb" => 1000u64.pow(3),
+        "t" | "tb" => 1000u64.pow(4),
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        other => return Err(format!("Unknown size unit: {}", other)),
+    };
+
+    Ok((value * multiplier as f64).round() as u64)
+}
+
+/// Parse a drive remap such as `D=E` or `d:=e:` into uppercase letters
+pub fn parse_drive_remap(s: &str) -> Result<(char, char), String> {
+    let letter = |part: &str| {
+        let mut chars = part.trim().trim_end_matches(':').chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_alphabetic() => Ok(c.to_ascii_uppercase()),
+            _ => Err(format!("Invalid drive remap: {} (expected FROM=TO, e.g. D=E)", s)),
+        }
+    };
+    let (from, to) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid drive remap: {} (expected FROM=TO, e.g. D=E)", s))?;
+    Ok((letter(from)?, letter(to)?))
+}
+
+/// Parse a duration such as `90`, `90s`, `30m`, `12h`, `7d` or `2w` into seconds
+///
+/// A bare number is seconds. Case-insensitive.
+pub fn parse_duration(s: &str) -> Result<u64, String> {
+    let trimmed = s.trim();
+    let split = trimmed.find(|c: char| !c.is_ascii_digit()).unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+
+    let value: u64 = number.parse().map_err(|_| format!("Invalid duration: {}", s))?;
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        other => return Err(format!("Unknown duration unit: {}", other)),
+    };
+
+    value
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("Duration too large: {}", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deserialize `value` the way a config file would hold it
+    fn from_toml<T: serde::de::DeserializeOwned>(value: &str) -> Result<T, toml::de::Error> {
+        #[derive(Deserialize)]
+        struct Setting<T> {
+            value: T,
+        }
+        toml::from_str::<Setting<T>>(&format!("value = \"{}\"", value)).map(|s| s.value)
+    }
+
+    /// Every spelling `FromStr` accepts must deserialize to the same variant
+    fn assert_serde_matches_from_str<T>(spellings: &[&str])
+    where
+        T: std::str::FromStr + serde::de::DeserializeOwned + Serialize + PartialEq + std::fmt::Debug,
+        T::Err: std::fmt::Debug,
+    {
+        for spelling in spellings {
+            let parsed: T = spelling.parse().unwrap();
+            assert_eq!(from_toml::<T>(spelling).unwrap(), parsed, "{spelling}");
+            // Serialized names parse back through `FromStr`
+            let name = toml::Value::try_from(&parsed).unwrap();
+            assert_eq!(name.as_str().unwrap().parse::<T>().unwrap(), parsed);
+        }
+    }
+
+    #[test]
+    fn test_cli_enums_deserialize_from_toml() {
+        assert_serde_matches_from_str::<OutputFormat>(&[
+            "tree", "ascii", "json", "mermaid", "plist", "toml", "yaml", "yml", "csv", "dot", "markdown", "md",
+        ]);
+        assert_serde_matches_from_str::<ColorMode>(&["auto", "always", "never"]);
+        assert_serde_matches_from_str::<TreeStyle>(&["classic", "rounded", "ascii", "minimal", "bold", "heavy"]);
+        assert_serde_matches_from_str::<ExtensionSort>(&["count", "size"]);
+        assert_serde_matches_from_str::<NameKind>(&["all", "files", "dirs"]);
+        assert_serde_matches_from_str::<SymlinkMode>(&["skip", "follow", "show"]);
+
+        assert_eq!(from_toml::<OutputFormat>("ascii").unwrap(), OutputFormat::Tree);
+        assert_eq!(from_toml::<TreeStyle>("heavy").unwrap(), TreeStyle::Bold);
+        assert_eq!(toml::Value::try_from(OutputFormat::Tree).unwrap().as_str(), Some("tree"));
+        assert!(from_toml::<ColorMode>("sometimes").is_err());
+    }
+
+    #[test]
+    fn test_value_enums_display_round_trip() {
+        for format in OutputFormat::value_variants() {
+            assert_eq!(format.to_string().parse::<OutputFormat>(), Ok(*format));
+            let args = Args::parse_from(["ptree", "--format", &format.to_string()]);
+            assert_eq!(args.format, *format);
+        }
+        for mode in ColorMode::value_variants() {
+            assert_eq!(mode.to_string().parse::<ColorMode>(), Ok(*mode));
+            assert_eq!(Args::parse_from(["ptree", "--color", &mode.to_string()]).color, *mode);
+        }
+        assert_eq!(OutputFormat::Plist.to_string(), "plist");
+        assert_eq!(ColorMode::Never.to_string(), "never");
+
+        // `ascii` stays an alias and matching ignores case
+        assert_eq!(Args::parse_from(["ptree", "--format", "ascii"]).format, OutputFormat::Tree);
+        assert_eq!(Args::parse_from(["ptree", "--format", "JSON"]).format, OutputFormat::Json);
+        assert_eq!("Always".parse::<ColorMode>(), Ok(ColorMode::Always));
+        assert_eq!(Args::parse_from(["ptree"]).format, OutputFormat::Tree);
+    }
+
+    #[test]
+    fn test_invalid_value_enum_lists_possible_values() {
+        let err = Args::try_parse_from(["ptree", "--format", "xml"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidValue);
+        let message = err.to_string();
+        assert!(
+            message.contains("possible values: tree, json, mermaid, plist, toml, yaml, csv, dot, markdown"),
+            "{message}"
+        );
+
+        let err = Args::try_parse_from(["ptree", "--color", "sometimes"]).unwrap_err();
+        assert!(err.to_string().contains("possible values: auto, always, never"), "{err}");
+        assert!("sometimes".parse::<ColorMode>().is_err());
+    }
+
+    #[test]
+    fn test_resume_rejects_stdin_roots() {
+        let err = Args::try_parse_from(["ptree", "--resume", "--stdin-roots"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+        assert!(Args::try_parse_from(["ptree", "--resume"]).is_ok());
+    }
+
+    #[test]
+    fn test_watch_debounce_defaults_and_needs_watch() {
+        assert_eq!(Args::parse_from(["ptree", "--watch"]).watch_debounce, 300);
+        assert_eq!(Args::parse_from(["ptree", "--watch", "--watch-debounce", "50"]).watch_debounce, 50);
+        let err = Args::try_parse_from(["ptree", "--watch-debounce", "50"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+    }
+
+    #[test]
+    fn test_parse_drive_remap() {
+        assert_eq!(parse_drive_remap("D=E"), Ok(('D', 'E')));
+        assert_eq!(parse_drive_remap("c:=d:"), Ok(('C', 'D')));
+        assert!(parse_drive_remap("D").is_err());
+        assert!(parse_drive_remap("DE=F").is_err());
+        assert!(parse_drive_remap("1=2").is_err());
+
+        let args = Args::parse_from(["ptree", "--validate-paths", "--remap-drive", "C=D"]);
+        assert_eq!(args.remap_drive, Some(('C', 'D')));
+        assert!(args.validate_paths);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90"), Ok(90));
+        assert_eq!(parse_duration("90s"), Ok(90));
+        assert_eq!(parse_duration("30m"), Ok(30 * 60));
+        assert_eq!(parse_duration("12H"), Ok(12 * 3600));
+        assert_eq!(parse_duration(" 7d "), Ok(7 * 86_400));
+        assert_eq!(parse_duration("2w"), Ok(14 * 86_400));
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("7 days").is_err());
+        assert!(parse_duration("1.5h").is_err());
+        assert!(parse_duration(&format!("{}w", u64::MAX)).is_err());
+
+        let args = Args::parse_from(["ptree", "--cache-max-age-warn", "7d"]);
+        assert_eq!(args.cache_max_age_warn, Some(7 * 86_400));
+    }
+
+    #[test]
+    fn test_no_ignore_defaults_drops_builtin_skips() {
+        let skips = |argv: &[&str]| Args::parse_from(argv).skip_dirs();
+
+        let default = skips(&["ptree"]);
+        assert!(default.contains(".git"));
+        assert!(default.contains("$Recycle.Bin"));
+
+        let no_defaults = skips(&["ptree", "--no-ignore-defaults", "--skip", "target"]);
+        assert!(!no_defaults.contains(".git"));
+        assert!(!no_defaults.contains("$Recycle.Bin"));
+        assert!(no_defaults.contains("target"));
+        // System directories are still governed by --admin
+        assert!(no_defaults.contains("System32"));
+        assert!(skips(&["ptree", "--no-ignore-defaults", "--admin"]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_thread_count() {
+        assert_eq!(parse_thread_count("8"), Ok(8));
+        assert_eq!(parse_thread_count("0"), Ok(0));
+        assert_eq!(parse_thread_count("256"), Ok(MAX_THREADS));
+        assert!(parse_thread_count("257").is_err());
+        assert!(parse_thread_count("99999999999999999999999").is_err());
+        assert!(parse_thread_count("-1").is_err());
+        assert!(parse_thread_count("four").is_err());
+
+        assert_eq!(Args::parse_from(["ptree", "-j", "0"]).threads, Some(0));
+        assert!(Args::try_parse_from(["ptree", "--threads", "100000"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_byte_size() {
+        assert_eq!(parse_byte_size("4096"), Ok(4096));
+        assert_eq!(parse_byte_size("500MB"), Ok(500_000_000));
+        assert_eq!(parse_byte_size("2GiB"), Ok(2 << 30));
+        assert_eq!(parse_byte_size("2g"), Ok(2_000_000_000));
+        assert_eq!(parse_byte_size("2gb"), parse_byte_size("2G"));
+        assert_eq!(parse_byte_size("1.5 KiB"), Ok(1536));
+        assert!(parse_byte_size("lots").is_err());
+        assert!(parse_byte_size("10 parsecs").is_err());
+    }
+}
@@ -0,0 +1,193 @@
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+
+use crate::cli::{Args, MAX_THREADS};
+use crate::error::{PTreeError, PTreeResult};
+
+// ============================================================================
+// Settings Layers
+// ============================================================================
+
+/// Settings supplied by a source other than the command line
+///
+/// Each field left as `None` defers to the next layer down. Layers are merged
+/// beneath CLI flags by [`Args::apply_layers`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SettingsLayer {
+    pub cache_dir: Option<String>,
+    pub threads:   Option<usize>,
+    pub drive:     Option<char>,
+    pub cache_ttl: Option<u64>,
+    pub skip:      Option<String>,
+}
+
+impl SettingsLayer {
+    /// Read `PTREE_CACHE_DIR`, `PTREE_THREADS`, `PTREE_DRIVE`, `PTREE_CACHE_TTL`
+    /// and `PTREE_SKIP` from the process environment
+    pub fn from_env() -> PTreeResult<Self> {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    /// Build a layer from an arbitrary variable lookup
+    ///
+    /// Empty values are treated as unset; malformed values are rejected.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> PTreeResult<Self> {
+        let get = |key: &str| lookup(key).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+
+        let threads = match get("PTREE_THREADS") {
+            Some(v) => {
+                match v.parse::<usize>() {
+                    Ok(n) if (1..=MAX_THREADS).contains(&n) => Some(n),
+                    _ => return Err(invalid("PTREE_THREADS", &v, &format!("an integer from 1 to {}", MAX_THREADS))),
+                }
+            }
+            None => None,
+        };
+
+        let drive = match get("PTREE_DRIVE") {
+            Some(v) => {
+                let mut chars = v.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c.is_ascii_alphabetic() => Some(c.to_ascii_uppercase()),
+                    _ => return Err(invalid("PTREE_DRIVE", &v, "a single drive letter")),
+                }
+            }
+            None => None,
+        };
+
+        let cache_ttl = match get("PTREE_CACHE_TTL") {
+            Some(v) => {
+                Some(
+                    v.parse::<u64>()
+                        .map_err(|_| invalid("PTREE_CACHE_TTL", &v, "a number of seconds"))?,
+                )
+            }
+            None => None,
+        };
+
+        Ok(SettingsLayer {
+            cache_dir: get("PTREE_CACHE_DIR"),
+            threads,
+            drive,
+            cache_ttl,
+            skip: get("PTREE_SKIP"),
+        })
+    }
+}
+
+fn invalid(key: &str, value: &str, expected: &str) -> PTreeError {
+    PTreeError::Config(format!("{}={:?} must be {}", key, value, expected))
+}
+
+impl Args {
+    /// Fill settings not given on the command line from lower-priority layers
+    ///
+    /// `layers` is ordered highest priority first, so a config-file layer goes
+    /// ahead of the environment; the first layer that sets a value wins, and
+    /// built-in defaults apply only when no layer does.
+    pub fn apply_layers(&mut self, matches: &ArgMatches, layers: &[SettingsLayer]) {
+        let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        for layer in layers.iter().rev() {
+            if !from_cli("cache_dir") && layer.cache_dir.is_some() {
+                self.cache_dir = layer.cache_dir.clone();
+            }
+            if !from_cli("threads") && layer.threads.is_some() {
+                self.threads = layer.threads;
+            }
+            if let Some(drive) = layer.drive.filter(|_| !from_cli("drive")) {
+                self.drive = drive;
+            }
+            if !from_cli("cache_ttl") && layer.cache_ttl.is_some() {
+                self.cache_ttl = layer.cache_ttl;
+            }
+            if !from_cli("skip") && layer.skip.is_some() {
+                self.skip = layer.skip.clone();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use clap::{CommandFactory, FromArgMatches};
+
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> PTreeResult<SettingsLayer> {
+        let map: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        SettingsLayer::from_lookup(|key| map.get(key).cloned())
+    }
+
+    fn resolve(argv: &[&str], layers: &[SettingsLayer]) -> Args {
+        let matches = Args::command().get_matches_from(argv);
+        let mut args = Args::from_arg_matches(&matches).unwrap();
+        args.apply_layers(&matches, layers);
+        args
+    }
+
+    #[test]
+    fn test_env_overrides_defaults() {
+        let layer = env(&[
+            ("PTREE_CACHE_DIR", "/tmp/ptree-ci"),
+            ("PTREE_THREADS", "6"),
+            ("PTREE_DRIVE", "d"),
+            ("PTREE_CACHE_TTL", "120"),
+            ("PTREE_SKIP", "target,node_modules"),
+        ])
+        .unwrap();
+
+        let args = resolve(&["ptree"], &[layer]);
+        assert_eq!(args.cache_dir.as_deref(), Some("/tmp/ptree-ci"));
+        assert_eq!(args.threads, Some(6));
+        assert_eq!(args.drive, 'D');
+        assert_eq!(args.cache_ttl, Some(120));
+        assert_eq!(args.skip.as_deref(), Some("target,node_modules"));
+    }
+
+    #[test]
+    fn test_cli_beats_env() {
+        let layer = env(&[("PTREE_THREADS", "6"), ("PTREE_DRIVE", "D"), ("PTREE_SKIP", "target")]).unwrap();
+
+        let args = resolve(&["ptree", "-j", "2", "--drive", "E", "--skip", "build"], &[layer]);
+        assert_eq!(args.threads, Some(2));
+        assert_eq!(args.drive, 'E');
+        assert_eq!(args.skip.as_deref(), Some("build"));
+    }
+
+    #[test]
+    fn test_higher_layer_beats_env() {
+        let config = SettingsLayer {
+            cache_ttl: Some(60),
+            ..Default::default()
+        };
+        let environment = env(&[("PTREE_CACHE_TTL", "900"), ("PTREE_THREADS", "3")]).unwrap();
+
+        let args = resolve(&["ptree"], &[config, environment]);
+        assert_eq!(args.cache_ttl, Some(60));
+        assert_eq!(args.threads, Some(3));
+    }
+
+    #[test]
+    fn test_unset_and_empty_vars_keep_defaults() {
+        let layer = env(&[("PTREE_CACHE_DIR", "  ")]).unwrap();
+        assert_eq!(layer, SettingsLayer::default());
+
+        let args = resolve(&["ptree"], &[layer]);
+        assert_eq!(args.drive, 'C');
+        assert_eq!(args.threads, None);
+        assert_eq!(args.cache_dir, None);
+    }
+
+    #[test]
+    fn test_invalid_env_values_rejected() {
+        assert!(env(&[("PTREE_THREADS", "0")]).is_err());
+        assert!(env(&[("PTREE_THREADS", "100000")]).is_err());
+        assert!(env(&[("PTREE_THREADS", "many")]).is_err());
+        assert!(env(&[("PTREE_DRIVE", "CD")]).is_err());
+        assert!(env(&[("PTREE_DRIVE", "1")]).is_err());
+        assert!(env(&[("PTREE_CACHE_TTL", "-5")]).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+use std::path::Path;
+use std::{fmt, io};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// ptree-driver Status Report
+// ============================================================================
+
+// Written by the ptree-driver service and read by `ptree --driver-status`;
+// kept here so the ptree binary doesn't depend on the service crate
+
+/// Status file written next to the cache, read by `ptree --driver-status`
+pub const STATUS_FILE_NAME: &str = "driver-status.json";
+
+/// Lifecycle state of the service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceStatus {
+    Stopped,
+    Running,
+    /// The last journal read or cache update failed; cleared by the next good cycle
+    Error,
+}
+
+impl fmt::Display for ServiceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ServiceStatus::Stopped => "stopped",
+            ServiceStatus::Running => "running",
+            ServiceStatus::Error => "error",
+        })
+    }
+}
+
+/// Snapshot of what the service has done, shared with `ptree --driver-status`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceStatusReport {
+    pub state:             ServiceStatus,
+    pub drive:             char,
+    /// Journal position after the last applied batch
+    pub last_usn:          i64,
+    /// When changes were last applied to the cache
+    pub last_cache_update: Option<DateTime<Utc>>,
+    /// Change records applied since the service started
+    pub entries_tracked:   u64,
+    pub error_count:       u64,
+    pub last_error:        Option<String>,
+}
+
+impl ServiceStatusReport {
+    /// Status of a service that hasn't started yet
+    pub fn new(drive: char) -> Self {
+        ServiceStatusReport {
+            state: ServiceStatus::Stopped,
+            drive,
+            last_usn: 0,
+            last_cache_update: None,
+            entries_tracked: 0,
+            error_count: 0,
+            last_error: None,
+        }
+    }
+
+    /// Monitoring is (again) healthy
+    pub fn mark_running(&mut self) {
+        self.state = ServiceStatus::Running;
+    }
+
+    /// A batch of `changes` records up to `last_usn` was applied to the cache
+    pub fn record_update(&mut self, last_usn: i64, changes: u64, at: DateTime<Utc>) {
+        self.state = ServiceStatus::Running;
+        self.last_usn = last_usn;
+        self.last_cache_update = Some(at);
+        self.entries_tracked += changes;
+    }
+
+    /// A journal read or cache update failed
+    pub fn record_error(&mut self, message: impl Into<String>) {
+        self.state = ServiceStatus::Error;
+        self.error_count += 1;
+        self.last_error = Some(message.into());
+    }
+
+    /// The service loop exited
+    pub fn mark_stopped(&mut self) {
+        self.state = ServiceStatus::Stopped;
+    }
+
+    /// Publish this report as JSON, replacing the previous one atomically
+    pub fn write_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_vec_pretty(self)?;
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, json)?;
+        std::fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    /// Read a report published by a running (or exited) service
+    pub fn read_from(path: &Path) -> io::Result<Self> {
+        let data = std::fs::read(path)?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+}
+
+/// Aligned `key: value` lines, shown by `ptree-driver status` and `ptree --driver-status`
+impl fmt::Display for ServiceStatusReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let last_update = match self.last_cache_update {
+            Some(at) => at.to_rfc3339(),
+            None => "never".to_string(),
+        };
+        for (label, value) in [
+            ("State", self.state.to_string()),
+            ("Drive", format!("{}:", self.drive)),
+            ("Last USN", self.last_usn.to_string()),
+            ("Last cache update", last_update),
+            ("Entries tracked", self.entries_tracked.to_string()),
+            ("Errors", self.error_count.to_string()),
+        ] {
+            writeln!(f, "{:<19}{}", format!("{}:", label), value)?;
+        }
+        if let Some(error) = &self.last_error {
+            writeln!(f, "{:<19}{}", "Last error:", error)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_transitions() {
+        let mut status = ServiceStatusReport::new('D');
+
+        status.mark_running();
+        assert_eq!(status.state, ServiceStatus::Running);
+
+        let at = Utc::now();
+        status.record_update(4096, 3, at);
+        status.record_update(8192, 2, at);
+        assert_eq!(status.state, ServiceStatus::Running);
+        assert_eq!(status.last_usn, 8192);
+        assert_eq!(status.last_cache_update, Some(at));
+        assert_eq!(status.entries_tracked, 5);
+
+        status.record_error("Failed to read journal: access denied");
+        assert_eq!(status.state, ServiceStatus::Error);
+        assert_eq!(status.error_count, 1);
+        assert_eq!(status.last_usn, 8192);
+
+        // A healthy cycle clears the error state but keeps the count
+        status.mark_running();
+        assert_eq!(status.state, ServiceStatus::Running);
+        assert_eq!(status.error_count, 1);
+
+        status.mark_stopped();
+        assert_eq!(status.state, ServiceStatus::Stopped);
+    }
+
+    #[test]
+    fn test_status_report_display() {
+        let mut status = ServiceStatusReport::new('C');
+        assert_eq!(
+            status.to_string(),
+            "State:             stopped\n\
+             Drive:             C:\n\
+             Last USN:          0\n\
+             Last cache update: never\n\
+             Entries tracked:   0\n\
+             Errors:            0\n"
+        );
+
+        let at = DateTime::parse_from_rfc3339("2026-01-02T03:04:05Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        status.record_update(8192, 5, at);
+        status.record_error("Failed to read journal: access denied");
+        assert_eq!(
+            status.to_string(),
+            "State:             error\n\
+             Drive:             C:\n\
+             Last USN:          8192\n\
+             Last cache update: 2026-01-02T03:04:05+00:00\n\
+             Entries tracked:   5\n\
+             Errors:            1\n\
+             Last error:        Failed to read journal: access denied\n"
+        );
+    }
+}
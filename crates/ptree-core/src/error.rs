@@ -1,4 +1,5 @@
 use std::io;
+use std::path::PathBuf;
 
 use thiserror::Error;
 
@@ -16,11 +17,20 @@ pub enum PTreeError {
     #[error("Invalid drive: {0}")]
     InvalidDrive(String),
 
+    #[error("Scan root is a file, not a directory: {} (ptree scans directories; use the folder containing it)", .0.display())]
+    NotADirectory(PathBuf),
+
+    #[error("Drive became unavailable during the scan of {} (the partial result was not saved)", .0.display())]
+    DriveUnavailable(PathBuf),
+
     #[error("Lock timeout: {0}")]
     LockTimeout(String),
 
     #[error("Traversal error: {0}")]
     Traversal(String),
+
+    #[error("Invalid setting: {0}")]
+    Config(String),
 }
 
 pub type PTreeResult<T> = Result<T, PTreeError>;
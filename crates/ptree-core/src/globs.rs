@@ -0,0 +1,111 @@
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+use crate::cli::Args;
+use crate::error::{PTreeError, PTreeResult};
+
+// ============================================================================
+// Name Globs (`--include`, `--exclude`)
+// ============================================================================
+
+/// Compiled `--include` / `--exclude` patterns
+///
+/// A pattern matches an entry when it matches either its name or its path
+/// relative to the scan root, so `*.log` works at any depth and `src/**`
+/// selects one subtree.
+#[derive(Debug, Clone, Default)]
+pub struct NameFilters {
+    /// Files must match one of these; `None` keeps every file
+    pub include: Option<GlobSet>,
+    /// Entries matching any of these are dropped, directories without being descended
+    pub exclude: Option<GlobSet>,
+}
+
+impl NameFilters {
+    /// Whether `--exclude` drops the entry
+    pub fn excludes(&self, name: &str, relative: &Path) -> bool {
+        self.exclude
+            .as_ref()
+            .is_some_and(|set| set.is_match(name) || set.is_match(relative))
+    }
+
+    /// Whether a file passes `--include` (always, without one)
+    pub fn includes(&self, name: &str, relative: &Path) -> bool {
+        self.include
+            .as_ref()
+            .is_none_or(|set| set.is_match(name) || set.is_match(relative))
+    }
+}
+
+impl Args {
+    /// Compile `--include` and `--exclude`, case-sensitive per `match_case`
+    pub fn filters(&self) -> PTreeResult<NameFilters> {
+        Ok(NameFilters {
+            include: build_set(&self.include, "--include", self.match_case())?,
+            exclude: build_set(&self.exclude, "--exclude", self.match_case())?,
+        })
+    }
+}
+
+fn build_set(patterns: &[String], flag: &str, match_case: bool) -> PTreeResult<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let invalid = |pattern: &str, e: globset::Error| PTreeError::Config(format!("{} {:?}: {}", flag, pattern, e));
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .case_insensitive(!match_case)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| invalid(pattern, e))?;
+        builder.add(glob);
+    }
+    builder.build().map(Some).map_err(|e| invalid(&patterns.join(","), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    fn filters(argv: &[&str]) -> NameFilters {
+        Args::parse_from(argv).filters().unwrap()
+    }
+
+    #[test]
+    fn test_exclude_matches_names_at_any_depth_and_paths() {
+        let f = filters(&["ptree", "--exclude", "*.log", "--exclude", "build/cache"]);
+        assert!(f.excludes("app.log", Path::new("logs/2024/app.log")));
+        assert!(!f.excludes("app.txt", Path::new("logs/app.txt")));
+        assert!(f.excludes("cache", Path::new("build/cache")));
+        assert!(!f.excludes("cache", Path::new("src/cache")));
+        assert!(f.includes("anything", Path::new("anything")));
+    }
+
+    #[test]
+    fn test_include_selects_subtree_and_names() {
+        let f = filters(&["ptree", "--include", "src/**", "--include", "*.md"]);
+        assert!(f.includes("main.rs", Path::new("src/main.rs")));
+        assert!(f.includes("mod.rs", Path::new("src/a/b/mod.rs")));
+        assert!(f.includes("README.md", Path::new("docs/README.md")));
+        assert!(!f.includes("main.rs", Path::new("tests/main.rs")));
+        assert!(!f.excludes("main.rs", Path::new("tests/main.rs")));
+    }
+
+    #[test]
+    fn test_case_mode_and_invalid_patterns() {
+        let f = filters(&["ptree", "--ignore-case", "--exclude", "*.LOG"]);
+        assert!(f.excludes("app.log", Path::new("app.log")));
+        let f = filters(&["ptree", "--case-sensitive", "--exclude", "*.LOG"]);
+        assert!(!f.excludes("app.log", Path::new("app.log")));
+
+        let err = Args::parse_from(["ptree", "--include", "src/[a"])
+            .filters()
+            .unwrap_err();
+        assert!(matches!(err, PTreeError::Config(ref msg) if msg.starts_with("--include \"src/[a\"")), "{err}");
+    }
+}
@@ -1,5 +1,29 @@
 pub mod cli;
+pub mod config;
+pub mod driver_status;
 pub mod error;
+pub mod globs;
 
-pub use cli::{parse_args, Args, ColorMode, OutputFormat};
+pub use cli::{
+    matches_skip,
+    parse_args,
+    parse_args_from,
+    parse_byte_size,
+    parse_duration,
+    parse_thread_count,
+    Args,
+    CacheFormat,
+    ColorMode,
+    ErrorPolicy,
+    ExtensionSort,
+    HashAlgorithm,
+    NameKind,
+    OutputFormat,
+    ScanScope,
+    SymlinkMode,
+    TreeStyle,
+    MAX_THREADS,
+};
+pub use config::SettingsLayer;
 pub use error::{PTreeError, PTreeResult};
+pub use globs::NameFilters;
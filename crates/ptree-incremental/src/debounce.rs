@@ -0,0 +1,158 @@
+// Event coalescing for watch-style refreshes
+// Turns bursts of filesystem events into at most one rescan per window
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Default coalescing window for watch refreshes
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+// ============================================================================
+// Changed Path Set
+// ============================================================================
+
+/// Directories whose listings need rescanning, with nested paths collapsed
+///
+/// An event on `path` changes the listing of its parent, so the parent is
+/// recorded. A directory already covered by a recorded ancestor is dropped,
+/// since rescanning the ancestor reaches it anyway.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeSet {
+    dirs: BTreeSet<PathBuf>,
+}
+
+impl ChangeSet {
+    /// Record an event on `path`
+    pub fn insert(&mut self, path: &Path) {
+        let dir = path.parent().unwrap_or(path);
+        if self.dirs.iter().any(|d| dir.starts_with(d)) {
+            return;
+        }
+        self.dirs.retain(|d| !d.starts_with(dir));
+        self.dirs.insert(dir.to_path_buf());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dirs.is_empty()
+    }
+
+    /// Directories to rescan, sorted
+    pub fn into_dirs(self) -> Vec<PathBuf> {
+        self.dirs.into_iter().collect()
+    }
+}
+
+// ============================================================================
+// Debouncer
+// ============================================================================
+
+/// Collects events into fixed windows and releases one batch per window
+///
+/// The first event after a quiet period opens a window of `window` length;
+/// everything recorded until it closes joins the same batch. Time is passed in
+/// by the caller so the debouncer stays independent of any event source.
+#[derive(Debug)]
+pub struct Debouncer {
+    window:  Duration,
+    opened:  Option<Instant>,
+    pending: ChangeSet,
+}
+
+impl Debouncer {
+    pub fn new(window: Duration) -> Self {
+        Debouncer {
+            window,
+            opened: None,
+            pending: ChangeSet::default(),
+        }
+    }
+
+    /// Record an event on `path` seen at `now`
+    pub fn record(&mut self, path: &Path, now: Instant) {
+        self.opened.get_or_insert(now);
+        self.pending.insert(path);
+    }
+
+    /// When the open window closes, if any (how long a caller may block)
+    pub fn deadline(&self) -> Option<Instant> {
+        self.opened.map(|opened| opened + self.window)
+    }
+
+    /// Take the batch once its window has closed; `None` while it is still open
+    pub fn poll(&mut self, now: Instant) -> Option<ChangeSet> {
+        if self.deadline().is_some_and(|deadline| now >= deadline) {
+            self.opened = None;
+            Some(std::mem::take(&mut self.pending))
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Debouncer {
+    fn default() -> Self {
+        Debouncer::new(DEFAULT_DEBOUNCE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_triggers_one_rescan_per_window() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::default();
+        let mut rescans = Vec::new();
+
+        // A checkout touching 5000 files over 250ms, polled every millisecond
+        for ms in 0..400u64 {
+            let now = start + Duration::from_millis(ms);
+            if ms < 250 {
+                for i in 0..20 {
+                    let path = PathBuf::from(format!("/repo/src/mod{}/file{}.rs", i % 4, ms * 20 + i));
+                    debouncer.record(&path, now);
+                }
+            }
+            if let Some(batch) = debouncer.poll(now) {
+                rescans.push((ms, batch.into_dirs()));
+            }
+        }
+
+        assert_eq!(rescans.len(), 1);
+        let (at, dirs) = &rescans[0];
+        assert_eq!(*at, 300);
+        assert_eq!(dirs.len(), 4);
+        assert!(dirs.contains(&PathBuf::from("/repo/src/mod0")));
+        assert_eq!(debouncer.deadline(), None);
+    }
+
+    #[test]
+    fn test_events_after_window_start_a_new_one() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(Duration::from_millis(100));
+        assert!(debouncer.poll(start).is_none());
+
+        debouncer.record(Path::new("/a/x"), start);
+        assert!(debouncer.poll(start + Duration::from_millis(99)).is_none());
+        assert!(debouncer.poll(start + Duration::from_millis(100)).is_some());
+
+        let later = start + Duration::from_millis(150);
+        debouncer.record(Path::new("/b/y"), later);
+        assert_eq!(debouncer.deadline(), Some(later + Duration::from_millis(100)));
+        let batch = debouncer.poll(later + Duration::from_millis(100)).unwrap();
+        assert_eq!(batch.into_dirs(), vec![PathBuf::from("/b")]);
+    }
+
+    #[test]
+    fn test_change_set_collapses_nested_dirs() {
+        let mut changes = ChangeSet::default();
+        changes.insert(Path::new("/repo/src/deep/a.rs"));
+        changes.insert(Path::new("/repo/docs/b.md"));
+        changes.insert(Path::new("/repo/src/c.rs"));
+        changes.insert(Path::new("/repo/src/deep/more/d.rs"));
+
+        assert_eq!(changes.into_dirs(), vec![PathBuf::from("/repo/docs"), PathBuf::from("/repo/src")]);
+    }
+}
@@ -26,10 +26,10 @@ mod tests {
     use super::*;
 
     #[test]
-    #[cfg(windows)]
-    fn test_change_impact_estimation() {
-        let changes = vec![];
-        let (c, m, d, r) = estimate_change_impact(&changes);
-        assert_eq!((c, m, d, r), (0, 0, 0, 0));
+    fn test_unavailable_journal_falls_back_to_full_scan() -> Result<()> {
+        let mut cache = DiskCache::new_empty();
+        assert!(!try_incremental_update(&mut cache, 'C')?);
+        assert!(cache.is_empty());
+        Ok(())
     }
 }
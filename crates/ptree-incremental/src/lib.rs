@@ -1,3 +1,5 @@
+pub mod debounce;
 pub mod incremental;
 
+pub use debounce::{ChangeSet, Debouncer, DEFAULT_DEBOUNCE};
 pub use incremental::try_incremental_update;
@@ -0,0 +1,142 @@
+use std::io;
+
+use thiserror::Error;
+
+/// Actionable failures from the platform scheduler backend
+///
+/// Failures reported on stderr carry the trimmed text; a scheduler tool that
+/// could not be started at all keeps its `io::Error` as the `source()`.
+#[derive(Error, Debug)]
+pub enum SchedulerError {
+    #[error(
+        "PowerShell execution policy blocked the scheduler script; run `Set-ExecutionPolicy -Scope CurrentUser \
+         RemoteSigned` and try again"
+    )]
+    ExecutionPolicy,
+
+    #[error("Access denied by Task Scheduler; re-run from an elevated (Run as Administrator) prompt")]
+    NeedsElevation,
+
+    #[error("Task Scheduler was temporarily unavailable: {0}")]
+    Transient(String),
+
+    #[error("Scheduler script failed: {0}")]
+    ScriptFailed(String),
+
+    #[error("Could not run {program}")]
+    Launch {
+        program: &'static str,
+        #[source]
+        source:  io::Error,
+    },
+}
+
+impl SchedulerError {
+    /// Whether retrying the same command has a reasonable chance of succeeding
+    pub fn is_transient(&self) -> bool {
+        matches!(self, SchedulerError::Transient(_))
+    }
+
+    /// Map a failure to start `program` (spawn, pipe or wait)
+    pub fn launch(program: &'static str) -> impl FnOnce(io::Error) -> Self {
+        move |source| SchedulerError::Launch { program, source }
+    }
+}
+
+/// Map PowerShell stderr to the most specific `SchedulerError`
+///
+/// Matching is case-insensitive and keyed on the stable parts of the messages
+/// (HRESULT codes and PowerShell error ids) rather than full localized text
+/// where possible.
+pub fn classify_powershell_failure(stderr: &str) -> SchedulerError {
+    let lower = stderr.to_lowercase();
+    let trimmed = stderr.trim().to_string();
+
+    const EXECUTION_POLICY: [&str; 3] = ["execution policy", "running scripts is disabled", "pssecurityexception"];
+    const ELEVATION: [&str; 4] = ["access is denied", "0x80070005", "e_accessdenied", "requires elevation"];
+    // RPC server unavailable / call failed / call rejected / server busy
+    const TRANSIENT: [&str; 5] = [
+        "0x800706ba",
+        "0x800706be",
+        "0x80010001",
+        "0x8001010a",
+        "rpc server is unavailable",
+    ];
+
+    if EXECUTION_POLICY.iter().any(|p| lower.contains(p)) {
+        SchedulerError::ExecutionPolicy
+    } else if ELEVATION.iter().any(|p| lower.contains(p)) {
+        SchedulerError::NeedsElevation
+    } else if TRANSIENT.iter().any(|p| lower.contains(p)) {
+        SchedulerError::Transient(trimmed)
+    } else {
+        SchedulerError::ScriptFailed(trimmed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_execution_policy() {
+        let stderr = "File C:\\x.ps1 cannot be loaded because running scripts is disabled on this system. \
+                      + FullyQualifiedErrorId : UnauthorizedAccess";
+        assert!(matches!(classify_powershell_failure(stderr), SchedulerError::ExecutionPolicy));
+    }
+
+    #[test]
+    fn test_classify_needs_elevation() {
+        let stderr = "Register-ScheduledTask : Access is denied.\r\n+ CategoryInfo : PermissionDenied: \
+                      (PS_ScheduledTask:Root/Microsoft/...) HRESULT 0x80070005";
+        assert!(matches!(classify_powershell_failure(stderr), SchedulerError::NeedsElevation));
+    }
+
+    #[test]
+    fn test_classify_transient_com_error() {
+        let err = classify_powershell_failure("Get-ScheduledTask : The RPC server is unavailable. (0x800706BA)");
+        assert!(err.is_transient());
+        assert!(!SchedulerError::NeedsElevation.is_transient());
+    }
+
+    #[test]
+    fn test_classify_unknown_failure() {
+        assert!(matches!(
+            classify_powershell_failure("  something else broke \n"),
+            SchedulerError::ScriptFailed(text) if text == "something else broke"
+        ));
+    }
+
+    #[test]
+    fn test_source_chain_per_variant() {
+        use std::error::Error as _;
+
+        // Classified stderr is the whole story: nothing underneath
+        for err in [
+            SchedulerError::ExecutionPolicy,
+            SchedulerError::NeedsElevation,
+            SchedulerError::Transient("busy".to_string()),
+            SchedulerError::ScriptFailed("broken".to_string()),
+        ] {
+            assert!(err.source().is_none(), "{err}");
+        }
+
+        let err = SchedulerError::launch("powershell")(io::Error::new(io::ErrorKind::NotFound, "program not found"));
+        assert_eq!(err.to_string(), "Could not run powershell");
+        let source = err.source().and_then(|s| s.downcast_ref::<io::Error>());
+        assert_eq!(source.map(io::Error::kind), Some(io::ErrorKind::NotFound));
+
+        // anyhow keeps the chain below added context
+        let err = anyhow::Error::from(err).context("Failed to create scheduled task");
+        let chain: Vec<String> = err.chain().map(|e| e.to_string()).collect();
+        assert_eq!(
+            chain,
+            [
+                "Failed to create scheduled task",
+                "Could not run powershell",
+                "program not found"
+            ]
+        );
+        assert!(err.root_cause().downcast_ref::<io::Error>().is_some());
+    }
+}
@@ -1,3 +1,5 @@
+pub mod error;
 pub mod scheduler;
 
-pub use scheduler::{check_scheduler_status, install_scheduler, uninstall_scheduler};
+pub use error::{classify_powershell_failure, SchedulerError};
+pub use scheduler::{check_scheduler_status, install_scheduler, run_scheduler_now, uninstall_scheduler, RefreshTask};
@@ -5,13 +5,126 @@ use std::process::Command;
 
 /// Scheduler module for automatic cache updates
 /// Supports Windows Task Scheduler and Unix cron
-use anyhow::{anyhow, Result};
+#[cfg(unix)]
+use anyhow::anyhow;
+use anyhow::Result;
+
+#[cfg(windows)]
+use crate::error::classify_powershell_failure;
+use crate::error::SchedulerError;
+
+/// Name of the Windows scheduled task
+const TASK_NAME: &str = "PTreeCacheRefresh";
+
+/// Arguments the scheduled refresh passes to ptree
+const REFRESH_ARGS: [&str; 2] = ["--force", "--quiet"];
 
 /// Get the ptree executable path
 fn get_ptree_path() -> Result<PathBuf> {
     Ok(std::env::current_exe()?)
 }
 
+// ============================================================================
+// Refresh Task
+// ============================================================================
+
+/// The scheduled cache refresh, as registered with the platform scheduler
+#[derive(Debug, Clone)]
+pub struct RefreshTask {
+    pub exe_path:  PathBuf,
+    pub task_name: String,
+}
+
+impl RefreshTask {
+    /// Task for the currently running ptree executable
+    pub fn current() -> Result<Self> {
+        Ok(RefreshTask {
+            exe_path:  get_ptree_path()?,
+            task_name: TASK_NAME.to_string(),
+        })
+    }
+
+    /// The command line the scheduler runs (`{exe} --force --quiet`)
+    pub fn refresh_command(&self) -> String {
+        format!("{} {}", self.exe_path.display(), REFRESH_ARGS.join(" "))
+    }
+
+    /// PowerShell script that triggers the registered task immediately
+    pub fn start_script(&self) -> String {
+        format!(
+            r#"Start-ScheduledTask -TaskName "{}" -ErrorAction Stop
+Write-Host "✓ Scheduled task '{}' started""#,
+            self.task_name, self.task_name
+        )
+    }
+
+    /// Run the refresh now instead of waiting for the next trigger
+    ///
+    /// On Windows this starts the registered task through Task Scheduler; on
+    /// Unix it runs the cron command directly and reports its exit status.
+    #[cfg(windows)]
+    pub fn run_now(&self) -> Result<()> {
+        let output = run_powershell(&self.start_script()).map_err(|e| e.context("Failed to start scheduled task"))?;
+        print!("{}", String::from_utf8_lossy(&output.stdout));
+        println!("  Run 'ptree --scheduler-status' to check the last run result");
+        Ok(())
+    }
+
+    /// Run the refresh now instead of waiting for the next trigger
+    ///
+    /// On Windows this starts the registered task through Task Scheduler; on
+    /// Unix it runs the cron command directly and reports its exit status.
+    #[cfg(unix)]
+    pub fn run_now(&self) -> Result<()> {
+        println!("Running: {}", self.refresh_command());
+
+        let status = Command::new(&self.exe_path).args(REFRESH_ARGS).status()?;
+
+        if !status.success() {
+            return Err(anyhow!("Cache refresh failed ({})", status));
+        }
+
+        println!("✓ Cache refresh completed ({})", status);
+        Ok(())
+    }
+}
+
+/// Trigger the scheduled cache refresh immediately
+pub fn run_scheduler_now() -> Result<()> {
+    RefreshTask::current()?.run_now()
+}
+
+/// Run a PowerShell script, retrying once on transient COM/RPC failures
+///
+/// Failures are mapped to a `SchedulerError` so callers can tell policy and
+/// elevation problems apart from a genuinely broken script.
+#[cfg(windows)]
+fn run_powershell(script: &str) -> Result<std::process::Output> {
+    const MAX_ATTEMPTS: usize = 2;
+
+    let mut attempt = 1;
+    loop {
+        let output = Command::new("powershell")
+            .arg("-NoProfile")
+            .arg("-Command")
+            .arg(script)
+            .output()
+            .map_err(SchedulerError::launch("powershell"))?;
+
+        if output.status.success() {
+            return Ok(output);
+        }
+
+        let err = classify_powershell_failure(&String::from_utf8_lossy(&output.stderr));
+        if err.is_transient() && attempt < MAX_ATTEMPTS {
+            attempt += 1;
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            continue;
+        }
+        return Err(err.into());
+    }
+}
+
 /// Install scheduler for automatic cache updates every 30 minutes
 #[cfg(windows)]
 pub fn install_scheduler() -> Result<()> {
@@ -19,12 +132,12 @@ pub fn install_scheduler() -> Result<()> {
     let exe_path_str = exe_path.display().to_string();
 
     // Task name
-    let task_name = "PTreeCacheRefresh";
+    let task_name = TASK_NAME;
 
     // PowerShell script to create scheduled task
     let ps_script = format!(
         r#"
-$action = New-ScheduledTaskAction -Execute "{}" -Argument "--force --quiet"
+$action = New-ScheduledTaskAction -Execute "{}" -Argument "{}"
 $trigger = New-ScheduledTaskTrigger -Once -At (Get-Date) -RepetitionInterval (New-TimeSpan -Minutes 30) -RepetitionDuration (New-TimeSpan -Days 36500)
 $principal = New-ScheduledTaskPrincipal -UserID "$env:USERNAME" -LogonType Interactive -RunLevel Highest
 $task = New-ScheduledTask -Action $action -Trigger $trigger -Principal $principal -Description "Automatic ptree cache refresh every 30 minutes"
@@ -32,21 +145,13 @@ Register-ScheduledTask -TaskName "{}" -InputObject $task -Force
 Write-Host "✓ Scheduled task '{}' created successfully"
 "#,
         exe_path_str.replace("\\", "\\\\"),
+        REFRESH_ARGS.join(" "),
         task_name,
         task_name
     );
 
     // Execute PowerShell script
-    let output = Command::new("powershell")
-        .arg("-NoProfile")
-        .arg("-Command")
-        .arg(&ps_script)
-        .output()?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow!("Failed to create scheduled task: {}", stderr));
-    }
+    run_powershell(&ps_script).map_err(|e| e.context("Failed to create scheduled task"))?;
 
     println!("✓ Cache refresh scheduled for every 30 minutes");
     println!("  Run 'ptree --scheduler-status' to verify installation");
@@ -56,7 +161,7 @@ Write-Host "✓ Scheduled task '{}' created successfully"
 /// Uninstall scheduler
 #[cfg(windows)]
 pub fn uninstall_scheduler() -> Result<()> {
-    let task_name = "PTreeCacheRefresh";
+    let task_name = TASK_NAME;
 
     let ps_script = format!(
         r#"
@@ -71,16 +176,7 @@ if ($task) {{
         task_name, task_name
     );
 
-    let output = Command::new("powershell")
-        .arg("-NoProfile")
-        .arg("-Command")
-        .arg(&ps_script)
-        .output()?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow!("Failed to remove scheduled task: {}", stderr));
-    }
+    run_powershell(&ps_script).map_err(|e| e.context("Failed to remove scheduled task"))?;
 
     println!("✓ Cache refresh scheduler removed");
     Ok(())
@@ -89,7 +185,7 @@ if ($task) {{
 /// Check scheduler status
 #[cfg(windows)]
 pub fn check_scheduler_status() -> Result<()> {
-    let task_name = "PTreeCacheRefresh";
+    let task_name = TASK_NAME;
 
     let ps_script = format!(
         r#"
@@ -114,19 +210,9 @@ if ($task) {{
         task_name, task_name
     );
 
-    let output = Command::new("powershell")
-        .arg("-NoProfile")
-        .arg("-Command")
-        .arg(&ps_script)
-        .output()?;
-
+    let output = run_powershell(&ps_script).map_err(|e| e.context("Failed to query scheduled task"))?;
     println!("{}", String::from_utf8_lossy(&output.stdout));
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        eprintln!("{}", stderr);
-    }
-
     Ok(())
 }
 
@@ -135,8 +221,7 @@ if ($task) {{
 pub fn install_scheduler() -> Result<()> {
     use std::process::Command;
 
-    let exe_path = get_ptree_path()?;
-    let exe_path_str = exe_path.display().to_string();
+    let task = RefreshTask::current()?;
 
     // Check if crontab is available
     let crontab_check = Command::new("which").arg("crontab").output();
@@ -162,7 +247,7 @@ pub fn install_scheduler() -> Result<()> {
     };
 
     // Add new cron entry (every 30 minutes)
-    let cron_entry = format!("*/30 * * * * {} --force --quiet\n", exe_path_str);
+    let cron_entry = format!("*/30 * * * * {}\n", task.refresh_command());
 
     if crontab_content.contains(&cron_entry) {
         println!("✓ Scheduler already installed");
@@ -175,7 +260,8 @@ pub fn install_scheduler() -> Result<()> {
     let mut child = Command::new("crontab")
         .arg("-")
         .stdin(std::process::Stdio::piped())
-        .spawn()?;
+        .spawn()
+        .map_err(SchedulerError::launch("crontab"))?;
 
     {
         use std::io::Write;
@@ -186,7 +272,7 @@ pub fn install_scheduler() -> Result<()> {
         stdin.write_all(crontab_content.as_bytes())?;
     }
 
-    let output = child.wait_with_output()?;
+    let output = child.wait_with_output().map_err(SchedulerError::launch("crontab"))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -201,8 +287,7 @@ pub fn install_scheduler() -> Result<()> {
 /// Uninstall scheduler on Unix/Linux
 #[cfg(unix)]
 pub fn uninstall_scheduler() -> Result<()> {
-    let exe_path = get_ptree_path()?;
-    let exe_path_str = exe_path.display().to_string();
+    let task = RefreshTask::current()?;
 
     // Get current crontab
     let current_crontab = Command::new("crontab").arg("-l").output().unwrap_or_else(|_| {
@@ -219,7 +304,7 @@ pub fn uninstall_scheduler() -> Result<()> {
     }
 
     let crontab_content = String::from_utf8_lossy(&current_crontab.stdout);
-    let cron_entry = format!("*/30 * * * * {} --force --quiet", exe_path_str);
+    let cron_entry = format!("*/30 * * * * {}", task.refresh_command());
 
     if !crontab_content.contains(&cron_entry) {
         println!("✗ ptree scheduler not found in crontab");
@@ -237,7 +322,8 @@ pub fn uninstall_scheduler() -> Result<()> {
     let mut child = Command::new("crontab")
         .arg("-")
         .stdin(std::process::Stdio::piped())
-        .spawn()?;
+        .spawn()
+        .map_err(SchedulerError::launch("crontab"))?;
 
     {
         use std::io::Write;
@@ -248,7 +334,7 @@ pub fn uninstall_scheduler() -> Result<()> {
         stdin.write_all(new_crontab.as_bytes())?;
     }
 
-    let output = child.wait_with_output()?;
+    let output = child.wait_with_output().map_err(SchedulerError::launch("crontab"))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -278,7 +364,7 @@ pub fn check_scheduler_status() -> Result<()> {
 
     if crontab_content.contains(&exe_path_str) {
         println!("✓ Scheduler installed and active");
-        println!("");
+        println!();
         println!("Cron entry:");
         for line in crontab_content.lines() {
             if line.contains("ptree") && line.contains("--force") {
@@ -287,9 +373,32 @@ pub fn check_scheduler_status() -> Result<()> {
         }
     } else {
         println!("✗ Scheduler not installed");
-        println!("");
+        println!();
         println!("Install with: ptree --scheduler");
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task() -> RefreshTask {
+        RefreshTask {
+            exe_path:  PathBuf::from("/opt/ptree/bin/ptree"),
+            task_name: TASK_NAME.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_refresh_command_matches_cron_entry() {
+        assert_eq!(task().refresh_command(), "/opt/ptree/bin/ptree --force --quiet");
+    }
+
+    #[test]
+    fn test_start_script_targets_task() {
+        let script = task().start_script();
+        assert!(script.starts_with("Start-ScheduledTask -TaskName \"PTreeCacheRefresh\" -ErrorAction Stop"));
+    }
+}
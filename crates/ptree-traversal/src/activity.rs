@@ -0,0 +1,250 @@
+use std::path::{Component, Path, Prefix};
+
+use ptree_cache::{DiskCache, USNJournalState};
+
+// ============================================================================
+// Drive Activity (adaptive freshness)
+// ============================================================================
+
+/// Journal growth that counts as a busy drive: roughly 10k USN records
+pub const BUSY_USN_DELTA: i64 = 1 << 20;
+
+/// Journal growth still treated as idle: the records written by saving the
+/// cache itself land after the position stored in it
+pub const IDLE_USN_SLACK: i64 = 16 * 1024;
+
+/// Identity and write position of a volume's USN journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JournalPosition {
+    pub journal_id: u64,
+    pub next_usn:   i64,
+}
+
+impl JournalPosition {
+    /// Position stored with the cache, if the last scan recorded one
+    pub fn stored(state: &USNJournalState) -> Option<Self> {
+        (state.journal_id != 0 || state.next_usn != 0).then_some(JournalPosition {
+            journal_id: state.journal_id,
+            next_usn:   state.next_usn,
+        })
+    }
+}
+
+impl From<JournalPosition> for USNJournalState {
+    fn from(position: JournalPosition) -> Self {
+        USNJournalState {
+            journal_id: position.journal_id,
+            next_usn:   position.next_usn,
+        }
+    }
+}
+
+/// How much a volume changed since the cache was written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriveActivity {
+    /// Nothing but the cache save itself: reuse the cache regardless of TTL
+    Idle,
+    /// Some changes: fall back to the TTL
+    Active,
+    /// Many changes, or a recreated/wrapped journal: rescan even within the TTL
+    Busy,
+}
+
+/// Compare the journal position stored with the cache against the live one
+pub fn classify_activity(stored: JournalPosition, current: JournalPosition) -> DriveActivity {
+    if stored.journal_id != current.journal_id || current.next_usn < stored.next_usn {
+        return DriveActivity::Busy;
+    }
+
+    match current.next_usn - stored.next_usn {
+        delta if delta <= IDLE_USN_SLACK => DriveActivity::Idle,
+        delta if delta >= BUSY_USN_DELTA => DriveActivity::Busy,
+        _ => DriveActivity::Active,
+    }
+}
+
+/// Freshness rule: drive activity, when known, overrides the TTL
+pub fn cache_is_fresh(age_seconds: i64, ttl_seconds: u64, activity: Option<DriveActivity>) -> bool {
+    match activity {
+        Some(DriveActivity::Idle) => true,
+        Some(DriveActivity::Busy) => false,
+        Some(DriveActivity::Active) | None => age_seconds < ttl_seconds as i64,
+    }
+}
+
+/// Activity on the cached root's volume since the cache was written
+///
+/// `None` when no journal position was stored or the journal can't be read,
+/// in which case the plain TTL applies.
+#[cfg(windows)]
+pub fn drive_activity(cache: &DiskCache) -> Option<DriveActivity> {
+    let stored = JournalPosition::stored(&cache.usn_state)?;
+    let current = query_journal(volume_letter(&cache.root)?)?;
+    Some(classify_activity(stored, current))
+}
+
+/// Activity on the cached root's volume (no journal off Windows, so always `None`)
+#[cfg(not(windows))]
+pub fn drive_activity(_cache: &DiskCache) -> Option<DriveActivity> {
+    None
+}
+
+/// Drive letter of the volume holding `path` (`C:\...` or `\\?\C:\...`)
+pub fn volume_letter(path: &Path) -> Option<char> {
+    match path.components().next()? {
+        Component::Prefix(prefix) => {
+            match prefix.kind() {
+                Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => Some((letter as char).to_ascii_uppercase()),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Current USN journal position of volume `letter`
+///
+/// `None` if the volume can't be opened (e.g. not elevated) or has no journal.
+#[cfg(windows)]
+pub fn query_journal(letter: char) -> Option<JournalPosition> {
+    use winapi::shared::minwindef::{DWORD, FALSE};
+    use winapi::um::fileapi::{CreateFileW, OPEN_EXISTING};
+    use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+    use winapi::um::ioapiset::DeviceIoControl;
+    use winapi::um::winioctl::FSCTL_QUERY_USN_JOURNAL;
+    use winapi::um::winnt::{FILE_SHARE_READ, FILE_SHARE_WRITE, GENERIC_READ};
+
+    /// USN_JOURNAL_DATA_V0
+    #[repr(C)]
+    #[derive(Default)]
+    struct JournalData {
+        usn_journal_id:   u64,
+        first_usn:        i64,
+        next_usn:         i64,
+        lowest_valid_usn: i64,
+        max_usn:          i64,
+        max_size:         u64,
+        allocation_delta: u64,
+    }
+
+    let volume: Vec<u16> = format!("\\\\.\\{}:", letter).encode_utf16().chain(Some(0)).collect();
+    let mut data = JournalData::default();
+    let mut returned: DWORD = 0;
+
+    // SAFETY: `volume` is NUL-terminated, `data` outlives the call and matches the
+    // size passed in, and the handle is closed on every path after it is opened.
+    unsafe {
+        let handle = CreateFileW(
+            volume.as_ptr(),
+            GENERIC_READ,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            std::ptr::null_mut(),
+            OPEN_EXISTING,
+            0,
+            std::ptr::null_mut(),
+        );
+        if handle == INVALID_HANDLE_VALUE {
+            return None;
+        }
+
+        let ok = DeviceIoControl(
+            handle,
+            FSCTL_QUERY_USN_JOURNAL,
+            std::ptr::null_mut(),
+            0,
+            &mut data as *mut JournalData as *mut _,
+            std::mem::size_of::<JournalData>() as DWORD,
+            &mut returned,
+            std::ptr::null_mut(),
+        );
+        CloseHandle(handle);
+
+        if ok == FALSE {
+            return None;
+        }
+    }
+
+    Some(JournalPosition {
+        journal_id: data.usn_journal_id,
+        next_usn:   data.next_usn,
+    })
+}
+
+/// Current USN journal position of volume `letter` (always `None` off Windows)
+#[cfg(not(windows))]
+pub fn query_journal(_letter: char) -> Option<JournalPosition> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(next_usn: i64) -> JournalPosition {
+        JournalPosition {
+            journal_id: 7,
+            next_usn,
+        }
+    }
+
+    #[test]
+    fn test_unchanged_usn_reuses_cache() {
+        let stored = at(5_000_000);
+
+        assert_eq!(classify_activity(stored, at(5_000_000)), DriveActivity::Idle);
+        // The cache save's own records don't count as activity
+        assert_eq!(classify_activity(stored, at(5_000_000 + 2048)), DriveActivity::Idle);
+
+        // Idle reuses the cache even far past the TTL
+        let idle = Some(classify_activity(stored, at(5_000_000)));
+        assert!(cache_is_fresh(86_400, 3600, idle));
+    }
+
+    #[test]
+    fn test_busy_drive_rescans_within_ttl() {
+        let stored = at(5_000_000);
+
+        let busy = classify_activity(stored, at(5_000_000 + BUSY_USN_DELTA));
+        assert_eq!(busy, DriveActivity::Busy);
+        assert!(!cache_is_fresh(10, 3600, Some(busy)));
+
+        // A recreated or rewound journal can't be trusted either
+        let recreated = JournalPosition {
+            journal_id: 8,
+            next_usn:   5_000_000,
+        };
+        assert_eq!(classify_activity(stored, recreated), DriveActivity::Busy);
+        assert_eq!(classify_activity(stored, at(10)), DriveActivity::Busy);
+    }
+
+    #[test]
+    fn test_moderate_activity_and_unknown_fall_back_to_ttl() {
+        let active = classify_activity(at(0), at(IDLE_USN_SLACK + 1));
+        assert_eq!(active, DriveActivity::Active);
+        assert!(cache_is_fresh(10, 3600, Some(active)));
+        assert!(!cache_is_fresh(4000, 3600, Some(active)));
+        assert!(cache_is_fresh(10, 3600, None));
+        assert!(!cache_is_fresh(4000, 3600, None));
+    }
+
+    #[test]
+    fn test_stored_position_requires_a_recorded_scan() {
+        assert_eq!(JournalPosition::stored(&USNJournalState::default()), None);
+        let state = USNJournalState::from(at(42));
+        assert_eq!(JournalPosition::stored(&state), Some(at(42)));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_volume_letter() {
+        assert_eq!(volume_letter(Path::new(r"c:\Users")), Some('C'));
+        assert_eq!(volume_letter(Path::new(r"\\?\D:\data")), Some('D'));
+        assert_eq!(volume_letter(Path::new(r"\\server\share\x")), None);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_volume_letter() {
+        assert_eq!(volume_letter(Path::new("/home/user")), None);
+    }
+}
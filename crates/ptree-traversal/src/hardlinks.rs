@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+use std::fs::Metadata;
+use std::path::Path;
+use std::sync::Mutex;
+
+// ============================================================================
+// Hardlink Deduplication (`--count-hardlinks-once`)
+// ============================================================================
+
+/// Identity shared by every link to one file: (device or volume serial, inode or file index)
+pub type FileId = (u64, u64);
+
+/// Hardlinked files whose size has already been counted during a scan
+///
+/// Shared by all workers; only files with more than one link are ever offered,
+/// so the set stays small on ordinary trees.
+#[derive(Debug, Default)]
+pub struct HardlinkSet {
+    seen: Mutex<HashSet<FileId>>,
+}
+
+impl HardlinkSet {
+    /// True the first time `id` is offered, false for every later link to it
+    pub fn first_sighting(&self, id: FileId) -> bool {
+        self.seen.lock().unwrap_or_else(|e| e.into_inner()).insert(id)
+    }
+
+    /// Size to record for a file: its real size on the first link, 0 on the others
+    pub fn counted_size(&self, id: Option<FileId>, size: u64) -> u64 {
+        match id {
+            Some(id) if !self.first_sighting(id) => 0,
+            _ => size,
+        }
+    }
+}
+
+/// `FileId` of a file with more than one hard link, `None` otherwise
+#[cfg(unix)]
+pub fn hardlink_id(_path: &Path, metadata: &Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+
+    (metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino()))
+}
+
+/// `FileId` of a file with more than one hard link, `None` otherwise
+///
+/// The link count and file index are not in directory listings, so the file
+/// is opened for `GetFileInformationByHandle`.
+#[cfg(windows)]
+pub fn hardlink_id(path: &Path, _metadata: &Metadata) -> Option<FileId> {
+    use std::os::windows::io::AsRawHandle;
+
+    use winapi::um::fileapi::{GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION};
+
+    let file = std::fs::File::open(path).ok()?;
+    let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+    if unsafe { GetFileInformationByHandle(file.as_raw_handle() as _, &mut info) } == 0 {
+        return None;
+    }
+    let index = (u64::from(info.nFileIndexHigh) << 32) | u64::from(info.nFileIndexLow);
+    (info.nNumberOfLinks > 1).then_some((u64::from(info.dwVolumeSerialNumber), index))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn hardlink_id(_path: &Path, _metadata: &Metadata) -> Option<FileId> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn test_first_sighting_per_device_and_inode() {
+        let seen = HardlinkSet::default();
+        assert!(seen.first_sighting((1, 100)));
+        assert!(!seen.first_sighting((1, 100)));
+        // Same inode number on another device is a different file
+        assert!(seen.first_sighting((2, 100)));
+        assert!(seen.first_sighting((1, 101)));
+
+        assert_eq!(seen.counted_size(Some((3, 7)), 4096), 4096);
+        assert_eq!(seen.counted_size(Some((3, 7)), 4096), 0);
+        assert_eq!(seen.counted_size(None, 4096), 4096);
+        assert_eq!(seen.counted_size(None, 4096), 4096);
+    }
+
+    #[test]
+    fn test_concurrent_links_counted_once() {
+        let seen = Arc::new(HardlinkSet::default());
+        let firsts: usize = (0..8)
+            .map(|_| {
+                let seen = Arc::clone(&seen);
+                std::thread::spawn(move || (0..100).filter(|ino| seen.first_sighting((9, *ino))).count())
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|t| t.join().unwrap())
+            .sum();
+        assert_eq!(firsts, 100);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hardlink_id_only_for_linked_files() -> std::io::Result<()> {
+        let dir = std::env::temp_dir().join("ptree_test_hardlink_id");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir)?;
+        let original = dir.join("a.bin");
+        let single = dir.join("single.bin");
+        std::fs::write(&original, b"data")?;
+        std::fs::write(&single, b"data")?;
+        std::fs::hard_link(&original, dir.join("b.bin"))?;
+
+        let id_a = hardlink_id(&original, &std::fs::metadata(&original)?);
+        let id_b = hardlink_id(&dir.join("b.bin"), &std::fs::metadata(dir.join("b.bin"))?);
+        assert!(id_a.is_some());
+        assert_eq!(id_a, id_b);
+        assert_eq!(hardlink_id(&single, &std::fs::metadata(&single)?), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+        Ok(())
+    }
+}
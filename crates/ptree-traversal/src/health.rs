@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+// ============================================================================
+// Drive Health (volumes disappearing mid-scan)
+// ============================================================================
+
+/// Consecutive `read_dir` failures after which the scan root is probed
+pub const FAILURE_BURST: usize = 16;
+
+/// Listing outcomes shared by all workers of one scan
+///
+/// Every directory that couldn't be listed is collected for the error policy.
+/// A run of failed listings is normal on its own (access-denied system
+/// folders), so a burst only triggers a probe of the scan root; the drive
+/// counts as lost when the root itself can no longer be listed.
+#[derive(Debug, Default)]
+pub struct ScanHealth {
+    consecutive_failures: AtomicUsize,
+    drive_lost:           AtomicBool,
+    unreadable:           Mutex<Vec<PathBuf>>,
+}
+
+impl ScanHealth {
+    /// Record whether `dir` could be listed, probing `scan_root` after every burst of failures
+    pub fn record(&self, dir: &Path, listed: bool, scan_root: &Path) {
+        if listed {
+            self.consecutive_failures.store(0, Ordering::Relaxed);
+            return;
+        }
+        self.unreadable
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(dir.to_path_buf());
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures.is_multiple_of(FAILURE_BURST) && fs::read_dir(scan_root).is_err() {
+            self.drive_lost.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// True once the scan root stopped being readable; workers stop claiming work
+    pub fn drive_lost(&self) -> bool {
+        self.drive_lost.load(Ordering::SeqCst)
+    }
+
+    /// Directories whose listing failed so far, sorted
+    pub fn unreadable(&self) -> Vec<PathBuf> {
+        let mut dirs = self.unreadable.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        dirs.sort();
+        dirs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failure_burst_probes_root() -> std::io::Result<()> {
+        let root = std::env::temp_dir().join("ptree_test_scan_health");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root)?;
+
+        // Failures with a readable root (e.g. access denied) never mark the drive lost
+        let health = ScanHealth::default();
+        for _ in 0..FAILURE_BURST * 3 {
+            health.record(&root.join("denied"), false, &root);
+        }
+        assert!(!health.drive_lost());
+        assert_eq!(health.unreadable().len(), FAILURE_BURST * 3);
+
+        // A success resets the run, so a later burst needs FAILURE_BURST failures again
+        fs::remove_dir_all(&root)?;
+        health.record(&root, true, &root);
+        for _ in 0..FAILURE_BURST - 1 {
+            health.record(&root.join("gone"), false, &root);
+        }
+        assert!(!health.drive_lost());
+        health.record(&root.join("gone"), false, &root);
+        assert!(health.drive_lost());
+        Ok(())
+    }
+}
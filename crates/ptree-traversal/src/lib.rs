@@ -1,3 +1,32 @@
+#[cfg(not(any(feature = "parallel", feature = "single-threaded")))]
+compile_error!("enable either the `parallel` or the `single-threaded` feature");
+
+pub mod activity;
+pub mod hardlinks;
+pub mod health;
+pub mod memory;
+pub mod progress;
+pub mod report;
+pub mod scan;
+pub mod stream;
 pub mod traversal;
 
-pub use traversal::{traverse_disk, DebugInfo, TraversalState};
+pub use hardlinks::{FileId, HardlinkSet};
+pub use health::ScanHealth;
+pub use memory::{MemoryGovernor, Pressure};
+pub use progress::EtaEstimator;
+pub use report::ScanReport;
+pub use scan::{scan, ScanOptions};
+pub use stream::{CompletionTracker, TreeStreamer};
+pub use traversal::{
+    compare_thread_counts,
+    default_thread_count,
+    interrupt_scan,
+    read_roots,
+    rescan_dirs,
+    traverse_disk,
+    traverse_roots,
+    DebugInfo,
+    TraversalState,
+    NON_UTF8_SKIP_NAME,
+};
@@ -0,0 +1,194 @@
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use parking_lot::RwLock;
+use ptree_cache::DiskCache;
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+
+// ============================================================================
+// Memory Governor (`--max-runtime-memory`)
+// ============================================================================
+
+/// How often the watcher samples the process's resident set size
+pub const SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Readings at or above this percentage of the limit start flushing
+const FLUSH_PERCENT: u64 = 80;
+
+/// How hard the scan should work to stay under the limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Pressure {
+    /// Well below the limit: buffer writes as usual
+    Normal,
+    /// Near the limit: flush buffered writes after every directory. This only
+    /// keeps buffers from piling up; entries already cached stay in memory.
+    Flush,
+    /// At the limit: save a `--resume` checkpoint and stop
+    Checkpoint,
+}
+
+/// Maps RSS readings to a `Pressure` level shared with the workers
+///
+/// The limit applies to growth above `baseline`, the RSS when the scan
+/// started. A `--resume` run reloads every entry saved so far before scanning,
+/// so measured from zero it would start at the limit and never get further.
+///
+/// The level only ever rises during a scan: memory handed to the allocator is
+/// rarely returned to the OS, so a reading that dips after a flush is no
+/// reason to start buffering again.
+#[derive(Debug)]
+pub struct MemoryGovernor {
+    limit:    u64,
+    baseline: u64,
+    level:    AtomicU8,
+}
+
+impl MemoryGovernor {
+    pub fn new(limit: u64, baseline: u64) -> Self {
+        MemoryGovernor {
+            limit,
+            baseline,
+            level: AtomicU8::new(Pressure::Normal as u8),
+        }
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Level a single reading of `rss` bytes calls for
+    pub fn decide(&self, rss: u64) -> Pressure {
+        let rss = rss.saturating_sub(self.baseline);
+        if rss >= self.limit {
+            Pressure::Checkpoint
+        } else if u128::from(rss) * 100 >= u128::from(self.limit) * u128::from(FLUSH_PERCENT) {
+            Pressure::Flush
+        } else {
+            Pressure::Normal
+        }
+    }
+
+    /// Record a reading; returns the new level when it rose
+    pub fn observe(&self, rss: u64) -> Option<Pressure> {
+        let wanted = self.decide(rss);
+        let previous = self.level.fetch_max(wanted as u8, Ordering::SeqCst);
+        (wanted as u8 > previous).then_some(wanted)
+    }
+
+    /// Current level; a single atomic load, cheap enough to check per directory
+    pub fn level(&self) -> Pressure {
+        match self.level.load(Ordering::SeqCst) {
+            0 => Pressure::Normal,
+            1 => Pressure::Flush,
+            _ => Pressure::Checkpoint,
+        }
+    }
+}
+
+/// This process's resident set size, if it can be read
+pub fn current_rss() -> Option<u64> {
+    let pid = sysinfo::get_current_pid().ok()?;
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        false,
+        ProcessRefreshKind::nothing().with_memory(),
+    );
+    system.process(pid).map(|p| p.memory())
+}
+
+/// Sample this process's RSS every `SAMPLE_INTERVAL` until `stop` is dropped
+///
+/// Entering `Flush` empties the shared pending-write buffer and makes every
+/// later cache write go straight to the entry map. Entering `Checkpoint` sets
+/// `interrupt`, so the scan winds down exactly as it does on Ctrl-C and its
+/// frontier is saved for `--resume`.
+pub fn watch_memory(
+    governor: &MemoryGovernor,
+    cache: &RwLock<DiskCache>,
+    interrupt: &AtomicBool,
+    stop: mpsc::Receiver<()>,
+) {
+    let Ok(pid) = sysinfo::get_current_pid() else {
+        return;
+    };
+    let mut system = System::new();
+
+    while let Err(mpsc::RecvTimeoutError::Timeout) = stop.recv_timeout(SAMPLE_INTERVAL) {
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[pid]),
+            false,
+            ProcessRefreshKind::nothing().with_memory(),
+        );
+        let Some(rss) = system.process(pid).map(|p| p.memory()) else {
+            continue;
+        };
+
+        match governor.observe(rss) {
+            Some(Pressure::Flush) => {
+                tracing::info!(rss, limit = governor.limit(), "memory pressure: flushing buffered writes");
+                let mut guard = cache.write();
+                guard.flush_pending_writes();
+                guard.flush_threshold = 1;
+            }
+            Some(Pressure::Checkpoint) => {
+                tracing::info!(rss, limit = governor.limit(), "memory limit reached: checkpointing scan");
+                interrupt.store(true, Ordering::SeqCst);
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    #[test]
+    fn test_decide_thresholds() {
+        let governor = MemoryGovernor::new(100 * MB, 0);
+        assert_eq!(governor.decide(0), Pressure::Normal);
+        assert_eq!(governor.decide(79 * MB), Pressure::Normal);
+        assert_eq!(governor.decide(80 * MB), Pressure::Flush);
+        assert_eq!(governor.decide(100 * MB - 1), Pressure::Flush);
+        assert_eq!(governor.decide(100 * MB), Pressure::Checkpoint);
+        assert_eq!(MemoryGovernor::new(u64::MAX, 0).decide(u64::MAX - 1), Pressure::Flush);
+    }
+
+    #[test]
+    fn test_limit_counts_growth_from_baseline() {
+        // A resumed scan that reloaded 300MB of entries still gets 100MB to grow
+        let governor = MemoryGovernor::new(100 * MB, 300 * MB);
+        assert_eq!(governor.decide(250 * MB), Pressure::Normal);
+        assert_eq!(governor.decide(350 * MB), Pressure::Normal);
+        assert_eq!(governor.decide(380 * MB), Pressure::Flush);
+        assert_eq!(governor.decide(400 * MB), Pressure::Checkpoint);
+        assert_eq!(governor.observe(320 * MB), None);
+    }
+
+    #[test]
+    fn test_synthetic_readings_escalate_once_and_never_relax() {
+        let governor = MemoryGovernor::new(200 * MB, 0);
+        let readings = [50, 120, 150, 170, 130, 90, 185, 210, 60, 250];
+        let rises: Vec<(u64, Pressure)> = readings
+            .iter()
+            .filter_map(|mb| governor.observe(mb * MB).map(|level| (*mb, level)))
+            .collect();
+
+        // Flush at the first reading past 160MB, Checkpoint at the first past 200MB
+        assert_eq!(rises, vec![(170, Pressure::Flush), (210, Pressure::Checkpoint)]);
+        assert_eq!(governor.level(), Pressure::Checkpoint);
+
+        // A single spike straight past the limit skips the flush stage
+        let spiky = MemoryGovernor::new(200 * MB, 0);
+        assert_eq!(spiky.observe(10 * MB), None);
+        assert_eq!(spiky.observe(400 * MB), Some(Pressure::Checkpoint));
+        assert_eq!(spiky.observe(170 * MB), None);
+        assert_eq!(spiky.level(), Pressure::Checkpoint);
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
+
+use ptree_cache::{format_age, group_thousands};
+
+// ============================================================================
+// Scan Progress & ETA (`--progress`)
+// ============================================================================
+
+/// How often the reporter samples the counters and redraws the line
+pub const REPORT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Throughput is averaged over samples no older than this
+const WINDOW: Duration = Duration::from_secs(10);
+
+/// No ETA until throughput has been measured over at least this long
+const MIN_SPAN: Duration = Duration::from_secs(2);
+
+/// Longest ETA shown; beyond it the estimate is noise
+const MAX_ETA: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Moving-average throughput and remaining-time estimate for a running scan
+///
+/// Fed `(elapsed, dirs scanned)` samples from the shared counter. The queue
+/// keeps growing while it drains, so the ETA is only a rough lower bound on
+/// a first run; it is withheld until enough samples exist and capped.
+#[derive(Debug, Default)]
+pub struct EtaEstimator {
+    samples: VecDeque<(Duration, usize)>,
+}
+
+impl EtaEstimator {
+    /// Add a sample, dropping those that fell out of the averaging window
+    pub fn record(&mut self, elapsed: Duration, dirs_scanned: usize) {
+        self.samples.push_back((elapsed, dirs_scanned));
+        while let Some(&(oldest, _)) = self.samples.front() {
+            if elapsed.saturating_sub(oldest) <= WINDOW {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+
+    /// Directories per second across the window, once it spans `MIN_SPAN`
+    pub fn throughput(&self) -> Option<f64> {
+        let (&(first_at, first), &(last_at, last)) = (self.samples.front()?, self.samples.back()?);
+        let span = last_at.saturating_sub(first_at);
+        (span >= MIN_SPAN).then(|| last.saturating_sub(first) as f64 / span.as_secs_f64())
+    }
+
+    /// Time to drain `queued` directories at the current throughput
+    ///
+    /// `None` while throughput is unknown or zero; otherwise between one
+    /// second and `MAX_ETA`.
+    pub fn eta(&self, queued: usize) -> Option<Duration> {
+        let rate = self.throughput().filter(|rate| *rate > 0.0)?;
+        let secs = (queued as f64 / rate).clamp(1.0, MAX_ETA.as_secs_f64());
+        Some(Duration::from_secs(secs.round() as u64))
+    }
+}
+
+/// Progress line, e.g. `12,340 dirs scanned, ~45s remaining`
+pub fn format_progress(dirs_scanned: usize, eta: Option<Duration>) -> String {
+    let mut line = format!("{} dirs scanned", group_thousands(dirs_scanned));
+    if let Some(eta) = eta {
+        line.push_str(&format!(", ~{} remaining", format_age(eta.as_secs())));
+    }
+    line
+}
+
+/// Redraw the progress line on stderr every `REPORT_INTERVAL` until `stop` is
+/// signalled or its sender dropped, then clear it
+///
+/// `scanned` is bumped by workers for each directory claimed; the queue length
+/// is the remaining work the ETA is based on.
+pub fn report_progress<T>(scanned: &AtomicUsize, work_queue: &Mutex<VecDeque<T>>, stop: mpsc::Receiver<()>) {
+    let start = Instant::now();
+    let mut estimator = EtaEstimator::default();
+    let mut stderr = std::io::stderr();
+    let mut width = 0;
+
+    while let Err(mpsc::RecvTimeoutError::Timeout) = stop.recv_timeout(REPORT_INTERVAL) {
+        let dirs = scanned.load(Ordering::Relaxed);
+        estimator.record(start.elapsed(), dirs);
+        let queued = work_queue.lock().map(|queue| queue.len()).unwrap_or(0);
+
+        let line = format_progress(dirs, estimator.eta(queued));
+        let _ = write!(stderr, "\r{:<width$}", line);
+        width = width.max(line.len());
+        let _ = stderr.flush();
+    }
+
+    if width > 0 {
+        let _ = write!(stderr, "\r{:width$}\r", "");
+        let _ = stderr.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: f64) -> Duration {
+        Duration::from_secs_f64(s)
+    }
+
+    #[test]
+    fn test_throughput_needs_min_span() {
+        let mut estimator = EtaEstimator::default();
+        assert_eq!(estimator.throughput(), None);
+        estimator.record(secs(0.5), 100);
+        estimator.record(secs(1.5), 300);
+        assert_eq!(estimator.throughput(), None);
+        assert_eq!(estimator.eta(1000), None);
+
+        estimator.record(secs(2.5), 500);
+        assert_eq!(estimator.throughput(), Some(200.0));
+        assert_eq!(estimator.eta(1000), Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_throughput_is_a_moving_average() {
+        let mut estimator = EtaEstimator::default();
+        // 100 dirs/s for 20s, then 1000 dirs/s for 10s
+        let mut dirs = 0;
+        for tick in 1..=60 {
+            dirs += if tick <= 40 { 50 } else { 500 };
+            estimator.record(secs(tick as f64 * 0.5), dirs);
+        }
+        // Only the last 10s are averaged, so the early slow phase is forgotten
+        assert_eq!(estimator.throughput(), Some(1000.0));
+        assert_eq!(estimator.eta(45_000), Some(Duration::from_secs(45)));
+    }
+
+    #[test]
+    fn test_eta_is_bounded() {
+        let mut estimator = EtaEstimator::default();
+        estimator.record(secs(0.0), 0);
+        estimator.record(secs(4.0), 0);
+        // Stalled scan: no estimate rather than infinity
+        assert_eq!(estimator.eta(10), None);
+
+        estimator.record(secs(8.0), 8);
+        assert_eq!(estimator.throughput(), Some(1.0));
+        assert_eq!(estimator.eta(10_000_000), Some(MAX_ETA));
+        assert_eq!(estimator.eta(0), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_format_progress() {
+        assert_eq!(format_progress(12_340, Some(Duration::from_secs(45))), "12,340 dirs scanned, ~45s remaining");
+        assert_eq!(format_progress(7, None), "7 dirs scanned");
+        assert_eq!(
+            format_progress(1_000_000, Some(Duration::from_secs(3725))),
+            "1,000,000 dirs scanned, ~1h 2m remaining"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use ptree_cache::DiskCache;
+use serde::Serialize;
+
+use crate::traversal::DebugInfo;
+
+// ============================================================================
+// Scan Audit Report (`--scan-report`)
+// ============================================================================
+
+/// One run's audit record, written as JSON after traversal
+///
+/// Separate from the cache: it describes what this run did, not the tree.
+/// Counts come from the cache after the scan, so a run served from the cache
+/// (`cache_used`) reports the cached totals with zero traversal time.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScanReport {
+    pub scan_root:       PathBuf,
+    pub started:         DateTime<Utc>,
+    pub finished:        DateTime<Utc>,
+    pub duration_ms:     u64,
+    pub cache_used:      bool,
+    pub dirs_scanned:    usize,
+    pub files_found:     usize,
+    pub bytes:           u64,
+    pub threads:         usize,
+    /// Skipped directory names and how often each was skipped
+    pub skipped:         BTreeMap<String, usize>,
+    /// Directories whose listing failed, sorted
+    pub unreadable_dirs: Vec<PathBuf>,
+}
+
+impl ScanReport {
+    /// Collect the report for a run that started at `started` and just finished
+    pub fn new(debug_info: &DebugInfo, cache: &DiskCache, started: DateTime<Utc>) -> Self {
+        let finished = Utc::now();
+        let stats = cache.stats();
+
+        ScanReport {
+            scan_root: debug_info.scan_root.clone(),
+            started,
+            finished,
+            duration_ms: u64::try_from((finished - started).num_milliseconds()).unwrap_or(0),
+            cache_used: debug_info.cache_used,
+            dirs_scanned: stats.dir_count,
+            files_found: stats.file_count,
+            bytes: stats.total_size,
+            threads: debug_info.threads_used,
+            skipped: cache
+                .skip_stats
+                .iter()
+                .map(|(name, count)| (name.clone(), *count))
+                .collect(),
+            unreadable_dirs: debug_info.unreadable_dirs.clone(),
+        }
+    }
+
+    /// Write the report to `path`
+    ///
+    /// Overwriting writes one pretty JSON document; appending adds one compact
+    /// JSON line per run, so the file stays a valid JSON Lines log.
+    pub fn write(&self, path: &Path, append: bool) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+
+        let result = if append {
+            let mut line = serde_json::to_vec(self)?;
+            line.push(b'\n');
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(&line))
+        } else {
+            let mut doc = serde_json::to_vec_pretty(self)?;
+            doc.push(b'\n');
+            fs::write(path, doc)
+        };
+        result.with_context(|| format!("Failed to write scan report {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use ptree_core::Args;
+
+    use super::*;
+    use crate::traversal::traverse_roots;
+
+    #[test]
+    fn test_scan_report_fields_after_fixture_scan() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_scan_report");
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("tree");
+        fs::create_dir_all(root.join("src"))?;
+        fs::create_dir_all(root.join("node_modules").join("dep"))?;
+        fs::write(root.join("src").join("main.rs"), b"fn main() {}")?;
+        fs::write(root.join("README.md"), b"hello")?;
+        let root = root.canonicalize()?;
+
+        let cache_path = base.join("cache").join("ptree.dat");
+        let mut cache = DiskCache::open(&cache_path)?;
+        let args = Args::parse_from(["ptree", "--no-cache", "-j", "2", "--skip", "node_modules"]);
+        let started = Utc::now();
+        let debug_info = traverse_roots(std::slice::from_ref(&root), &mut cache, &args, &cache_path)?;
+
+        let report_path = base.join("reports").join("scan.json");
+        ScanReport::new(&debug_info, &cache, started).write(&report_path, false)?;
+        let report: serde_json::Value = serde_json::from_slice(&fs::read(&report_path)?)?;
+
+        for field in [
+            "scan_root",
+            "started",
+            "finished",
+            "duration_ms",
+            "cache_used",
+            "dirs_scanned",
+            "files_found",
+            "bytes",
+            "threads",
+            "skipped",
+            "unreadable_dirs",
+        ] {
+            assert!(report.get(field).is_some(), "missing {field} in {report}");
+        }
+        assert_eq!(report["scan_root"], root.to_string_lossy().as_ref());
+        assert_eq!(report["dirs_scanned"], 2);
+        assert_eq!(report["files_found"], 2);
+        assert_eq!(report["bytes"], 17);
+        assert_eq!(report["skipped"]["node_modules"], 1);
+        assert_eq!(report["cache_used"], false);
+        assert_eq!(report["unreadable_dirs"].as_array().map(Vec::len), Some(0));
+        assert!(report["started"].as_str() <= report["finished"].as_str());
+
+        // Appending keeps one compact record per line
+        let log_path = base.join("reports").join("scans.jsonl");
+        let again = ScanReport::new(&debug_info, &cache, started);
+        again.write(&log_path, true)?;
+        again.write(&log_path, true)?;
+        let log = fs::read_to_string(&log_path)?;
+        assert_eq!(log.lines().count(), 2);
+        for line in log.lines() {
+            assert_eq!(serde_json::from_str::<serde_json::Value>(line)?["files_found"], 2);
+        }
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use chrono::Utc;
+use ptree_cache::{DiskCache, ScanMode};
+use ptree_core::{Args, HashAlgorithm, NameFilters, PTreeError, PTreeResult, SymlinkMode};
+
+use crate::traversal::{check_scan_root, default_thread_count, resolve_thread_count, scan_root_into_cache, ActiveScan};
+
+// ============================================================================
+// Library Scan API
+// ============================================================================
+
+/// Everything a scan needs, independent of the command line
+///
+/// `ScanOptions::new` gives library defaults (nothing skipped, default
+/// hashing, no progress output); `ScanOptions::from_args` mirrors what the
+/// `ptree` binary would do for the same flags.
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// Directories to scan; entries from all of them are merged into one cache
+    pub roots:                Vec<PathBuf>,
+    /// Worker threads (the `single-threaded` build always uses one)
+    pub threads:              usize,
+    /// Directory names (or `*`/`?` patterns) never descended into
+    pub skip_dirs:            HashSet<String>,
+    /// Compare `skip_dirs` case-sensitively
+    pub case_sensitive:       bool,
+    pub hash_algo:            HashAlgorithm,
+    pub symlinks:             SymlinkMode,
+    /// Only directories are cached
+    pub structure_only:       bool,
+    /// Never stat entries (`--no-metadata`)
+    pub no_metadata:          bool,
+    /// Don't descend into hidden directories
+    pub skip_hidden_dirs:     bool,
+    /// Don't descend into directories this many levels below a root
+    pub max_depth:            Option<usize>,
+    /// Leave out files and directories whose names start with `.`
+    pub exclude_dotfiles:     bool,
+    /// `--include` / `--exclude` globs
+    pub filters:              NameFilters,
+    /// Record Unix permission bits for `--long` and `--classify`
+    pub capture_permissions:  bool,
+    /// Count each hard-linked file's size once
+    pub count_hardlinks_once: bool,
+    /// Buffered cache writes before flushing into the cache
+    pub flush_threshold:      usize,
+    /// Soft cap in bytes on memory growth during the scan; reaching it fails the scan
+    pub max_runtime_memory:   Option<u64>,
+    /// Report progress on stderr
+    pub progress:             bool,
+    /// Depth limit for the tree streamed during a scan (`--stream`); the scan itself is never limited
+    pub stream_depth:         Option<usize>,
+}
+
+impl ScanOptions {
+    /// Options for scanning `roots` with library defaults
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        ScanOptions {
+            roots,
+            threads: default_thread_count(false),
+            skip_dirs: HashSet::new(),
+            case_sensitive: !cfg!(windows),
+            hash_algo: HashAlgorithm::default(),
+            symlinks: SymlinkMode::default(),
+            structure_only: false,
+            no_metadata: false,
+            skip_hidden_dirs: false,
+            max_depth: None,
+            exclude_dotfiles: false,
+            filters: NameFilters::default(),
+            capture_permissions: false,
+            count_hardlinks_once: false,
+            flush_threshold: 5000,
+            max_runtime_memory: None,
+            progress: false,
+            stream_depth: None,
+        }
+    }
+
+    /// Options for scanning `roots` the way the command line in `args` asks for
+    ///
+    /// Fails only on an invalid `--include` / `--exclude` glob.
+    pub fn from_args(roots: Vec<PathBuf>, args: &Args) -> PTreeResult<Self> {
+        let filters = args.filters()?;
+        Ok(ScanOptions {
+            roots,
+            threads: resolve_thread_count(args),
+            skip_dirs: args.skip_dirs(),
+            case_sensitive: args.match_case(),
+            hash_algo: args.hash_algo,
+            symlinks: args.symlinks,
+            structure_only: args.structure_only,
+            no_metadata: args.no_metadata,
+            // Hidden directories shown with --hidden must have been entered
+            skip_hidden_dirs: args.exclude_hidden_dirs_from_scan && !args.hidden,
+            // A cut-off tree is only scanned when it won't be saved for later runs to trust
+            max_depth: args.max_depth.filter(|_| args.no_cache),
+            // Likewise for name filters: a saved cache keeps every entry, filtered when rendered
+            exclude_dotfiles: args.exclude_dotfiles && args.no_cache,
+            filters: if args.no_cache { filters } else { NameFilters::default() },
+            // --classify reads the execute bits from the cache when it can
+            capture_permissions: args.long || args.classify,
+            count_hardlinks_once: args.count_hardlinks_once,
+            flush_threshold: args.flush_threshold,
+            max_runtime_memory: args.max_runtime_memory,
+            progress: args.progress,
+            stream_depth: args.max_depth,
+        })
+    }
+
+    /// The settings among these that decide what a scan caches
+    pub fn scan_mode(&self) -> ScanMode {
+        ScanMode {
+            structure_only:   self.structure_only,
+            no_metadata:      self.no_metadata,
+            skip_hidden_dirs: self.skip_hidden_dirs,
+            symlinks:         self.symlinks,
+        }
+    }
+}
+
+/// Scan every root in `options` into a fresh in-memory cache
+///
+/// Nothing is read from or written to disk besides the scanned trees: callers
+/// wanting persistence save the returned cache themselves. Roots are
+/// canonicalized, and `cache.root` is set to the first one.
+pub fn scan(options: &ScanOptions) -> PTreeResult<DiskCache> {
+    if options.roots.is_empty() {
+        return Err(PTreeError::Traversal("No scan roots provided".to_string()));
+    }
+
+    let mut roots = Vec::with_capacity(options.roots.len());
+    for root in &options.roots {
+        check_scan_root(root).map_err(into_ptree_error)?;
+        roots.push(root.canonicalize()?);
+    }
+
+    let mut cache = DiskCache::new_empty();
+    cache.hash_algo = options.hash_algo;
+    cache.scan_mode = options.scan_mode();
+
+    let _active = ActiveScan::start();
+    for root in &roots {
+        cache.root = root.clone();
+        let timings = scan_root_into_cache(root, &mut cache, options, None).map_err(into_ptree_error)?;
+        if timings.memory_limited {
+            return Err(PTreeError::Traversal("Scan reached the memory limit".to_string()));
+        }
+        if !timings.frontier.is_empty() {
+            return Err(PTreeError::Traversal("Scan interrupted".to_string()));
+        }
+    }
+    cache.root = roots[0].clone();
+    cache.last_scan = Utc::now();
+    Ok(cache)
+}
+
+/// Keep typed errors (e.g. `NotADirectory`) and wrap everything else
+fn into_ptree_error(err: anyhow::Error) -> PTreeError {
+    err.downcast::<PTreeError>()
+        .unwrap_or_else(|err| PTreeError::Traversal(format!("{:#}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use clap::Parser;
+
+    use super::*;
+
+    #[test]
+    fn test_scan_returns_cache_for_fixture() -> anyhow::Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_library_scan");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("src"))?;
+        fs::create_dir_all(base.join("target").join("debug"))?;
+        fs::write(base.join("src").join("lib.rs"), b"pub fn f() {}")?;
+        fs::write(base.join("Cargo.toml"), b"[package]")?;
+        let root = base.canonicalize()?;
+
+        let mut options = ScanOptions::new(vec![base.clone()]);
+        options.threads = 2;
+        options.skip_dirs.insert("target".to_string());
+        let cache = scan(&options)?;
+
+        assert_eq!(cache.root, root);
+        let mut children = cache.entries[&root].children.clone();
+        children.sort();
+        assert_eq!(children, vec!["Cargo.toml", "src"]);
+        assert_eq!(cache.entries[&root.join("src").join("lib.rs")].size, 13);
+        assert!(!cache.entries.contains_key(&root.join("target")));
+        assert_eq!(cache.skip_stats.get("target"), Some(&1));
+
+        // Typed errors come through unchanged
+        let file = root.join("Cargo.toml");
+        let err = scan(&ScanOptions::new(vec![file.clone()])).unwrap_err();
+        assert!(matches!(err, PTreeError::NotADirectory(ref path) if path == &file), "{err}");
+        assert!(matches!(scan(&ScanOptions::new(Vec::new())), Err(PTreeError::Traversal(_))));
+        assert!(scan(&ScanOptions::new(vec![Path::new("/no/such/ptree/root").to_path_buf()])).is_err());
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_depth_limits_scan_and_render() -> anyhow::Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_library_max_depth");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("l1/l2/l3/l4/l5"))?;
+        fs::write(base.join("l1/l2/l3/deep.txt"), b"deep")?;
+        fs::write(base.join("l1/top.txt"), b"top")?;
+        let root = base.canonicalize()?;
+        let two_levels = format!("{}\n└── l1\n    ├── l2\n    └── top.txt\n", root.display());
+
+        // A full scan still renders shallowly
+        let mut options = ScanOptions::new(vec![base.clone()]);
+        let full = scan(&options)?;
+        assert!(full.entries.contains_key(&root.join("l1/l2/l3/l4/l5")));
+        assert_eq!(full.build_tree_output_with_depth(Some(2))?, two_levels);
+
+        // A limited scan never lists what lies below the limit
+        options.max_depth = Some(2);
+        let shallow = scan(&options)?;
+        assert!(shallow.entries.values().all(|e| e.depth <= 2));
+        assert!(shallow.entries[&root.join("l1/l2")].is_dir);
+        assert!(!shallow.entries.contains_key(&root.join("l1/l2/l3")));
+        assert_eq!(shallow.build_tree_output()?, two_levels);
+
+        // The binary only cuts the scan short when nothing is saved
+        let from = |argv: &[&str]| ScanOptions::from_args(Vec::new(), &Args::parse_from(argv)).map(|o| o.max_depth);
+        assert_eq!(from(&["ptree", "--max-depth", "2"])?, None);
+        assert_eq!(from(&["ptree", "--max-depth", "2", "--no-cache"])?, Some(2));
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use ptree_cache::DiskCache;
+
+/// Tracks when a directory and all of its descendants have been cached
+///
+/// Each directory holds one pending unit for its own enumeration plus one per
+/// queued subdirectory. Workers `register` a directory before queueing its
+/// children and `finish` it once its entries are flushed into the shared
+/// cache; when a count reaches zero the directory is complete, its path is
+/// sent on the channel, and the parent's count is decremented in turn.
+#[derive(Debug)]
+pub struct CompletionTracker {
+    scan_root: PathBuf,
+    pending:   Mutex<HashMap<PathBuf, usize>>,
+    sender:    Mutex<Sender<PathBuf>>,
+}
+
+impl CompletionTracker {
+    pub fn new(scan_root: &Path, sender: Sender<PathBuf>) -> Self {
+        CompletionTracker {
+            scan_root: scan_root.to_path_buf(),
+            pending:   Mutex::new(HashMap::new()),
+            sender:    Mutex::new(sender),
+        }
+    }
+
+    /// Record a directory with `child_dirs` subdirectories about to be queued
+    pub fn register(&self, dir: &Path, child_dirs: usize) {
+        self.pending.lock().unwrap().insert(dir.to_path_buf(), child_dirs + 1);
+    }
+
+    /// Release one pending unit of `dir`, propagating completion upwards
+    ///
+    /// Finishing a directory that was never registered (e.g. unreadable)
+    /// completes it immediately.
+    pub fn finish(&self, dir: &Path) {
+        let mut pending = self.pending.lock().unwrap();
+        let mut current = dir.to_path_buf();
+
+        loop {
+            let remaining = match pending.get_mut(&current) {
+                Some(count) => {
+                    *count -= 1;
+                    *count
+                }
+                None => 0,
+            };
+            if remaining > 0 {
+                return;
+            }
+            pending.remove(&current);
+
+            // Receiver may have gone away (e.g. output error); keep traversing regardless
+            let _ = self.sender.lock().unwrap().send(current.clone());
+
+            if current == self.scan_root {
+                return;
+            }
+            match current.parent() {
+                Some(parent) => current = parent.to_path_buf(),
+                None => return,
+            }
+        }
+    }
+}
+
+/// Writes top-level branches of the tree as soon as they are complete
+///
+/// Branches are emitted in the same sorted order as the full tree render, so
+/// a branch is held back until every earlier sibling has been written. The
+/// concatenated output matches `build_tree_output_with_depth`.
+pub struct TreeStreamer<'a> {
+    out:       &'a mut dyn Write,
+    root:      PathBuf,
+    max_depth: Option<usize>,
+    children:  Option<Vec<String>>,
+    next:      usize,
+    completed: HashSet<PathBuf>,
+}
+
+impl<'a> TreeStreamer<'a> {
+    /// Start a stream, writing `root_line` immediately
+    pub fn new(out: &'a mut dyn Write, root: &Path, root_line: &str, max_depth: Option<usize>) -> Result<Self> {
+        writeln!(out, "{}", root_line)?;
+        Ok(TreeStreamer {
+            out,
+            root: root.to_path_buf(),
+            max_depth,
+            children: None,
+            next: 0,
+            completed: HashSet::new(),
+        })
+    }
+
+    /// Note a completed directory and write any branches that became ready
+    pub fn on_complete(&mut self, path: PathBuf, cache: &DiskCache) -> Result<()> {
+        self.completed.insert(path);
+        self.emit_ready(cache)
+    }
+
+    /// True once every top-level branch has been written
+    pub fn is_done(&self) -> bool {
+        self.children.as_ref().is_some_and(|c| self.next >= c.len())
+    }
+
+    fn emit_ready(&mut self, cache: &DiskCache) -> Result<()> {
+        if self.children.is_none() {
+            let Some(entry) = cache.get_entry(&self.root) else {
+                return Ok(());
+            };
+            let mut children = entry.children.clone();
+            children.sort();
+            self.children = Some(children);
+        }
+
+        let children = self.children.as_ref().unwrap();
+        while let Some(child_name) = children.get(self.next) {
+            let child_path = self.root.join(child_name);
+            let is_file = cache.get_entry(&child_path).is_some_and(|e| !e.is_dir);
+            if !is_file && !self.completed.contains(&child_path) {
+                break;
+            }
+
+            let is_last_child = self.next == children.len() - 1;
+            let branch = cache.build_tree_branch(child_name, is_last_child, self.max_depth)?;
+            self.out.write_all(branch.as_bytes())?;
+            self.out.flush()?;
+            self.next += 1;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use ptree_cache::fixtures::{cache_with, dir};
+
+    use super::*;
+
+    #[test]
+    fn test_tracker_completes_parents_after_children() {
+        let (tx, rx) = mpsc::channel();
+        let tracker = CompletionTracker::new(Path::new("/r"), tx);
+
+        tracker.register(Path::new("/r"), 2);
+        tracker.finish(Path::new("/r"));
+        tracker.register(Path::new("/r/a"), 0);
+        tracker.register(Path::new("/r/b"), 0);
+        tracker.finish(Path::new("/r/b"));
+        tracker.finish(Path::new("/r/a"));
+
+        let order: Vec<PathBuf> = rx.try_iter().collect();
+        assert_eq!(order, vec![PathBuf::from("/r/b"), PathBuf::from("/r/a"), PathBuf::from("/r")]);
+    }
+
+    #[test]
+    fn test_streamer_holds_branches_until_earlier_siblings_finish() -> Result<()> {
+        let cache = cache_with("/r", [dir("/r", &["a", "b"]), dir("/r/a", &[]), dir("/r/b", &[])]);
+
+        let mut out = Vec::new();
+        {
+            let mut streamer = TreeStreamer::new(&mut out, Path::new("/r"), "/r", None)?;
+            streamer.on_complete(PathBuf::from("/r/b"), &cache)?;
+            assert!(!streamer.is_done());
+            streamer.on_complete(PathBuf::from("/r/a"), &cache)?;
+            assert!(streamer.is_done());
+        }
+
+        assert_eq!(String::from_utf8(out)?, cache.build_tree_output()?);
+        Ok(())
+    }
+}
@@ -1,14 +1,46 @@
 use std::collections::VecDeque;
 use std::fs;
+use std::io::{BufRead, Write};
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use parking_lot::RwLock;
-use ptree_cache::{DirEntry, DiskCache};
-use ptree_core::Args;
+#[cfg(windows)]
+use ptree_cache::attributes::attributes_from_metadata;
+use ptree_cache::attributes::has_hidden_attribute;
+use ptree_cache::{
+    compute_content_hash_with,
+    DirEntry,
+    DiskCache,
+    ScanProgress,
+    UnixPermissions,
+    NO_METADATA_MODIFIED,
+};
+use ptree_core::{
+    matches_skip,
+    Args,
+    HashAlgorithm,
+    NameFilters,
+    OutputFormat,
+    PTreeError,
+    ScanScope,
+    SymlinkMode,
+    MAX_THREADS,
+};
+
+use crate::activity::{cache_is_fresh, drive_activity};
+#[cfg(windows)]
+use crate::activity::{query_journal, volume_letter};
+use crate::hardlinks::{hardlink_id, HardlinkSet};
+use crate::health::ScanHealth;
+use crate::memory::{current_rss, watch_memory, MemoryGovernor, Pressure};
+use crate::progress::report_progress;
+use crate::scan::ScanOptions;
+use crate::stream::{CompletionTracker, TreeStreamer};
 
 /// Debug timing information and statistics
 #[derive(Debug, Clone)]
@@ -22,12 +54,19 @@ pub struct DebugInfo {
     pub total_dirs:       usize,
     pub total_files:      usize,
     pub threads_used:     usize,
+    pub flush_count:      usize,
+    /// True when the tree was already written to stdout during the scan (`--stream`)
+    pub output_streamed:  bool,
+    /// Directories that couldn't be listed, sorted (empty when the cache was used)
+    pub unreadable_dirs:  Vec<PathBuf>,
 }
 
 /// Shared state for parallel DFS traversal across worker threads
 pub struct TraversalState {
-    /// Work queue: directories to be processed
-    pub work_queue: Arc<Mutex<VecDeque<PathBuf>>>,
+    /// Work queue: directories to be processed, with their depth below the root
+    ///
+    /// Private so its element type can keep changing with the scheduler.
+    work_queue: Arc<Mutex<VecDeque<(PathBuf, usize)>>>,
 
     /// Shared cache across all worker threads
     pub cache: Arc<RwLock<DiskCache>>,
@@ -35,6 +74,9 @@ pub struct TraversalState {
     /// Track directories currently being processed (prevents duplicates)
     pub in_progress: Arc<Mutex<std::collections::HashSet<PathBuf>>>,
 
+    /// Directories taken off the queue whose children may not be queued yet
+    in_flight: Arc<AtomicUsize>,
+
     /// Directories to skip during traversal
     pub skip_dirs: std::collections::HashSet<String>,
 
@@ -46,6 +88,41 @@ pub struct TraversalState {
     pub skip_stats: Arc<Mutex<std::collections::HashMap<String, usize>>>,
 }
 
+// ============================================================================
+// Interruption (Ctrl-C)
+// ============================================================================
+
+/// Set while `traverse_disk` or `traverse_roots` is running
+static SCAN_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Set by `interrupt_scan`; workers stop taking new directories once it is set
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Ask the running scan to stop and save its progress for `--resume`
+///
+/// Returns `false` when there is nothing to stop gracefully (no scan running,
+/// or a stop was already requested), so the caller can exit immediately.
+pub fn interrupt_scan() -> bool {
+    SCAN_ACTIVE.load(Ordering::SeqCst) && !INTERRUPTED.swap(true, Ordering::SeqCst)
+}
+
+/// Marks a scan as running for `interrupt_scan` until dropped
+pub(crate) struct ActiveScan;
+
+impl ActiveScan {
+    pub(crate) fn start() -> Self {
+        INTERRUPTED.store(false, Ordering::SeqCst);
+        SCAN_ACTIVE.store(true, Ordering::SeqCst);
+        ActiveScan
+    }
+}
+
+impl Drop for ActiveScan {
+    fn drop(&mut self) {
+        SCAN_ACTIVE.store(false, Ordering::SeqCst);
+    }
+}
+
 /// Traverse disk and update cache (per README spec)
 ///
 /// Cache Correctness Model:
@@ -71,57 +148,81 @@ pub struct TraversalState {
 /// 6. Initialize work queue with drive root
 /// 7. Spawn worker threads that process queue in parallel (iterative DFS)
 /// 8. Flush all pending writes and save cache atomically
+#[tracing::instrument(skip_all, fields(drive = %drive))]
 pub fn traverse_disk(drive: &char, cache: &mut DiskCache, args: &Args, cache_path: &Path) -> Result<DebugInfo> {
-    #[cfg(not(windows))]
-    let _ = drive;
+    // ============================================================================
+    // Detect Interrupted Scans
+    // ============================================================================
+
+    // Roots are filled in once the scan root is known
+    let mut settings = ScanOptions::from_args(Vec::new(), args)?;
+    let scan_mode = settings.scan_mode();
 
-    // Determine scan root: current directory by default, full drive with --force
-    let scan_root = if args.force {
-        // --force: scan full filesystem root for the current platform
-        #[cfg(windows)]
+    // A progress sidecar means the cache holds a partial scan: resume it or start over
+    let progress = if args.no_cache {
+        None
+    } else {
+        ScanProgress::load(cache_path).unwrap_or_else(|e| {
+            eprintln!("warning: {:#}; starting a fresh scan", e);
+            Some(ScanProgress::default())
+        })
+    };
+    let resume = match &progress {
+        Some(p)
+            if args.resume && p.root.is_dir() && cache.hash_algo == args.hash_algo && cache.scan_mode == scan_mode =>
         {
-            let root = PathBuf::from(format!("{}:\\", drive));
-            if !root.exists() {
-                anyhow::bail!("Drive {} does not exist", drive);
+            Some(p.clone())
+        }
+        Some(_) => {
+            if args.resume {
+                eprintln!("warning: interrupted scan can't be resumed; starting a fresh scan");
             }
-            root
+            None
         }
-
-        #[cfg(not(windows))]
-        {
-            PathBuf::from("/")
+        None => {
+            if args.resume {
+                eprintln!("warning: no interrupted scan to resume; running a normal scan");
+            }
+            None
         }
-    } else {
-        // Default: scan current directory and subdirectories
-        std::env::current_dir()?
     };
 
-    // Verify scan root exists and is a directory
-    if !scan_root.exists() {
-        anyhow::bail!("Scan root does not exist: {}", scan_root.display());
-    }
-    if !scan_root.is_dir() {
-        anyhow::bail!("Scan root is not a directory: {}", scan_root.display());
-    }
+    let scan_root = match &resume {
+        Some(progress) => progress.root.clone(),
+        None => resolve_scan_root(drive, cache, args)?,
+    };
+
+    check_scan_root(&scan_root)?;
 
     let is_first_run = !cache.has_cache_snapshot();
     cache.root = scan_root.clone();
 
+    // Hashes from different algorithms can't be compared; drop stale entries and rescan
+    let hash_algo_changed = cache.hash_algo != args.hash_algo;
+    if hash_algo_changed {
+        if !is_first_run {
+            eprintln!(
+                "warning: cache hashes were produced with {}; rescanning with {}",
+                cache.hash_algo, args.hash_algo
+            );
+        }
+        cache.entries.clear();
+        cache.hash_algo = args.hash_algo;
+    }
+
+    // Entries scanned in another mode would be missing or zeroed in this one; rescan
+    let scan_mode_changed = cache.scan_mode != scan_mode;
+    if scan_mode_changed {
+        if !is_first_run {
+            eprintln!("warning: cache was scanned with different scan settings; rescanning");
+        }
+        cache.entries.clear();
+        cache.scan_mode = scan_mode;
+    }
+
     // Ensure root directory is added to cache (important for --no-cache mode)
     if is_first_run && !cache.entries.contains_key(&scan_root) {
-        let root_entry = DirEntry {
-            path:           scan_root.clone(),
-            name:           scan_root
-                .file_name()
-                .and_then(|n| n.to_str().map(|s| s.to_string()))
-                .unwrap_or_default(),
-            modified:       Utc::now(),
-            content_hash:   0,
-            children:       Vec::new(),
-            symlink_target: None,
-            is_hidden:      false,
-            is_dir:         true,
-        };
+        let root_entry = DirEntry::builder(&scan_root).build()?;
         cache.entries.insert(scan_root.clone(), root_entry);
     }
 
@@ -131,25 +232,28 @@ pub fn traverse_disk(drive: &char, cache: &mut DiskCache, args: &Args, cache_pat
 
     let cache_ttl_seconds = args.cache_ttl.unwrap_or(3600);
 
-    let should_use_cache = if args.no_cache {
-        false // --no-cache always triggers rescan
-    } else if args.force {
-        false // --force always triggers rescan
-    } else if is_first_run {
-        false // First run always scans
+    let should_use_cache = if args.no_cache
+        || args.force
+        || is_first_run
+        || hash_algo_changed
+        || scan_mode_changed
+        || progress.is_some()
+    {
+        // --no-cache and --force always trigger a rescan; first run and partial caches always scan
+        false
     } else {
-        // Check cache freshness rule (time-based only)
-        let now = Utc::now();
-        let age = now.signed_duration_since(cache.last_scan);
-        age.num_seconds() < cache_ttl_seconds as i64
+        // Time-based, unless --adaptive-freshness lets drive activity decide
+        let activity = if args.adaptive_freshness {
+            drive_activity(cache)
+        } else {
+            None
+        };
+        let age = Utc::now().signed_duration_since(cache.last_scan);
+        cache_is_fresh(age.num_seconds(), cache_ttl_seconds, activity)
     };
 
     if should_use_cache {
-        let total_files = if cache.entries.is_empty() {
-            0
-        } else {
-            cache.entries.values().map(|e| e.children.len()).sum()
-        };
+        let total_files = cache.stats().total_children;
         return Ok(DebugInfo {
             is_first_run: false,
             scan_root: cache.root.clone(),
@@ -160,9 +264,413 @@ pub fn traverse_disk(drive: &char, cache: &mut DiskCache, args: &Args, cache_pat
             total_dirs: cache.entry_count_hint(),
             total_files,
             threads_used: 0,
+            flush_count: 0,
+            output_streamed: false,
+            unreadable_dirs: Vec::new(),
         });
     }
 
+    // ============================================================================
+    // Traverse Scan Root
+    // ============================================================================
+
+    // Resuming continues from the saved frontier on top of the entries already cached
+    let resuming = resume.is_some();
+    let seeds = match resume {
+        Some(progress) => {
+            cache.load_all_entries_lazy(cache_path)?;
+            progress.frontier
+        }
+        None => vec![(scan_root.clone(), 0)],
+    };
+
+    let _active = ActiveScan::start();
+    cache.skip_stats.clear();
+    cache.flush_count = 0;
+    // --stream renders the plain tree as branches complete instead of after the save
+    let streaming = args.stream && !args.quiet && !resuming && matches!(args.format, OutputFormat::Tree);
+    let mut stdout = std::io::stdout();
+    let stream: Option<&mut dyn Write> = if streaming { Some(&mut stdout) } else { None };
+    settings.roots = vec![scan_root.clone()];
+    let timings = scan_seeds_into_cache(&scan_root, cache, &settings, stream, seeds, &INTERRUPTED)?;
+
+    if !timings.frontier.is_empty() {
+        let remaining = timings.frontier.len();
+        let reason = if timings.memory_limited {
+            "Scan reached --max-runtime-memory"
+        } else {
+            "Scan interrupted"
+        };
+        if args.no_cache {
+            anyhow::bail!("{}", reason);
+        }
+        cache.save_partial(cache_path, timings.frontier)?;
+        anyhow::bail!("{} with {} directories left; run again with --resume to continue", reason, remaining);
+    }
+    cache.previous_scan = (!is_first_run).then_some(cache.last_scan);
+    cache.last_scan = Utc::now();
+
+    // Journal position for --adaptive-freshness on later runs
+    #[cfg(windows)]
+    if let Some(position) = volume_letter(&scan_root).and_then(query_journal) {
+        cache.usn_state = position.into();
+    }
+
+    let save_start = Instant::now();
+    if !args.no_cache {
+        cache.save(cache_path)?;
+        ScanProgress::clear(cache_path)?;
+    }
+    let save_elapsed = save_start.elapsed();
+
+    // ============================================================================
+    // Return Debug Info
+    // ============================================================================
+
+    let stats = cache.stats();
+
+    Ok(DebugInfo {
+        is_first_run,
+        scan_root: cache.root.clone(),
+        cache_used: false,
+        traversal_time: timings.traversal_time,
+        save_time: save_elapsed,
+        cache_index_time: timings.cache_index_time,
+        total_dirs: stats.entry_count,
+        total_files: stats.total_children,
+        threads_used: timings.threads_used,
+        flush_count: cache.flush_count,
+        output_streamed: streaming,
+        unreadable_dirs: timings.unreadable,
+    })
+}
+
+/// Pick the scan root for `traverse_disk` according to `--scan-scope`
+///
+/// `auto` keeps the historical rule: current directory by default, the whole
+/// drive with `--force`. The choice never depends on whether the cache is empty.
+fn resolve_scan_root(drive: &char, cache: &DiskCache, args: &Args) -> Result<PathBuf> {
+    match args.scan_scope {
+        ScanScope::Auto if args.force => drive_root(drive),
+        ScanScope::Drive => drive_root(drive),
+        ScanScope::Root if !cache.root.as_os_str().is_empty() => Ok(cache.root.clone()),
+        ScanScope::Auto | ScanScope::Cwd | ScanScope::Root => Ok(std::env::current_dir()?),
+    }
+}
+
+/// Verify a scan root exists and is a directory
+///
+/// A file root would fail `read_dir` and be dropped, leaving an unexplained
+/// empty tree; it is reported as `PTreeError::NotADirectory` instead.
+pub(crate) fn check_scan_root(root: &Path) -> Result<()> {
+    if !root.exists() {
+        anyhow::bail!("Scan root does not exist: {}", root.display());
+    }
+    if !root.is_dir() {
+        return Err(PTreeError::NotADirectory(root.to_path_buf()).into());
+    }
+    Ok(())
+}
+
+/// Full filesystem root for the current platform
+fn drive_root(drive: &char) -> Result<PathBuf> {
+    #[cfg(windows)]
+    {
+        let root = PathBuf::from(format!("{}:\\", drive));
+        if !root.exists() {
+            anyhow::bail!("Drive {} does not exist", drive);
+        }
+        Ok(root)
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = drive;
+        Ok(PathBuf::from("/"))
+    }
+}
+
+/// Timing information from scanning a single root
+pub(crate) struct ScanTimings {
+    traversal_time:            Duration,
+    cache_index_time:          Duration,
+    threads_used:              usize,
+    /// Directories left unenumerated because the scan was interrupted
+    pub(crate) frontier:       Vec<(PathBuf, usize)>,
+    /// Directories whose listing failed
+    unreadable:                Vec<PathBuf>,
+    /// The scan stopped because it reached `--max-runtime-memory`
+    pub(crate) memory_limited: bool,
+}
+
+/// Traverse every given root into the cache and save once (for `--stdin-roots`)
+///
+/// Roots are always rescanned (no freshness check); entries from all roots are
+/// merged into the same cache, and `cache.root` is left at the last root scanned.
+pub fn traverse_roots(roots: &[PathBuf], cache: &mut DiskCache, args: &Args, cache_path: &Path) -> Result<DebugInfo> {
+    if roots.is_empty() {
+        anyhow::bail!("No scan roots provided");
+    }
+
+    let is_first_run = !cache.has_cache_snapshot();
+    let mut traversal_time = Duration::ZERO;
+    let mut cache_index_time = Duration::ZERO;
+    let mut threads_used = 0;
+    let mut unreadable_dirs = Vec::new();
+
+    let settings = ScanOptions::from_args(roots.to_vec(), args)?;
+    if cache.hash_algo != args.hash_algo || cache.scan_mode != settings.scan_mode() {
+        cache.entries.clear();
+        cache.hash_algo = args.hash_algo;
+        cache.scan_mode = settings.scan_mode();
+    }
+
+    for root in roots {
+        check_scan_root(root)?;
+    }
+
+    let _active = ActiveScan::start();
+    cache.skip_stats.clear();
+    cache.flush_count = 0;
+    for root in roots {
+        cache.root = root.clone();
+        let timings = scan_root_into_cache(root, cache, &settings, None)?;
+        if timings.memory_limited {
+            anyhow::bail!("Scan reached --max-runtime-memory");
+        }
+        if !timings.frontier.is_empty() {
+            anyhow::bail!("Scan interrupted");
+        }
+        traversal_time += timings.traversal_time;
+        cache_index_time += timings.cache_index_time;
+        threads_used = threads_used.max(timings.threads_used);
+        unreadable_dirs.extend(timings.unreadable);
+    }
+    cache.previous_scan = (!is_first_run).then_some(cache.last_scan);
+    cache.last_scan = Utc::now();
+
+    let save_start = Instant::now();
+    if !args.no_cache {
+        cache.save(cache_path)?;
+        ScanProgress::clear(cache_path)?;
+    }
+    let save_elapsed = save_start.elapsed();
+
+    let stats = cache.stats();
+
+    Ok(DebugInfo {
+        is_first_run,
+        scan_root: cache.root.clone(),
+        cache_used: false,
+        traversal_time,
+        save_time: save_elapsed,
+        cache_index_time,
+        total_dirs: stats.entry_count,
+        total_files: stats.total_children,
+        threads_used,
+        flush_count: cache.flush_count,
+        output_streamed: false,
+        unreadable_dirs,
+    })
+}
+
+/// Rescan only `dirs` (and what lies below them) into a cache of `cache.root`
+///
+/// Used by `--watch` to refresh the directories a batch of filesystem events
+/// touched. A directory that no longer exists is refreshed through its nearest
+/// surviving ancestor, whose listing drops it; paths outside the root are
+/// ignored.
+pub fn rescan_dirs(dirs: &[PathBuf], cache: &mut DiskCache, args: &Args, cache_path: &Path) -> Result<()> {
+    let root = cache.root.clone();
+    let mut seeds: Vec<(PathBuf, usize)> = dirs
+        .iter()
+        .filter_map(|dir| dir.ancestors().find(|d| d.is_dir()))
+        .filter_map(|dir| {
+            let depth = dir.strip_prefix(&root).ok()?.components().count();
+            Some((dir.to_path_buf(), depth))
+        })
+        .collect();
+    seeds.sort();
+    seeds.dedup();
+    if seeds.is_empty() {
+        return Ok(());
+    }
+
+    let settings = ScanOptions::from_args(vec![root.clone()], args)?;
+    let _active = ActiveScan::start();
+    let timings = scan_seeds_into_cache(&root, cache, &settings, None, seeds, &INTERRUPTED)?;
+    if timings.memory_limited {
+        anyhow::bail!("Scan reached --max-runtime-memory");
+    }
+    if !timings.frontier.is_empty() {
+        anyhow::bail!("Scan interrupted");
+    }
+    cache.previous_scan = Some(cache.last_scan);
+    cache.last_scan = Utc::now();
+
+    if !args.no_cache {
+        cache.save(cache_path)?;
+    }
+    Ok(())
+}
+
+/// Read newline-delimited scan roots (e.g. piped from `find`)
+///
+/// Blank lines are ignored. Paths that don't exist or aren't directories are
+/// skipped with a warning, or rejected with an error when `strict` is set.
+pub fn read_roots<R: BufRead>(reader: R, strict: bool) -> Result<Vec<PathBuf>> {
+    let mut roots = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        let path = PathBuf::from(trimmed);
+        if !path.is_dir() {
+            if strict {
+                check_scan_root(&path)?;
+            }
+            eprintln!("warning: skipping invalid scan root: {}", path.display());
+            continue;
+        }
+
+        let path = path.canonicalize().unwrap_or(path);
+        if !roots.contains(&path) {
+            roots.push(path);
+        }
+    }
+
+    if roots.is_empty() {
+        anyhow::bail!("No valid scan roots read from stdin");
+    }
+
+    Ok(roots)
+}
+
+/// Determine worker thread count from `--threads` or the CPU count
+///
+/// `--threads 0` means the default; counts from a config layer are capped at
+/// `MAX_THREADS` like the command line's.
+pub(crate) fn resolve_thread_count(args: &Args) -> usize {
+    match args.threads {
+        Some(threads) if threads > 0 => threads.min(MAX_THREADS),
+        _ => default_thread_count(args.force),
+    }
+}
+
+/// Worker count when none was asked for: every core for `--force`, at most 4 otherwise
+pub fn default_thread_count(force: bool) -> usize {
+    let cores = num_cpus::get().max(1);
+    if force {
+        cores
+    } else {
+        // Normal (non-force) scans are often small and lock-heavy.
+        // Keep default worker count low to reduce contention.
+        cores.min(4)
+    }
+}
+
+/// Build the scan pool, or `None` (with a warning) when the threads can't be started
+///
+/// Resource limits on thread creation shouldn't cost the whole scan: the
+/// caller falls back to a single worker on its own thread.
+#[cfg(not(feature = "single-threaded"))]
+fn build_pool(num_threads: usize) -> Option<rayon::ThreadPool> {
+    let builder = rayon::ThreadPoolBuilder::new().num_threads(num_threads);
+    #[cfg(test)]
+    let result = if tests::FAIL_POOL_BUILD.get() {
+        builder
+            .spawn_handler(|_| Err(std::io::Error::other("forced failure")))
+            .build()
+    } else {
+        builder.build()
+    };
+    #[cfg(not(test))]
+    let result = builder.build();
+
+    match result {
+        Ok(pool) => Some(pool),
+        Err(e) => {
+            eprintln!("warning: couldn't start {} scan threads ({}); scanning sequentially", num_threads, e);
+            None
+        }
+    }
+}
+
+/// Scan `root` single-threaded and with `threads` workers, reporting any divergence
+///
+/// Backs the hidden `--self-test` mode: both scans start from an empty cache and
+/// must produce identical entry sets and child lists. Returns one human-readable
+/// line per divergence (empty when the scans agree).
+pub fn compare_thread_counts(root: &Path, args: &Args, threads: usize) -> Result<Vec<String>> {
+    let mut settings = ScanOptions::from_args(vec![root.to_path_buf()], args)?;
+
+    let mut single = DiskCache::new_empty();
+    single.root = root.to_path_buf();
+    settings.threads = 1;
+    scan_root_into_cache(root, &mut single, &settings, None)?;
+
+    let mut multi = DiskCache::new_empty();
+    multi.root = root.to_path_buf();
+    settings.threads = threads.max(2);
+    scan_root_into_cache(root, &mut multi, &settings, None)?;
+
+    let mut divergences = Vec::new();
+
+    let mut paths: Vec<&PathBuf> = single.entries.keys().chain(multi.entries.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    for path in paths {
+        match (single.entries.get(path), multi.entries.get(path)) {
+            (Some(_), None) => divergences.push(format!("only in single-threaded scan: {}", path.display())),
+            (None, Some(_)) => divergences.push(format!("only in multi-threaded scan: {}", path.display())),
+            (Some(a), Some(b)) => {
+                let mut a_children = a.children.clone();
+                let mut b_children = b.children.clone();
+                a_children.sort();
+                b_children.sort();
+                if a_children != b_children || a.is_dir != b.is_dir {
+                    divergences.push(format!("entry differs between scans: {}", path.display()));
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    Ok(divergences)
+}
+
+/// Run the parallel DFS for one root, merging results into `cache`
+///
+/// With `stream` set, the tree is written to it branch by branch while the
+/// scan runs (see `TreeStreamer`) instead of being left for a later render.
+pub(crate) fn scan_root_into_cache(
+    scan_root: &Path,
+    cache: &mut DiskCache,
+    settings: &ScanOptions,
+    stream: Option<&mut dyn Write>,
+) -> Result<ScanTimings> {
+    let seeds = vec![(scan_root.to_path_buf(), 0)];
+    scan_seeds_into_cache(scan_root, cache, settings, stream, seeds, &INTERRUPTED)
+}
+
+/// Run the parallel DFS starting from `seeds`, merging results into `cache`
+///
+/// `seeds` is the initial work queue: the root alone, or a saved frontier when
+/// resuming. Once `interrupt` is set, workers stop claiming directories and the
+/// unfinished queue is returned as `ScanTimings::frontier`.
+fn scan_seeds_into_cache(
+    scan_root: &Path,
+    cache: &mut DiskCache,
+    settings: &ScanOptions,
+    stream: Option<&mut dyn Write>,
+    seeds: Vec<(PathBuf, usize)>,
+    interrupt: &'static AtomicBool,
+) -> Result<ScanTimings> {
     // ============================================================================
     // Prepare for Traversal
     // ============================================================================
@@ -175,62 +683,179 @@ pub fn traverse_disk(drive: &char, cache: &mut DiskCache, args: &Args, cache_pat
     // Initialize Traversal State
     // ============================================================================
 
-    let mut work_queue = VecDeque::new();
-    work_queue.push_back(scan_root.clone());
+    let work_queue = VecDeque::from(seeds);
+    let num_threads = settings.threads;
+
+    cache.flush_threshold = settings.flush_threshold;
 
     let state = TraversalState {
         work_queue: Arc::new(Mutex::new(work_queue)),
         cache: Arc::new(RwLock::new(cache.clone())),
         in_progress: Arc::new(Mutex::new(std::collections::HashSet::new())),
-        skip_dirs: args.skip_dirs(),
+        in_flight: Arc::new(AtomicUsize::new(0)),
+        skip_dirs: settings.skip_dirs.clone(),
         changed_dirs_filter,
         skip_stats: Arc::new(Mutex::new(std::collections::HashMap::new())),
     };
 
     // ============================================================================
-    // Create Thread Pool & Determine Thread Count
+    // Create Thread Pool
     // ============================================================================
 
-    let num_threads = args.threads.unwrap_or_else(|| {
-        let cores = num_cpus::get().max(1);
-        if args.force {
-            cores
-        } else {
-            // Normal (non-force) scans are often small and lock-heavy.
-            // Keep default worker count low to reduce contention.
-            cores.min(4)
-        }
-    });
-
-    let pool = rayon::ThreadPoolBuilder::new().num_threads(num_threads).build()?;
+    #[cfg(not(feature = "single-threaded"))]
+    let pool = build_pool(num_threads);
+    // A single worker on this thread: same queue, same cache, no pool
+    #[cfg(not(feature = "single-threaded"))]
+    let num_threads = if pool.is_some() { num_threads } else { 1 };
+    #[cfg(feature = "single-threaded")]
+    let num_threads = num_threads.min(1);
 
     // ============================================================================
     // Spawn Worker Threads for Parallel DFS Traversal
     // ============================================================================
 
     let traversal_start = Instant::now();
-    let filter = state.changed_dirs_filter.clone();
-    let root = scan_root.clone();
+    let (completion_tx, completion_rx) = mpsc::channel();
+    let options = WorkerOptions {
+        skip_dirs: state.skip_dirs.clone(),
+        changed_dirs_filter: state.changed_dirs_filter.clone(),
+        scan_root: scan_root.to_path_buf(),
+        hash_algo: settings.hash_algo,
+        structure_only: settings.structure_only,
+        no_metadata: settings.no_metadata,
+        skip_hidden_dirs: settings.skip_hidden_dirs,
+        max_depth: settings.max_depth,
+        exclude_dotfiles: settings.exclude_dotfiles,
+        filters: settings.filters.clone(),
+        capture_permissions: settings.capture_permissions,
+        case_sensitive: settings.case_sensitive,
+        symlinks: settings.symlinks,
+        followed_links: Arc::new(Mutex::new(std::collections::HashSet::new())),
+        hardlinks: settings.count_hardlinks_once.then(|| Arc::new(HardlinkSet::default())),
+        scanned: Arc::new(AtomicUsize::new(0)),
+        health: Arc::new(ScanHealth::default()),
+        interrupt,
+        completion: stream
+            .is_some()
+            .then(|| Arc::new(CompletionTracker::new(scan_root, completion_tx))),
+        memory: settings
+            .max_runtime_memory
+            .map(|limit| Arc::new(MemoryGovernor::new(limit, current_rss().unwrap_or(0)))),
+    };
     let skip_stats_ref = Arc::clone(&state.skip_stats);
-    pool.in_place_scope(|s| {
-        for _ in 0..num_threads {
-            let work = Arc::clone(&state.work_queue);
-            let cache_ref = Arc::clone(&state.cache);
-            let skip = state.skip_dirs.clone();
-            let in_progress = Arc::clone(&state.in_progress);
-            let filter_ref = filter.clone();
-            let root_ref = root.clone();
-            let stats_ref = Arc::clone(&skip_stats_ref);
-
-            s.spawn(move |_| {
-                dfs_worker(&work, &cache_ref, &skip, &in_progress, &filter_ref, &root_ref, &stats_ref);
-            });
-        }
+
+    // Progress line on stderr; dropping `stop_progress` ends it
+    let (stop_progress, stop_rx) = mpsc::channel::<()>();
+    let reporter = settings.progress.then(|| {
+        let scanned = Arc::clone(&options.scanned);
+        let work = Arc::clone(&state.work_queue);
+        std::thread::spawn(move || report_progress(&scanned, &work, stop_rx))
     });
+
+    // RSS sampler for --max-runtime-memory; dropping `stop_watcher` ends it
+    let (stop_watcher, watcher_rx) = mpsc::channel::<()>();
+    let watcher = options.memory.as_ref().map(|governor| {
+        let governor = Arc::clone(governor);
+        let cache_ref = Arc::clone(&state.cache);
+        std::thread::spawn(move || watch_memory(&governor, &cache_ref, interrupt, watcher_rx))
+    });
+
+    // Render finished branches as workers report them, until the root completes
+    // or no worker is left to report anything
+    let scan_over = || {
+        interrupt.load(Ordering::SeqCst)
+            || options.health.drive_lost()
+            || (state.in_flight.load(Ordering::SeqCst) == 0 && state.work_queue.lock().unwrap().is_empty())
+    };
+    let render_stream = || {
+        match stream {
+            Some(out) => {
+                stream_completed_branches(
+                    out,
+                    scan_root,
+                    completion_rx,
+                    &state.cache,
+                    settings.stream_depth,
+                    &scan_over,
+                )
+            }
+            None => Ok(()),
+        }
+    };
+
+    // Workers run on pool threads: carry the subscriber and span over so
+    // their spans nest under the scan when `--trace` is on
+    #[cfg(not(feature = "single-threaded"))]
+    let stream_result = if let Some(pool) = &pool {
+        let dispatch = tracing::dispatcher::get_default(|d| d.clone());
+        let parent = tracing::Span::current();
+        pool.in_place_scope(|s| {
+            for worker in 0..num_threads {
+                let work = Arc::clone(&state.work_queue);
+                let cache_ref = Arc::clone(&state.cache);
+                let in_progress = Arc::clone(&state.in_progress);
+                let in_flight = Arc::clone(&state.in_flight);
+                let stats_ref = Arc::clone(&skip_stats_ref);
+                let options_ref = options.clone();
+                let dispatch = dispatch.clone();
+                let parent = parent.clone();
+
+                s.spawn(move |_| {
+                    tracing::dispatcher::with_default(&dispatch, || {
+                        let _parent = parent.enter();
+                        dfs_worker(worker, &work, &cache_ref, &in_progress, &in_flight, &stats_ref, &options_ref);
+                    });
+                });
+            }
+
+            // Render on this thread while the pool keeps scanning
+            render_stream()
+        })
+    } else {
+        dfs_worker(0, &state.work_queue, &state.cache, &state.in_progress, &state.in_flight, &skip_stats_ref, &options);
+        render_stream()
+    };
+
+    // The worker drains the queue first; its completions wait in the channel
+    #[cfg(feature = "single-threaded")]
+    let stream_result = {
+        dfs_worker(0, &state.work_queue, &state.cache, &state.in_progress, &state.in_flight, &skip_stats_ref, &options);
+        render_stream()
+    };
+    drop(stop_progress);
+    if let Some(reporter) = reporter {
+        let _ = reporter.join();
+    }
+    drop(stop_watcher);
+    if let Some(watcher) = watcher {
+        let _ = watcher.join();
+    }
+    // Don't let a vanished volume's half-listed tree pass for a complete scan
+    if options.health.drive_lost() {
+        return Err(PTreeError::DriveUnavailable(scan_root.to_path_buf()).into());
+    }
+    stream_result?;
     let traversal_elapsed = traversal_start.elapsed();
 
+    // After an interrupt, queued directories not yet claimed by a worker were never enumerated
+    let frontier = if interrupt.load(Ordering::SeqCst) {
+        let claimed = state.in_progress.lock().unwrap();
+        let mut frontier: Vec<_> = state
+            .work_queue
+            .lock()
+            .unwrap()
+            .drain(..)
+            .filter(|(path, _)| !claimed.contains(path))
+            .collect();
+        frontier.sort();
+        frontier.dedup_by(|a, b| a.0 == b.0);
+        frontier
+    } else {
+        Vec::new()
+    };
+
     // ============================================================================
-    // Extract & Save Final Cache
+    // Extract Final Cache
     // ============================================================================
 
     let mut final_cache = match Arc::try_unwrap(state.cache) {
@@ -247,9 +872,8 @@ pub fn traverse_disk(drive: &char, cache: &mut DiskCache, args: &Args, cache_pat
     let cache_index_start = Instant::now();
 
     *cache = final_cache;
-    cache.last_scan = Utc::now();
 
-    // Transfer skip statistics from traversal state to cache
+    // Merge skip statistics from traversal state into cache
     let skip_stats = match Arc::try_unwrap(state.skip_stats) {
         Ok(lock) => lock.into_inner().unwrap_or_default(),
         Err(arc) => {
@@ -257,56 +881,140 @@ pub fn traverse_disk(drive: &char, cache: &mut DiskCache, args: &Args, cache_pat
             guard.clone()
         }
     };
-    cache.skip_stats = skip_stats;
-
-    let cache_index_elapsed = cache_index_start.elapsed();
-
-    let save_start = Instant::now();
-    if !args.no_cache {
-        cache.save(&cache_path)?;
+    for (name, count) in skip_stats {
+        *cache.skip_stats.entry(name).or_insert(0) += count;
     }
-    let save_elapsed = save_start.elapsed();
-
-    // ============================================================================
-    // Return Debug Info
-    // ============================================================================
 
-    let total_files = cache.entries.values().map(|e| e.children.len()).sum();
+    // Directories were recorded before their subdirectories were listed
+    cache.compute_sizes();
 
-    Ok(DebugInfo {
-        is_first_run,
-        scan_root: cache.root.clone(),
-        cache_used: false,
+    Ok(ScanTimings {
         traversal_time: traversal_elapsed,
-        save_time: save_elapsed,
-        cache_index_time: cache_index_elapsed,
-        total_dirs: cache.entries.len(),
-        total_files,
+        cache_index_time: cache_index_start.elapsed(),
         threads_used: num_threads,
+        frontier,
+        unreadable: options.health.unreadable(),
+        memory_limited: options.memory.is_some_and(|m| m.level() == Pressure::Checkpoint),
     })
 }
 
+/// Write top-level branches as workers report them complete, until the root finishes
+///
+/// `scan_over` reports when no more completions can arrive (workers done,
+/// interrupted, or the drive gone); the loop then stops even if the root's
+/// completion was never reported.
+fn stream_completed_branches(
+    out: &mut dyn Write,
+    scan_root: &Path,
+    completions: mpsc::Receiver<PathBuf>,
+    cache: &Arc<RwLock<DiskCache>>,
+    max_depth: Option<usize>,
+    scan_over: &dyn Fn() -> bool,
+) -> Result<()> {
+    let root_line = cache.read().root_line(scan_root)?;
+    let mut streamer = TreeStreamer::new(out, scan_root, &root_line, max_depth)?;
+
+    loop {
+        let path = match completions.recv_timeout(Duration::from_millis(100)) {
+            Ok(path) => path,
+            // Nothing queued: an interrupted or finished scan won't report more
+            Err(mpsc::RecvTimeoutError::Timeout) if scan_over() => break,
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        };
+        let is_root = path == scan_root;
+        let mut guard = cache.write();
+        guard.flush_pending_writes();
+        streamer.on_complete(path, &guard)?;
+        if is_root {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Why `DirEntry::build` can't fail in the workers: names are taken from the
+/// listed path (non-UTF-8 ones are skipped first) and only directories get children
+const ENTRY_FROM_LISTING: &str = "listed entries are named after their path";
+
+/// Skip-statistics key for entries left out because their names aren't valid UTF-8
+pub const NON_UTF8_SKIP_NAME: &str = "<non-UTF-8 name>";
+
+/// Per-scan settings shared read-only by every worker
+#[derive(Debug, Clone)]
+struct WorkerOptions {
+    skip_dirs:           std::collections::HashSet<String>,
+    changed_dirs_filter: Option<std::collections::HashSet<String>>,
+    scan_root:           PathBuf,
+    hash_algo:           HashAlgorithm,
+    structure_only:      bool,
+    /// Never stat entries (`--no-metadata`); the listing's file types decide dir vs file
+    no_metadata:         bool,
+    /// Hidden subdirectories are cached as leaves instead of being descended
+    skip_hidden_dirs:    bool,
+    /// Subdirectories at this depth are cached as leaves instead of being descended
+    max_depth:           Option<usize>,
+    /// Leave out entries named `.*` entirely (`--exclude-dotfiles`)
+    exclude_dotfiles:    bool,
+    /// `--exclude` drops entries outright; `--include` only thins out files
+    filters:             NameFilters,
+    /// Record mode bits and ownership for `--long` (Unix only)
+    capture_permissions: bool,
+    case_sensitive:      bool,
+    symlinks:            SymlinkMode,
+    /// Canonical targets already descended through a symlink (`--symlinks follow`)
+    followed_links:      Arc<Mutex<std::collections::HashSet<PathBuf>>>,
+    /// Hardlinked files already sized in this scan (`--count-hardlinks-once`)
+    hardlinks:           Option<Arc<HardlinkSet>>,
+    /// Directories claimed so far, read by the `--progress` reporter
+    scanned:             Arc<AtomicUsize>,
+    /// Consecutive listing failures; set to lost when the scan root vanishes
+    health:              Arc<ScanHealth>,
+    /// Stop flag checked before each directory is claimed
+    interrupt:           &'static AtomicBool,
+    /// Set when streaming output: workers report finished subtrees through it
+    completion:          Option<Arc<CompletionTracker>>,
+    /// Pressure from `--max-runtime-memory`, raised by the RSS watcher
+    memory:              Option<Arc<MemoryGovernor>>,
+}
+
+impl WorkerOptions {
+    /// Hand each directory to the shared cache as soon as it is listed
+    ///
+    /// Streaming needs every directory visible before it is reported complete,
+    /// and under memory pressure nothing should sit in worker buffers.
+    fn flush_each_directory(&self) -> bool {
+        self.completion.is_some() || self.memory.as_ref().is_some_and(|m| m.level() >= Pressure::Flush)
+    }
+}
+
+/// How long an idle worker waits before checking the queue again
+const IDLE_BACKOFF: Duration = Duration::from_millis(1);
+
 /// Worker thread for DFS traversal
 ///
 /// Each worker thread:
-/// 1. Pulls directories from shared work queue
+/// 1. Pulls directories from shared work queue, exiting once it is empty and nothing is in flight
 /// 2. Acquires per-directory lock to prevent duplicate processing
 /// 3. Enumerates directory, filters skipped entries
 /// 4. For incremental updates: only process directories in changed_dirs_filter
 /// 5. Buffers children in cache and queues directories for processing
+#[tracing::instrument(skip_all, fields(worker = worker, dirs = tracing::field::Empty))]
 fn dfs_worker(
-    work_queue: &Arc<Mutex<VecDeque<PathBuf>>>,
+    worker: usize,
+    work_queue: &Arc<Mutex<VecDeque<(PathBuf, usize)>>>,
     cache: &Arc<RwLock<DiskCache>>,
-    skip_dirs: &std::collections::HashSet<String>,
     in_progress: &Arc<Mutex<std::collections::HashSet<PathBuf>>>,
-    changed_dirs_filter: &Option<std::collections::HashSet<String>>,
-    scan_root: &PathBuf,
+    in_flight: &AtomicUsize,
     skip_stats: &Arc<Mutex<std::collections::HashMap<String, usize>>>,
+    options: &WorkerOptions,
 ) {
     // Thread-local buffers to batch cache writes and reduce lock contention
     let mut entry_buffer: Vec<(PathBuf, DirEntry)> = Vec::with_capacity(500);
     let mut skip_buffer: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
     let flush_threshold = 500;
+    let mut dirs_scanned = 0usize;
 
     loop {
         // ====================================================================
@@ -314,27 +1022,37 @@ fn dfs_worker(
         // Reduces lock contention on work_queue significantly
         // ====================================================================
 
-        let batch = {
+        let stopping = options.interrupt.load(Ordering::SeqCst) || options.health.drive_lost();
+        let (batch, finished) = if stopping {
+            (Vec::new(), true)
+        } else {
             let mut queue = work_queue.lock().unwrap();
             let mut batch = Vec::new();
             for _ in 0..10 {
                 // Grab up to 10 items in single lock
-                if let Some(path) = queue.pop_front() {
-                    batch.push(path);
+                if let Some(item) = queue.pop_front() {
+                    batch.push(item);
                 } else {
                     break;
                 }
             }
-            batch
+            // Counted under the queue lock: children are queued before their parent is
+            // uncounted, so an empty queue with nothing in flight means the walk is done
+            in_flight.fetch_add(batch.len(), Ordering::SeqCst);
+            let finished = batch.is_empty() && in_flight.load(Ordering::SeqCst) == 0;
+            (batch, finished)
         };
 
+        if batch.is_empty() && !finished {
+            // Another worker is still listing a directory and may queue more work
+            std::thread::sleep(IDLE_BACKOFF);
+            continue;
+        }
+
         if batch.is_empty() {
             // Flush remaining buffers before exiting
             if !entry_buffer.is_empty() {
-                let mut cache_guard = cache.write();
-                for (p, e) in entry_buffer.drain(..) {
-                    cache_guard.add_entry(p, e);
-                }
+                flush_entry_buffer(cache, &mut entry_buffer);
             }
             if !skip_buffer.is_empty() {
                 let mut stats = skip_stats.lock().unwrap();
@@ -342,11 +1060,19 @@ fn dfs_worker(
                     *stats.entry(name).or_insert(0) += count;
                 }
             }
+            tracing::Span::current().record("dirs", dirs_scanned);
             break;
         }
 
         // Process batch of directories
-        for path in batch {
+        let batch_len = batch.len();
+        for (path, depth) in batch {
+            // Interrupted: hand the rest of the batch back so it lands in the frontier
+            if options.interrupt.load(Ordering::SeqCst) {
+                work_queue.lock().unwrap().push_back((path, depth));
+                continue;
+            }
+
             // ================================================================
             // Acquire Per-Directory Lock (prevents duplicate processing)
             // ================================================================
@@ -362,14 +1088,17 @@ fn dfs_worker(
             };
 
             if acquired {
+                options.scanned.fetch_add(1, Ordering::Relaxed);
+                dirs_scanned += 1;
+
                 // ============================================================
                 // Check Incremental Filter (if applicable)
                 // ============================================================
 
-                let should_process = if let Some(filter) = changed_dirs_filter {
+                let should_process = if let Some(filter) = &options.changed_dirs_filter {
                     // Incremental mode: only process if this directory changed
                     let dir_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-                    filter.contains(dir_name) || path == *scan_root
+                    filter.contains(dir_name) || path == options.scan_root
                 } else {
                     // Full scan mode: process all directories
                     true
@@ -380,61 +1109,127 @@ fn dfs_worker(
                     // Enumerate Directory & Process Entries
                     // ============================================================
 
-                    if let Ok(entries) = fs::read_dir(&path) {
+                    let listing = fs::read_dir(&path);
+                    options.health.record(&path, listing.is_ok(), &options.scan_root);
+                    if let Ok(entries) = listing {
                         let mut children = Vec::new();
-                        let mut child_entries = Vec::new();
                         let mut child_dirs_to_queue = Vec::new();
                         let mut child_files_to_cache = Vec::new();
+                        let mut leaf_dirs_to_cache = Vec::new();
+                        // Attributes come free with the listing on Windows; elsewhere only `--long` stats
+                        let wants_metadata = !options.no_metadata && (options.capture_permissions || cfg!(windows));
                         let mut skipped = Vec::new(); // Batch skipped directories
 
-                        for entry_result in entries {
-                            if let Ok(entry) = entry_result {
-                                let file_name = entry.file_name();
-                                let file_name_str = file_name.to_string_lossy();
+                        for entry in entries.flatten() {
+                            let file_name = entry.file_name();
+                            // Names are stored as UTF-8; a lossy conversion couldn't be joined back
+                            // onto the parent path, so such entries are counted and left out
+                            let Some(file_name_str) = file_name.to_str() else {
+                                skipped.push(NON_UTF8_SKIP_NAME.to_string());
+                                continue;
+                            };
 
-                                // Skip filtered directories
-                                if should_skip(&file_name_str, skip_dirs) {
-                                    // Batch skip statistics (don't lock on every skip)
-                                    skipped.push(file_name_str.to_string());
-                                    continue;
-                                }
+                            // --exclude-dotfiles: gone entirely, unlike hidden dirs which stay listed
+                            if options.exclude_dotfiles && file_name_str.starts_with('.') {
+                                continue;
+                            }
 
-                                let child_path = entry.path();
-                                children.push(file_name_str.to_string());
-
-                                // Check if this is a directory (avoid unnecessary metadata calls for files)
-                                match entry.file_type() {
-                                    Ok(ft) if ft.is_dir() => {
-                                        // Queue directories for processing
-                                        child_dirs_to_queue.push(child_path.clone());
-                                        // Also add to cache for file listing
-                                        if !child_files_to_cache.iter().any(|p| p == &child_path) {
-                                            child_files_to_cache.push(child_path);
-                                        }
-                                    }
-                                    Ok(ft) if ft.is_symlink() => {
-                                        // Capture symlink target - add to both queues if it's a dir symlink
-                                        let target = fs::read_link(&child_path).ok();
-                                        child_entries.push((file_name_str.to_string(), target));
-                                        child_files_to_cache.push(child_path.clone());
-                                        // Don't queue symlinks for traversal - they would cause loops
-                                    }
-                                    Ok(_) => {
-                                        // Regular file: add to cache but don't queue for traversal
-                                        child_files_to_cache.push(child_path);
+                            // Skip filtered directories
+                            if should_skip(file_name_str, &options.skip_dirs, options.case_sensitive) {
+                                // Batch skip statistics (don't lock on every skip)
+                                skipped.push(file_name_str.to_string());
+                                continue;
+                            }
+
+                            let child_path = entry.path();
+                            let relative = child_path.strip_prefix(&options.scan_root).unwrap_or(&child_path);
+                            if options.filters.excludes(file_name_str, relative) {
+                                continue;
+                            }
+
+                            // Check if this is a directory (avoid unnecessary metadata calls for files)
+                            let file_type = entry.file_type();
+                            let is_symlink = matches!(&file_type, Ok(ft) if ft.is_symlink());
+                            if is_symlink && options.symlinks == SymlinkMode::Skip {
+                                continue;
+                            }
+
+                            // --symlinks follow: descend into linked directories unless that would loop
+                            let follow = is_symlink
+                                && options.symlinks == SymlinkMode::Follow
+                                && should_follow(&child_path, &path, &options.followed_links);
+                            let is_dir = follow || matches!(&file_type, Ok(ft) if ft.is_dir());
+                            if options.structure_only && !is_dir {
+                                // Structure-only mode: files are neither listed nor cached
+                                continue;
+                            }
+                            if !is_dir && !options.filters.includes(file_name_str, relative) {
+                                continue;
+                            }
+                            children.push(file_name_str.to_string());
+
+                            // Directories that may end up cached as leaves are stat'ed once; the same
+                            // metadata carries the hidden attribute on Windows, while elsewhere only a
+                            // dot name makes a directory hidden
+                            let at_depth_limit = is_dir && options.max_depth.is_some_and(|max| depth + 1 >= max);
+                            let may_be_hidden =
+                                options.skip_hidden_dirs && (cfg!(windows) || file_name_str.starts_with('.'));
+                            let leaf_candidate = is_dir && (at_depth_limit || may_be_hidden);
+                            let dir_metadata = (leaf_candidate && wants_metadata)
+                                .then(|| read_metadata(|| entry.metadata()))
+                                .flatten();
+                            let is_hidden = leaf_candidate && is_hidden_dir(file_name_str, dir_metadata.as_ref());
+
+                            match file_type {
+                                Ok(_) if is_hidden && options.skip_hidden_dirs => {
+                                    // Listed, but never queued: nothing can race on its entry
+                                    leaf_dirs_to_cache.push((child_path, dir_metadata, true));
+                                }
+                                Ok(_) if at_depth_limit => {
+                                    // At the depth limit: cached as a leaf, like a hidden directory
+                                    leaf_dirs_to_cache.push((child_path, dir_metadata, is_hidden));
+                                }
+                                Ok(_) if is_dir => {
+                                    // Queue directories for processing; the directory's own entry is
+                                    // written when it is enumerated. Buffering a file placeholder here
+                                    // could race with (and overwrite) that entry in another worker.
+                                    child_dirs_to_queue.push(child_path);
+                                }
+                                Ok(_) if is_symlink => {
+                                    // Symlink shown as a leaf (never descended) with its target
+                                    let target = fs::read_link(&child_path).ok();
+                                    let metadata = wants_metadata
+                                        .then(|| read_metadata(|| fs::symlink_metadata(&child_path)))
+                                        .flatten();
+                                    child_files_to_cache.push((child_path, target, 0, metadata));
+                                }
+                                Ok(_) => {
+                                    // Regular file: add to cache but don't queue for traversal.
+                                    // On Windows the size comes with the directory listing.
+                                    let metadata = (!options.no_metadata)
+                                        .then(|| read_metadata(|| entry.metadata()))
+                                        .flatten();
+                                    let mut size = metadata.as_ref().map_or(0, |m| m.len());
+                                    // Further links to an already-sized file are cached with size 0
+                                    if let (Some(seen), Some(m)) = (&options.hardlinks, &metadata) {
+                                        size = seen.counted_size(hardlink_id(&child_path, m), size);
                                     }
-                                    _ => {} // Couldn't get file type, skip
+                                    child_files_to_cache.push((child_path, None, size, metadata));
                                 }
+                                _ => {} // Couldn't get file type, skip
                             }
                         }
 
                         // ========================================================
                         // Batch queue directories (reduce lock contention)
                         // ========================================================
+                        if let Some(tracker) = &options.completion {
+                            tracker.register(&path, child_dirs_to_queue.len());
+                        }
                         if !child_dirs_to_queue.is_empty() {
                             let mut queue = work_queue.lock().unwrap();
                             for dir_path in child_dirs_to_queue {
-                                queue.push_back(dir_path);
+                                queue.push_back((dir_path, depth + 1));
                             }
                         }
 
@@ -442,31 +1237,52 @@ fn dfs_worker(
                         // Buffer file entries (thread-local, flush periodically)
                         // Reduces cache.write() lock acquisitions dramatically
                         // ========================================================
-                        for file_path in child_files_to_cache {
-                            let file_entry = DirEntry {
-                                path:           file_path.clone(),
-                                name:           file_path
-                                    .file_name()
-                                    .and_then(|n| n.to_str().map(|s| s.to_string()))
-                                    .unwrap_or_default(),
-                                modified:       Utc::now(),
-                                content_hash:   0,
-                                children:       Vec::new(),
-                                symlink_target: None,
-                                is_hidden:      false,
-                                is_dir:         false,
-                            };
+                        // Entries that were never stat'ed get a fixed placeholder time
+                        let placeholder_modified = if options.no_metadata {
+                            NO_METADATA_MODIFIED
+                        } else {
+                            Utc::now()
+                        };
+                        for (file_path, symlink_target, size, metadata) in child_files_to_cache {
+                            let attributes = capture_attributes(metadata.as_ref());
+                            let name = file_path
+                                .file_name()
+                                .and_then(|n| n.to_str().map(|s| s.to_string()))
+                                .unwrap_or_default();
+                            // The hidden attribute on Windows, a leading dot elsewhere
+                            let is_hidden = attributes.is_some_and(has_hidden_attribute)
+                                || (cfg!(not(windows)) && name.starts_with('.'));
+                            let file_entry = DirEntry::builder(&file_path)
+                                .modified(placeholder_modified)
+                                .symlink_target(symlink_target)
+                                .hidden(is_hidden)
+                                .is_dir(false)
+                                .size(size)
+                                .depth((depth + 1) as u32)
+                                .permissions(capture_permissions(options.capture_permissions, metadata.as_ref()))
+                                .attributes(attributes)
+                                .build()
+                                .expect(ENTRY_FROM_LISTING);
                             entry_buffer.push((file_path, file_entry));
 
                             // Flush if threshold reached
                             if entry_buffer.len() >= flush_threshold {
-                                let mut cache_guard = cache.write();
-                                for (p, e) in entry_buffer.drain(..) {
-                                    cache_guard.add_entry(p, e);
-                                }
+                                flush_entry_buffer(cache, &mut entry_buffer);
                             }
                         }
 
+                        for (dir_path, metadata, is_hidden) in leaf_dirs_to_cache {
+                            let dir_entry = DirEntry::builder(&dir_path)
+                                .modified(placeholder_modified)
+                                .hidden(is_hidden)
+                                .depth((depth + 1) as u32)
+                                .permissions(capture_permissions(options.capture_permissions, metadata.as_ref()))
+                                .attributes(capture_attributes(metadata.as_ref()))
+                                .build()
+                                .expect(ENTRY_FROM_LISTING);
+                            entry_buffer.push((dir_path, dir_entry));
+                        }
+
                         // ========================================================
                         // Buffer skip statistics (thread-local, flush on exit)
                         // ========================================================
@@ -479,17 +1295,22 @@ fn dfs_worker(
                         // Children list stored unsorted for now
                         // ========================================================
 
+                        // Single metadata call per directory: modification time (and hidden attribute on Windows)
+                        let dir_metadata = (!options.no_metadata)
+                            .then(|| read_metadata(|| fs::metadata(&path)))
+                            .flatten();
+                        let modified = dir_metadata
+                            .as_ref()
+                            .and_then(|m| m.modified().ok())
+                            .map(DateTime::<Utc>::from)
+                            .unwrap_or(placeholder_modified);
+
                         // Check if directory has hidden attribute (Windows only)
+                        let attributes = capture_attributes(dir_metadata.as_ref());
                         let is_hidden = {
                             #[cfg(windows)]
                             {
-                                use std::os::windows::fs::MetadataExt;
-                                fs::metadata(&path)
-                                    .map(|m| {
-                                        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x02;
-                                        (m.file_attributes() & FILE_ATTRIBUTE_HIDDEN) != 0
-                                    })
-                                    .unwrap_or(false)
+                                attributes.is_some_and(has_hidden_attribute)
                             }
                             #[cfg(not(windows))]
                             {
@@ -501,34 +1322,55 @@ fn dfs_worker(
                             }
                         };
 
-                        let dir_entry = DirEntry {
-                            path: path.clone(),
-                            name: path
-                                .file_name()
-                                .and_then(|n| n.to_str().map(|s| s.to_string()))
-                                .unwrap_or_default(),
-                            modified: Utc::now(),
-                            content_hash: 0,
-                            children,
-                            symlink_target: None,
-                            is_hidden,
-                            is_dir: true,
+                        // Content hash lets later runs detect changes against the live filesystem
+                        let content_hash = compute_content_hash_with(
+                            options.hash_algo,
+                            &path,
+                            modified,
+                            &children,
+                            &std::collections::HashMap::new(),
+                        );
+
+                        // A followed symlink keeps its target so output can show `name -> target`
+                        let symlink_target = if options.symlinks == SymlinkMode::Follow && options.no_metadata {
+                            // read_link fails on anything that isn't a symlink, no stat needed
+                            fs::read_link(&path).ok()
+                        } else if options.symlinks == SymlinkMode::Follow {
+                            read_metadata(|| fs::symlink_metadata(&path))
+                                .is_some_and(|m| m.file_type().is_symlink())
+                                .then(|| fs::read_link(&path).ok())
+                                .flatten()
+                        } else {
+                            None
                         };
 
+                        let dir_entry = DirEntry::builder(&path)
+                            .modified(modified)
+                            .content_hash(content_hash)
+                            .children(children)
+                            .symlink_target(symlink_target)
+                            .hidden(is_hidden)
+                            .depth(depth as u32)
+                            .permissions(capture_permissions(options.capture_permissions, dir_metadata.as_ref()))
+                            .attributes(attributes)
+                            .build()
+                            .expect(ENTRY_FROM_LISTING);
+
                         // ========================================================
                         // Buffer directory entry (thread-local, flush periodically)
                         // Minimizes cache.write() lock acquisitions
                         // ========================================================
                         entry_buffer.push((path.clone(), dir_entry));
 
-                        if entry_buffer.len() >= flush_threshold {
-                            let mut cache_guard = cache.write();
-                            for (p, e) in entry_buffer.drain(..) {
-                                cache_guard.add_entry(p, e);
-                            }
+                        if entry_buffer.len() >= flush_threshold || options.flush_each_directory() {
+                            flush_entry_buffer(cache, &mut entry_buffer);
                         }
                     }
 
+                    if let Some(tracker) = &options.completion {
+                        tracker.finish(&path);
+                    }
+
                     // ============================================================
                     // Release Per-Directory Lock
                     // ============================================================
@@ -539,6 +1381,9 @@ fn dfs_worker(
                     }
                 } else {
                     // Directory filtered out (incremental mode): skip it
+                    if let Some(tracker) = &options.completion {
+                        tracker.finish(&path);
+                    }
                     {
                         let mut progress = in_progress.lock().unwrap();
                         progress.remove(&path);
@@ -546,26 +1391,1315 @@ fn dfs_worker(
                 }
             }
         }
+        in_flight.fetch_sub(batch_len, Ordering::SeqCst);
+    }
+}
+
+/// Move a worker's buffered entries into the shared cache
+///
+/// A rescanned directory's previous entry is still cached at this point, so its
+/// old child list is reconciled first: subdirectories and files that vanished
+/// since the last scan are dropped along with their descendants.
+fn flush_entry_buffer(cache: &Arc<RwLock<DiskCache>>, buffer: &mut Vec<(PathBuf, DirEntry)>) {
+    let mut cache_guard = cache.write();
+    for (p, e) in buffer.drain(..) {
+        if e.is_dir {
+            cache_guard.reconcile_children(&p, &e.children);
+        }
+        cache_guard.add_entry(p, e);
+    }
+}
+
+/// Mode and ownership for `--long`, when enabled and the metadata was readable
+///
+/// Always `None` off Unix, where there are no mode bits to show.
+fn capture_permissions(enabled: bool, metadata: Option<&fs::Metadata>) -> Option<UnixPermissions> {
+    #[cfg(unix)]
+    {
+        metadata.filter(|_| enabled).map(UnixPermissions::from_metadata)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (enabled, metadata);
+        None
+    }
+}
+
+/// Raw attribute word for `--attributes` and hidden detection
+///
+/// Always `None` off Windows.
+fn capture_attributes(metadata: Option<&fs::Metadata>) -> Option<u32> {
+    #[cfg(windows)]
+    {
+        metadata.map(attributes_from_metadata)
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = metadata;
+        None
     }
 }
 
-fn should_skip(name: &str, skip_dirs: &std::collections::HashSet<String>) -> bool {
-    skip_dirs.iter().any(|skip| name.eq_ignore_ascii_case(skip))
+/// Stat through `stat`; every metadata read in the workers goes through here
+/// so tests can check that `--no-metadata` makes none
+fn read_metadata(stat: impl FnOnce() -> std::io::Result<fs::Metadata>) -> Option<fs::Metadata> {
+    #[cfg(test)]
+    tests::METADATA_CALLS.set(tests::METADATA_CALLS.get() + 1);
+    stat().ok()
+}
+
+/// Whether a directory counts as hidden for `--exclude-hidden-dirs-from-scan`
+///
+/// A leading dot on any platform, or the hidden attribute in `metadata` on
+/// Windows; without metadata (`--no-metadata`) only the name counts.
+fn is_hidden_dir(name: &str, metadata: Option<&fs::Metadata>) -> bool {
+    name.starts_with('.') || capture_attributes(metadata).is_some_and(has_hidden_attribute)
+}
+
+/// Decide whether a symlink should be descended in `--symlinks follow` mode
+///
+/// Only links to directories are followed. A link is refused if its target
+/// contains the directory holding the link (a cycle back to an ancestor), or if
+/// the same target was already descended through another link.
+fn should_follow(link: &Path, parent: &Path, followed: &Mutex<std::collections::HashSet<PathBuf>>) -> bool {
+    let Ok(target) = fs::canonicalize(link) else {
+        return false;
+    };
+    if !target.is_dir() {
+        return false;
+    }
+    if fs::canonicalize(parent).is_ok_and(|p| p.starts_with(&target)) {
+        return false;
+    }
+    followed.lock().unwrap().insert(target)
+}
+
+fn should_skip(name: &str, skip_dirs: &std::collections::HashSet<String>, case_sensitive: bool) -> bool {
+    matches_skip(name, skip_dirs, case_sensitive)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::cell::Cell;
+
+    use clap::Parser;
+
     use super::*;
 
-    #[cfg(windows)]
+    #[cfg(not(feature = "single-threaded"))]
+    thread_local! {
+        /// Makes `build_pool` fail on this test thread
+        pub(super) static FAIL_POOL_BUILD: Cell<bool> = const { Cell::new(false) };
+    }
+
+    thread_local! {
+        /// Metadata reads made through `read_metadata` on this test thread
+        pub(super) static METADATA_CALLS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Scan settings for `args` with a fixed worker count
+    fn settings(args: &Args, threads: usize) -> ScanOptions {
+        ScanOptions {
+            threads,
+            ..ScanOptions::from_args(Vec::new(), args).unwrap()
+        }
+    }
+
     #[test]
     fn test_should_skip() {
         let mut skip = std::collections::HashSet::new();
         skip.insert("System32".to_string());
         skip.insert(".git".to_string());
 
-        assert!(should_skip("System32", &skip));
-        assert!(should_skip(".git", &skip));
-        assert!(!should_skip("Documents", &skip));
+        assert!(should_skip("System32", &skip, true));
+        assert!(should_skip(".git", &skip, true));
+        assert!(!should_skip("Documents", &skip, true));
+    }
+
+    #[test]
+    fn test_should_skip_case_modes() {
+        let mut skip = std::collections::HashSet::new();
+        skip.insert("Temp".to_string());
+
+        assert!(should_skip("temp", &skip, false));
+        assert!(!should_skip("temp", &skip, true));
+        assert!(should_skip("Temp", &skip, true));
+
+        assert!(Args::parse_from(["ptree", "--case-sensitive"]).match_case());
+        assert!(!Args::parse_from(["ptree", "--ignore-case"]).match_case());
+        assert_eq!(Args::parse_from(["ptree"]).match_case(), !cfg!(windows));
+        assert!(Args::try_parse_from(["ptree", "--case-sensitive", "--ignore-case"]).is_err());
+    }
+
+    #[test]
+    fn test_read_roots_skips_invalid_unless_strict() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_read_roots");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("one"))?;
+
+        let input = format!("{}\n\n{}\n", base.join("one").display(), base.join("missing").display());
+        let roots = read_roots(std::io::Cursor::new(input.clone()), false)?;
+        assert_eq!(roots.len(), 1);
+        assert!(read_roots(std::io::Cursor::new(input), true).is_err());
+        assert!(read_roots(std::io::Cursor::new(""), false).is_err());
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_scan_root_is_a_clear_error() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_file_root");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base)?;
+        let file = base.join("notes.txt");
+        fs::write(&file, b"not a directory")?;
+        let cache_path = base.join("cache").join("ptree.dat");
+        let is_file_error =
+            |err: &anyhow::Error| matches!(err.downcast_ref(), Some(PTreeError::NotADirectory(path)) if path == &file);
+
+        // --scan-scope root reusing a cached root that is now a file
+        let mut cache = DiskCache::open(&cache_path)?;
+        cache.root = file.clone();
+        let args = Args::parse_from(["ptree", "--no-cache", "--scan-scope", "root"]);
+        let err = traverse_disk(&'C', &mut cache, &args, &cache_path).unwrap_err();
+        assert!(is_file_error(&err), "{err:#}");
+        assert!(err.to_string().contains("use the folder containing it"), "{err}");
+
+        let err = traverse_roots(
+            std::slice::from_ref(&file),
+            &mut cache,
+            &Args::parse_from(["ptree", "--no-cache"]),
+            &cache_path,
+        )
+        .unwrap_err();
+        assert!(is_file_error(&err), "{err:#}");
+        assert!(!cache.entries.contains_key(&file));
+
+        let err = read_roots(std::io::Cursor::new(format!("{}\n", file.display())), true).unwrap_err();
+        assert!(is_file_error(&err), "{err:#}");
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    /// `(path, is_dir, depth, sorted children, file size)` for everything under `root`
+    type Shape = Vec<(PathBuf, bool, u32, Vec<String>, u64)>;
+
+    fn reference_walk(root: &Path, path: &Path, out: &mut Shape) -> std::io::Result<()> {
+        let metadata = fs::symlink_metadata(path)?;
+        let mut children = Vec::new();
+        if metadata.is_dir() {
+            for child in fs::read_dir(path)? {
+                let child = child?;
+                children.push(child.file_name().to_string_lossy().to_string());
+                reference_walk(root, &child.path(), out)?;
+            }
+        }
+        children.sort();
+        let size = if metadata.is_dir() { 0 } else { metadata.len() };
+        out.push((path.to_path_buf(), metadata.is_dir(), ptree_cache::relative_depth(root, path), children, size));
+        Ok(())
+    }
+
+    /// Runs in the default and the `single-threaded` build alike: each scan must
+    /// match the same plain recursive walk, so both builds cache the same tree
+    #[test]
+    fn test_scan_matches_sequential_reference_walk() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_reference_walk");
+        let _ = fs::remove_dir_all(&base);
+        for (dir, files) in [("a/a1", 3), ("a/a2/deep", 2), ("b", 4), ("c/c1/c2/c3", 1), ("empty", 0)] {
+            fs::create_dir_all(base.join(dir))?;
+            for i in 0..files {
+                fs::write(base.join(dir).join(format!("f{i}.txt")), vec![b'x'; i * 10])?;
+            }
+        }
+        let root = base.canonicalize()?;
+
+        let mut expected = Shape::new();
+        reference_walk(&root, &root, &mut expected)?;
+        expected.sort();
+
+        let mut cache = DiskCache::new_empty();
+        cache.root = root.clone();
+        scan_root_into_cache(&root, &mut cache, &settings(&Args::parse_from(["ptree"]), 4), None)?;
+        let mut scanned: Shape = cache
+            .iter()
+            .map(|(path, e)| {
+                let mut children = e.children.clone();
+                children.sort();
+                let size = if e.is_dir { 0 } else { e.size };
+                (path.clone(), e.is_dir, e.depth, children, size)
+            })
+            .collect();
+        scanned.sort();
+        assert_eq!(scanned, expected);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    /// A deep chain keeps the queue nearly empty while one worker lists the next
+    /// level; idle workers must wait for it rather than end the scan early
+    #[test]
+    fn test_deep_tree_fully_scanned_on_every_run() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_deep_tree");
+        let _ = fs::remove_dir_all(&base);
+        let mut dir = base.clone();
+        for level in 0..40 {
+            dir = dir.join(format!("d{level}"));
+            fs::create_dir_all(dir.join("side"))?;
+            fs::write(dir.join("f.txt"), b"x")?;
+        }
+        let root = base.canonicalize()?;
+
+        let mut expected = Shape::new();
+        reference_walk(&root, &root, &mut expected)?;
+
+        for _ in 0..5 {
+            let mut cache = DiskCache::new_empty();
+            cache.root = root.clone();
+            scan_root_into_cache(&root, &mut cache, &settings(&Args::parse_from(["ptree"]), 8), None)?;
+            assert_eq!(cache.len(), expected.len());
+        }
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "single-threaded"))]
+    #[test]
+    fn test_pool_build_failure_degrades_to_sequential_scan() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_pool_failure");
+        let _ = fs::remove_dir_all(&base);
+        let roots = vec![base.join("tree")];
+        fs::create_dir_all(roots[0].join("a").join("deep"))?;
+        fs::create_dir_all(roots[0].join("b"))?;
+        fs::write(roots[0].join("a").join("file.txt"), b"data")?;
+        let cache_path = base.join("cache").join("ptree.dat");
+        let args = Args::parse_from(["ptree", "--no-cache", "-j", "8"]);
+
+        let mut pooled = DiskCache::open(&cache_path)?;
+        assert_eq!(traverse_roots(&roots, &mut pooled, &args, &cache_path)?.threads_used, 8);
+
+        FAIL_POOL_BUILD.set(true);
+        let mut sequential = DiskCache::open(&cache_path)?;
+        let result = traverse_roots(&roots, &mut sequential, &args, &cache_path);
+        FAIL_POOL_BUILD.set(false);
+
+        assert_eq!(result?.threads_used, 1);
+        let mut expected: Vec<&PathBuf> = pooled.entries.keys().collect();
+        let mut found: Vec<&PathBuf> = sequential.entries.keys().collect();
+        expected.sort();
+        found.sort();
+        assert_eq!(found, expected);
+        assert_eq!(found.len(), 5);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_metadata_scan_never_stats_entries() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_no_metadata");
+        let _ = fs::remove_dir_all(&base);
+        let roots = vec![base.join("tree")];
+        fs::create_dir_all(roots[0].join("a").join("deep"))?;
+        fs::create_dir_all(roots[0].join("b"))?;
+        fs::write(roots[0].join("a").join("file.txt"), b"data")?;
+        fs::write(roots[0].join("b").join("other.txt"), b"more data")?;
+        let cache_path = base.join("cache").join("ptree.dat");
+
+        // Run the workers on this thread so the thread-local counter sees every stat
+        let scan = |extra: &[&str]| -> Result<(DiskCache, usize)> {
+            let args = Args::parse_from(["ptree", "--no-cache"].iter().chain(extra));
+            let mut cache = DiskCache::open(&cache_path)?;
+            #[cfg(not(feature = "single-threaded"))]
+            FAIL_POOL_BUILD.set(true);
+            METADATA_CALLS.set(0);
+            let result = traverse_roots(&roots, &mut cache, &args, &cache_path);
+            #[cfg(not(feature = "single-threaded"))]
+            FAIL_POOL_BUILD.set(false);
+            result?;
+            Ok((cache, METADATA_CALLS.get()))
+        };
+
+        let (full, full_calls) = scan(&[])?;
+        let (fast, fast_calls) = scan(&["--no-metadata"])?;
+        assert!(full_calls > 0);
+        assert_eq!(fast_calls, 0);
+
+        // Same structure, with every entry flagged as never stat'ed
+        assert_eq!(fast.entries.len(), full.entries.len());
+        for (path, entry) in &full.entries {
+            let bare = &fast.entries[path];
+            assert_eq!(bare.is_dir, entry.is_dir, "{}", path.display());
+            let mut children = bare.children.clone();
+            let mut expected = entry.children.clone();
+            children.sort();
+            expected.sort();
+            assert_eq!(children, expected, "{}", path.display());
+            assert_eq!(bare.size, 0);
+            assert!(!bare.has_metadata());
+        }
+        assert!(full.entries.values().all(DirEntry::has_metadata));
+        assert_eq!(full.stats().total_size, 13);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_metadata_cache_is_rescanned_for_full_scan() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_no_metadata_rescan");
+        let _ = fs::remove_dir_all(&base);
+        let roots = vec![base.join("tree")];
+        fs::create_dir_all(&roots[0])?;
+        fs::write(roots[0].join("file.txt"), b"data")?;
+        let cache_path = base.join("cache").join("ptree.dat");
+
+        let mut cache = DiskCache::open(&cache_path)?;
+        traverse_roots(&roots, &mut cache, &Args::parse_from(["ptree", "--no-metadata"]), &cache_path)?;
+
+        // Well within the TTL, but the saved entries were never stat'ed
+        let args = Args::parse_from(["ptree", "--scan-scope", "root"]);
+        let mut cache = DiskCache::open(&cache_path)?;
+        assert!(cache.scan_mode.no_metadata);
+        let info = traverse_disk(&'C', &mut cache, &args, &cache_path)?;
+        assert!(!info.cache_used);
+        let file = &cache.entries[&roots[0].join("file.txt")];
+        assert_eq!(file.size, 4);
+        assert!(file.has_metadata());
+
+        // The same mode again is served from the cache
+        let mut cache = DiskCache::open(&cache_path)?;
+        assert!(traverse_disk(&'C', &mut cache, &args, &cache_path)?.cache_used);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_thread_count_treats_zero_as_auto() {
+        let auto = resolve_thread_count(&Args::parse_from(["ptree"]));
+        assert!((1..=4).contains(&auto));
+        assert_eq!(resolve_thread_count(&Args::parse_from(["ptree", "-j", "0"])), auto);
+        assert_eq!(resolve_thread_count(&Args::parse_from(["ptree", "-j", "3"])), 3);
+
+        // Config layers bypass the CLI parser, so the cap applies here too
+        let mut args = Args::parse_from(["ptree"]);
+        args.threads = Some(100_000);
+        assert_eq!(resolve_thread_count(&args), MAX_THREADS);
+    }
+
+    #[test]
+    fn test_traverse_roots_scans_every_root() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_stdin_roots");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("first").join("nested"))?;
+        fs::create_dir_all(base.join("second").join("inner"))?;
+
+        let input = format!("{}\n{}\n", base.join("first").display(), base.join("second").display());
+        let roots = read_roots(std::io::Cursor::new(input), true)?;
+
+        let cache_path = base.join("cache").join("ptree.dat");
+        let mut cache = DiskCache::open(&cache_path)?;
+        let args = Args::parse_from(["ptree", "--no-cache", "-j", "2"]);
+        traverse_roots(&roots, &mut cache, &args, &cache_path)?;
+
+        for root in &roots {
+            assert!(cache.entries.get(root).is_some_and(|e| e.is_dir), "missing root {}", root.display());
+        }
+        assert!(cache.entries.contains_key(&roots[0].join("nested")));
+        assert!(cache.entries.contains_key(&roots[1].join("inner")));
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_algo_switch_rehashes_entries() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_hash_algo_switch");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("tree").join("child"))?;
+        let roots = vec![base.join("tree")];
+        let cache_path = base.join("cache").join("ptree.dat");
+
+        let mut cache = DiskCache::open(&cache_path)?;
+        traverse_roots(&roots, &mut cache, &Args::parse_from(["ptree", "--no-cache"]), &cache_path)?;
+        let xx_hash = cache.entries[&roots[0]].content_hash;
+        assert_eq!(cache.hash_algo, HashAlgorithm::Xxhash);
+
+        let args = Args::parse_from(["ptree", "--no-cache", "--hash-algo", "blake3"]);
+        traverse_roots(&roots, &mut cache, &args, &cache_path)?;
+        assert_eq!(cache.hash_algo, HashAlgorithm::Blake3);
+        assert_ne!(cache.entries[&roots[0]].content_hash, xx_hash);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rescan_keeps_previous_last_scan() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_previous_scan");
+        let _ = fs::remove_dir_all(&base);
+        let roots = vec![base.join("tree")];
+        fs::create_dir_all(roots[0].join("child"))?;
+        let cache_path = base.join("cache").join("ptree.dat");
+
+        let mut cache = DiskCache::open(&cache_path)?;
+        traverse_roots(&roots, &mut cache, &Args::parse_from(["ptree"]), &cache_path)?;
+        assert_eq!(cache.previous_scan, None, "a first scan has nothing to compare against");
+
+        let earlier = Utc::now() - chrono::Duration::days(1);
+        cache.last_scan = earlier;
+        traverse_roots(&roots, &mut cache, &Args::parse_from(["ptree", "--force"]), &cache_path)?;
+        assert_eq!(cache.previous_scan, Some(earlier));
+        assert!(cache.last_scan > earlier);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_depth_assigned_during_traversal() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_depth");
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("tree");
+        fs::create_dir_all(root.join("a").join("b").join("c"))?;
+        fs::create_dir_all(root.join("x"))?;
+        fs::write(root.join("a").join("b").join("file.txt"), b"x")?;
+        let root = root.canonicalize()?;
+
+        let cache_path = base.join("cache").join("ptree.dat");
+        let mut cache = DiskCache::open(&cache_path)?;
+        let args = Args::parse_from(["ptree", "--no-cache", "-j", "4"]);
+        traverse_roots(std::slice::from_ref(&root), &mut cache, &args, &cache_path)?;
+
+        assert_eq!(cache.entries[&root].depth, 0);
+        assert_eq!(cache.entries[&root.join("a")].depth, 1);
+        assert_eq!(cache.entries[&root.join("x")].depth, 1);
+        assert_eq!(cache.entries[&root.join("a").join("b")].depth, 2);
+        assert_eq!(cache.entries[&root.join("a").join("b").join("file.txt")].depth, 3);
+        assert_eq!(cache.entries[&root.join("a").join("b").join("c")].depth, 3);
+        assert_eq!(cache.max_entry_depth(), 3);
+        assert_eq!(cache.entries_at_depth(1).len(), 2);
+        for entry in cache.entries.values() {
+            assert_eq!(entry.depth, ptree_cache::relative_depth(&root, &entry.path));
+        }
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_structure_only_omits_files() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_structure_only");
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("tree");
+        fs::create_dir_all(root.join("sub"))?;
+        for i in 0..20 {
+            fs::write(root.join(format!("file{}.txt", i)), b"x")?;
+            fs::write(root.join("sub").join(format!("inner{}.txt", i)), b"x")?;
+        }
+        let root = root.canonicalize()?;
+
+        let scan = |flag: Option<&str>, name: &str| -> Result<(DiskCache, u64)> {
+            let cache_path = base.join(name).join("ptree.dat");
+            let mut cache = DiskCache::open(&cache_path)?;
+            let mut argv = vec!["ptree"];
+            argv.extend(flag);
+            traverse_roots(std::slice::from_ref(&root), &mut cache, &Args::parse_from(argv), &cache_path)?;
+            Ok((cache, fs::metadata(cache_path.with_extension("dat"))?.len()))
+        };
+
+        let (full, full_size) = scan(None, "full")?;
+        let (dirs, dirs_size) = scan(Some("--structure-only"), "dirs")?;
+
+        assert_eq!(full.entries[&root].children.len(), 21);
+        assert_eq!(dirs.entries[&root].children, vec!["sub".to_string()]);
+        assert!(dirs.entries[&root.join("sub")].children.is_empty());
+        assert_eq!(full.entries[&root.join("file0.txt")].size, 1);
+        assert!(dirs.entries.values().all(|e| e.is_dir));
+        assert_eq!(dirs.entries.len(), 2);
+        assert!(dirs_size < full_size, "{} !< {}", dirs_size, full_size);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_structure_only_cache_is_rescanned_for_normal_scan() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_structure_only_rescan");
+        let _ = fs::remove_dir_all(&base);
+        let roots = vec![base.join("tree")];
+        fs::create_dir_all(roots[0].join("sub"))?;
+        fs::write(roots[0].join("sub").join("inner.txt"), b"x")?;
+        let cache_path = base.join("cache").join("ptree.dat");
+
+        let mut cache = DiskCache::open(&cache_path)?;
+        traverse_roots(&roots, &mut cache, &Args::parse_from(["ptree", "--structure-only"]), &cache_path)?;
+        assert!(cache.entries[&roots[0].join("sub")].children.is_empty());
+
+        // Well within the TTL, but the saved cache has no files to show
+        let args = Args::parse_from(["ptree", "--scan-scope", "root"]);
+        let mut cache = DiskCache::open(&cache_path)?;
+        let info = traverse_disk(&'C', &mut cache, &args, &cache_path)?;
+        assert!(!info.cache_used);
+        assert!(!cache.scan_mode.structure_only);
+        assert_eq!(cache.entries[&roots[0].join("sub")].children, vec!["inner.txt".to_string()]);
+
+        let mut reopened = DiskCache::open(&cache_path)?;
+        reopened.load_all_entries_lazy(&cache_path)?;
+        assert!(reopened.entries.contains_key(&roots[0].join("sub").join("inner.txt")));
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hidden_dirs_not_descended_by_default() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_hidden_dirs");
+        let _ = fs::remove_dir_all(&base);
+        // The scan root itself is hidden: only its hidden children are cut off
+        let root = base.join(".tree");
+        fs::create_dir_all(root.join(".venv").join("lib"))?;
+        fs::create_dir_all(root.join("src").join(".cache"))?;
+        fs::create_dir_all(root.join("target"))?;
+        fs::write(root.join(".venv").join("pyvenv.cfg"), b"x")?;
+        fs::write(root.join("src").join("main.rs"), b"x")?;
+        fs::write(root.join("src").join(".env"), b"x")?;
+        let root = root.canonicalize()?;
+
+        let scan = |argv: &[&str]| -> Result<DiskCache> {
+            let mut cache = DiskCache::new_empty();
+            cache.root = root.clone();
+            scan_root_into_cache(&root, &mut cache, &settings(&Args::parse_from(argv), 2), None)?;
+            Ok(cache)
+        };
+
+        let cache = scan(&["ptree", "--skip", "target"])?;
+        let venv = &cache.entries[&root.join(".venv")];
+        assert!(venv.is_dir && venv.is_hidden && venv.children.is_empty());
+        assert!(!cache.contains(&root.join(".venv").join("lib")));
+        assert!(!cache.contains(&root.join(".venv").join("pyvenv.cfg")));
+        assert!(cache.entries[&root.join("src").join(".cache")].children.is_empty());
+        assert!(!cache.entries[&root.join("src").join("main.rs")].is_hidden);
+        #[cfg(not(windows))]
+        assert!(cache.entries[&root.join("src").join(".env")].is_hidden, "dot-named files count as hidden");
+        assert!(!cache.contains(&root.join("target")));
+
+        let mut children = cache.entries[&root].children.clone();
+        children.sort();
+        assert_eq!(children, [".venv", "src"]);
+
+        let full = scan(&["ptree", "--exclude-hidden-dirs-from-scan=false"])?;
+        assert!(full.contains(&root.join(".venv").join("lib")));
+        assert!(full.contains(&root.join(".venv").join("pyvenv.cfg")));
+
+        // Hidden directories shown with --hidden are entered too
+        let shown = scan(&["ptree", "--hidden"])?;
+        assert!(shown.contains(&root.join(".venv").join("pyvenv.cfg")));
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_hidden_attribute_marks_dirs_hidden() -> Result<()> {
+        use ptree_cache::attributes::FILE_ATTRIBUTE_HIDDEN;
+        const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x10;
+
+        let base = std::env::temp_dir().join("ptree_test_hidden_attribute");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("secret").join("inner"))?;
+        fs::create_dir_all(base.join("visible"))?;
+        let status = std::process::Command::new("attrib")
+            .arg("+h")
+            .arg(base.join("secret"))
+            .status()?;
+        assert!(status.success(), "attrib +h failed");
+
+        let attributes = capture_attributes(Some(&fs::metadata(base.join("secret"))?)).unwrap();
+        assert_eq!(attributes & (FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_DIRECTORY), 0x12);
+        assert_eq!(capture_attributes(None), None);
+
+        let entries: Vec<fs::DirEntry> = fs::read_dir(&base)?.collect::<std::io::Result<_>>()?;
+        let hidden = |name: &str, no_metadata: bool| {
+            let entry = entries.iter().find(|e| e.file_name() == name).unwrap();
+            let metadata = (!no_metadata).then(|| entry.metadata().unwrap());
+            is_hidden_dir(name, metadata.as_ref())
+        };
+        assert!(hidden("secret", false));
+        assert!(!hidden("visible", false));
+        // --no-metadata rules out the stat that reads the attribute
+        assert!(!hidden("secret", true));
+
+        // The default scan lists the hidden directory but doesn't descend it
+        let root = base.canonicalize()?;
+        let cache_path = root.join("cache").join("ptree.dat");
+        let mut cache = DiskCache::open(&cache_path)?;
+        traverse_roots(
+            std::slice::from_ref(&root),
+            &mut cache,
+            &Args::parse_from(["ptree", "--no-cache"]),
+            &cache_path,
+        )?;
+        assert!(cache.entries[&root].children.iter().any(|c| c == "secret"));
+        assert!(!cache.contains(&root.join("secret").join("inner")));
+
+        let _ = std::process::Command::new("attrib")
+            .arg("-h")
+            .arg(base.join("secret"))
+            .status();
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_include_and_exclude_globs() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_include_exclude");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("src").join("util"))?;
+        fs::create_dir_all(base.join("logs").join("old"))?;
+        fs::create_dir_all(base.join("target").join("debug"))?;
+        for file in [
+            "src/main.rs",
+            "src/util/mod.rs",
+            "src/debug.log",
+            "logs/old/app.log",
+            "README.md",
+            "notes.txt",
+        ] {
+            fs::write(base.join(file), b"x")?;
+        }
+        let root = base.canonicalize()?;
+
+        let scan = |argv: &[&str]| -> Result<Vec<String>> {
+            let mut cache = DiskCache::new_empty();
+            cache.root = root.clone();
+            scan_root_into_cache(&root, &mut cache, &settings(&Args::parse_from(argv), 2), None)?;
+            let mut paths: Vec<String> = cache
+                .entries
+                .keys()
+                .filter_map(|p| p.strip_prefix(&root).ok())
+                .filter(|p| !p.as_os_str().is_empty())
+                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                .collect();
+            paths.sort();
+            Ok(paths)
+        };
+
+        // Excluded names go at any depth; an excluded directory isn't entered
+        let excluded = scan(&["ptree", "--no-cache", "--exclude", "*.log", "--exclude", "target"])?;
+        assert_eq!(
+            excluded,
+            [
+                "README.md",
+                "logs",
+                "logs/old",
+                "notes.txt",
+                "src",
+                "src/main.rs",
+                "src/util",
+                "src/util/mod.rs"
+            ]
+        );
+
+        // Includes thin out files only: every directory is still walked
+        let included = scan(&["ptree", "--no-cache", "--include", "src/**", "--exclude", "*.log"])?;
+        assert_eq!(
+            included,
+            [
+                "logs",
+                "logs/old",
+                "src",
+                "src/main.rs",
+                "src/util",
+                "src/util/mod.rs",
+                "target",
+                "target/debug"
+            ]
+        );
+
+        // A scan that will be saved keeps everything: the globs apply when rendering
+        assert_eq!(scan(&["ptree", "--include", "src/**", "--exclude", "*.log"])?, scan(&["ptree"])?);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_exclude_dotfiles_drops_dot_names_only() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_exclude_dotfiles");
+        let _ = fs::remove_dir_all(&base);
+        // The root's own dot name doesn't matter, only its entries' names
+        let root = base.join(".tree");
+        fs::create_dir_all(root.join(".vscode").join("extensions"))?;
+        fs::create_dir_all(root.join("src").join(".cache"))?;
+        fs::write(root.join(".env"), b"x")?;
+        fs::write(root.join("src").join(".gitignore"), b"x")?;
+        fs::write(root.join("src").join("main.rs"), b"x")?;
+        let root = root.canonicalize()?;
+
+        let scan = |argv: &[&str]| -> Result<DiskCache> {
+            let mut cache = DiskCache::new_empty();
+            cache.root = root.clone();
+            scan_root_into_cache(&root, &mut cache, &settings(&Args::parse_from(argv), 2), None)?;
+            Ok(cache)
+        };
+
+        let cache = scan(&["ptree", "--no-cache", "--exclude-dotfiles"])?;
+        assert_eq!(cache.entries[&root].children, ["src"]);
+        assert_eq!(cache.entries[&root.join("src")].children, ["main.rs"]);
+        assert!(cache
+            .entries
+            .keys()
+            .all(|p| p == &root || !p.starts_with(root.join(".vscode"))));
+        assert!(!cache.contains(&root.join(".env")));
+        assert!(!cache.contains(&root.join("src").join(".cache")));
+        // Entries that remain are judged by the attribute/hidden-dir logic as before
+        assert!(!cache.entries[&root.join("src")].is_hidden);
+
+        // Independent of hidden-dir handling: with descent enabled dot entries are
+        // still dropped, and without the flag they are cached as hidden
+        let descend = scan(&[
+            "ptree",
+            "--no-cache",
+            "--exclude-dotfiles",
+            "--exclude-hidden-dirs-from-scan=false",
+        ])?;
+        assert_eq!(descend.entries.len(), cache.entries.len());
+        let plain = scan(&["ptree"])?;
+        assert!(plain.entries[&root.join(".vscode")].is_hidden);
+        assert!(plain.contains(&root.join(".env")));
+
+        // A scan that will be saved keeps dot entries for runs without the flag
+        let cached = scan(&["ptree", "--exclude-dotfiles"])?;
+        assert_eq!(cached.entries.len(), plain.entries.len());
+        assert!(cached.contains(&root.join(".env")));
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_name_filters_render_like_no_cache_scans() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_name_filters_render");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("src").join(".cache"))?;
+        fs::create_dir_all(base.join("target").join("debug"))?;
+        for file in ["src/main.rs", "src/debug.log", "target/debug/app", ".env", "README.md"] {
+            fs::write(base.join(file), b"x")?;
+        }
+        let root = base.canonicalize()?;
+
+        let render = |argv: &[&str]| -> Result<String> {
+            let args = Args::parse_from(argv);
+            let mut cache = DiskCache::new_empty();
+            cache.root = root.clone();
+            scan_root_into_cache(&root, &mut cache, &settings(&args, 2), None)?;
+            cache.show_hidden = true;
+            cache.exclude_dotfiles = args.exclude_dotfiles;
+            cache.name_filters = args.filters()?;
+            cache.build_tree_output()
+        };
+
+        // The cache a filtered run saves must not leak its filters into later runs,
+        // so it is filtered while rendering and has to print what --no-cache does
+        for filters in [
+            &["--exclude-dotfiles"][..],
+            &["--exclude", "*.log", "--exclude", "target"],
+            &["--include", "src/**", "--exclude-dotfiles"],
+        ] {
+            let cached = render(&[&["ptree"], filters].concat())?;
+            assert_eq!(cached, render(&[&["ptree", "--no-cache"], filters].concat())?, "{filters:?}");
+            assert_ne!(cached, render(&["ptree"])?, "{filters:?}");
+        }
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_long_captures_permissions() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let base = std::env::temp_dir().join("ptree_test_long_permissions");
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("tree");
+        fs::create_dir_all(root.join("bin"))?;
+        fs::write(root.join("bin").join("run.sh"), b"#!/bin/sh\n")?;
+        fs::set_permissions(root.join("bin").join("run.sh"), fs::Permissions::from_mode(0o754))?;
+        fs::set_permissions(root.join("bin"), fs::Permissions::from_mode(0o750))?;
+        let root = root.canonicalize()?;
+
+        let scan = |argv: &[&str]| -> Result<DiskCache> {
+            let mut cache = DiskCache::new_empty();
+            cache.root = root.clone();
+            scan_root_into_cache(&root, &mut cache, &settings(&Args::parse_from(argv), 2), None)?;
+            Ok(cache)
+        };
+
+        let plain = scan(&["ptree"])?;
+        assert!(plain.entries.values().all(|e| e.permissions.is_none()));
+
+        let mut cache = scan(&["ptree", "--long"])?;
+        let script = cache.entries[&root.join("bin").join("run.sh")].permissions.unwrap();
+        assert_eq!(ptree_cache::format_permissions(script.mode, false), "-rwxr-xr--");
+        let bin = cache.entries[&root.join("bin")].permissions.unwrap();
+        assert_eq!(ptree_cache::format_permissions(bin.mode, true), "drwxr-x---");
+
+        cache.long_listing = true;
+        let output = cache.build_tree_output()?;
+        let line = output.lines().find(|l| l.ends_with("run.sh")).unwrap();
+        assert!(line.starts_with("-rwxr-xr-- "), "unexpected line: {line}");
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rescan_drops_deleted_subdirectories() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_rescan_reconcile");
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("tree");
+        fs::create_dir_all(root.join("keep"))?;
+        fs::create_dir_all(root.join("gone").join("nested").join("deeper"))?;
+        fs::write(root.join("gone").join("nested").join("file.txt"), b"x")?;
+        let root = root.canonicalize()?;
+        let cache_path = base.join("cache").join("ptree.dat");
+
+        let mut cache = DiskCache::open(&cache_path)?;
+        let args = Args::parse_from(["ptree"]);
+        traverse_roots(std::slice::from_ref(&root), &mut cache, &args, &cache_path)?;
+        assert!(cache.contains(&root.join("gone").join("nested").join("deeper")));
+
+        fs::remove_dir_all(root.join("gone"))?;
+        fs::create_dir_all(root.join("added"))?;
+        traverse_roots(std::slice::from_ref(&root), &mut cache, &args, &cache_path)?;
+
+        let mut children = cache.entries[&root].children.clone();
+        children.sort();
+        assert_eq!(children, ["added", "keep"]);
+        assert!(cache.contains(&root.join("added")));
+        assert!(!cache.iter().any(|(p, _)| p.starts_with(root.join("gone"))), "deleted subtree should not linger");
+
+        // The saved cache is reconciled too
+        let mut reloaded = DiskCache::open(&cache_path)?;
+        reloaded.load_all_entries_lazy(&cache_path)?;
+        assert!(!reloaded.contains(&root.join("gone")));
+        assert_eq!(reloaded.build_tree_output()?, format!("{}\n├── added\n└── keep\n", root.display()));
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rescan_dirs_refreshes_only_changed_directories() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_rescan_dirs");
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("tree");
+        fs::create_dir_all(root.join("watched").join("gone"))?;
+        fs::create_dir_all(root.join("other"))?;
+        let root = root.canonicalize()?;
+        let cache_path = base.join("cache").join("ptree.dat");
+
+        let mut cache = DiskCache::open(&cache_path)?;
+        let args = Args::parse_from(["ptree", "--no-cache"]);
+        traverse_roots(std::slice::from_ref(&root), &mut cache, &args, &cache_path)?;
+
+        fs::remove_dir_all(root.join("watched").join("gone"))?;
+        fs::write(root.join("watched").join("new.txt"), b"x")?;
+        fs::write(root.join("other").join("unwatched.txt"), b"x")?;
+        rescan_dirs(&[root.join("watched").join("gone")], &mut cache, &args, &cache_path)?;
+
+        assert_eq!(cache.entries[&root.join("watched")].children, ["new.txt"]);
+        assert!(!cache.contains(&root.join("watched").join("gone")));
+        assert!(cache.entries[&root.join("other")].children.is_empty(), "unchanged directories aren't rescanned");
+        assert_eq!(cache.entries[&root.join("watched")].depth, 1);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_scope_selects_root() -> Result<()> {
+        let cwd = std::env::current_dir()?;
+        let drive = drive_root(&'C')?;
+        let cached_root = std::env::temp_dir();
+
+        let mut populated = DiskCache::new_empty();
+        populated.root = cached_root.clone();
+        let empty = DiskCache::new_empty();
+
+        for cache in [&empty, &populated] {
+            let pick = |argv: &[&str]| resolve_scan_root(&'C', cache, &Args::parse_from(argv));
+            assert_eq!(pick(&["ptree"])?, cwd);
+            assert_eq!(pick(&["ptree", "--force"])?, drive);
+            assert_eq!(pick(&["ptree", "--scan-scope", "drive"])?, drive);
+            assert_eq!(pick(&["ptree", "--scan-scope", "cwd"])?, cwd);
+            assert_eq!(pick(&["ptree", "--scan-scope", "cwd", "--force"])?, cwd);
+        }
+
+        let root_args = Args::parse_from(["ptree", "--scan-scope", "root"]);
+        assert_eq!(resolve_scan_root(&'C', &populated, &root_args)?, cached_root);
+        assert_eq!(resolve_scan_root(&'C', &empty, &root_args)?, cwd);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_writes_full_tree_in_order() -> Result<()> {
+        let base = std::env::temp_dir().join("ptree_test_stream");
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("tree");
+        for top in ["alpha", "beta", "gamma", "delta"] {
+            for sub in ["one", "two", "three"] {
+                fs::create_dir_all(root.join(top).join(sub).join("leaf"))?;
+                fs::write(root.join(top).join(sub).join("f.txt"), b"x")?;
+            }
+        }
+        fs::write(root.join("readme.txt"), b"x")?;
+        let root = root.canonicalize()?;
+
+        let mut cache = DiskCache::new_empty();
+        cache.root = root.clone();
+        let args = Args::parse_from(["ptree", "--stream"]);
+        let mut streamed = Vec::new();
+        scan_root_into_cache(&root, &mut cache, &settings(&args, 4), Some(&mut streamed))?;
+
+        let streamed = String::from_utf8(streamed)?;
+        assert_eq!(streamed, cache.build_tree_output()?);
+        let alpha = streamed.find("── alpha").unwrap();
+        let beta = streamed.find("── beta").unwrap();
+        let readme = streamed.find("── readme.txt").unwrap();
+        assert!(alpha < beta && beta < readme);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_stops_when_scan_is_over_without_root_completion() -> Result<()> {
+        let root = PathBuf::from("/never/completed");
+        let mut cache = DiskCache::new_empty();
+        cache.root = root.clone();
+        let cache = Arc::new(RwLock::new(cache));
+
+        // The sender stays alive, as the worker options hold one for the whole scan
+        let (_completion_tx, completion_rx) = mpsc::channel::<PathBuf>();
+        let mut out = Vec::new();
+        let started = Instant::now();
+        stream_completed_branches(&mut out, &root, completion_rx, &cache, None, &|| true)?;
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(String::from_utf8(out)?, "/never/completed\n");
+        Ok(())
+    }
+
+    /// Full-scan a fixture tree, then save a copy of its cache with the `b`
+    /// and `a/a2` subtrees cut off and recorded as the unfinished frontier
+    ///
+    /// Returns the fixture base, scan root, cache path and the full entry set.
+    fn truncated_cache_fixture(name: &str) -> Result<(PathBuf, PathBuf, PathBuf, Vec<PathBuf>)> {
+        let base = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("tree");
+        fs::create_dir_all(root.join("a").join("a1"))?;
+        fs::create_dir_all(root.join("a").join("a2").join("deep"))?;
+        fs::create_dir_all(root.join("b").join("b1"))?;
+        fs::create_dir_all(root.join("c"))?;
+        fs::write(root.join("a").join("a2").join("deep").join("f.txt"), b"x")?;
+        fs::write(root.join("b").join("g.txt"), b"x")?;
+        fs::write(root.join("c").join("h.txt"), b"x")?;
+        let root = root.canonicalize()?;
+
+        let options = settings(&Args::parse_from(["ptree"]), 2);
+        let mut full = DiskCache::new_empty();
+        full.root = root.clone();
+        full.scan_mode = options.scan_mode();
+        scan_root_into_cache(&root, &mut full, &options, None)?;
+        let mut expected: Vec<PathBuf> = full.entries.keys().cloned().collect();
+        expected.sort();
+
+        let cut = [root.join("b"), root.join("a").join("a2")];
+        full.entries.retain(|path, _| !cut.iter().any(|c| path.starts_with(c)));
+        let cache_path = base.join("cache").join("ptree.dat");
+        full.save_partial(&cache_path, vec![(cut[0].clone(), 1), (cut[1].clone(), 2)])?;
+
+        // Lands in a directory the partial scan already enumerated
+        fs::write(root.join("c").join("late.txt"), b"x")?;
+        Ok((base, root, cache_path, expected))
+    }
+
+    fn sorted_keys(cache: &DiskCache) -> Vec<PathBuf> {
+        let mut keys: Vec<PathBuf> = cache.entries.keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_resume_completes_truncated_cache() -> Result<()> {
+        let (base, root, cache_path, expected) = truncated_cache_fixture("ptree_test_resume")?;
+
+        let mut cache = DiskCache::open(&cache_path)?;
+        traverse_disk(&'C', &mut cache, &Args::parse_from(["ptree", "--resume", "-j", "2"]), &cache_path)?;
+
+        // Only the frontier was scanned, so the late file in `c` isn't picked up
+        assert_eq!(cache.root, root);
+        assert_eq!(sorted_keys(&cache), expected);
+        assert!(ScanProgress::load(&cache_path)?.is_none());
+
+        let mut reopened = DiskCache::open(&cache_path)?;
+        reopened.load_all_entries_lazy(&cache_path)?;
+        assert_eq!(sorted_keys(&reopened), expected);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_partial_cache_without_resume_rescans() -> Result<()> {
+        let (base, root, cache_path, mut expected) = truncated_cache_fixture("ptree_test_partial_rescan")?;
+
+        // Well within the TTL, but a partial cache must never be served as-is
+        let mut cache = DiskCache::open(&cache_path)?;
+        let args = Args::parse_from(["ptree", "--scan-scope", "root", "-j", "2"]);
+        let info = traverse_disk(&'C', &mut cache, &args, &cache_path)?;
+
+        assert!(!info.cache_used);
+        expected.push(root.join("c").join("late.txt"));
+        expected.sort();
+        assert_eq!(sorted_keys(&cache), expected);
+        assert!(ScanProgress::load(&cache_path)?.is_none());
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    /// Records the name of every span opened while it is the default subscriber
+    #[derive(Clone, Default)]
+    struct SpanNames(Arc<Mutex<Vec<&'static str>>>);
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for SpanNames {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            _id: &tracing::span::Id,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            self.0.lock().unwrap().push(attrs.metadata().name());
+        }
+    }
+
+    #[test]
+    fn test_trace_spans_cover_scan_workers_and_save() -> Result<()> {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let (base, _root, cache_path, _) = truncated_cache_fixture("ptree_test_trace_spans")?;
+        let names = SpanNames::default();
+        let subscriber = tracing_subscriber::registry().with(names.clone());
+
+        tracing::subscriber::with_default(subscriber, || -> Result<()> {
+            let mut cache = DiskCache::open(&cache_path)?;
+            let args = Args::parse_from(["ptree", "--scan-scope", "root", "-j", "2"]);
+            traverse_disk(&'C', &mut cache, &args, &cache_path)?;
+            Ok(())
+        })?;
+
+        let names = names.0.lock().unwrap();
+        assert_eq!(names.iter().filter(|n| **n == "traverse_disk").count(), 1, "{names:?}");
+        let workers = if cfg!(feature = "single-threaded") { 1 } else { 2 };
+        assert_eq!(names.iter().filter(|n| **n == "dfs_worker").count(), workers, "{names:?}");
+        assert!(names.contains(&"save"), "{names:?}");
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[test]
+    fn test_interrupted_scan_returns_frontier() -> Result<()> {
+        static STOPPED: AtomicBool = AtomicBool::new(true);
+
+        let base = std::env::temp_dir().join("ptree_test_interrupt_frontier");
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("tree");
+        fs::create_dir_all(root.join("child"))?;
+
+        let mut cache = DiskCache::new_empty();
+        cache.root = root.clone();
+        let seeds = vec![(root.clone(), 0), (root.join("child"), 1), (root.clone(), 0)];
+        let timings = scan_seeds_into_cache(
+            &root,
+            &mut cache,
+            &settings(&Args::parse_from(["ptree"]), 2),
+            None,
+            seeds,
+            &STOPPED,
+        )?;
+
+        assert_eq!(timings.frontier, vec![(root.clone(), 0), (root.join("child"), 1)]);
+        assert!(cache.entries.is_empty());
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_root_vanishing_mid_scan_is_drive_unavailable() -> Result<()> {
+        use crate::health::FAILURE_BURST;
+
+        static RUNNING: AtomicBool = AtomicBool::new(false);
+
+        let base = std::env::temp_dir().join("ptree_test_drive_unavailable");
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("volume");
+        fs::create_dir_all(&root)?;
+        let args = Args::parse_from(["ptree"]);
+
+        // The root was listed, then the volume went away: every queued directory fails
+        let queued: Vec<(PathBuf, usize)> = (0..FAILURE_BURST * 2)
+            .map(|i| (root.join(format!("d{i}")), 1))
+            .collect();
+        fs::remove_dir_all(&root)?;
+        let mut cache = DiskCache::new_empty();
+        let Err(err) = scan_seeds_into_cache(&root, &mut cache, &settings(&args, 2), None, queued.clone(), &RUNNING)
+        else {
+            panic!("scan of a vanished root succeeded");
+        };
+        assert!(matches!(err.downcast_ref(), Some(PTreeError::DriveUnavailable(path)) if path == &root), "{err:#}");
+        assert!(cache.entries.is_empty());
+
+        // The same failures under a root that is still readable are collected, not fatal
+        fs::create_dir_all(&root)?;
+        let mut cache = DiskCache::new_empty();
+        let timings = scan_seeds_into_cache(&root, &mut cache, &settings(&args, 2), None, queued.clone(), &RUNNING)?;
+        assert!(cache.entries.is_empty());
+        let mut expected: Vec<PathBuf> = queued.into_iter().map(|(path, _)| path).collect();
+        expected.sort();
+        assert_eq!(timings.unreadable, expected);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_names_are_skipped_not_mangled() -> Result<()> {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let base = std::env::temp_dir().join("ptree_test_non_utf8");
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("tree");
+        fs::create_dir_all(root.join("good"))?;
+        fs::write(root.join("good.txt"), b"x")?;
+        fs::write(root.join(OsStr::from_bytes(b"bad\xff.txt")), b"x")?;
+        fs::create_dir_all(root.join(OsStr::from_bytes(b"dir\xfe")).join("inner"))?;
+        let root = root.canonicalize()?;
+
+        let mut cache = DiskCache::new_empty();
+        cache.root = root.clone();
+        scan_root_into_cache(&root, &mut cache, &settings(&Args::parse_from(["ptree"]), 2), None)?;
+
+        let mut children = cache.entries[&root].children.clone();
+        children.sort();
+        assert_eq!(children, ["good", "good.txt"]);
+        assert_eq!(cache.skip_stats.get(NON_UTF8_SKIP_NAME), Some(&2));
+
+        // Every listed child resolves to a cache entry: nothing was mangled
+        for (path, entry) in &cache.entries {
+            assert!(!path.to_string_lossy().contains('\u{FFFD}'));
+            for child in &entry.children {
+                assert!(cache.contains(&path.join(child)), "dangling child {}", child);
+            }
+        }
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn symlink_fixture(name: &str) -> Result<(PathBuf, PathBuf)> {
+        use std::os::unix::fs::symlink;
+
+        let base = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("tree");
+        fs::create_dir_all(root.join("real").join("inner"))?;
+        fs::write(root.join("file.txt"), b"x")?;
+        let root = root.canonicalize()?;
+        symlink(root.join("real"), root.join("link_dir"))?;
+        symlink(root.join("file.txt"), root.join("link_file"))?;
+        symlink(&root, root.join("real").join("loop"))?;
+        Ok((base, root))
+    }
+
+    #[cfg(unix)]
+    fn scan_with(root: &Path, mode: &str) -> Result<DiskCache> {
+        let mut cache = DiskCache::new_empty();
+        cache.root = root.to_path_buf();
+        let args = Args::parse_from(["ptree", "--symlinks", mode]);
+        scan_root_into_cache(root, &mut cache, &settings(&args, 4), None)?;
+        Ok(cache)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_skip_mode() -> Result<()> {
+        let (base, root) = symlink_fixture("ptree_test_symlinks_skip")?;
+        let cache = scan_with(&root, "skip")?;
+
+        let mut children = cache[root.as_path()].children.clone();
+        children.sort();
+        assert_eq!(children, ["file.txt", "real"]);
+        assert_eq!(cache[root.join("real").as_path()].children, ["inner"]);
+        assert!(!cache.contains(&root.join("link_dir")));
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_show_mode() -> Result<()> {
+        let (base, root) = symlink_fixture("ptree_test_symlinks_show")?;
+        let cache = scan_with(&root, "show")?;
+
+        let link = &cache[root.join("link_dir").as_path()];
+        assert!(!link.is_dir);
+        assert_eq!(link.symlink_target.as_deref(), Some(root.join("real").as_path()));
+        assert!(!cache.contains(&root.join("link_dir").join("inner")));
+        assert!(cache.contains(&root.join("link_file")));
+
+        let output = cache.build_tree_output()?;
+        assert!(output.contains(&format!("link_dir -> {}", root.join("real").display())), "{}", output);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_follow_mode_avoids_loops() -> Result<()> {
+        let (base, root) = symlink_fixture("ptree_test_symlinks_follow")?;
+        let cache = scan_with(&root, "follow")?;
+
+        let link = &cache[root.join("link_dir").as_path()];
+        assert!(link.is_dir);
+        assert_eq!(link.symlink_target.as_deref(), Some(root.join("real").as_path()));
+        assert!(cache.contains(&root.join("link_dir").join("inner")));
+
+        // The link back to the root is listed but never descended
+        let loop_link = &cache[root.join("real").join("loop").as_path()];
+        assert!(!loop_link.is_dir);
+        assert!(!cache.contains(&root.join("real").join("loop").join("real")));
+        assert!(!cache[root.join("link_file").as_path()].is_dir);
+
+        let _ = fs::remove_dir_all(&base);
+        Ok(())
     }
 }
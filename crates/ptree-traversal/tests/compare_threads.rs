@@ -0,0 +1,36 @@
+use std::fs;
+use std::path::Path;
+
+use clap::Parser;
+use ptree_core::Args;
+use ptree_traversal::compare_thread_counts;
+
+fn build_fixture(dir: &Path, depth: usize, breadth: usize) -> std::io::Result<()> {
+    if depth == 0 {
+        return Ok(());
+    }
+    for i in 0..breadth {
+        let child = dir.join(format!("dir_{}_{}", depth, i));
+        fs::create_dir_all(&child)?;
+        fs::write(child.join(format!("file_{}.txt", i)), b"data")?;
+        build_fixture(&child, depth - 1, breadth)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn single_and_multi_threaded_scans_match() -> anyhow::Result<()> {
+    let root = std::env::temp_dir().join("ptree_test_compare_threads");
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root)?;
+    build_fixture(&root, 4, 4)?;
+
+    let args = Args::parse_from(["ptree", "--no-cache"]);
+    for _ in 0..3 {
+        let divergences = compare_thread_counts(&root, &args, 8)?;
+        assert!(divergences.is_empty(), "divergences: {:#?}", divergences);
+    }
+
+    let _ = fs::remove_dir_all(&root);
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use std::fs;
+use std::path::Path;
+
+use clap::Parser;
+use ptree_cache::DiskCache;
+use ptree_core::Args;
+use ptree_traversal::traverse_roots;
+
+fn build_fixture(dir: &Path, depth: usize, breadth: usize) -> std::io::Result<()> {
+    if depth == 0 {
+        return Ok(());
+    }
+    for i in 0..breadth {
+        let child = dir.join(format!("dir_{}_{}", depth, i));
+        fs::create_dir_all(&child)?;
+        fs::write(child.join(format!("file_{}.txt", i)), b"data")?;
+        build_fixture(&child, depth - 1, breadth)?;
+    }
+    Ok(())
+}
+
+/// Every cached directory must keep the children listed by its own worker,
+/// never a placeholder written for it while its parent was being listed
+#[test]
+fn parallel_scan_keeps_directory_entries() -> anyhow::Result<()> {
+    let base = std::env::temp_dir().join("ptree_test_dir_entries");
+    let _ = fs::remove_dir_all(&base);
+    let root = base.join("tree");
+    fs::create_dir_all(&root)?;
+    build_fixture(&root, 3, 6)?;
+    let root = root.canonicalize()?;
+
+    let args = Args::parse_from(["ptree", "--no-cache", "--threads", "8"]);
+    for _ in 0..5 {
+        let mut cache = DiskCache::open(&base.join("cache").join("ptree.dat"))?;
+        traverse_roots(std::slice::from_ref(&root), &mut cache, &args, &base.join("cache").join("ptree.dat"))?;
+
+        let mut dirs = vec![root.clone()];
+        while let Some(dir) = dirs.pop() {
+            let mut expected: Vec<String> = fs::read_dir(&dir)?
+                .map(|e| e.map(|e| e.file_name().to_string_lossy().to_string()))
+                .collect::<Result<_, _>>()?;
+            expected.sort();
+
+            let entry = cache
+                .get_entry(&dir)
+                .unwrap_or_else(|| panic!("{} not cached", dir.display()));
+            let mut children = entry.children.clone();
+            children.sort();
+            assert!(entry.is_dir, "{} cached as a file", dir.display());
+            assert_eq!(children, expected, "children of {}", dir.display());
+
+            dirs.extend(expected.iter().map(|name| dir.join(name)).filter(|p| p.is_dir()));
+        }
+    }
+
+    let _ = fs::remove_dir_all(&base);
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use ptree_core::driver_status::{ServiceStatusReport, STATUS_FILE_NAME};
+
+// ============================================================================
+// ptree-driver Status (`--driver-status`)
+// ============================================================================
+
+/// Location of the status file the ptree-driver service keeps next to the cache at `cache_path`
+pub fn driver_status_path(cache_path: &Path) -> PathBuf {
+    cache_path.with_file_name(STATUS_FILE_NAME)
+}
+
+/// Read and render the status last published by the driver
+pub fn read_driver_status(cache_path: &Path) -> Result<String> {
+    let path = driver_status_path(cache_path);
+    if !path.exists() {
+        anyhow::bail!("ptree-driver has not reported any status ({} missing)", path.display());
+    }
+    let status =
+        ServiceStatusReport::read_from(&path).with_context(|| format!("Corrupt driver status {}", path.display()))?;
+    Ok(status.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_read_driver_status_next_to_cache() -> Result<()> {
+        let dir = std::env::temp_dir().join("ptree_test_driver_status");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+        let cache_path = dir.join("ptree.dat");
+
+        assert!(read_driver_status(&cache_path).is_err());
+
+        let mut status = ServiceStatusReport::new('C');
+        status.mark_running();
+        status.write_to(&driver_status_path(&cache_path))?;
+        assert_eq!(read_driver_status(&cache_path)?, status.to_string());
+        assert!(read_driver_status(&cache_path)?.starts_with("State:             running\n"));
+
+        fs::write(driver_status_path(&cache_path), "{")?;
+        assert!(read_driver_status(&cache_path).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+        Ok(())
+    }
+}
@@ -1,16 +1,52 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 use anyhow::Result;
-use ptree_cache::DiskCache;
-use ptree_core::{ColorMode, OutputFormat};
+use chrono::Utc;
+use ptree_cache::{
+    build_baseline_report,
+    rebuild_index_from_data,
+    CollapseRule,
+    DiskCache,
+    EntryFilter,
+    EntryTemplate,
+    NamePattern,
+    PathValidation,
+    Theme,
+    NOT_SCANNED_MARKER,
+};
+use ptree_core::{Args, ColorMode, ErrorPolicy, OutputFormat};
 #[cfg(feature = "scheduler")]
 use ptree_scheduler as scheduler;
-use ptree_traversal::traverse_disk;
+use ptree_traversal::{
+    compare_thread_counts,
+    default_thread_count,
+    interrupt_scan,
+    read_roots,
+    traverse_disk,
+    traverse_roots,
+    ScanReport,
+    NON_UTF8_SKIP_NAME,
+};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
+mod driver_status;
+mod pager;
+// Handlers are always built (and tested); only the listener needs the `serve` feature
+#[cfg_attr(not(feature = "serve"), allow(dead_code))]
+mod serve;
+mod volumes;
+// Event filtering is always built (and tested); only the loop needs the `watch` feature
+#[cfg_attr(not(feature = "watch"), allow(dead_code))]
+mod watch;
 
 fn main() -> Result<()> {
     let program_start = Instant::now();
 
     let args = ptree_core::parse_args();
+    init_tracing(&args);
 
     // ========================================================================
     // Handle Scheduler Commands (Early Exit)
@@ -32,6 +68,55 @@ fn main() -> Result<()> {
             scheduler::check_scheduler_status()?;
             return Ok(());
         }
+
+        if args.scheduler_run_now {
+            scheduler::run_scheduler_now()?;
+            return Ok(());
+        }
+    }
+
+    // ========================================================================
+    // JSON Output Schema (Early Exit)
+    // ========================================================================
+
+    if args.json_schema {
+        println!("{}", ptree_cache::build_json_schema_output());
+        return Ok(());
+    }
+
+    // ========================================================================
+    // Volume Listing (Early Exit)
+    // ========================================================================
+
+    if args.list_drives {
+        let volumes = volumes::list_volumes();
+        match args.format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&volumes::volumes_json(&volumes))?),
+            _ => print!("{}", volumes::format_volume_table(&volumes, args.si)),
+        }
+        return Ok(());
+    }
+
+    // ========================================================================
+    // Traversal Self-Test (Early Exit)
+    // ========================================================================
+
+    if args.self_test {
+        let root = std::env::current_dir()?;
+        // Same core count the scan itself uses (`--force` default: every core)
+        let threads = args
+            .threads
+            .filter(|&threads| threads > 0)
+            .unwrap_or_else(|| default_thread_count(true));
+        let divergences = compare_thread_counts(&root, &args, threads)?;
+        if divergences.is_empty() {
+            println!("✓ Single- and multi-threaded scans of {} match", root.display());
+            return Ok(());
+        }
+        for line in &divergences {
+            eprintln!("  {}", line);
+        }
+        anyhow::bail!("Traversal self-test found {} divergence(s)", divergences.len());
     }
 
     // ========================================================================
@@ -43,55 +128,232 @@ fn main() -> Result<()> {
         ColorMode::Always => true,
         ColorMode::Never => false,
     };
+    // An explicit --color wins over NO_COLOR and tty detection in `colored`
+    match args.color {
+        ColorMode::Auto => {}
+        mode => colored::control::set_override(mode == ColorMode::Always),
+    }
 
     // ========================================================================
     // Load or Create Cache
     // ========================================================================
 
-    let cache_path = ptree_cache::get_cache_path_custom(args.cache_dir.as_deref())?;
+    let cache_path = resolve_cache_path(&args)?;
+
+    if args.print_cache_path {
+        println!("{}", cache_path.display());
+        return Ok(());
+    }
+
+    if args.dump_cache {
+        print!("{}", DiskCache::open_readonly(&cache_path)?.build_dump_output());
+        return Ok(());
+    }
+
+    if args.driver_status {
+        print!("{}", driver_status::read_driver_status(&cache_path)?);
+        return Ok(());
+    }
+
+    // Before `open`, which would start from an empty index
+    if args.cache_repair {
+        let index = rebuild_index_from_data(&cache_path.with_extension("dat"))?;
+        index.save(&cache_path.with_extension("idx"))?;
+        println!("Rebuilt cache index: {} entries under {}", index.offsets.len(), index.root.display());
+        return Ok(());
+    }
+
     let cache_load_start = Instant::now();
     let mut cache = DiskCache::open(&cache_path)?;
     let cache_load_elapsed = cache_load_start.elapsed();
+    cache.min_free_space = args.min_free_space.unwrap_or(0);
+    if let Some(format) = args.cache_format {
+        cache.cache_format = format;
+    }
+    cache.connectors = args.tree_style.into();
+
+    // Fix up a cache carried to another machine or drive letter before it is used
+    if args.validate_paths || args.remap_drive.is_some() {
+        cache.load_all_entries_lazy(&cache_path)?;
+        let report = match args.remap_drive {
+            // Remap alone keeps entries that don't exist (yet) on the new drive
+            Some((from, to)) if !args.validate_paths => {
+                PathValidation {
+                    checked:  cache.entries.len(),
+                    remapped: cache.remap_drive(from, to),
+                    removed:  0,
+                }
+            }
+            remap => cache.validate_paths(remap),
+        };
+        if report.changed() {
+            cache.save(&cache_path)?;
+        }
+        if !args.quiet {
+            eprint!("{}", report);
+        }
+    }
+
+    if args.cache_compact {
+        print!("{}", cache.compact(&cache_path)?);
+        return Ok(());
+    }
+
+    // Display settings are needed up front when --stream renders during the scan,
+    // and by --serve, which renders like the command line would
+    apply_display_settings(&mut cache, &args)?;
+
+    // ========================================================================
+    // HTTP Server Mode (runs until stopped)
+    // ========================================================================
+
+    if let Some(addr) = args.serve.clone() {
+        cache.load_all_entries_lazy(&cache_path)?;
+        return serve::run(
+            &addr,
+            serve::ServeState {
+                cache,
+                args,
+                cache_path,
+                last_patch: None,
+            },
+        );
+    }
+
+    // ========================================================================
+    // Changed-Only Render (compares cached snapshot against live filesystem)
+    // ========================================================================
+
+    if args.changed_only {
+        cache.load_all_entries_lazy(&cache_path)?;
+        print!("{}", cache.build_changed_tree_output(&args.skip_dirs(), args.match_case())?);
+        return Ok(());
+    }
 
     // ========================================================================
     // Traverse Disk & Update Cache
     // ========================================================================
 
-    let debug_info = traverse_disk(&args.drive, &mut cache, &args, &cache_path)?;
+    let stdin_roots = if args.stdin_roots {
+        Some(read_roots(std::io::stdin().lock(), args.strict)?)
+    } else {
+        None
+    };
+
+    // Cache-only search: answered from the last saved scan without touching the disk
+    if let Some(pattern) = &args.find {
+        cache.load_all_entries_lazy(&cache_path)?;
+        if cache.is_empty() {
+            anyhow::bail!("No cached scan at {} to search; run ptree once first", cache_path.display());
+        }
+        print!("{}", find_output(&mut cache, pattern, &args, use_colors)?);
+        return Ok(());
+    }
+
+    // First Ctrl-C stops the scan and saves progress for --resume; otherwise exit right away
+    ctrlc::set_handler(|| {
+        if !interrupt_scan() {
+            std::process::exit(130);
+        }
+    })?;
+
+    let scan_started = Utc::now();
+    let debug_info = match &stdin_roots {
+        Some(roots) => traverse_roots(roots, &mut cache, &args, &cache_path)?,
+        None => traverse_disk(&args.drive, &mut cache, &args, &cache_path)?,
+    };
+
+    // Written before error-policy checks so a --strict-errors failure is still audited
+    if let Some(report_path) = &args.scan_report {
+        if cache.is_empty() {
+            cache.load_all_entries_lazy(&cache_path)?;
+        }
+        ScanReport::new(&debug_info, &cache, scan_started).write(Path::new(report_path), args.scan_report_append)?;
+    }
+
+    report_unreadable(args.error_policy(), &debug_info.unreadable_dirs, &mut std::io::stderr())?;
+    if !debug_info.cache_used {
+        let skipped = cache.skip_stats.get(NON_UTF8_SKIP_NAME).copied().unwrap_or(0);
+        report_non_utf8(args.error_policy(), skipped, &mut std::io::stderr())?;
+    }
+
+    if args.since_scan {
+        cache.mark_since = cache.previous_scan;
+    }
+
+    // A tree served from an old cache gets a warning above it (not with --quiet)
+    if let Some(max_age) = args.cache_max_age_warn {
+        if debug_info.cache_used && !args.quiet {
+            if let Some(warning) = cache.stale_cache_warning(max_age) {
+                eprintln!("{}", warning);
+            }
+        }
+    }
 
     // ========================================================================
     // Output Results (with lazy-loading for cold-start)
     // ========================================================================
 
-    cache.show_hidden = args.hidden;
-
-    if cache.entries.is_empty() {
+    if cache.is_empty() {
         let _ = cache.load_all_entries_lazy(&cache_path);
     }
 
+    // Counts come straight from the entries: no renderer runs on this path
+    if args.count_only {
+        print!("{}", count_output(&cache, &args));
+        return Ok(());
+    }
+
+    if args.only_empty_files {
+        print!("{}", empty_files_output(&cache, args.format));
+        return Ok(());
+    }
+
+    if let Some(baseline) = &args.baseline {
+        return check_baseline(&cache, Path::new(baseline), args.update_baseline, &mut std::io::stdout());
+    }
+
+    // Plain JSON of a single root is never paged, so it goes out without an intermediate String
+    let stream_json =
+        !args.quiet && !debug_info.output_streamed && stdin_roots.is_none() && streams_json(&args, use_colors);
+
     let formatting_start = Instant::now();
-    let output = if !args.quiet {
-        Some(match args.format {
-            OutputFormat::Tree => {
-                if use_colors {
-                    cache.build_colored_tree_output_with_depth(args.max_depth)?
-                } else {
-                    cache.build_tree_output_with_depth(args.max_depth)?
-                }
-            }
-            OutputFormat::Json => cache.build_json_output_with_depth(args.max_depth)?,
-        })
+    let output = if !args.quiet && !debug_info.output_streamed && !stream_json {
+        // Each stdin root renders as its own tree/document; otherwise just the cache root
+        let roots = stdin_roots.unwrap_or_else(|| vec![cache.root.clone()]);
+        let mut rendered = Vec::with_capacity(roots.len());
+        for root in roots {
+            cache.root = root;
+            rendered.push(render_output(&cache, &args, use_colors)?);
+        }
+        Some(rendered.join("\n"))
     } else {
         None
     };
     let formatting_elapsed = formatting_start.elapsed();
 
     let output_start = Instant::now();
-    if let Some(output) = output {
-        println!("{}", output);
+    if stream_json {
+        let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+        cache.write_json_output(&mut out, args.max_depth)?;
+        writeln!(out)?;
+        out.flush()?;
+    } else if let Some(output) = output {
+        let is_tree = matches!(args.format, OutputFormat::Tree);
+        let tty = atty::is(atty::Stream::Stdout);
+        let no_pager = args.no_pager || args.watch;
+        if pager::should_page(is_tree, tty, no_pager, args.quiet, output.lines().count(), pager::terminal_height()) {
+            pager::page(&pager::pager_command(std::env::var("PAGER").ok()), &output)?;
+        } else {
+            println!("{}", output);
+        }
     }
     let output_elapsed = output_start.elapsed();
 
+    if args.watch {
+        return watch::run(&mut cache, &args, &cache_path, |cache| render_output(cache, &args, use_colors));
+    }
+
     // ========================================================================
     // Skip Statistics (if requested)
     // ========================================================================
@@ -119,6 +381,228 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// Copy the command line's render settings onto `cache`
+fn apply_display_settings(cache: &mut DiskCache, args: &Args) -> Result<()> {
+    cache.show_hidden = args.hidden;
+    cache.long_listing = args.long;
+    cache.show_attributes = args.attributes;
+    cache.depth_colors = args.depth_colors;
+    if let Some(path) = &args.theme {
+        cache.theme = Theme::load(std::path::Path::new(path))?;
+    }
+    cache.classify = args.classify;
+    cache.max_name_length = args.max_name_length;
+    cache.root_label = args.render_root_name.clone();
+    cache.root_basename = args.root_basename;
+    cache.strip_prefix = args.strip_prefix.as_ref().map(PathBuf::from);
+    cache.strip_prefix_strict = args.strict;
+    if let Some(template) = &args.entries_format {
+        cache.entry_template = Some(
+            EntryTemplate::parse(template)?
+                .with_si(args.si)
+                .with_strip_prefix(cache.strip_prefix.clone()),
+        );
+    }
+    if let Some(expr) = &args.entries_filter {
+        cache.entry_filter = Some(EntryFilter::parse(expr, args.match_case())?);
+    }
+    if let Some(pattern) = &args.match_name {
+        cache.entry_filter = Some(EntryFilter::name_regex(pattern, args.match_case())?);
+    }
+    cache.only_dirs_with_files = args.only_dirs_with_files;
+    cache.exclude_empty_files = args.exclude_empty_files;
+    cache.exclude_dotfiles = args.exclude_dotfiles;
+    cache.name_filters = args.filters()?;
+    cache.prune_empty = args.prune_empty;
+    cache.collapse = args.collapse_threshold.map(|threshold| {
+        CollapseRule {
+            threshold,
+            // Cached paths are canonical; a path that doesn't resolve can't match anyway
+            expand: args
+                .expand
+                .iter()
+                .map(|p| std::fs::canonicalize(p).unwrap_or_else(|_| PathBuf::from(p)))
+                .collect(),
+            si: args.si,
+        }
+    });
+    cache.show_sizes = args.size;
+    cache.si_sizes = args.si;
+    cache.sample = args.sample;
+    cache.sample_seed = args.seed;
+    Ok(())
+}
+
+/// Render the cache rooted at `cache.root` in the requested output format
+fn render_output(cache: &DiskCache, args: &Args, use_colors: bool) -> Result<String> {
+    if let Some(sort) = args.group_by_extension {
+        return match args.format {
+            OutputFormat::Json => cache.build_extension_json(sort),
+            _ => Ok(cache.build_extension_table(sort, args.si)),
+        };
+    }
+    if let Some(kind) = args.dedupe_names {
+        return match args.format {
+            OutputFormat::Json => cache.build_duplicate_names_json(kind),
+            _ => Ok(cache.build_duplicate_names_report(kind)),
+        };
+    }
+
+    Ok(match args.format {
+        OutputFormat::Tree => {
+            if use_colors {
+                cache.build_colored_tree_output_with_depth(args.max_depth)?
+            } else {
+                cache.build_tree_output_with_depth(args.max_depth)?
+            }
+        }
+        OutputFormat::Json => {
+            if use_colors {
+                cache.build_colored_json_output_with_depth(args.max_depth)?
+            } else {
+                cache.build_json_output_with_depth(args.max_depth)?
+            }
+        }
+        OutputFormat::Mermaid => cache.build_mermaid_output(args.max_depth),
+        OutputFormat::Plist => cache.build_plist_output(args.max_depth)?,
+        OutputFormat::Toml => cache.build_toml_output(args.max_depth)?,
+        OutputFormat::Yaml => cache.build_yaml_output(args.max_depth)?,
+        OutputFormat::Csv => cache.build_csv_output_with_depth(args.max_depth)?,
+        OutputFormat::Dot => cache.build_dot_output(args.max_depth),
+        OutputFormat::Markdown => cache.build_markdown_output(args.max_depth)?,
+    })
+}
+
+/// Whether `render_output` would give the plain JSON tree, which `write_json_output` streams instead
+fn streams_json(args: &Args, use_colors: bool) -> bool {
+    args.format == OutputFormat::Json && !use_colors && args.group_by_extension.is_none() && args.dedupe_names.is_none()
+}
+
+/// `--find` output: matching cached paths one per line or, when `--format` was
+/// given, the matches and their parents rendered in that format
+fn find_output(cache: &mut DiskCache, pattern: &str, args: &Args, use_colors: bool) -> Result<String> {
+    let pattern = NamePattern::parse(pattern, args.match_case())?;
+    if args.format_given {
+        cache.entry_filter = Some(pattern.into());
+        return render_output(cache, args, use_colors);
+    }
+    Ok(cache
+        .find(|e| pattern.matches(e))
+        .iter()
+        .map(|e| {
+            let marker = if cache.is_unscanned(e) { NOT_SCANNED_MARKER } else { "" };
+            format!("{}{}\n", e.path.display(), marker)
+        })
+        .collect())
+}
+
+/// `--count-only`: `dirs files bytes` over the entries the tree would show, or a JSON object
+fn count_output(cache: &DiskCache, args: &Args) -> String {
+    let stats = cache.visible_stats(args.max_depth);
+    match args.format {
+        OutputFormat::Json => format!("{}\n", stats.count_json()),
+        _ => format!("{}\n", stats.count_line()),
+    }
+}
+
+/// `--only-empty-files`: zero-byte file paths one per line, or a JSON array
+fn empty_files_output(cache: &DiskCache, format: OutputFormat) -> String {
+    let empty = cache.empty_files();
+    match format {
+        OutputFormat::Json => {
+            format!("{}\n", serde_json::json!(empty.iter().map(|e| e.path.to_string_lossy()).collect::<Vec<_>>()))
+        }
+        _ => empty.iter().map(|e| format!("{}\n", e.path.display())).collect(),
+    }
+}
+
+/// `--baseline`: record the scanned tree, or compare it with the recording
+///
+/// Differences are written to `stdout` one per line and fail the run, so a CI
+/// step exits nonzero when anything under the root changed.
+fn check_baseline(cache: &DiskCache, path: &Path, update: bool, stdout: &mut dyn Write) -> Result<()> {
+    if update {
+        cache.save_baseline(path)?;
+        writeln!(stdout, "Baseline written to {}", path.display())?;
+        return Ok(());
+    }
+    if !path.exists() {
+        anyhow::bail!("No baseline at {}; record one with --update-baseline", path.display());
+    }
+
+    let changes = cache.changes_since_baseline(&DiskCache::load_baseline(path)?)?;
+    if changes.is_empty() {
+        return Ok(());
+    }
+    write!(stdout, "{}", build_baseline_report(&changes))?;
+    anyhow::bail!("{} change(s) against baseline {}", changes.len(), path.display())
+}
+
+/// Apply the error policy to directories that couldn't be listed during the scan
+///
+/// `Report` writes a one-line count, `Quiet` nothing, and `Strict` fails the
+/// run (naming the first few directories) so the process exits nonzero.
+fn report_unreadable(policy: ErrorPolicy, unreadable: &[PathBuf], stderr: &mut dyn Write) -> Result<()> {
+    const SHOWN: usize = 5;
+
+    if unreadable.is_empty() {
+        return Ok(());
+    }
+    match policy {
+        ErrorPolicy::Quiet => Ok(()),
+        ErrorPolicy::Report => {
+            writeln!(stderr, "warning: {} directories could not be read", unreadable.len())?;
+            Ok(())
+        }
+        ErrorPolicy::Strict => {
+            let mut listed: Vec<String> = unreadable.iter().take(SHOWN).map(|p| p.display().to_string()).collect();
+            if unreadable.len() > SHOWN {
+                listed.push(format!("... and {} more", unreadable.len() - SHOWN));
+            }
+            anyhow::bail!("{} directories could not be read: {}", unreadable.len(), listed.join(", "))
+        }
+    }
+}
+
+/// Report entries the scan left out because their names aren't valid UTF-8
+///
+/// One line with the count after the scan, under every policy but `Quiet`; the
+/// names themselves are only tallied (see `--skip-stats`).
+fn report_non_utf8(policy: ErrorPolicy, skipped: usize, stderr: &mut dyn Write) -> Result<()> {
+    if skipped > 0 && policy != ErrorPolicy::Quiet {
+        writeln!(stderr, "warning: skipped {} entries with non-UTF-8 names", skipped)?;
+    }
+    Ok(())
+}
+
+/// Log spans with their timings to stderr for `--trace`, `PTREE_TRACE` or `RUST_LOG`
+///
+/// Without any of them no subscriber is installed and the instrumented spans
+/// are disabled at their callsites.
+fn init_tracing(args: &Args) {
+    let filter = std::env::var("PTREE_TRACE")
+        .or_else(|_| std::env::var("RUST_LOG"))
+        .ok()
+        .or_else(|| args.trace.then(|| "info".to_string()));
+    let Some(filter) = filter else {
+        return;
+    };
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::new(filter))
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr)
+        .with_ansi(atty::is(atty::Stream::Stderr))
+        .try_init();
+}
+
+/// Cache file for this run: `--cache-dir` (or `PTREE_CACHE_DIR`), else the
+/// per-user default (`%APPDATA%`, `$XDG_CACHE_HOME` or `~/.cache`)
+///
+/// The cache is shared by every drive, so `--drive` does not change it.
+fn resolve_cache_path(args: &Args) -> Result<PathBuf> {
+    ptree_cache::get_cache_path_custom(args.cache_dir.as_deref())
+}
+
 /// Format duration in both milliseconds and picoseconds
 fn format_duration(duration: std::time::Duration) -> String {
     let ms = duration.as_secs_f64() * 1000.0;
@@ -155,6 +639,7 @@ fn print_debug_summary(
     eprintln!("\n{:<40} {}", "Directories Scanned:", format_number(debug_info.total_dirs));
     eprintln!("{:<40} {}", "Files Scanned:", format_number(debug_info.total_files));
     eprintln!("{:<40} {}", "Threads Used:", debug_info.threads_used);
+    eprintln!("{:<40} {}", "Cache Flushes:", format_number(debug_info.flush_count));
 
     eprintln!("\n{:<40} {}", "Cache Load Time:", format_duration(cache_load_time));
     if !debug_info.cache_used {
@@ -183,3 +668,218 @@ fn format_number(n: usize) -> String {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use ptree_cache::fixtures::{cache_with, dir, file, renders};
+
+    use super::*;
+
+    #[test]
+    fn test_print_cache_path_matches_real_resolution() -> Result<()> {
+        let args = Args::parse_from([
+            "ptree",
+            "--print-cache-path",
+            "--cache-dir",
+            "/tmp/ptree-ci",
+            "--drive",
+            "D",
+        ]);
+        assert_eq!(resolve_cache_path(&args)?, PathBuf::from("/tmp/ptree-ci").join("ptree.dat"));
+        assert_eq!(resolve_cache_path(&args)?, ptree_cache::get_cache_path_custom(Some("/tmp/ptree-ci"))?);
+
+        // Without --cache-dir it falls back to the platform default, whatever the drive
+        for drive in ["C", "E"] {
+            let args = Args::parse_from(["ptree", "--print-cache-path", "--drive", drive]);
+            assert_eq!(resolve_cache_path(&args)?, ptree_cache::get_cache_path()?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_baseline_passes_unchanged_and_fails_on_changes() -> Result<()> {
+        let temp = std::env::temp_dir().join("ptree_test_baseline_check");
+        let _ = std::fs::remove_dir_all(&temp);
+        let baseline = temp.join("dist.json");
+        let mut cache = cache_with(
+            "/dist",
+            [
+                dir("/dist", &["app.js", "index.html"]),
+                file("/dist/app.js", 0),
+                file("/dist/index.html", 0),
+            ],
+        );
+        let run = |cache: &DiskCache, update: bool| {
+            let mut stdout = Vec::new();
+            let result = check_baseline(cache, &baseline, update, &mut stdout);
+            (result.map_err(|e| e.to_string()), String::from_utf8(stdout).unwrap())
+        };
+
+        assert!(run(&cache, false).0.unwrap_err().contains("--update-baseline"));
+        assert_eq!(run(&cache, true).0, Ok(()));
+        // Unchanged tree: exit zero, nothing printed
+        assert_eq!(run(&cache, false), (Ok(()), String::new()));
+
+        // Changed tree: exit nonzero with the diff on stdout
+        cache.entries.get_mut(Path::new("/dist/app.js")).unwrap().size = 2048;
+        cache.entries.remove(Path::new("/dist/index.html"));
+        let (result, stdout) = run(&cache, false);
+        assert_eq!(result, Err(format!("2 change(s) against baseline {}", baseline.display())));
+        assert_eq!(stdout, "~ /dist/app.js\n- /dist/index.html\n");
+
+        // --update-baseline accepts the new state
+        assert_eq!(run(&cache, true).0, Ok(()));
+        assert_eq!(run(&cache, false), (Ok(()), String::new()));
+
+        let _ = std::fs::remove_dir_all(&temp);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unreadable_dirs_per_error_policy() {
+        let policy = |argv: &[&str]| Args::parse_from(argv).error_policy();
+        let unreadable = vec![PathBuf::from("/data/locked")];
+        let run = |policy: ErrorPolicy, dirs: &[PathBuf]| {
+            let mut stderr = Vec::new();
+            let result = report_unreadable(policy, dirs, &mut stderr);
+            (result.map_err(|e| e.to_string()), String::from_utf8(stderr).unwrap())
+        };
+
+        // Default: succeeds (exit 0) with a count on stderr
+        assert_eq!(
+            run(policy(&["ptree"]), &unreadable),
+            (Ok(()), "warning: 1 directories could not be read\n".to_string())
+        );
+        // --quiet-errors: succeeds silently
+        assert_eq!(run(policy(&["ptree", "--quiet-errors"]), &unreadable), (Ok(()), String::new()));
+        // --strict-errors: fails (nonzero exit) naming the directory
+        let (result, stderr) = run(policy(&["ptree", "--strict-errors"]), &unreadable);
+        assert_eq!(result, Err("1 directories could not be read: /data/locked".to_string()));
+        assert!(stderr.is_empty());
+
+        let many: Vec<PathBuf> = (0..8).map(|i| PathBuf::from(format!("/d{i}"))).collect();
+        let (result, _) = run(ErrorPolicy::Strict, &many);
+        assert!(result.unwrap_err().ends_with("/d4, ... and 3 more"));
+
+        // A clean scan is silent under every policy
+        for p in [ErrorPolicy::Report, ErrorPolicy::Quiet, ErrorPolicy::Strict] {
+            assert_eq!(run(p, &[]), (Ok(()), String::new()));
+        }
+        assert!(Args::try_parse_from(["ptree", "--strict-errors", "--quiet-errors"]).is_err());
+    }
+
+    #[test]
+    fn test_non_utf8_skips_reported_once_unless_quiet() {
+        let run = |policy: ErrorPolicy, skipped: usize| {
+            let mut stderr = Vec::new();
+            report_non_utf8(policy, skipped, &mut stderr).unwrap();
+            String::from_utf8(stderr).unwrap()
+        };
+
+        assert_eq!(run(ErrorPolicy::Report, 1000), "warning: skipped 1000 entries with non-UTF-8 names\n");
+        assert_eq!(run(ErrorPolicy::Strict, 2), "warning: skipped 2 entries with non-UTF-8 names\n");
+        assert_eq!(run(ErrorPolicy::Quiet, 1000), "");
+        assert_eq!(run(ErrorPolicy::Report, 0), "");
+    }
+
+    #[test]
+    fn test_find_lists_paths_or_renders_pruned_tree() -> Result<()> {
+        let mut cache = cache_with(
+            "/proj",
+            [
+                dir("/proj", &[".env", "src"]),
+                file("/proj/.env", 0),
+                dir("/proj/src", &["app.env", "main.rs"]),
+                file("/proj/src/app.env", 0),
+                file("/proj/src/main.rs", 0),
+            ],
+        );
+
+        let args = ptree_core::parse_args_from(["ptree", "--find", "*.env"]);
+        assert!(!args.format_given);
+        assert_eq!(find_output(&mut cache, "*.env", &args, false)?, "/proj/.env\n/proj/src/app.env\n");
+        assert_eq!(find_output(&mut cache, "main", &args, false)?, "/proj/src/main.rs\n");
+        assert_eq!(find_output(&mut cache, "*.toml", &args, false)?, "");
+
+        let args = ptree_core::parse_args_from(["ptree", "--find", "*.rs", "--format", "tree"]);
+        assert!(args.format_given);
+        assert_eq!(find_output(&mut cache, "*.rs", &args, false)?, "/proj\n└── src\n    └── main.rs\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_only_matches_rendered_tree() -> Result<()> {
+        let mut cache = cache_with(
+            "/proj",
+            [
+                dir("/proj", &[".git", "logs", "src"]),
+                dir("/proj/.git", &["HEAD"]),
+                file("/proj/.git/HEAD", 0),
+                dir("/proj/logs", &["app.log"]),
+                file("/proj/logs/app.log", 0),
+                dir("/proj/src", &["main.rs", "notes.md"]),
+                file("/proj/src/main.rs", 0),
+                file("/proj/src/notes.md", 0),
+            ],
+        );
+
+        // Render-time filters, set the way `main` sets them
+        let args = ptree_core::parse_args_from(["ptree", "--count-only", "--exclude-dotfiles", "--include", "*.rs"]);
+        cache.exclude_dotfiles = args.exclude_dotfiles;
+        cache.name_filters = args.filters()?;
+
+        let before = renders();
+        let tree = render_output(&cache, &args, false)?;
+        assert_eq!(tree, "/proj\n├── logs\n└── src\n    └── main.rs\n");
+        assert_eq!(renders(), before + 1);
+
+        // No tree builder runs for the counts, in either format
+        let before = renders();
+        assert_eq!(count_output(&cache, &args), "3 1 0\n");
+
+        let args = ptree_core::parse_args_from(["ptree", "--count-only", "--format", "json"]);
+        cache.exclude_dotfiles = false;
+        cache.name_filters = args.filters()?;
+        assert_eq!(count_output(&cache, &args), "{\"bytes\":0,\"dirs\":4,\"files\":4}\n");
+        assert_eq!(renders(), before, "--count-only rendered a tree");
+        Ok(())
+    }
+
+    #[test]
+    fn test_streamed_json_matches_rendered_json() -> Result<()> {
+        let cache = cache_with(
+            "/proj",
+            [
+                dir("/proj", &["src"]),
+                dir("/proj/src", &["main.rs"]),
+                file("/proj/src/main.rs", 3),
+            ],
+        );
+
+        let args = Args::parse_from(["ptree", "--format", "json", "--max-depth", "1"]);
+        assert!(streams_json(&args, false));
+        assert!(!streams_json(&args, true), "colored JSON is rendered");
+        assert!(!streams_json(&Args::parse_from(["ptree", "--format", "json", "--dedupe-names"]), false));
+        assert!(!streams_json(&Args::parse_from(["ptree"]), false));
+
+        let mut streamed = Vec::new();
+        cache.write_json_output(&mut streamed, args.max_depth)?;
+        assert_eq!(String::from_utf8(streamed)?, render_output(&cache, &args, false)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_only_empty_files_output() -> Result<()> {
+        let mut cache = DiskCache::new_empty();
+        cache.root = PathBuf::from("/proj");
+        for (path, size) in [("/proj/a.txt", 0), ("/proj/b.txt", 3), ("/proj/c.log", 0)] {
+            let entry = ptree_cache::DirEntry::builder(path).is_dir(false).size(size).build()?;
+            cache.entries.insert(PathBuf::from(path), entry);
+        }
+
+        assert_eq!(empty_files_output(&cache, OutputFormat::Tree), "/proj/a.txt\n/proj/c.log\n");
+        assert_eq!(empty_files_output(&cache, OutputFormat::Json), "[\"/proj/a.txt\",\"/proj/c.log\"]\n");
+        Ok(())
+    }
+}
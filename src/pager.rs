@@ -0,0 +1,98 @@
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use anyhow::Result;
+
+// ============================================================================
+// Pager (long tree output on a terminal)
+// ============================================================================
+
+/// Pager used when `$PAGER` is unset or empty
+#[cfg(windows)]
+const DEFAULT_PAGER: &str = "more";
+/// Pager used when `$PAGER` is unset or empty (`-R` passes colors through)
+#[cfg(not(windows))]
+const DEFAULT_PAGER: &str = "less -R";
+
+/// Whether rendered tree output should go through a pager
+///
+/// Only tree output on a terminal is paged, only when it is taller than the
+/// terminal, and never with `--no-pager` or `--quiet`.
+pub fn should_page(
+    is_tree: bool,
+    stdout_is_tty: bool,
+    no_pager: bool,
+    quiet: bool,
+    output_lines: usize,
+    terminal_height: Option<u16>,
+) -> bool {
+    is_tree
+        && stdout_is_tty
+        && !no_pager
+        && !quiet
+        && terminal_height.is_some_and(|rows| output_lines > usize::from(rows))
+}
+
+/// `$PAGER`, or the platform default
+pub fn pager_command(env_pager: Option<String>) -> String {
+    env_pager
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string())
+}
+
+/// Rows of the terminal attached to stdout, if any
+pub fn terminal_height() -> Option<u16> {
+    terminal_size::terminal_size().map(|(_, terminal_size::Height(rows))| rows)
+}
+
+/// Write `output` through `command`, falling back to stdout if it can't be started
+///
+/// `command` is split on whitespace (`less -R`); quitting the pager early is
+/// not an error.
+pub fn page(command: &str, output: &str) -> Result<()> {
+    let mut parts = command.split_whitespace();
+    let child = parts
+        .next()
+        .and_then(|program| Command::new(program).args(parts).stdin(Stdio::piped()).spawn().ok());
+
+    let Some(mut child) = child else {
+        println!("{}", output);
+        return Ok(());
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        match writeln!(stdin, "{}", output) {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => return Err(e.into()),
+            _ => {}
+        }
+    }
+    child.wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_page_decision() {
+        // Tall tree output on a terminal
+        assert!(should_page(true, true, false, false, 100, Some(40)));
+
+        // Fits on screen, or no terminal size known
+        assert!(!should_page(true, true, false, false, 40, Some(40)));
+        assert!(!should_page(true, true, false, false, 100, None));
+        // Redirected, --no-pager, --quiet, or not tree output
+        assert!(!should_page(true, false, false, false, 100, Some(40)));
+        assert!(!should_page(true, true, true, false, 100, Some(40)));
+        assert!(!should_page(true, true, false, true, 100, Some(40)));
+        assert!(!should_page(false, true, false, false, 100, Some(40)));
+    }
+
+    #[test]
+    fn test_pager_command_honors_env() {
+        assert_eq!(pager_command(Some("bat --plain".to_string())), "bat --plain");
+        assert_eq!(pager_command(Some("  ".to_string())), DEFAULT_PAGER);
+        assert_eq!(pager_command(None), DEFAULT_PAGER);
+    }
+}
@@ -0,0 +1,316 @@
+use std::path::PathBuf;
+
+#[cfg(feature = "serve")]
+use anyhow::anyhow;
+use anyhow::Result;
+use ptree_cache::{build_json_patch, DiskCache};
+use ptree_core::Args;
+use ptree_traversal::traverse_roots;
+use serde_json::{json, Value};
+
+// ============================================================================
+// HTTP Handlers
+// ============================================================================
+
+/// Status code and JSON body for one HTTP response
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub body:   String,
+}
+
+impl Response {
+    fn json(status: u16, body: String) -> Self {
+        Response { status, body }
+    }
+
+    fn error(status: u16, message: impl std::fmt::Display) -> Self {
+        Response::json(status, json!({ "error": message.to_string() }).to_string())
+    }
+}
+
+/// Loaded cache plus what is needed to refresh it, shared by every request
+pub struct ServeState {
+    pub cache:      DiskCache,
+    pub args:       Args,
+    pub cache_path: PathBuf,
+    /// Delta produced by the most recent `POST /rescan`
+    pub last_patch: Option<Value>,
+}
+
+impl ServeState {
+    /// Route a request to its handler
+    ///
+    /// - `GET /tree` - the whole cached tree (same JSON as `--format json`)
+    /// - `GET /tree?path=<dir>` - the cached subtree rooted at `<dir>`
+    /// - `GET /tree/updates` - `{added, removed, modified}` delta from the last rescan
+    /// - `POST /rescan` - rescan the cache root and save the cache
+    pub fn handle(&mut self, method: &str, url: &str) -> Response {
+        let (route, query) = url.split_once('?').unwrap_or((url, ""));
+
+        let result = match (method, route) {
+            ("GET", "/tree") => self.tree(query_param(query, "path")),
+            ("GET", "/tree/updates") => Ok(self.updates()),
+            ("POST", "/rescan") => self.rescan(),
+            (_, "/tree") | (_, "/tree/updates") | (_, "/rescan") => return Response::error(405, "Method not allowed"),
+            _ => return Response::error(404, format!("No route for {}", route)),
+        };
+
+        result.unwrap_or_else(|e| Response::error(500, e))
+    }
+
+    fn tree(&self, path: Option<String>) -> Result<Response> {
+        let max_depth = self.args.max_depth;
+        match path {
+            None => Ok(Response::json(200, self.cache.build_json_output_with_depth(max_depth)?)),
+            Some(path) => {
+                Ok(match self.cache.build_subtree_json_output(&PathBuf::from(&path), max_depth)? {
+                    Some(body) => Response::json(200, body),
+                    None => Response::error(404, format!("Directory not in cache: {}", path)),
+                })
+            }
+        }
+    }
+
+    /// Empty delta until the first rescan
+    fn updates(&self) -> Response {
+        let body = self
+            .last_patch
+            .clone()
+            .unwrap_or_else(|| json!({ "added": [], "removed": [], "modified": [] }));
+        Response::json(200, body.to_string())
+    }
+
+    fn rescan(&mut self) -> Result<Response> {
+        let root = self.cache.root.clone();
+        if root.as_os_str().is_empty() {
+            return Ok(Response::error(409, "Cache has no root to rescan; run ptree once first"));
+        }
+
+        let before = self.cache.clone();
+        let info = traverse_roots(&[root], &mut self.cache, &self.args, &self.cache_path)?;
+        self.last_patch = Some(build_json_patch(&before, &self.cache));
+        let body = json!({
+            "root": info.scan_root.to_string_lossy(),
+            "dirs": info.total_dirs,
+            "files": info.total_files,
+            "traversal_ms": info.traversal_time.as_millis() as u64,
+        });
+        Ok(Response::json(200, body.to_string()))
+    }
+}
+
+/// Value of `key` in a URL query string, percent-decoded
+fn query_param(query: &str, key: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| percent_decode(v))
+        .filter(|v| !v.is_empty())
+}
+
+/// Decode `%XX` escapes and `+` (space) in a query value
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        out.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b'+' => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// ============================================================================
+// Server Loop
+// ============================================================================
+
+/// Serve `state` over HTTP on `addr` until the process is stopped
+#[cfg(feature = "serve")]
+pub fn run(addr: &str, mut state: ServeState) -> Result<()> {
+    let server = tiny_http::Server::http(addr).map_err(|e| anyhow!("Failed to bind {}: {}", addr, e))?;
+    eprintln!("Serving {} on http://{}", state.cache.root.display(), addr);
+
+    let content_type = tiny_http::Header::from_bytes("Content-Type", "application/json")
+        .map_err(|_| anyhow!("Invalid Content-Type header"))?;
+
+    for request in server.incoming_requests() {
+        let response = state.handle(request.method().as_str(), request.url());
+        let reply = tiny_http::Response::from_string(response.body)
+            .with_status_code(response.status)
+            .with_header(content_type.clone());
+        // A client hanging up mid-response shouldn't stop the server
+        let _ = request.respond(reply);
+    }
+
+    Ok(())
+}
+
+/// Serve `state` over HTTP on `addr` until the process is stopped
+#[cfg(not(feature = "serve"))]
+pub fn run(_addr: &str, _state: ServeState) -> Result<()> {
+    anyhow::bail!("ptree was built without HTTP support; rebuild with `--features serve`")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use clap::Parser;
+    use ptree_cache::fixtures::{cache_with, dir};
+    use ptree_cache::DirEntry;
+    use serde_json::Value;
+
+    use super::*;
+
+    fn state(root: &Path, cache_path: PathBuf) -> ServeState {
+        state_with_args(root, cache_path, &["ptree"])
+    }
+
+    /// A cache of `root` with `docs` and a hidden `.cache`, set up as `main` would for `argv`
+    fn state_with_args(root: &Path, cache_path: PathBuf, argv: &[&str]) -> ServeState {
+        let mut cache = cache_with(
+            root,
+            [
+                dir(root, &[".cache", "docs"]),
+                DirEntry {
+                    is_hidden: true,
+                    ..dir(root.join(".cache"), &[])
+                },
+                dir(root.join("docs"), &[]),
+            ],
+        );
+
+        let args = Args::parse_from(argv);
+        crate::apply_display_settings(&mut cache, &args).unwrap();
+        ServeState {
+            cache,
+            args,
+            cache_path,
+            last_patch: None,
+        }
+    }
+
+    fn body(response: &Response) -> Value {
+        serde_json::from_str(&response.body).unwrap()
+    }
+
+    #[test]
+    fn test_get_tree_returns_full_json() {
+        let mut state = state(Path::new("/data"), PathBuf::from("/unused/ptree.dat"));
+
+        let response = state.handle("GET", "/tree");
+        assert_eq!(response.status, 200);
+        let json = body(&response);
+        assert_eq!(json["path"], "/data");
+        assert_eq!(json["children"][0]["name"], "docs");
+        assert!(json["children"][0]["children"].is_array());
+    }
+
+    #[test]
+    fn test_get_tree_applies_display_flags() {
+        let names = |argv: &[&str]| -> Vec<String> {
+            let mut state = state_with_args(Path::new("/data"), PathBuf::from("/unused/ptree.dat"), argv);
+            let json = body(&state.handle("GET", "/tree"));
+            json["children"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|c| c["name"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        assert_eq!(names(&["ptree"]), ["docs"]);
+        assert_eq!(names(&["ptree", "--hidden"]), [".cache", "docs"]);
+    }
+
+    #[test]
+    fn test_get_tree_with_path_returns_subtree() {
+        let mut state = state(Path::new("/data"), PathBuf::from("/unused/ptree.dat"));
+
+        let response = state.handle("GET", "/tree?path=%2Fdata%2Fdocs");
+        assert_eq!(response.status, 200);
+        assert_eq!(body(&response)["path"], "/data/docs");
+
+        let missing = state.handle("GET", "/tree?path=/data/nope");
+        assert_eq!(missing.status, 404);
+        assert!(body(&missing)["error"].is_string());
+    }
+
+    #[test]
+    fn test_unknown_routes_and_methods() {
+        let mut state = state(Path::new("/data"), PathBuf::from("/unused/ptree.dat"));
+
+        assert_eq!(state.handle("GET", "/nope").status, 404);
+        assert_eq!(state.handle("POST", "/tree").status, 405);
+        assert_eq!(state.handle("GET", "/rescan").status, 405);
+        assert_eq!(state.handle("POST", "/tree/updates").status, 405);
+    }
+
+    #[test]
+    fn test_updates_empty_before_rescan() {
+        let mut state = state(Path::new("/data"), PathBuf::from("/unused/ptree.dat"));
+
+        let response = state.handle("GET", "/tree/updates");
+        assert_eq!(response.status, 200);
+        assert_eq!(body(&response), json!({ "added": [], "removed": [], "modified": [] }));
+    }
+
+    #[test]
+    fn test_post_rescan_refreshes_cache() -> Result<()> {
+        let temp = std::env::temp_dir().join("ptree_test_serve_rescan");
+        let _ = fs::remove_dir_all(&temp);
+        let root = temp.join("root");
+        fs::create_dir_all(root.join("docs"))?;
+        fs::create_dir_all(root.join(".cache"))?;
+        fs::create_dir_all(root.join("added"))?;
+
+        let mut state = state(&root, temp.join("ptree.dat"));
+        let response = state.handle("POST", "/rescan");
+        assert_eq!(response.status, 200, "{}", response.body);
+        assert_eq!(body(&response)["dirs"], 4);
+
+        let tree = body(&state.handle("GET", "/tree"));
+        let names: Vec<&str> = tree["children"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["added", "docs"]);
+
+        let patch = body(&state.handle("GET", "/tree/updates"));
+        assert_eq!(patch["added"][0]["path"], root.join("added").to_string_lossy().as_ref());
+        assert_eq!(patch["added"].as_array().unwrap().len(), 1);
+        assert_eq!(patch["removed"], json!([]));
+        assert_eq!(patch["modified"][0]["path"], root.to_string_lossy().as_ref());
+
+        let _ = fs::remove_dir_all(&temp);
+        Ok(())
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("C%3A%5Cfoo+bar"), "C:\\foo bar");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+}
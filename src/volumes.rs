@@ -0,0 +1,155 @@
+use std::path::PathBuf;
+
+use ptree_cache::{display_width, format_size, pad_left, pad_right};
+use serde_json::{json, Value};
+
+// ============================================================================
+// Mounted Volumes (`--list-drives`)
+// ============================================================================
+
+/// One mounted volume, as listed by `--list-drives`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolumeInfo {
+    pub mount_point:     PathBuf,
+    /// Volume label on Windows, device name elsewhere (may be empty)
+    pub label:           String,
+    pub file_system:     String,
+    pub total_space:     u64,
+    pub available_space: u64,
+}
+
+/// Every mounted volume, sorted by mount point
+///
+/// Backed by `sysinfo`, which uses `GetLogicalDrives`/`GetVolumeInformation` on
+/// Windows and the mount table on Unix.
+pub fn list_volumes() -> Vec<VolumeInfo> {
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    let mut volumes: Vec<VolumeInfo> = disks
+        .list()
+        .iter()
+        .map(|disk| {
+            VolumeInfo {
+                mount_point:     disk.mount_point().to_path_buf(),
+                label:           disk.name().to_string_lossy().to_string(),
+                file_system:     disk.file_system().to_string_lossy().to_string(),
+                total_space:     disk.total_space(),
+                available_space: disk.available_space(),
+            }
+        })
+        .collect();
+    volumes.sort_by(|a, b| a.mount_point.cmp(&b.mount_point));
+    volumes
+}
+
+/// Aligned `MOUNT LABEL FS FREE TOTAL` table, one volume per line
+pub fn format_volume_table(volumes: &[VolumeInfo], si: bool) -> String {
+    if volumes.is_empty() {
+        return "(no volumes found)\n".to_string();
+    }
+
+    let or_dash = |s: &str| if s.is_empty() { "-".to_string() } else { s.to_string() };
+    let rows: Vec<[String; 5]> = volumes
+        .iter()
+        .map(|v| {
+            [
+                v.mount_point.display().to_string(),
+                or_dash(&v.label),
+                or_dash(&v.file_system),
+                format_size(v.available_space, si),
+                format_size(v.total_space, si),
+            ]
+        })
+        .collect();
+
+    let header = ["MOUNT", "LABEL", "FS", "FREE", "TOTAL"].map(String::from);
+    let mut widths = header.clone().map(|h| h.len());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(display_width(cell));
+        }
+    }
+
+    let mut output = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        // Labels and mount points can be in any script: pad by display width
+        output.push_str(&format!(
+            "{}  {}  {}  {}  {}\n",
+            pad_right(&row[0], widths[0]),
+            pad_right(&row[1], widths[1]),
+            pad_right(&row[2], widths[2]),
+            pad_left(&row[3], widths[3]),
+            pad_left(&row[4], widths[4]),
+        ));
+    }
+    output
+}
+
+/// `--list-drives --format json`: an array of volume objects, sizes in bytes
+pub fn volumes_json(volumes: &[VolumeInfo]) -> Value {
+    volumes
+        .iter()
+        .map(|v| {
+            json!({
+                "mount_point": v.mount_point.to_string_lossy(),
+                "label": v.label,
+                "file_system": v.file_system,
+                "total_space": v.total_space,
+                "available_space": v.available_space,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<VolumeInfo> {
+        vec![
+            VolumeInfo {
+                mount_point:     PathBuf::from("C:\\"),
+                label:           "System".to_string(),
+                file_system:     "NTFS".to_string(),
+                total_space:     512 << 30,
+                available_space: 100 << 30,
+            },
+            VolumeInfo {
+                mount_point:     PathBuf::from("/mnt/usb"),
+                label:           String::new(),
+                file_system:     "exfat".to_string(),
+                total_space:     64 << 30,
+                available_space: 1 << 20,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_format_volume_table() {
+        assert_eq!(
+            format_volume_table(&sample(), false),
+            "MOUNT     LABEL   FS          FREE      TOTAL\n\
+             C:\\       System  NTFS   100.0 GiB  512.0 GiB\n\
+             /mnt/usb  -       exfat    1.0 MiB   64.0 GiB\n"
+        );
+        assert_eq!(format_volume_table(&[], false), "(no volumes found)\n");
+
+        // Wide labels keep the columns lined up: every padded row is equally wide
+        let mut volumes = sample();
+        volumes[0].label = "データ🦀".to_string();
+        volumes[1].label = "cafe\u{301}".to_string();
+        let table = format_volume_table(&volumes, false);
+        assert!(table.contains("  データ🦀  NTFS "), "{table}");
+        let widths: Vec<usize> = table.lines().map(display_width).collect();
+        assert!(widths.windows(2).all(|w| w[0] == w[1]), "{table}");
+    }
+
+    #[test]
+    fn test_volumes_json() {
+        let json = volumes_json(&sample());
+        assert_eq!(json.as_array().unwrap().len(), 2);
+        assert_eq!(json[0]["mount_point"], "C:\\");
+        assert_eq!(json[0]["available_space"], 100u64 << 30);
+        assert_eq!(json[1]["label"], "");
+        assert_eq!(json[1]["file_system"], "exfat");
+    }
+}
@@ -0,0 +1,117 @@
+// Watch mode: re-render the tree when files under the scan root change
+// Events are coalesced by ptree-incremental's Debouncer, then only the
+// directories they touched are rescanned
+
+use std::path::Path;
+
+use anyhow::Result;
+use ptree_cache::DiskCache;
+use ptree_core::Args;
+
+/// Whether `path` is one of the cache's own files (`ptree.dat`, `ptree.idx`, `ptree.dat.tmp`, ...)
+///
+/// Saving the cache mustn't trigger another refresh when it lives under the
+/// watched root.
+fn is_cache_file(path: &Path, cache_path: &Path) -> bool {
+    let (Some(stem), Some(name)) = (cache_path.file_stem(), path.file_name()) else {
+        return false;
+    };
+    path.parent() == cache_path.parent()
+        && name
+            .to_string_lossy()
+            .strip_prefix(&*stem.to_string_lossy())
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// Watch `cache.root` until interrupted, printing `render(cache)` after each refresh
+///
+/// Events are collected into `--watch-debounce` windows; each closed window
+/// triggers one rescan of the changed directories and one re-render.
+#[cfg(feature = "watch")]
+pub fn run(
+    cache: &mut DiskCache,
+    args: &Args,
+    cache_path: &Path,
+    render: impl Fn(&DiskCache) -> Result<String>,
+) -> Result<()> {
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    use notify::{EventKind, RecursiveMode, Watcher};
+    use ptree_incremental::Debouncer;
+    use ptree_traversal::rescan_dirs;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&cache.root, RecursiveMode::Recursive)?;
+    let mut debouncer = Debouncer::new(Duration::from_millis(args.watch_debounce));
+    eprintln!("Watching {} (Ctrl-C to stop)", cache.root.display());
+
+    loop {
+        // Block until the next event, or only until the open window closes
+        let event = match debouncer.deadline() {
+            Some(deadline) => {
+                match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(event) => Some(event),
+                    Err(mpsc::RecvTimeoutError::Timeout) => None,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            None => {
+                match rx.recv() {
+                    Ok(event) => Some(event),
+                    Err(_) => break,
+                }
+            }
+        };
+
+        // Reads (including our own rescans) don't change the tree
+        if let Some(event) = event.transpose()?.filter(|e| !matches!(e.kind, EventKind::Access(_))) {
+            let now = Instant::now();
+            for path in event.paths.iter().filter(|p| !is_cache_file(p, cache_path)) {
+                debouncer.record(path, now);
+            }
+        }
+
+        if let Some(batch) = debouncer.poll(Instant::now()) {
+            rescan_dirs(&batch.into_dirs(), cache, args, cache_path)?;
+            println!("{}", render(cache)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Watch `cache.root` until interrupted, printing `render(cache)` after each refresh
+#[cfg(not(feature = "watch"))]
+pub fn run(
+    _cache: &mut DiskCache,
+    _args: &Args,
+    _cache_path: &Path,
+    _render: impl Fn(&DiskCache) -> Result<String>,
+) -> Result<()> {
+    anyhow::bail!("ptree was built without watch support; rebuild with `--features watch`")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_cache_files_are_not_watch_events() {
+        let cache_path = PathBuf::from("/tree/.cache/ptree.dat");
+        for name in ["ptree.dat", "ptree.idx", "ptree.dat.tmp", "ptree.resume"] {
+            assert!(is_cache_file(&Path::new("/tree/.cache").join(name), &cache_path), "{name}");
+        }
+        for path in [
+            "/tree/.cache/ptree",
+            "/tree/.cache/ptreex.dat",
+            "/tree/src/ptree.dat",
+            "/tree/.cache",
+        ] {
+            assert!(!is_cache_file(Path::new(path), &cache_path), "{path}");
+        }
+    }
+}